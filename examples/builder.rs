@@ -0,0 +1,22 @@
+use svg_simple_parser::{stringify_pretty, svg, Element};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let circle = Element::build("circle")
+        .attr("cx", 100)
+        .attr("cy", 50)
+        .attr("r", 40)
+        .attr("stroke", "black")
+        .attr("stroke-width", 2)
+        .attr("fill", "red");
+
+    let r = 20;
+    let svg = svg! {
+        <svg xmlns="http://www.w3.org/2000/svg" version="1.1">
+            {circle}
+            <circle cx=100 cy=50 r={r} fill="white"/>
+        </svg>
+    };
+
+    println!("{:#?}", stringify_pretty(svg));
+    Ok(())
+}
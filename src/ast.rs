@@ -1,5 +1,7 @@
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::rc::{Rc, Weak};
 
 type NewWithChildren<'a> = (&'a str, HashMap<String, &'a str>, Vec<Rc<Element<'a>>>);
@@ -8,15 +10,29 @@ type NewWithChildren<'a> = (&'a str, HashMap<String, &'a str>, Vec<Rc<Element<'a
 ///
 /// `ele_type` the type of the element
 ///
-/// `attributes` the attributes in the element
+/// `attributes` the attributes in the element, borrowed from the parsed text or owned when computed
 ///
 /// `children` the children in the element
+///
+/// `text` the character data of the element before its first child, as written
+/// in the source with its entities and CDATA sections
+///
+/// `tail` the character data after the element, up to its next sibling or the
+/// end of its parent. the runs of mixed content keep their place this way.
+/// whitespace-only character data is only kept inside `text`, `tspan` and `textPath`.
+///
+/// `Clone` is shallow: the clone shares its children with the original,
+/// use `deep_clone` for a independent subtree.
+/// `PartialEq`/`Eq`/`Hash` compare the type, the attributes, the text and the
+/// children with their tail recursively. the parent and the own tail are ignored.
 #[derive(Debug, Clone)]
 pub struct Element<'a> {
     pub ele_type: &'a str,
-    pub attributes: RefCell<HashMap<String, &'a str>>,
+    pub attributes: RefCell<HashMap<String, Cow<'a, str>>>,
     pub parent: RefCell<Weak<Element<'a>>>,
     pub children: RefCell<Vec<Rc<Element<'a>>>>,
+    pub text: RefCell<Cow<'a, str>>,
+    pub tail: RefCell<Cow<'a, str>>,
 }
 
 impl<'a> Element<'a> {
@@ -34,8 +50,10 @@ impl<'a> Element<'a> {
         Rc::new(Element {
             ele_type,
             parent: RefCell::new(Weak::new()),
-            attributes: RefCell::new(attributes),
+            attributes: RefCell::new(into_cow_hash(attributes)),
             children: RefCell::new(vec![]),
+            text: RefCell::new(Cow::Borrowed("")),
+            tail: RefCell::new(Cow::Borrowed("")),
         })
     }
 
@@ -53,9 +71,11 @@ impl<'a> Element<'a> {
     pub fn new_width_children((ele_type, attributes, children): NewWithChildren<'a>) -> Rc<Self> {
         let parent = Rc::new(Element {
             ele_type,
-            attributes: RefCell::new(attributes),
+            attributes: RefCell::new(into_cow_hash(attributes)),
             parent: RefCell::new(Weak::new()),
             children: RefCell::new(vec![]),
+            text: RefCell::new(Cow::Borrowed("")),
+            tail: RefCell::new(Cow::Borrowed("")),
         });
         *parent.children.borrow_mut() = children
             .iter()
@@ -74,12 +94,13 @@ impl<'a> Element<'a> {
     ///
     /// ``` rust
     /// use std::collections::HashMap;
+    /// use std::rc::Rc;
     /// use svg_simple_parser::Element;
     ///
     /// let parent = Element::new(("rect",HashMap::from([("width".to_owned(), "100"),("height".to_owned(), "100")])));
     /// let child = Element::new(("rect",HashMap::from([("width".to_owned(), "100"),("height".to_owned(), "100")])));
-    /// Element::add_child(parent.clone(),child.clone());
-    /// assert_eq!(parent.borrow().children.borrow().get(0),Some(&child));
+    /// parent.add_child(child.clone());
+    /// assert!(Rc::ptr_eq(&parent.children.borrow()[0], &child));
     /// ```
     ///
     pub fn add_child(self: &Rc<Element<'a>>, new_item: Rc<Element<'a>>) {
@@ -94,12 +115,13 @@ impl<'a> Element<'a> {
     ///
     /// ``` rust
     /// use std::collections::HashMap;
+    /// use std::rc::Rc;
     /// use svg_simple_parser::Element;
     ///
     /// let parent = Element::new(("rect",HashMap::from([("width".to_owned(), "100"),("height".to_owned(), "100")])));
     /// let child = Element::new(("rect",HashMap::from([("width".to_owned(), "100"),("height".to_owned(), "100")])));
-    /// Element::add_children(parent.clone(),vec![child.clone()].as_mut());
-    /// assert_eq!(parent.borrow().children.borrow().get(0),Some(&child));
+    /// parent.add_children(vec![child.clone()]);
+    /// assert!(Rc::ptr_eq(&parent.children.borrow()[0], &child));
     /// ```
     ///
     pub fn add_children(self: &Rc<Element<'a>>, new_items: Vec<Rc<Element<'a>>>) {
@@ -113,4 +135,470 @@ impl<'a> Element<'a> {
             .collect();
         (self.children.borrow_mut()).append(&mut new_items);
     }
+
+    /// insert a element into the children of the element at `index`.
+    ///
+    /// a `index` past the end appends the element.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use std::collections::HashMap;
+    /// use std::rc::Rc;
+    /// use svg_simple_parser::Element;
+    ///
+    /// let last = Element::new(("rect",HashMap::new()));
+    /// let parent = Element::new_width_children(("g",HashMap::new(),vec![last.clone()]));
+    /// let first = Element::new(("circle",HashMap::new()));
+    /// parent.insert_child(0, first.clone());
+    /// assert!(Rc::ptr_eq(&parent.children.borrow()[0], &first));
+    /// assert!(Rc::ptr_eq(&parent.children.borrow()[1], &last));
+    /// ```
+    ///
+    pub fn insert_child(self: &Rc<Element<'a>>, index: usize, new_item: Rc<Element<'a>>) {
+        *new_item.parent.borrow_mut() = Rc::downgrade(self);
+        let mut children = self.children.borrow_mut();
+        let index = index.min(children.len());
+        children.insert(index, new_item);
+    }
+
+    /// get a attribute of the element.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use std::collections::HashMap;
+    /// use svg_simple_parser::Element;
+    ///
+    /// let rect = Element::new(("rect",HashMap::from([("width".to_owned(), "100")])));
+    /// assert_eq!(rect.get_attribute("width").as_deref(), Some("100"));
+    /// assert_eq!(rect.get_attribute("height"), None);
+    /// ```
+    ///
+    pub fn get_attribute(&self, key: &str) -> Option<Cow<'a, str>> {
+        self.attributes.borrow().get(key).cloned()
+    }
+
+    /// set a attribute of the element, the value can be borrowed or owned.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use std::collections::HashMap;
+    /// use svg_simple_parser::Element;
+    ///
+    /// let rect = Element::new(("rect",HashMap::from([("width".to_owned(), "100")])));
+    /// rect.set_attribute("width", format!("{}", 200));
+    /// rect.set_attribute("height", "50");
+    /// assert_eq!(rect.get_attribute("width").as_deref(), Some("200"));
+    /// assert_eq!(rect.get_attribute("height").as_deref(), Some("50"));
+    /// ```
+    ///
+    pub fn set_attribute<V>(&self, key: &str, value: V)
+    where
+        V: Into<Cow<'a, str>>,
+    {
+        self.attributes
+            .borrow_mut()
+            .insert(key.to_owned(), value.into());
+    }
+
+    /// remove a attribute of the element and return its value.
+    pub fn remove_attribute(&self, key: &str) -> Option<Cow<'a, str>> {
+        self.attributes.borrow_mut().remove(key)
+    }
+
+    /// remove a element from the children of the element.
+    ///
+    /// return the removed element, or `None` if it is not a child of the element.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use std::collections::HashMap;
+    /// use svg_simple_parser::Element;
+    ///
+    /// let parent = Element::new(("g",HashMap::new()));
+    /// let child = Element::new(("rect",HashMap::from([("width".to_owned(), "100")])));
+    /// parent.add_child(child.clone());
+    /// assert!(parent.remove_child(&child).is_some());
+    /// assert!(parent.children.borrow().is_empty());
+    /// assert!(child.parent.borrow().upgrade().is_none());
+    /// ```
+    ///
+    pub fn remove_child(
+        self: &Rc<Element<'a>>,
+        child: &Rc<Element<'a>>,
+    ) -> Option<Rc<Element<'a>>> {
+        let mut children = self.children.borrow_mut();
+        let index = children.iter().position(|node| Rc::ptr_eq(node, child))?;
+        let node = children.remove(index);
+        *node.parent.borrow_mut() = Weak::new();
+        Some(node)
+    }
+
+    /// replace a child of the element with a new element.
+    ///
+    /// the tail of `old` moves to `new_item`, so the character data after it stays in place.
+    /// return the replaced element, or `None` if `old` is not a child of the element.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use std::collections::HashMap;
+    /// use std::rc::Rc;
+    /// use svg_simple_parser::Element;
+    ///
+    /// let old = Element::new(("rect",HashMap::new()));
+    /// let parent = Element::new_width_children(("g",HashMap::new(),vec![old.clone()]));
+    /// let new = Element::new(("circle",HashMap::new()));
+    /// assert!(parent.replace_child(&old, new.clone()).is_some());
+    /// assert!(Rc::ptr_eq(&parent.children.borrow()[0], &new));
+    /// assert!(Rc::ptr_eq(&new.parent.borrow().upgrade().unwrap(), &parent));
+    /// ```
+    ///
+    pub fn replace_child(
+        self: &Rc<Element<'a>>,
+        old: &Rc<Element<'a>>,
+        new_item: Rc<Element<'a>>,
+    ) -> Option<Rc<Element<'a>>> {
+        let mut children = self.children.borrow_mut();
+        let index = children.iter().position(|node| Rc::ptr_eq(node, old))?;
+        *new_item.parent.borrow_mut() = Rc::downgrade(self);
+        *new_item.tail.borrow_mut() = old.tail.replace(Cow::Borrowed(""));
+        let node = std::mem::replace(&mut children[index], new_item);
+        *node.parent.borrow_mut() = Weak::new();
+        Some(node)
+    }
+
+    /// copy the element and its whole subtree.
+    ///
+    /// the copy is detached: it has no parent nor tail, and the parents of the
+    /// copied children point into the copy.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use std::collections::HashMap;
+    /// use std::rc::Rc;
+    /// use svg_simple_parser::Element;
+    ///
+    /// let child = Element::new(("rect",HashMap::from([("width".to_owned(), "100")])));
+    /// let root = Element::new_width_children(("g",HashMap::new(),vec![child.clone()]));
+    /// let copy = root.deep_clone();
+    /// assert_eq!(copy, root);
+    /// let copy_child = copy.children.borrow()[0].clone();
+    /// assert!(!Rc::ptr_eq(&copy_child, &child));
+    /// assert!(Rc::ptr_eq(&copy_child.parent.borrow().upgrade().unwrap(), &copy));
+    /// ```
+    ///
+    pub fn deep_clone(&self) -> Rc<Element<'a>> {
+        let copy = Rc::new(Element {
+            ele_type: self.ele_type,
+            attributes: RefCell::new(self.attributes.borrow().clone()),
+            parent: RefCell::new(Weak::new()),
+            children: RefCell::new(vec![]),
+            text: RefCell::new(self.text.borrow().clone()),
+            tail: RefCell::new(Cow::Borrowed("")),
+        });
+        let children = self
+            .children
+            .borrow()
+            .iter()
+            .map(|child| {
+                let copy = child.deep_clone();
+                *copy.tail.borrow_mut() = child.tail.borrow().clone();
+                copy
+            })
+            .collect();
+        copy.add_children(children);
+        copy
+    }
+
+    /// compare two elements structurally with options.
+    ///
+    /// attributes are unordered, so their order never matters.
+    /// with `ignore_whitespace`, whitespace runs in attribute values and in the
+    /// text are collapsed and trimmed before comparing.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use std::collections::HashMap;
+    /// use svg_simple_parser::Element;
+    ///
+    /// let a = Element::new(("path",HashMap::from([("d".to_owned(), "M0 0  L10 10 ")])));
+    /// let b = Element::new(("path",HashMap::from([("d".to_owned(), "M0 0 L10 10")])));
+    /// assert_ne!(a, b);
+    /// assert!(a.structural_eq(&b, true));
+    /// ```
+    ///
+    pub fn structural_eq(&self, other: &Element, ignore_whitespace: bool) -> bool {
+        if self.ele_type != other.ele_type {
+            return false;
+        }
+        let (attributes, other_attributes) = (self.attributes.borrow(), other.attributes.borrow());
+        let same_attributes = attributes.len() == other_attributes.len()
+            && attributes
+                .iter()
+                .all(|(k, v)| match other_attributes.get(k) {
+                    Some(other_v) if ignore_whitespace => {
+                        normalize_whitespace(v) == normalize_whitespace(other_v)
+                    }
+                    Some(other_v) => v == other_v,
+                    None => false,
+                });
+        let same_text = |text: &str, other_text: &str| {
+            if ignore_whitespace {
+                normalize_whitespace(text) == normalize_whitespace(other_text)
+            } else {
+                text == other_text
+            }
+        };
+        let (children, other_children) = (self.children.borrow(), other.children.borrow());
+        same_attributes
+            && same_text(&self.text.borrow(), &other.text.borrow())
+            && children.len() == other_children.len()
+            && children.iter().zip(other_children.iter()).all(|(a, b)| {
+                same_text(&a.tail.borrow(), &b.tail.borrow())
+                    && a.structural_eq(b, ignore_whitespace)
+            })
+    }
+
+    /// all the descendants of the element in document order, without the element itself.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use std::collections::HashMap;
+    /// use svg_simple_parser::Element;
+    ///
+    /// let leaf = Element::new(("rect",HashMap::new()));
+    /// let group = Element::new_width_children(("g",HashMap::new(),vec![leaf]));
+    /// let root = Element::new_width_children(("svg",HashMap::new(),vec![group]));
+    /// let types: Vec<&str> = root.descendants().iter().map(|e| e.ele_type).collect();
+    /// assert_eq!(types, vec!["g", "rect"]);
+    /// ```
+    ///
+    pub fn descendants(self: &Rc<Element<'a>>) -> Vec<Rc<Element<'a>>> {
+        let mut list = vec![];
+        for child in self.children.borrow().iter() {
+            list.push(child.clone());
+            list.append(&mut child.descendants());
+        }
+        list
+    }
+
+    /// the character data of the element, its `text` and the `tail` of its
+    /// children in order, with its CDATA sections unwrapped and its entities decoded.
+    /// the text of the descendants is not included.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use svg_simple_parser::parse;
+    ///
+    /// let (_, text) = parse("<text>a &lt; b<![CDATA[ & <c>]]><tspan>d</tspan> e</text>").unwrap();
+    /// assert_eq!(text.text_content(), "a < b & <c> e");
+    /// ```
+    ///
+    pub fn text_content(&self) -> String {
+        let mut content = decode_text(&self.text.borrow());
+        for child in self.children.borrow().iter() {
+            content.push_str(&child.tail_content());
+        }
+        content
+    }
+
+    /// the `tail` of the element with its CDATA sections unwrapped and its entities decoded
+    pub fn tail_content(&self) -> String {
+        decode_text(&self.tail.borrow())
+    }
+
+    /// set the character data of the element, escaping `&` and `<`. the text
+    /// is placed before the children, whose `tail` is removed.
+    pub fn set_text_content(&self, content: &str) {
+        let escaped = content.replace('&', "&amp;").replace('<', "&lt;");
+        *self.text.borrow_mut() = Cow::Owned(escaped);
+        for child in self.children.borrow().iter() {
+            *child.tail.borrow_mut() = Cow::Borrowed("");
+        }
+    }
+}
+
+/// unwrap the CDATA sections and decode the entities of character data
+pub(crate) fn decode_text(text: &str) -> String {
+    let mut content = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<![CDATA[") {
+        content.push_str(&decode_entities(&rest[..start]));
+        let section = &rest[start + "<![CDATA[".len()..];
+        let end = section.find("]]>").unwrap_or(section.len());
+        content.push_str(&section[..end]);
+        rest = section.get(end + "]]>".len()..).unwrap_or("");
+    }
+    content.push_str(&decode_entities(rest));
+    content
+}
+
+/// decode the predefined entities and the character references of a text,
+/// a unknown entity is kept as it is
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let character = entity.and_then(|entity| match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => {
+                let code = match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity.strip_prefix('#').and_then(|n| n.parse().ok()),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// copy a element and its subtree, without the `id`s
+pub(crate) fn clone_subtree<'a>(ele: &Rc<Element<'a>>) -> Rc<Element<'a>> {
+    let copy = ele.deep_clone();
+    copy.remove_attribute("id");
+    for node in copy.descendants() {
+        node.remove_attribute("id");
+    }
+    copy
+}
+
+/// convert the values of a attribute hash to `Cow`
+#[inline(always)]
+fn into_cow_hash(attributes: HashMap<String, &str>) -> HashMap<String, Cow<'_, str>> {
+    attributes
+        .into_iter()
+        .map(|(k, v)| (k, Cow::Borrowed(v)))
+        .collect()
+}
+
+impl<'a> PartialEq for Element<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.structural_eq(other, false)
+    }
+}
+
+impl<'a> Eq for Element<'a> {}
+
+impl<'a> Hash for Element<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.ele_type.hash(state);
+        let attributes = self.attributes.borrow();
+        let mut attributes: Vec<(&String, &Cow<str>)> = attributes.iter().collect();
+        attributes.sort();
+        attributes.hash(state);
+        self.text.borrow().hash(state);
+        let children = self.children.borrow();
+        children.len().hash(state);
+        for child in children.iter() {
+            child.tail.borrow().hash(state);
+            child.hash(state);
+        }
+    }
+}
+
+/// collapse the whitespace runs of a text and trim it
+#[inline(always)]
+fn normalize_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::{HashMap, HashSet};
+    use std::hash::{Hash, Hasher};
+    use std::rc::Rc;
+
+    use crate::Element;
+
+    fn hash(ele: &Element) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        ele.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn tree<'a>(fill: &'a str) -> Rc<Element<'a>> {
+        Element::new_width_children((
+            "svg",
+            HashMap::from([("width".to_owned(), "10"), ("height".to_owned(), "10")]),
+            vec![Element::new_width_children((
+                "g",
+                HashMap::from([("fill".to_owned(), fill)]),
+                vec![Element::new((
+                    "rect",
+                    HashMap::from([("x".to_owned(), "1")]),
+                ))],
+            ))],
+        ))
+    }
+
+    #[test]
+    fn test_deep_clone() {
+        let root = tree("red");
+        let copy = root.deep_clone();
+        assert_eq!(copy, root);
+        assert!(copy.parent.borrow().upgrade().is_none());
+
+        // the copy does not share anything with the original
+        let group = copy.children.borrow()[0].clone();
+        group.set_attribute("fill", "blue");
+        group.children.borrow()[0].set_attribute("x", "2");
+        assert_eq!(root, tree("red"));
+        assert_ne!(copy, root);
+
+        let rect = group.children.borrow()[0].clone();
+        assert!(Rc::ptr_eq(&rect.parent.borrow().upgrade().unwrap(), &group));
+        assert!(Rc::ptr_eq(&group.parent.borrow().upgrade().unwrap(), &copy));
+    }
+
+    #[test]
+    fn test_eq_and_hash() {
+        assert_eq!(tree("red"), tree("red"));
+        assert_eq!(hash(&tree("red")), hash(&tree("red")));
+        assert_ne!(tree("red"), tree("blue"));
+
+        let set: HashSet<u64> = [tree("red"), tree("red"), tree("blue")]
+            .iter()
+            .map(|ele| hash(ele))
+            .collect();
+        assert_eq!(set.len(), 2);
+
+        // the parent does not take part in the comparison
+        let detached = tree("red").children.borrow()[0].deep_clone();
+        assert_eq!(&detached, &tree("red").children.borrow()[0]);
+    }
+
+    #[test]
+    fn test_structural_eq() {
+        assert!(tree(" red ").structural_eq(&tree("red"), true));
+        assert!(!tree(" red ").structural_eq(&tree("red"), false));
+        assert!(!tree("red").structural_eq(&tree("blue"), true));
+    }
 }
@@ -0,0 +1,718 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::{clone_subtree, Element};
+use crate::css::ComputedStyles;
+use crate::document::Document;
+use crate::number::{parse_number, parse_number_list, snap};
+use crate::path::{shortest_number, PathData, PathError, PathSegment};
+use crate::path_normalize::end_point;
+use crate::reference::{element_href, url_ids};
+use crate::shape::{number_or_zero, radii, SHAPES};
+use crate::transform::{Matrix, Transform, TransformError};
+
+/// A invalid attribute which prevents baking the transforms of a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BakeError {
+    Transform(TransformError),
+    Path(PathError),
+}
+
+impl fmt::Display for BakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BakeError::Transform(e) => write!(f, "invalid transform: {}", e),
+            BakeError::Path(e) => write!(f, "invalid path data: {}", e),
+        }
+    }
+}
+
+impl Error for BakeError {}
+
+impl From<TransformError> for BakeError {
+    fn from(e: TransformError) -> Self {
+        BakeError::Transform(e)
+    }
+}
+
+impl From<PathError> for BakeError {
+    fn from(e: PathError) -> Self {
+        BakeError::Path(e)
+    }
+}
+
+/// the attributes which establish a coordinate system for their own content
+const EFFECTS: [&str; 3] = ["clip-path", "mask", "filter"];
+
+const MARKERS: [&str; 3] = ["marker-start", "marker-mid", "marker-end"];
+
+/// apply the transforms of the document directly to its geometry
+///
+/// the transforms of groups are accumulated down to the shapes, whose
+/// coordinates are transformed: paths, lines and polylines always, circles
+/// under uniform scaling and rotation, rects and ellipses under axis-aligned
+/// scaling. other shapes become a `<path>`. the stroke width and dashes are
+/// scaled by the mean scale of the transform, which is exact for uniform
+/// scaling. a gradient in `userSpaceOnUse` is copied with the transform applied
+/// to its coordinates, or to its `gradientTransform` when they can not hold it.
+///
+/// a element which can not be baked keeps the accumulated transform: text,
+/// images, `<use>`, elements with a clip path, mask, filter or marker, shapes
+/// with a pattern or a `objectBoundingBox` gradient which would be distorted,
+/// and shapes with geometry which is not plain numbers. a nested `<svg>` is
+/// wrapped in a `<g>` with the transform.
+///
+/// return a error and leave the tree unchanged if a transform or path data is invalid.
+///
+/// ## Example
+/// ```rust
+/// use svg_simple_parser::{bake_transforms, parse_document, stringify};
+///
+/// let (_, document) = parse_document(
+///     r#"<svg><g transform="translate(10 0)"><rect width="5" height="5" transform="scale(2)" stroke="red"/></g></svg>"#,
+/// ).unwrap();
+/// bake_transforms(&document).unwrap();
+/// assert_eq!(
+///     stringify(document.root.clone()),
+///     r#"<svg><g><rect height="10" stroke="red" stroke-width="2" width="10" x="10" y="0"/></g></svg>"#
+/// );
+/// ```
+///
+pub fn bake_transforms(document: &Document) -> Result<(), BakeError> {
+    let root = document.root.clone();
+    for ele in std::iter::once(root.clone()).chain(root.descendants()) {
+        ele.transform()?;
+        if ele.ele_type == "path" {
+            ele.path_data()?;
+        }
+    }
+    let styles = ComputedStyles::new(document.stylesheet());
+    if root.ele_type == "svg" {
+        bake_container(document, &styles, &root, root.transform()?.to_matrix());
+    } else {
+        visit(document, &styles, &root, Matrix::IDENTITY);
+    }
+    document.reindex();
+    Ok(())
+}
+
+/// bake a element, `matrix` the transform accumulated from its ancestors
+fn visit<'a>(
+    document: &Document<'a>,
+    styles: &ComputedStyles<'a>,
+    ele: &Rc<Element<'a>>,
+    matrix: Matrix,
+) {
+    let total = matrix * ele.transform().unwrap_or_default().to_matrix();
+    match ele.ele_type {
+        "g" | "a" | "switch" => bake_container(document, styles, ele, total),
+        "svg" => {
+            ele.set_transform(&Transform::default());
+            if !total.is_identity() {
+                let parent = ele.parent.borrow().upgrade();
+                if let Some(parent) = parent {
+                    let group = Element::new(("g", HashMap::new()));
+                    group.set_transform(&total.to_transform());
+                    parent.replace_child(ele, group.clone());
+                    group.add_child(ele.clone());
+                }
+            }
+            bake_container(document, styles, ele, Matrix::IDENTITY);
+        }
+        "text" | "image" | "use" | "foreignObject" => ele.set_transform(&total.to_transform()),
+        "path" => bake_shape(document, styles, ele, total),
+        ele_type if SHAPES.contains(&ele_type) => bake_shape(document, styles, ele, total),
+        _ => {}
+    }
+}
+
+fn bake_container<'a>(
+    document: &Document<'a>,
+    styles: &ComputedStyles<'a>,
+    ele: &Rc<Element<'a>>,
+    total: Matrix,
+) {
+    let children = ele.children.borrow().clone();
+    let matrix = if has_effects(ele) {
+        ele.set_transform(&total.to_transform());
+        Matrix::IDENTITY
+    } else {
+        ele.set_transform(&Transform::default());
+        total
+    };
+    for child in children.iter() {
+        visit(document, styles, child, matrix);
+    }
+}
+
+fn has_effects(ele: &Element) -> bool {
+    EFFECTS.iter().any(|key| {
+        ele.get_attribute(key)
+            .is_some_and(|value| value.trim() != "none")
+    })
+}
+
+/// the gradient painting a element, `Err` if it is painted by another paint server
+fn paint_gradient<'a>(
+    document: &Document<'a>,
+    styles: &ComputedStyles<'a>,
+    ele: &Rc<Element<'a>>,
+    key: &str,
+) -> Result<Option<Rc<Element<'a>>>, ()> {
+    let value = match styles.get(ele, key) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    let target = url_ids(&value)
+        .first()
+        .and_then(|id| document.element_by_id(id));
+    match target {
+        Some(target) if target.ele_type.ends_with("Gradient") => Ok(Some(target)),
+        Some(_) => Err(()),
+        None => Ok(None),
+    }
+}
+
+/// the value of a gradient attribute, inherited through `href`
+fn gradient_attribute<'a>(
+    document: &Document<'a>,
+    gradient: &Rc<Element<'a>>,
+    key: &str,
+) -> Option<Cow<'a, str>> {
+    let mut current = gradient.clone();
+    // a bound on the chain, which may be a cycle
+    for _ in 0..32 {
+        if let Some(value) = current.get_attribute(key) {
+            return Some(value);
+        }
+        current = element_href(&current).and_then(|id| document.element_by_id(&id))?;
+    }
+    None
+}
+
+fn is_user_space<'a>(document: &Document<'a>, gradient: &Rc<Element<'a>>) -> bool {
+    gradient_attribute(document, gradient, "gradientUnits")
+        .is_some_and(|units| units.trim() == "userSpaceOnUse")
+}
+
+/// whether the matrix keeps circles circles: a rotation, uniform scaling and
+/// translation, with a optional reflection
+fn is_conformal(m: &Matrix) -> bool {
+    let eps = 1e-9 * (m.a.abs() + m.b.abs() + m.c.abs() + m.d.abs());
+    ((m.a - m.d).abs() <= eps && (m.b + m.c).abs() <= eps)
+        || ((m.a + m.d).abs() <= eps && (m.b - m.c).abs() <= eps)
+}
+
+fn is_axis_aligned(m: &Matrix) -> bool {
+    m.b == 0.0 && m.c == 0.0
+}
+
+fn can_bake<'a>(
+    document: &Document<'a>,
+    styles: &ComputedStyles<'a>,
+    ele: &Rc<Element<'a>>,
+    m: &Matrix,
+) -> bool {
+    if has_effects(ele) {
+        return false;
+    }
+    let has_marker = MARKERS.iter().any(|key| {
+        styles
+            .get(ele, key)
+            .is_some_and(|value| value.trim() != "none")
+    });
+    if has_marker {
+        return false;
+    }
+    ["fill", "stroke"]
+        .iter()
+        .all(|key| match paint_gradient(document, styles, ele, key) {
+            Err(()) => false,
+            Ok(Some(gradient)) if !is_user_space(document, &gradient) => {
+                // the bounding box follows a positive axis-aligned scaling
+                is_axis_aligned(m) && m.a > 0.0 && m.d > 0.0
+            }
+            Ok(_) => true,
+        })
+}
+
+fn bake_shape<'a>(
+    document: &Document<'a>,
+    styles: &ComputedStyles<'a>,
+    ele: &Rc<Element<'a>>,
+    total: Matrix,
+) {
+    if total.is_identity() {
+        ele.set_transform(&Transform::default());
+        return;
+    }
+    if !can_bake(document, styles, ele, &total) {
+        ele.set_transform(&total.to_transform());
+        return;
+    }
+    let ele = match bake_geometry(ele, &total) {
+        Some(ele) => ele,
+        None => {
+            ele.set_transform(&total.to_transform());
+            return;
+        }
+    };
+    ele.set_transform(&Transform::default());
+    scale_stroke(styles, &ele, total.determinant().abs().sqrt());
+    for key in ["fill", "stroke"] {
+        if let Ok(Some(gradient)) = paint_gradient(document, styles, &ele, key) {
+            if is_user_space(document, &gradient) {
+                let id = transform_gradient(document, &gradient, &total);
+                ele.set_attribute(key, format!("url(#{})", id));
+            }
+        }
+    }
+}
+
+/// transform the geometry of a shape, return the element holding it, which is
+/// a new `<path>` if the shape can not hold the transform. `None` if the
+/// geometry is not plain numbers.
+fn bake_geometry<'a>(ele: &Rc<Element<'a>>, m: &Matrix) -> Option<Rc<Element<'a>>> {
+    let point = |x: f64, y: f64| m.apply(x, y);
+    let number = |key| number_or_zero(ele, key);
+    let set = |key: &str, n: f64| ele.set_attribute(key, shortest_number(snap(n)));
+    match ele.ele_type {
+        "path" => {
+            let path_data = ele.path_data().ok()?.transformed(m);
+            ele.set_path_data(&path_data);
+        }
+        "line" => {
+            let (x1, y1) = point(number("x1")?, number("y1")?);
+            let (x2, y2) = point(number("x2")?, number("y2")?);
+            set("x1", x1);
+            set("y1", y1);
+            set("x2", x2);
+            set("y2", y2);
+        }
+        "polyline" | "polygon" => {
+            let points = parse_number_list(&ele.get_attribute("points").unwrap_or_default())?;
+            let points: Vec<String> = points
+                .chunks_exact(2)
+                .map(|p| {
+                    let (x, y) = point(p[0], p[1]);
+                    format!("{},{}", shortest_number(snap(x)), shortest_number(snap(y)))
+                })
+                .collect();
+            ele.set_attribute("points", points.join(" "));
+        }
+        "circle" if is_conformal(m) => {
+            let (cx, cy) = point(number("cx")?, number("cy")?);
+            let r = number("r")? * m.determinant().abs().sqrt();
+            set("cx", cx);
+            set("cy", cy);
+            set("r", r);
+        }
+        "ellipse" if is_axis_aligned(m) => {
+            let (cx, cy) = point(number("cx")?, number("cy")?);
+            let (rx, ry) = radii(ele)?;
+            let (rx, ry) = (rx * m.a.abs(), ry * m.d.abs());
+            set("cx", cx);
+            set("cy", cy);
+            set("rx", rx);
+            set("ry", ry);
+        }
+        "rect" if is_axis_aligned(m) => {
+            let (x, y) = (number("x")?, number("y")?);
+            let (width, height) = (number("width")?, number("height")?);
+            let has_radii = ele.get_attribute("rx").is_some() || ele.get_attribute("ry").is_some();
+            let radii = radii(ele)?;
+            let (x1, y1) = point(x, y);
+            let (x2, y2) = point(x + width, y + height);
+            set("x", x1.min(x2));
+            set("y", y1.min(y2));
+            set("width", width * m.a.abs());
+            set("height", height * m.d.abs());
+            if has_radii {
+                set("rx", radii.0 * m.a.abs());
+                set("ry", radii.1 * m.d.abs());
+            }
+        }
+        _ => {
+            let path = ele.to_path()?;
+            let path_data = path.path_data().ok()?.transformed(m);
+            path.set_path_data(&path_data);
+            let parent = ele.parent.borrow().upgrade();
+            if let Some(parent) = parent {
+                parent.replace_child(ele, path.clone());
+            }
+            return Some(path);
+        }
+    }
+    Some(ele.clone())
+}
+
+/// scale the stroke width and dashes of a stroked shape
+fn scale_stroke<'a>(styles: &ComputedStyles<'a>, ele: &Rc<Element<'a>>, factor: f64) {
+    if snap(factor) == 1.0 {
+        return;
+    }
+    let style = styles.style(ele);
+    match style.get("stroke") {
+        Some(stroke) if stroke.trim() != "none" => {}
+        _ => return,
+    }
+    let set = |key: &str, value: String| {
+        ele.remove_style_property(key);
+        ele.set_attribute(key, value);
+    };
+    let width = style.get("stroke-width").map_or(Some(1.0), parse_number);
+    if let Some(width) = width {
+        set("stroke-width", shortest_number(snap(width * factor)));
+    }
+    let dashes = style.get("stroke-dasharray").and_then(parse_number_list);
+    if let Some(dashes) = dashes {
+        let dashes: Vec<String> = dashes
+            .iter()
+            .map(|n| shortest_number(snap(n * factor)))
+            .collect();
+        set("stroke-dasharray", dashes.join(" "));
+    }
+    let offset = style.get("stroke-dashoffset").and_then(parse_number);
+    if let Some(offset) = offset {
+        set("stroke-dashoffset", shortest_number(snap(offset * factor)));
+    }
+}
+
+/// add a copy of a `userSpaceOnUse` gradient with the transform applied, return its id
+fn transform_gradient<'a>(
+    document: &Document<'a>,
+    gradient: &Rc<Element<'a>>,
+    m: &Matrix,
+) -> String {
+    let copy = clone_subtree(gradient);
+    let base = gradient
+        .get_attribute("id")
+        .map_or_else(|| gradient.ele_type.to_owned(), |id| id.to_string());
+    let id = (1..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|id| document.element_by_id(id).is_none())
+        .unwrap_or(base);
+    copy.set_attribute("id", id.clone());
+    let own = gradient_attribute(document, gradient, "gradientTransform")
+        .and_then(|value| Transform::parse(&value).ok())
+        .unwrap_or_default();
+    let matrix = *m * own.to_matrix();
+    if bake_gradient_coordinates(document, gradient, &copy, &matrix) {
+        copy.set_transform(&Transform::default());
+    } else {
+        copy.set_transform(&matrix.to_transform());
+    }
+    let parent = gradient.parent.borrow().upgrade();
+    document.add_child(&parent.unwrap_or_else(|| document.root.clone()), copy);
+    id
+}
+
+/// write the transformed coordinates of a gradient to its copy, return whether
+/// the coordinates can hold the transform
+fn bake_gradient_coordinates<'a>(
+    document: &Document<'a>,
+    gradient: &Rc<Element<'a>>,
+    copy: &Rc<Element<'a>>,
+    m: &Matrix,
+) -> bool {
+    if !is_conformal(m) {
+        return false;
+    }
+    // `Some(None)` for a missing attribute, `None` for one which is not a plain number
+    let number = |key| match gradient_attribute(document, gradient, key) {
+        Some(value) => parse_number(&value).map(Some),
+        None => Some(None),
+    };
+    let set = |key: &str, n: f64| copy.set_attribute(key, shortest_number(snap(n)));
+    match gradient.ele_type {
+        "linearGradient" => {
+            let (x1, y1, x2, y2) = match (number("x1"), number("y1"), number("x2"), number("y2")) {
+                (Some(Some(x1)), Some(Some(y1)), Some(Some(x2)), Some(Some(y2))) => {
+                    (x1, y1, x2, y2)
+                }
+                _ => return false,
+            };
+            let (x1, y1) = m.apply(x1, y1);
+            let (x2, y2) = m.apply(x2, y2);
+            set("x1", x1);
+            set("y1", y1);
+            set("x2", x2);
+            set("y2", y2);
+        }
+        "radialGradient" => {
+            let (cx, cy, r) = match (number("cx"), number("cy"), number("r")) {
+                (Some(Some(cx)), Some(Some(cy)), Some(Some(r))) => (cx, cy, r),
+                _ => return false,
+            };
+            let (fx, fy, fr) = match (number("fx"), number("fy"), number("fr")) {
+                (Some(fx), Some(fy), Some(fr)) => (fx, fy, fr),
+                _ => return false,
+            };
+            let scale = m.determinant().abs().sqrt();
+            let (tcx, tcy) = m.apply(cx, cy);
+            set("cx", tcx);
+            set("cy", tcy);
+            set("r", r * scale);
+            if fx.is_some() || fy.is_some() {
+                let (fx, fy) = m.apply(fx.unwrap_or(cx), fy.unwrap_or(cy));
+                set("fx", fx);
+                set("fy", fy);
+            }
+            if let Some(fr) = fr {
+                set("fr", fr * scale);
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// transform the radii and rotation of a elliptical arc, the ellipse maps to
+/// the ellipse given by the singular values of the transformed axes
+fn transform_arc(m: &Matrix, rx: f64, ry: f64, rotation: f64) -> (f64, f64, f64) {
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (p, r) = m.apply_vector(rx * cos, rx * sin);
+    let (q, s) = m.apply_vector(-ry * sin, ry * cos);
+    let (e, f, g, h) = ((p + s) / 2.0, (p - s) / 2.0, (r + q) / 2.0, (r - q) / 2.0);
+    let (big_q, big_r) = (e.hypot(h), f.hypot(g));
+    let (a1, a2) = (g.atan2(f), h.atan2(e));
+    let (rx, ry) = (snap(big_q + big_r), snap((big_q - big_r).abs()));
+    // the rotation of a ellipse is modulo 180 degrees, and none for a circle
+    let rotation = if rx == ry {
+        0.0
+    } else {
+        snap(((a2 + a1) / 2.0).to_degrees().rem_euclid(180.0)) % 180.0
+    };
+    (rx, ry, rotation)
+}
+
+impl PathData {
+    /// apply a transform to the path, the result is absolute
+    ///
+    /// `H` and `V` stay when the transform is axis-aligned, arcs get new radii
+    /// and rotation, and a reflection flips their sweep.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{Matrix, PathData};
+    ///
+    /// let path = PathData::parse("M0 0 h10 a5 5 0 0 1 0 10").unwrap();
+    /// let rotated = path.transformed(&Matrix::rotate(90.0));
+    /// assert_eq!(rotated.to_string(), "M0 0 0 10A5 5 0 01-10 10");
+    /// ```
+    ///
+    pub fn transformed(&self, matrix: &Matrix) -> PathData {
+        let point = |x: f64, y: f64| {
+            let (x, y) = matrix.apply(x, y);
+            (snap(x), snap(y))
+        };
+        let axis_aligned = is_axis_aligned(matrix);
+        let flip = matrix.determinant() < 0.0;
+        let (mut current, mut start) = ((0.0, 0.0), (0.0, 0.0));
+        let mut segments = vec![];
+        for segment in self.to_absolute().segments {
+            let transformed = match segment {
+                PathSegment::MoveTo { x, y, .. } => {
+                    let (x, y) = point(x, y);
+                    PathSegment::MoveTo { abs: true, x, y }
+                }
+                PathSegment::HorizontalLineTo { x, .. } if axis_aligned => {
+                    PathSegment::HorizontalLineTo {
+                        abs: true,
+                        x: snap(matrix.a * x + matrix.e),
+                    }
+                }
+                PathSegment::VerticalLineTo { y, .. } if axis_aligned => {
+                    PathSegment::VerticalLineTo {
+                        abs: true,
+                        y: snap(matrix.d * y + matrix.f),
+                    }
+                }
+                PathSegment::LineTo { .. }
+                | PathSegment::HorizontalLineTo { .. }
+                | PathSegment::VerticalLineTo { .. } => {
+                    let (x, y) = end_point(&segment, current, start);
+                    let (x, y) = point(x, y);
+                    PathSegment::LineTo { abs: true, x, y }
+                }
+                PathSegment::CurveTo {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                    ..
+                } => {
+                    let ((x1, y1), (x2, y2), (x, y)) = (point(x1, y1), point(x2, y2), point(x, y));
+                    PathSegment::CurveTo {
+                        abs: true,
+                        x1,
+                        y1,
+                        x2,
+                        y2,
+                        x,
+                        y,
+                    }
+                }
+                PathSegment::SmoothCurveTo { x2, y2, x, y, .. } => {
+                    let ((x2, y2), (x, y)) = (point(x2, y2), point(x, y));
+                    PathSegment::SmoothCurveTo {
+                        abs: true,
+                        x2,
+                        y2,
+                        x,
+                        y,
+                    }
+                }
+                PathSegment::Quadratic { x1, y1, x, y, .. } => {
+                    let ((x1, y1), (x, y)) = (point(x1, y1), point(x, y));
+                    PathSegment::Quadratic {
+                        abs: true,
+                        x1,
+                        y1,
+                        x,
+                        y,
+                    }
+                }
+                PathSegment::SmoothQuadratic { x, y, .. } => {
+                    let (x, y) = point(x, y);
+                    PathSegment::SmoothQuadratic { abs: true, x, y }
+                }
+                PathSegment::ArcTo {
+                    rx,
+                    ry,
+                    x_axis_rotation,
+                    large_arc,
+                    sweep,
+                    x,
+                    y,
+                    ..
+                } => {
+                    let (rx, ry, x_axis_rotation) = transform_arc(matrix, rx, ry, x_axis_rotation);
+                    let (x, y) = point(x, y);
+                    PathSegment::ArcTo {
+                        abs: true,
+                        rx,
+                        ry,
+                        x_axis_rotation,
+                        large_arc,
+                        sweep: sweep != flip,
+                        x,
+                        y,
+                    }
+                }
+                PathSegment::ClosePath { .. } => segment,
+            };
+            if let PathSegment::MoveTo { x, y, .. } = segment {
+                start = (x, y);
+            }
+            current = end_point(&segment, current, start);
+            segments.push(transformed);
+        }
+        PathData { segments }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bake::transform_arc;
+    use crate::{bake_transforms, parse_document, stringify, BakeError, Matrix, PathData};
+
+    fn bake(source: &str) -> String {
+        let (_, document) = parse_document(source).unwrap();
+        bake_transforms(&document).unwrap();
+        stringify(document.root.clone())
+    }
+
+    #[test]
+    fn test_transform_arc() {
+        assert_eq!(
+            transform_arc(&Matrix::IDENTITY, 2.0, 1.0, 0.0),
+            (2.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            transform_arc(&Matrix::rotate(90.0), 2.0, 1.0, 0.0),
+            (2.0, 1.0, 90.0)
+        );
+        assert_eq!(
+            transform_arc(&Matrix::scale(3.0, 1.0), 1.0, 1.0, 0.0).0,
+            3.0
+        );
+        let (rx, ry, _) = transform_arc(&Matrix::scale(2.0, 2.0), 2.0, 1.0, 30.0);
+        assert_eq!((rx, ry), (4.0, 2.0));
+    }
+
+    #[test]
+    fn test_transformed_path() {
+        let path = PathData::parse("M1 1 H5 V5 Q1 1 0 0 A1 1 0 0 1 2 2 Z").unwrap();
+        assert_eq!(
+            path.transformed(&Matrix::translate(1.0, 2.0)).to_string(),
+            "M2 3H6V7Q2 3 1 2A1 1 0 013 4Z"
+        );
+        assert_eq!(
+            path.transformed(&Matrix::scale(-1.0, 1.0)).to_string(),
+            "M-1 1H-5V5Q-1 1 0 0A1 1 0 00-2 2Z"
+        );
+        assert_eq!(
+            path.transformed(&Matrix::skew_x(45.0)).to_string(),
+            "M2 1 6 1 10 5Q2 1 0 0A1.618033989.618033989 31.717474411 014 2Z"
+        );
+    }
+
+    #[test]
+    fn test_bake_shapes() {
+        assert_eq!(
+            bake(
+                r#"<svg><g transform="rotate(90)"><circle cx="1" r="1"/><ellipse cx="1" rx="2" ry="1"/><line x2="1"/></g></svg>"#
+            ),
+            r#"<svg><g><circle cx="0" cy="1" r="1"/><path d="M0 3A2 1 90 010-1 2 1 90 010 3Z"/><line x1="0" x2="0" y1="0" y2="1"/></g></svg>"#
+        );
+        assert_eq!(
+            bake(
+                r#"<svg transform="scale(2 1)"><rect x="1" width="2" height="2" rx="1"/><polygon points="0,0 1,1"/><circle r="1"/></svg>"#
+            ),
+            r#"<svg><rect height="2" rx="2" ry="1" width="4" x="2" y="0"/><polygon points="0,0 2,1"/><path d="M2 0A2 1 0 01-2 0 2 1 0 012 0Z"/></svg>"#
+        );
+    }
+
+    #[test]
+    fn test_bake_stroke_and_gradient() {
+        assert_eq!(
+            bake(
+                r##"<svg><defs><linearGradient id="g" gradientUnits="userSpaceOnUse" x1="0" y1="0" x2="1" y2="0"/></defs><g stroke="#000" stroke-dasharray="1 2" transform="scale(3)"><path d="M0 0 1 1" fill="url(#g)"/></g></svg>"##
+            ),
+            r##"<svg><defs><linearGradient gradientUnits="userSpaceOnUse" id="g" x1="0" x2="1" y1="0" y2="0"/><linearGradient gradientUnits="userSpaceOnUse" id="g-1" x1="0" x2="3" y1="0" y2="0"/></defs><g stroke="#000" stroke-dasharray="1 2"><path d="M0 0 3 3" fill="url(#g-1)" stroke-dasharray="3 6" stroke-width="3"/></g></svg>"##
+        );
+        // the gradient keeps a transform which its coordinates can not hold
+        assert_eq!(
+            bake(
+                r##"<svg><linearGradient id="g" gradientUnits="userSpaceOnUse"/><path d="M0 0" fill="url(#g)" transform="scale(2 1)"/></svg>"##
+            ),
+            r##"<svg><linearGradient gradientUnits="userSpaceOnUse" id="g"/><path d="M0 0" fill="url(#g-1)"/><linearGradient gradientTransform="scale(2 1)" gradientUnits="userSpaceOnUse" id="g-1"/></svg>"##
+        );
+    }
+
+    #[test]
+    fn test_bake_keeps_transform() {
+        assert_eq!(
+            bake(
+                r##"<svg><g transform="translate(5)"><text transform="scale(2)"></text><g clip-path="url(#c)"><rect width="1" height="1"/></g><svg width="1"></svg><rect width="1%" height="1"/></g></svg>"##
+            ),
+            r##"<svg><g><text transform="matrix(2 0 0 2 5 0)"/><g clip-path="url(#c)" transform="translate(5)"><rect height="1" width="1"/></g><g transform="translate(5)"><svg width="1"/></g><rect height="1" transform="translate(5)" width="1%"/></g></svg>"##
+        );
+        let (_, document) =
+            parse_document(r#"<svg><g transform="scale(2)"><path d="M0 0 L"/></g></svg>"#).unwrap();
+        assert!(matches!(
+            bake_transforms(&document),
+            Err(BakeError::Path(_))
+        ));
+        assert_eq!(
+            stringify(document.root.clone()),
+            r#"<svg><g transform="scale(2)"><path d="M0 0 L"/></g></svg>"#
+        );
+    }
+}
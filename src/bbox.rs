@@ -0,0 +1,873 @@
+use std::f64::consts::PI;
+use std::rc::Rc;
+
+use crate::ast::Element;
+use crate::css::ComputedStyles;
+use crate::length::{Length, LengthContext, LengthDirection};
+use crate::number::parse_number;
+use crate::path::{PathData, PathSegment};
+use crate::path_normalize::CenterArc;
+use crate::shape::{number_or_zero, shape_path_data};
+use crate::style::Style;
+use crate::transform::Matrix;
+
+type Point = (f64, f64);
+
+/// A axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl BBox {
+    pub fn new(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> BBox {
+        BBox {
+            min_x,
+            min_y,
+            max_x,
+            max_y,
+        }
+    }
+
+    /// the smallest box around the points, `None` for no points
+    pub fn from_points(points: impl IntoIterator<Item = (f64, f64)>) -> Option<BBox> {
+        points.into_iter().fold(None, |bbox, (x, y)| {
+            let point = BBox::new(x, y, x, y);
+            Some(bbox.map_or(point, |bbox: BBox| bbox.union(&point)))
+        })
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+
+    /// the smallest box around both boxes
+    pub fn union(&self, other: &BBox) -> BBox {
+        BBox::new(
+            self.min_x.min(other.min_x),
+            self.min_y.min(other.min_y),
+            self.max_x.max(other.max_x),
+            self.max_y.max(other.max_y),
+        )
+    }
+
+    /// the box around the transformed corners of the box
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{BBox, Matrix};
+    ///
+    /// let bbox = BBox::new(0.0, 0.0, 10.0, 5.0);
+    /// assert_eq!(
+    ///     bbox.transformed(&(Matrix::translate(1.0, 1.0) * Matrix::scale(-1.0, 2.0))),
+    ///     BBox::new(-9.0, 1.0, 1.0, 11.0)
+    /// );
+    /// ```
+    ///
+    pub fn transformed(&self, matrix: &Matrix) -> BBox {
+        BBox::from_points(
+            [
+                (self.min_x, self.min_y),
+                (self.max_x, self.min_y),
+                (self.max_x, self.max_y),
+                (self.min_x, self.max_y),
+            ]
+            .map(|(x, y)| matrix.apply(x, y)),
+        )
+        .unwrap()
+    }
+}
+
+/// The shape at the corners of a stroke, `stroke-linejoin`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    MiterClip,
+    Round,
+    Bevel,
+    Arcs,
+}
+
+impl LineJoin {
+    /// parse a `stroke-linejoin` value
+    pub fn parse(value: &str) -> Option<LineJoin> {
+        match value.trim() {
+            "miter" => Some(LineJoin::Miter),
+            "miter-clip" => Some(LineJoin::MiterClip),
+            "round" => Some(LineJoin::Round),
+            "bevel" => Some(LineJoin::Bevel),
+            "arcs" => Some(LineJoin::Arcs),
+            _ => None,
+        }
+    }
+}
+
+/// The shape at the ends of a open subpath, `stroke-linecap`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+impl LineCap {
+    /// parse a `stroke-linecap` value
+    pub fn parse(value: &str) -> Option<LineCap> {
+        match value.trim() {
+            "butt" => Some(LineCap::Butt),
+            "round" => Some(LineCap::Round),
+            "square" => Some(LineCap::Square),
+            _ => None,
+        }
+    }
+}
+
+/// The geometry of a stroke
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stroke {
+    pub width: f64,
+    pub line_join: LineJoin,
+    pub line_cap: LineCap,
+    pub miter_limit: f64,
+}
+
+impl Default for Stroke {
+    /// the initial values, a width of `1`, miter joins, butt caps and a miter limit of `4`
+    fn default() -> Self {
+        Stroke {
+            width: 1.0,
+            line_join: LineJoin::default(),
+            line_cap: LineCap::default(),
+            miter_limit: 4.0,
+        }
+    }
+}
+
+/// A drawing segment of a subpath with its directions at both ends.
+/// the direction is `None` for a segment of zero length.
+struct Piece {
+    end: Point,
+    start_tangent: Option<Point>,
+    end_tangent: Option<Point>,
+    /// the points inside the segment where it turns on the x axis
+    x_extrema: Vec<Point>,
+    /// the points inside the segment where it turns on the y axis
+    y_extrema: Vec<Point>,
+}
+
+struct Subpath {
+    start: Point,
+    pieces: Vec<Piece>,
+    closed: bool,
+}
+
+fn normalize((x, y): Point) -> Option<Point> {
+    let length = x.hypot(y);
+    if length > 1e-12 {
+        Some((x / length, y / length))
+    } else {
+        None
+    }
+}
+
+fn line_piece(start: Point, end: Point) -> Piece {
+    let tangent = normalize((end.0 - start.0, end.1 - start.1));
+    Piece {
+        end,
+        start_tangent: tangent,
+        end_tangent: tangent,
+        x_extrema: vec![],
+        y_extrema: vec![],
+    }
+}
+
+/// the parameters in `(0, 1)` where `a t² + b t + c` is zero
+fn quadratic_roots(a: f64, b: f64, c: f64) -> Vec<f64> {
+    let roots = if a.abs() < 1e-12 {
+        if b.abs() < 1e-12 {
+            vec![]
+        } else {
+            vec![-c / b]
+        }
+    } else {
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            vec![]
+        } else {
+            let root = discriminant.sqrt();
+            vec![(-b + root) / (2.0 * a), (-b - root) / (2.0 * a)]
+        }
+    };
+    roots.into_iter().filter(|t| *t > 0.0 && *t < 1.0).collect()
+}
+
+fn cubic_piece(p0: Point, p1: Point, p2: Point, p3: Point) -> Piece {
+    let at = |t: f64| {
+        let mt = 1.0 - t;
+        let coordinate = |a: f64, b: f64, c: f64, d: f64| {
+            mt * mt * mt * a + 3.0 * mt * mt * t * b + 3.0 * mt * t * t * c + t * t * t * d
+        };
+        (
+            coordinate(p0.0, p1.0, p2.0, p3.0),
+            coordinate(p0.1, p1.1, p2.1, p3.1),
+        )
+    };
+    // the roots of the derivative, divided by 3
+    let extrema = |a: f64, b: f64, c: f64, d: f64| {
+        quadratic_roots(-a + 3.0 * b - 3.0 * c + d, 2.0 * (a - 2.0 * b + c), b - a)
+            .into_iter()
+            .map(at)
+            .collect()
+    };
+    let direction = |from: Point, to: Point| normalize((to.0 - from.0, to.1 - from.1));
+    Piece {
+        end: p3,
+        start_tangent: direction(p0, p1)
+            .or_else(|| direction(p0, p2))
+            .or_else(|| direction(p0, p3)),
+        end_tangent: direction(p2, p3)
+            .or_else(|| direction(p1, p3))
+            .or_else(|| direction(p0, p3)),
+        x_extrema: extrema(p0.0, p1.0, p2.0, p3.0),
+        y_extrema: extrema(p0.1, p1.1, p2.1, p3.1),
+    }
+}
+
+fn arc_piece(arc: &CenterArc, end: Point) -> Piece {
+    let (sin, cos) = arc.phi.sin_cos();
+    // the angles where the derivative of x or y is zero, one of each pair of opposite angles
+    let x_angle = (-arc.ry * sin).atan2(arc.rx * cos);
+    let y_angle = (arc.ry * cos).atan2(arc.rx * sin);
+    let inside = |angle: f64| {
+        let offset = ((angle - arc.theta) * arc.delta.signum()).rem_euclid(2.0 * PI);
+        offset > 0.0 && offset < arc.delta.abs()
+    };
+    let extrema = |angle: f64| {
+        [angle, angle + PI]
+            .into_iter()
+            .filter(|angle| inside(*angle))
+            .map(|angle| arc.point(angle))
+            .collect()
+    };
+    let tangent = |t: f64| {
+        let (x, y) = arc.derivative(t);
+        normalize((x * arc.delta.signum(), y * arc.delta.signum()))
+    };
+    Piece {
+        end,
+        start_tangent: tangent(arc.theta),
+        end_tangent: tangent(arc.theta + arc.delta),
+        x_extrema: extrema(x_angle),
+        y_extrema: extrema(y_angle),
+    }
+}
+
+/// split the path into subpaths of drawing segments, exact curves and arcs
+fn subpaths(path: &PathData) -> Vec<Subpath> {
+    let mut subpaths: Vec<Subpath> = vec![];
+    let mut current = (0.0, 0.0);
+    for segment in path.simplified(None).segments {
+        let (piece, end) = match segment {
+            PathSegment::MoveTo { x, y, .. } => {
+                current = (x, y);
+                subpaths.push(Subpath {
+                    start: current,
+                    pieces: vec![],
+                    closed: false,
+                });
+                continue;
+            }
+            PathSegment::LineTo { x, y, .. } => (line_piece(current, (x, y)), (x, y)),
+            PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+                ..
+            } => (cubic_piece(current, (x1, y1), (x2, y2), (x, y)), (x, y)),
+            PathSegment::ArcTo {
+                rx,
+                ry,
+                x_axis_rotation,
+                large_arc,
+                sweep,
+                x,
+                y,
+                ..
+            } => {
+                let end = (x, y);
+                match CenterArc::from_endpoints(
+                    current,
+                    (rx, ry),
+                    x_axis_rotation,
+                    large_arc,
+                    sweep,
+                    end,
+                ) {
+                    Some(arc) => (arc_piece(&arc, end), end),
+                    None => (line_piece(current, end), end),
+                }
+            }
+            PathSegment::ClosePath { .. } => {
+                let Some(subpath) = subpaths.last_mut() else {
+                    continue;
+                };
+                let start = subpath.start;
+                subpath.pieces.push(line_piece(current, start));
+                subpath.closed = true;
+                // a segment after a close starts a new subpath at the same point
+                current = start;
+                subpaths.push(Subpath {
+                    start,
+                    pieces: vec![],
+                    closed: false,
+                });
+                continue;
+            }
+            _ => unreachable!("simplified paths only have M, L, C, A and Z"),
+        };
+        // a path must start with a move, but start a subpath anyway
+        if subpaths.is_empty() {
+            subpaths.push(Subpath {
+                start: current,
+                pieces: vec![],
+                closed: false,
+            });
+        }
+        subpaths.last_mut().unwrap().pieces.push(piece);
+        current = end;
+    }
+    subpaths.retain(|subpath| !subpath.pieces.is_empty());
+    subpaths
+}
+
+fn fill_points(subpaths: &[Subpath]) -> Vec<Point> {
+    let mut points = vec![];
+    for subpath in subpaths {
+        points.push(subpath.start);
+        for piece in &subpath.pieces {
+            points.push(piece.end);
+            points.extend(&piece.x_extrema);
+            points.extend(&piece.y_extrema);
+        }
+    }
+    points
+}
+
+fn stroke_points(subpaths: &[Subpath], stroke: &Stroke) -> Vec<Point> {
+    let half = stroke.width / 2.0;
+    let mut points = vec![];
+    let circle = |points: &mut Vec<Point>, (x, y): Point| {
+        points.extend([(x - half, y - half), (x + half, y + half)]);
+    };
+    let sides = |points: &mut Vec<Point>, (x, y): Point, tangent: Option<Point>| {
+        if let Some((tx, ty)) = tangent {
+            points.extend([
+                (x - ty * half, y + tx * half),
+                (x + ty * half, y - tx * half),
+            ]);
+        }
+    };
+    for subpath in subpaths {
+        let mut start = subpath.start;
+        // the ends of the segments with a direction, for the joins and caps
+        let mut ends: Vec<(Point, Point, Point)> = vec![];
+        for piece in &subpath.pieces {
+            points.extend(
+                piece
+                    .x_extrema
+                    .iter()
+                    .flat_map(|&(x, y)| [(x - half, y), (x + half, y)]),
+            );
+            points.extend(
+                piece
+                    .y_extrema
+                    .iter()
+                    .flat_map(|&(x, y)| [(x, y - half), (x, y + half)]),
+            );
+            sides(&mut points, start, piece.start_tangent);
+            sides(&mut points, piece.end, piece.end_tangent);
+            if let (Some(start_tangent), Some(end_tangent)) =
+                (piece.start_tangent, piece.end_tangent)
+            {
+                ends.push((start, start_tangent, end_tangent));
+            }
+            start = piece.end;
+        }
+        let Some(&(first, first_tangent, _)) = ends.first() else {
+            // a subpath without length is a dot for round and square caps
+            if stroke.line_cap != LineCap::Butt {
+                circle(&mut points, subpath.start);
+            }
+            continue;
+        };
+        let mut joins: Vec<(Point, Point, Point)> = ends
+            .windows(2)
+            .map(|pair| (pair[1].0, pair[0].2, pair[1].1))
+            .collect();
+        let last_tangent = ends.last().unwrap().2;
+        if subpath.closed {
+            joins.push((first, last_tangent, first_tangent));
+        } else {
+            let end = subpath.pieces.last().unwrap().end;
+            for ((x, y), (tx, ty)) in [
+                (first, (-first_tangent.0, -first_tangent.1)),
+                (end, last_tangent),
+            ] {
+                match stroke.line_cap {
+                    LineCap::Butt => {}
+                    LineCap::Round => circle(&mut points, (x, y)),
+                    LineCap::Square => {
+                        let (x, y) = (x + tx * half, y + ty * half);
+                        sides(&mut points, (x, y), Some((tx, ty)));
+                    }
+                }
+            }
+        }
+        for ((x, y), incoming, outgoing) in joins {
+            match stroke.line_join {
+                LineJoin::Bevel => {}
+                LineJoin::Round => circle(&mut points, (x, y)),
+                LineJoin::Miter | LineJoin::MiterClip | LineJoin::Arcs => {
+                    let dot = incoming.0 * outgoing.0 + incoming.1 * outgoing.1;
+                    // the ratio of the miter length to the stroke width
+                    let ratio = 1.0 / ((1.0 + dot) / 2.0).sqrt();
+                    let outside = normalize((incoming.0 - outgoing.0, incoming.1 - outgoing.1));
+                    match outside {
+                        Some((bx, by)) if ratio <= stroke.miter_limit => {
+                            points.push((x + bx * half * ratio, y + by * half * ratio))
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    points
+}
+
+impl PathData {
+    /// the exact bounding box of the fill of the path, with the extrema of
+    /// curves and arcs. `None` for a path which draws nothing.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{BBox, PathData};
+    ///
+    /// let path = PathData::parse("M0 0 Q5 10 10 0 A5 5 0 0 0 0 0").unwrap();
+    /// assert_eq!(path.bbox(), Some(BBox::new(0.0, -5.0, 10.0, 5.0)));
+    /// ```
+    ///
+    pub fn bbox(&self) -> Option<BBox> {
+        BBox::from_points(fill_points(&subpaths(self)))
+    }
+
+    /// the bounding box of the area painted by a stroke of the path
+    ///
+    /// curves and arcs grow by half the width where they turn, miter joins up
+    /// to the miter limit, and caps follow `line_cap`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{BBox, LineCap, PathData, Stroke};
+    ///
+    /// let path = PathData::parse("M0 0 H10").unwrap();
+    /// let stroke = Stroke { width: 2.0, ..Default::default() };
+    /// assert_eq!(path.stroke_bbox(&stroke), Some(BBox::new(0.0, -1.0, 10.0, 1.0)));
+    /// let stroke = Stroke { line_cap: LineCap::Square, ..stroke };
+    /// assert_eq!(path.stroke_bbox(&stroke), Some(BBox::new(-1.0, -1.0, 11.0, 1.0)));
+    /// ```
+    ///
+    pub fn stroke_bbox(&self, stroke: &Stroke) -> Option<BBox> {
+        BBox::from_points(stroke_points(&subpaths(self), stroke))
+    }
+}
+
+/// elements whose children are rendered in their user space
+pub(crate) const CONTAINERS: [&str; 4] = ["a", "g", "svg", "switch"];
+
+/// whether the transform keeps angles, so a stroke is only scaled
+fn is_similarity(m: &Matrix) -> bool {
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+    (close(m.a, m.d) && close(m.b, -m.c)) || (close(m.a, -m.d) && close(m.b, m.c))
+}
+
+/// the bounding box of a element in the coordinates given by `matrix`, with
+/// the stroke of its shapes if `stroked`
+pub(crate) fn element_bbox<'a>(
+    ele: &Rc<Element<'a>>,
+    matrix: &Matrix,
+    styles: &ComputedStyles<'a>,
+    stroked: bool,
+) -> Option<BBox> {
+    let style = styles.style(ele);
+    if style.get("display").map(str::trim) == Some("none") {
+        return None;
+    }
+    if CONTAINERS.contains(&ele.ele_type) {
+        return ele
+            .children
+            .borrow()
+            .iter()
+            .filter_map(|child| {
+                let mut m = *matrix * child.transform().unwrap_or_default().to_matrix();
+                if child.ele_type == "svg" {
+                    m = m * child
+                        .viewport_transform(&LengthContext::default())
+                        .unwrap_or_default();
+                }
+                element_bbox(child, &m, styles, stroked)
+            })
+            .reduce(|a, b| a.union(&b));
+    }
+    if ele.ele_type == "image" {
+        let number = |key| number_or_zero(ele, key);
+        let (x, y) = (number("x")?, number("y")?);
+        let (width, height) = (number("width")?, number("height")?);
+        if width <= 0.0 || height <= 0.0 {
+            return None;
+        }
+        return Some(BBox::new(x, y, x + width, y + height).transformed(matrix));
+    }
+    let path = match ele.ele_type {
+        "path" => ele.path_data().ok()?,
+        _ => shape_path_data(ele)?,
+    };
+    let stroke = if stroked { style_stroke(&style) } else { None };
+    match stroke {
+        Some(stroke) if is_similarity(matrix) => {
+            let scale = matrix.determinant().abs().sqrt();
+            let stroke = Stroke {
+                width: stroke.width * scale,
+                ..stroke
+            };
+            path.transformed(matrix).stroke_bbox(&stroke)
+        }
+        // the stroke is distorted, so bound the box of the untransformed stroke
+        Some(stroke) => path
+            .stroke_bbox(&stroke)
+            .map(|bbox| bbox.transformed(matrix)),
+        None => path.transformed(matrix).bbox(),
+    }
+}
+
+/// the stroke of a computed style, `None` if there is no stroke or its width
+/// is not positive
+pub(crate) fn style_stroke(style: &Style) -> Option<Stroke> {
+    let paint = style.get("stroke")?;
+    if paint.trim() == "none" {
+        return None;
+    }
+    let default = Stroke::default();
+    let width = style
+        .get("stroke-width")
+        .and_then(|value| Length::parse(value).ok())
+        .map_or(default.width, |length| {
+            length.to_user_units(&LengthContext::default(), LengthDirection::Other)
+        });
+    if width <= 0.0 {
+        return None;
+    }
+    Some(Stroke {
+        width,
+        line_join: style
+            .get("stroke-linejoin")
+            .and_then(LineJoin::parse)
+            .unwrap_or(default.line_join),
+        line_cap: style
+            .get("stroke-linecap")
+            .and_then(LineCap::parse)
+            .unwrap_or(default.line_cap),
+        miter_limit: style
+            .get("stroke-miterlimit")
+            .and_then(parse_number)
+            .filter(|limit| *limit >= 1.0)
+            .unwrap_or(default.miter_limit),
+    })
+}
+
+/// the dashes and the dash offset of a computed style in user units, see
+/// `Element::stroke_dashes`
+pub(crate) fn style_dashes(style: &Style, context: &LengthContext) -> Option<(Vec<f64>, f64)> {
+    let length = |value: &str| {
+        Length::parse(value)
+            .ok()
+            .map(|length| length.to_user_units(context, LengthDirection::Other))
+    };
+    let value = style.get("stroke-dasharray")?;
+    let mut dashes = value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(length)
+        .collect::<Option<Vec<f64>>>()?;
+    if dashes.iter().any(|dash| *dash < 0.0) || dashes.iter().sum::<f64>() <= 0.0 {
+        return None;
+    }
+    if dashes.len() % 2 == 1 {
+        dashes.extend(dashes.clone());
+    }
+    let offset = style
+        .get("stroke-dashoffset")
+        .and_then(length)
+        .unwrap_or(0.0);
+    Some((dashes, offset))
+}
+
+impl<'a> Element<'a> {
+    /// the stroke of the element from its computed style, with the rules of the
+    /// `<style>` elements of its tree, `None` if there is no stroke or its width
+    /// is not positive
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, LineJoin, Stroke};
+    ///
+    /// let (_, root) = parse(r#"<g stroke="red" stroke-linejoin="round"><path stroke-width="2"/></g>"#).unwrap();
+    /// let path = root.children.borrow()[0].clone();
+    /// assert_eq!(
+    ///     path.stroke(),
+    ///     Some(Stroke { width: 2.0, line_join: LineJoin::Round, ..Default::default() })
+    /// );
+    /// assert_eq!(root.stroke().unwrap().width, 1.0);
+    /// ```
+    ///
+    pub fn stroke(self: &Rc<Self>) -> Option<Stroke> {
+        style_stroke(&ComputedStyles::of_tree(self).style(self))
+    }
+
+    /// the `stroke-dasharray` and `stroke-dashoffset` of the computed style in
+    /// user units, `None` for a solid stroke. a odd list is repeated, a negative
+    /// or all zero list is ignored.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, LengthContext};
+    ///
+    /// let (_, root) = parse(r#"<g stroke-dasharray="1 2, 3" style="stroke-dashoffset:1em"><path/></g>"#).unwrap();
+    /// let path = root.children.borrow()[0].clone();
+    /// assert_eq!(
+    ///     path.stroke_dashes(&LengthContext::default()),
+    ///     Some((vec![1.0, 2.0, 3.0, 1.0, 2.0, 3.0], 16.0))
+    /// );
+    /// ```
+    ///
+    pub fn stroke_dashes(self: &Rc<Self>, context: &LengthContext) -> Option<(Vec<f64>, f64)> {
+        style_dashes(&ComputedStyles::of_tree(self).style(self), context)
+    }
+
+    /// the bounding box of the geometry of the element in its user space,
+    /// without its own transform
+    ///
+    /// the box of a path or basic shape is exact, the box of a `image` is its
+    /// viewport, the box of a `g`, `svg`, `a` or `switch` is the union of its
+    /// children under their transforms.
+    /// `None` for other elements, a element with `display="none"` or no geometry.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, BBox};
+    ///
+    /// let (_, root) = parse(r#"<g><circle r="5"/><rect x="10" width="10" height="10" transform="rotate(90)"/></g>"#).unwrap();
+    /// assert_eq!(root.bbox(), Some(BBox::new(-10.0, -5.0, 5.0, 20.0)));
+    /// ```
+    ///
+    pub fn bbox(self: &Rc<Self>) -> Option<BBox> {
+        element_bbox(
+            self,
+            &Matrix::default(),
+            &ComputedStyles::of_tree(self),
+            false,
+        )
+    }
+
+    /// the bounding box of the painted area of the element, like `Element::bbox`
+    /// but including the stroke of each shape, see `Element::stroke`
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, BBox};
+    ///
+    /// let (_, root) = parse(r#"<g stroke="red" stroke-width="2"><rect width="10" height="10"/></g>"#).unwrap();
+    /// assert_eq!(root.stroke_bbox(), Some(BBox::new(-1.0, -1.0, 11.0, 11.0)));
+    /// ```
+    ///
+    pub fn stroke_bbox(self: &Rc<Self>) -> Option<BBox> {
+        element_bbox(
+            self,
+            &Matrix::default(),
+            &ComputedStyles::of_tree(self),
+            true,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, BBox, LineCap, LineJoin, PathData, Stroke};
+
+    fn close(a: BBox, b: BBox) -> bool {
+        [
+            (a.min_x, b.min_x),
+            (a.min_y, b.min_y),
+            (a.max_x, b.max_x),
+            (a.max_y, b.max_y),
+        ]
+        .iter()
+        .all(|(a, b)| (a - b).abs() < 1e-9)
+    }
+
+    fn bbox(d: &str) -> BBox {
+        PathData::parse(d).unwrap().bbox().unwrap()
+    }
+
+    #[test]
+    fn test_path_bbox() {
+        assert_eq!(bbox("M1 2 l3 4 h-10 v10"), BBox::new(-6.0, 2.0, 4.0, 16.0));
+        // the extremum of the cubic is at t = 0.5
+        assert!(close(
+            bbox("M0 0 C0 10 10 10 10 0"),
+            BBox::new(0.0, 0.0, 10.0, 7.5)
+        ));
+        assert!(close(
+            bbox("M0 0 S5 -10 10 0"),
+            BBox::new(0.0, -40.0 / 9.0, 10.0, 0.0)
+        ));
+        // a quarter of a circle, and the rest of it
+        assert!(close(
+            bbox("M10 0 A10 10 0 0 1 0 10"),
+            BBox::new(0.0, 0.0, 10.0, 10.0)
+        ));
+        assert!(close(
+            bbox("M10 0 A10 10 0 1 0 0 10"),
+            BBox::new(-10.0, -10.0, 10.0, 10.0)
+        ));
+        // a rotated ellipse reaches sqrt(50 + 12.5)
+        let extent = 62.5_f64.sqrt();
+        let end = 50.0_f64.sqrt();
+        let ellipse = format!("M{end} {end} A10 5 45 0 0 -{end} -{end} A10 5 45 0 0 {end} {end}");
+        assert!(close(
+            bbox(&ellipse),
+            BBox::new(-extent, -extent, extent, extent)
+        ));
+        assert_eq!(PathData::parse("M1 1").unwrap().bbox(), None);
+    }
+
+    #[test]
+    fn test_stroke_bbox() {
+        let stroke_bbox =
+            |d: &str, stroke: Stroke| PathData::parse(d).unwrap().stroke_bbox(&stroke).unwrap();
+        let stroke = Stroke {
+            width: 2.0,
+            ..Default::default()
+        };
+        // the miter of a right angle reaches sqrt(2) times the half width
+        assert!(close(
+            stroke_bbox("M0 0 10 0 10 10", stroke),
+            BBox::new(0.0, -1.0, 11.0, 10.0)
+        ));
+        // a sharp angle is beveled beyond the miter limit
+        let sharp = "M0 0 10 1 0 2";
+        assert!(
+            stroke_bbox(
+                sharp,
+                Stroke {
+                    miter_limit: 20.0,
+                    ..stroke
+                }
+            )
+            .max_x
+                > 15.0
+        );
+        let bevel = stroke_bbox(
+            sharp,
+            Stroke {
+                miter_limit: 1.0,
+                ..stroke
+            },
+        );
+        assert!(bevel.max_x < 10.2);
+        let round = stroke_bbox(
+            sharp,
+            Stroke {
+                line_join: LineJoin::Round,
+                ..stroke
+            },
+        );
+        assert!((round.max_x - 11.0).abs() < 1e-9);
+        assert!(close(
+            stroke_bbox(
+                "M0 0 10 0",
+                Stroke {
+                    line_cap: LineCap::Round,
+                    ..stroke
+                }
+            ),
+            BBox::new(-1.0, -1.0, 11.0, 1.0)
+        ));
+        assert!(close(
+            stroke_bbox("M0 0 H10 V10 H0 Z", stroke),
+            BBox::new(-1.0, -1.0, 11.0, 11.0)
+        ));
+        // a dot is painted with round and square caps only
+        let dot = "M5 5 Z";
+        assert_eq!(PathData::parse(dot).unwrap().stroke_bbox(&stroke), None);
+        assert!(close(
+            stroke_bbox(
+                dot,
+                Stroke {
+                    line_cap: LineCap::Square,
+                    ..stroke
+                }
+            ),
+            BBox::new(4.0, 4.0, 6.0, 6.0)
+        ));
+        // a circle grows by the half width everywhere
+        assert!(close(
+            stroke_bbox("M10 0 A10 10 0 0 1 -10 0 A10 10 0 0 1 10 0", stroke),
+            BBox::new(-11.0, -11.0, 11.0, 11.0)
+        ));
+    }
+
+    #[test]
+    fn test_element_bbox() {
+        let (_, root) = parse(
+            r#"<svg><defs><rect width="100" height="100"/></defs><g transform="translate(10 10) scale(2)"><line x1="0" y1="0" x2="5" y2="0" stroke="red" stroke-width="2"/><ellipse rx="1" ry="2" display="none"/></g><text>a</text></svg>"#,
+        )
+        .unwrap();
+        assert_eq!(root.bbox(), Some(BBox::new(10.0, 10.0, 20.0, 10.0)));
+        assert_eq!(root.stroke_bbox(), Some(BBox::new(10.0, 8.0, 20.0, 12.0)));
+        // a skew distorts the stroke, its box is bounded
+        let (_, root) =
+            parse(r#"<g><rect width="10" height="10" stroke="red" transform="skewX(45)"/></g>"#)
+                .unwrap();
+        assert_eq!(root.bbox(), Some(BBox::new(0.0, 0.0, 20.0, 10.0)));
+        assert_eq!(root.stroke_bbox(), Some(BBox::new(-1.0, -0.5, 21.0, 10.5)));
+        let (_, root) = parse(r#"<use href="a"/>"#).unwrap();
+        assert_eq!(root.bbox(), None);
+    }
+
+    #[test]
+    fn test_styled_stroke_bbox() {
+        let (_, rect) =
+            parse(r#"<rect width="10" height="10" style="stroke:red;stroke-width:4"/>"#).unwrap();
+        assert_eq!(rect.stroke_bbox(), Some(BBox::new(-2.0, -2.0, 12.0, 12.0)));
+        let (_, root) = parse(
+            r#"<svg><style>.a{stroke:red;stroke-width:4} .hidden{display:none}</style><rect class="a" width="10" height="10"/><circle class="hidden" r="50"/></svg>"#,
+        )
+        .unwrap();
+        assert_eq!(root.stroke_bbox(), Some(BBox::new(-2.0, -2.0, 12.0, 12.0)));
+        let rect = root.children.borrow()[1].clone();
+        assert_eq!(rect.stroke().unwrap().width, 4.0);
+    }
+}
@@ -0,0 +1,238 @@
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::rc::Rc;
+
+use crate::ast::Element;
+
+/// A fluent builder of a element, see `Element::build`
+#[derive(Debug)]
+pub struct ElementBuilder<'a> {
+    element: Rc<Element<'a>>,
+}
+
+impl<'a> Element<'a> {
+    /// start to build a element with a fluent builder
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use svg_simple_parser::{stringify, Element};
+    ///
+    /// let root = Element::build("svg")
+    ///     .attr("width", 100)
+    ///     .child(Element::build("rect").attr("x", 10).attr("fill", "red"))
+    ///     .build();
+    /// assert_eq!(stringify(root), r#"<svg width="100"><rect fill="red" x="10"/></svg>"#);
+    /// ```
+    ///
+    pub fn build(ele_type: &'a str) -> ElementBuilder<'a> {
+        ElementBuilder {
+            element: Element::new((ele_type, Default::default())),
+        }
+    }
+}
+
+impl<'a> ElementBuilder<'a> {
+    /// set a attribute, the value is formatted with `Display`
+    pub fn attr<V: Display>(self, key: &str, value: V) -> Self {
+        self.element
+            .set_attribute(key, Cow::Owned(value.to_string()));
+        self
+    }
+
+    /// set a list of attributes
+    pub fn attrs<K, V, I>(self, attributes: I) -> Self
+    where
+        K: AsRef<str>,
+        V: Display,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        attributes.into_iter().fold(self, |builder, (key, value)| {
+            builder.attr(key.as_ref(), value)
+        })
+    }
+
+    /// add a child, either a element or another builder
+    pub fn child<C: Into<Rc<Element<'a>>>>(self, child: C) -> Self {
+        self.element.add_child(child.into());
+        self
+    }
+
+    /// add a list of children
+    pub fn children<C, I>(self, children: I) -> Self
+    where
+        C: Into<Rc<Element<'a>>>,
+        I: IntoIterator<Item = C>,
+    {
+        children
+            .into_iter()
+            .fold(self, |builder, child| builder.child(child))
+    }
+
+    /// finish the element
+    pub fn build(self) -> Rc<Element<'a>> {
+        self.element
+    }
+}
+
+impl<'a> From<ElementBuilder<'a>> for Rc<Element<'a>> {
+    fn from(builder: ElementBuilder<'a>) -> Self {
+        builder.build()
+    }
+}
+
+/// fail the constant evaluation if a closing tag of `svg!` does not match
+#[doc(hidden)]
+pub const fn check_closing_tag(open: &str, close: &str) {
+    let (open, close) = (open.as_bytes(), close.as_bytes());
+    let mut same = open.len() == close.len();
+    let mut i = 0;
+    while same && i < open.len() {
+        same = open[i] == close[i];
+        i += 1;
+    }
+    if !same {
+        panic!("svg!: the closing tag does not match the opening tag");
+    }
+}
+
+/// build a element tree with a svg-like syntax
+///
+/// attribute values are literals or rust expressions in braces, children can
+/// also be expressions in braces returning a `Rc<Element>` or a `ElementBuilder`.
+/// a closing tag which does not match its opening tag is a compile error.
+///
+/// ## Example
+///
+/// ``` rust
+/// use svg_simple_parser::{stringify, svg};
+///
+/// let x = 10;
+/// let dot = svg! { <circle r=2/> };
+/// let root = svg! {
+///     <svg width=100 xlink:href="#a">
+///         <rect x={x * 2} y=-5 stroke-width=1.5/>
+///         <g>{dot}</g>
+///     </svg>
+/// };
+/// assert_eq!(
+///     stringify(root),
+///     r##"<svg width="100" xlink:href="#a"><rect stroke-width="1.5" x="20" y="-5"/><g><circle r="2"/></g></svg>"##
+/// );
+/// ```
+///
+/// ``` compile_fail
+/// use svg_simple_parser::svg;
+///
+/// let root = svg! { <svg><g></svg></g> };
+/// ```
+#[macro_export]
+macro_rules! svg {
+    ($($tokens:tt)+) => {
+        $crate::__svg!(@parse [] $($tokens)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __svg {
+    // closing tag of the element on the top of the stack
+    (@parse [($open:ident ($($builder:tt)*)) $($stack:tt)*] < / $close:ident > $($rest:tt)*) => {
+        $crate::__svg!(@done [$($stack)*] ({
+            const _: () = $crate::check_closing_tag(stringify!($open), stringify!($close));
+            $($builder)*
+        }) $($rest)*)
+    };
+    // opening tag
+    (@parse [$($stack:tt)*] < $name:ident $($rest:tt)*) => {
+        $crate::__svg!(@attrs [$($stack)*] $name ($crate::Element::build(stringify!($name))) $($rest)*)
+    };
+    // child expression
+    (@parse [($open:ident ($($builder:tt)*)) $($stack:tt)*] { $child:expr } $($rest:tt)*) => {
+        $crate::__svg!(@parse [($open ($($builder)*.child($child))) $($stack)*] $($rest)*)
+    };
+
+    // attributes
+    (@attrs $stack:tt $name:ident ($($builder:tt)*) $ns:ident : $key:ident $(- $more:ident)* = $($rest:tt)*) => {
+        $crate::__svg!(@value $stack $name ($($builder)*)
+            (concat!(stringify!($ns), ":", stringify!($key) $(, "-", stringify!($more))*)) $($rest)*)
+    };
+    (@attrs $stack:tt $name:ident ($($builder:tt)*) $key:ident $(- $more:ident)* = $($rest:tt)*) => {
+        $crate::__svg!(@value $stack $name ($($builder)*)
+            (concat!(stringify!($key) $(, "-", stringify!($more))*)) $($rest)*)
+    };
+    // self closing element
+    (@attrs $stack:tt $name:ident ($($builder:tt)*) / > $($rest:tt)*) => {
+        $crate::__svg!(@done $stack ($($builder)*) $($rest)*)
+    };
+    // element with children
+    (@attrs [$($stack:tt)*] $name:ident ($($builder:tt)*) > $($rest:tt)*) => {
+        $crate::__svg!(@parse [($name ($($builder)*)) $($stack)*] $($rest)*)
+    };
+
+    // attribute values
+    (@value $stack:tt $name:ident ($($builder:tt)*) ($key:expr) { $value:expr } $($rest:tt)*) => {
+        $crate::__svg!(@attrs $stack $name ($($builder)*.attr($key, $value)) $($rest)*)
+    };
+    (@value $stack:tt $name:ident ($($builder:tt)*) ($key:expr) - $value:literal $($rest:tt)*) => {
+        $crate::__svg!(@attrs $stack $name ($($builder)*.attr($key, -$value)) $($rest)*)
+    };
+    (@value $stack:tt $name:ident ($($builder:tt)*) ($key:expr) $value:literal $($rest:tt)*) => {
+        $crate::__svg!(@attrs $stack $name ($($builder)*.attr($key, $value)) $($rest)*)
+    };
+
+    // a finished element is the root, or a child of the top of the stack
+    (@done [] ($($builder:tt)*)) => {
+        $($builder)*.build()
+    };
+    (@done [($open:ident ($($parent:tt)*)) $($stack:tt)*] ($($builder:tt)*) $($rest:tt)*) => {
+        $crate::__svg!(@parse [($open ($($parent)*.child($($builder)*))) $($stack)*] $($rest)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{parse, Element};
+
+    #[test]
+    fn test_builder() {
+        let root = Element::build("svg")
+            .attrs([("width", "100"), ("height", "50")])
+            .children((0..3).map(|i| Element::build("rect").attr("x", i * 10)))
+            .child(Element::new((
+                "circle",
+                HashMap::from([("r".to_owned(), "5")]),
+            )))
+            .build();
+        let (_, expected) = parse(
+            r#"<svg width="100" height="50"><rect x="0"/><rect x="10"/><rect x="20"/><circle r="5"/></svg>"#,
+        )
+        .unwrap();
+        assert_eq!(root, expected);
+        let rect = root.children.borrow()[1].clone();
+        assert!(std::rc::Rc::ptr_eq(
+            &rect.parent.borrow().upgrade().unwrap(),
+            &root
+        ));
+    }
+
+    #[test]
+    fn test_svg_macro() {
+        let fill = "blue";
+        let items = Element::build("g").children((1..=2).map(|r| svg! { <circle r={r}/> }));
+        let root = svg! {
+            <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10">
+                <defs></defs>
+                <rect x=1 y=-2 fill={fill} stroke-dasharray="1 2"/>
+                {items}
+            </svg>
+        };
+        let (_, expected) = parse(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 10 10"><defs></defs><rect x="1" y="-2" fill="blue" stroke-dasharray="1 2"/><g><circle r="1"/><circle r="2"/></g></svg>"#,
+        )
+        .unwrap();
+        assert_eq!(root, expected);
+    }
+}
@@ -0,0 +1,555 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::ast::Element;
+use crate::bbox::{style_stroke, BBox};
+use crate::color::Rgba;
+use crate::css::ComputedStyles;
+use crate::document::Document;
+use crate::length::{Length, LengthContext};
+use crate::number::snap;
+use crate::paint::{property, resolve_length, Units};
+use crate::path::shortest_number;
+use crate::reference::{element_href, url_ids};
+use crate::shape::{number_or_zero, radii, SHAPES};
+use crate::transform::Matrix;
+
+/// How the inside of a path is decided, `fill-rule` or `clip-rule`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillRule {
+    #[default]
+    NonZero,
+    EvenOdd,
+}
+
+impl FillRule {
+    /// parse a `fill-rule` or `clip-rule` value
+    pub fn parse(value: &str) -> Option<FillRule> {
+        match value.trim() {
+            "nonzero" => Some(FillRule::NonZero),
+            "evenodd" => Some(FillRule::EvenOdd),
+            _ => None,
+        }
+    }
+}
+
+/// A shape of a `clipPath`
+///
+/// `element` is a path, basic shape or `text`, a `use` is replaced by the shape
+/// it references. `transform` maps the shape to the user space of the clip path,
+/// it includes the `x` and `y` of a `use`. `clip_path` is the clip path of the
+/// shape itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipShape<'a> {
+    pub element: Rc<Element<'a>>,
+    pub transform: Matrix,
+    pub clip_rule: FillRule,
+    pub clip_path: Option<ClipPath<'a>>,
+}
+
+/// A resolved `clipPath`
+///
+/// the clipped area is the union of the `shapes`, intersected with the area of
+/// `clip_path`, the clip path of the `clipPath` element. `transform` is the
+/// `transform` of the `clipPath`, applied before the `units`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipPath<'a> {
+    pub element: Rc<Element<'a>>,
+    pub units: Units,
+    pub transform: Matrix,
+    pub shapes: Vec<ClipShape<'a>>,
+    pub clip_path: Option<Box<ClipPath<'a>>>,
+}
+
+impl<'a> ClipPath<'a> {
+    /// the transform from the clip path to the user space of a element with the
+    /// bounding box `bbox`. `None` for bounding box units and a box without area.
+    pub fn to_user_space(&self, bbox: &BBox) -> Option<Matrix> {
+        Some(self.units.to_user_space(bbox)? * self.transform)
+    }
+}
+
+/// Which value of the content of a mask is its coverage, `mask-type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MaskType {
+    #[default]
+    Luminance,
+    Alpha,
+}
+
+impl MaskType {
+    /// parse a `mask-type` value
+    pub fn parse(value: &str) -> Option<MaskType> {
+        match value.trim() {
+            "luminance" => Some(MaskType::Luminance),
+            "alpha" => Some(MaskType::Alpha),
+            _ => None,
+        }
+    }
+
+    /// the coverage of a color of the content of a mask, between `0` and `1`
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{MaskType, Rgba};
+    ///
+    /// assert!((MaskType::Luminance.coverage(Rgba::new(255, 255, 255, 0.5)) - 0.5).abs() < 1e-9);
+    /// assert_eq!(MaskType::Luminance.coverage(Rgba::new(0, 0, 0, 1.0)), 0.0);
+    /// assert_eq!(MaskType::Alpha.coverage(Rgba::new(0, 0, 0, 1.0)), 1.0);
+    /// ```
+    ///
+    pub fn coverage(&self, color: Rgba) -> f64 {
+        match self {
+            MaskType::Luminance => {
+                let luminance = 0.2125 * color.red as f64
+                    + 0.7154 * color.green as f64
+                    + 0.0721 * color.blue as f64;
+                (luminance / 255.0 * color.alpha).clamp(0.0, 1.0)
+            }
+            MaskType::Alpha => color.alpha,
+        }
+    }
+}
+
+/// A resolved `mask`
+///
+/// the region `x`, `y`, `width` and `height` is in `units`, the children of
+/// `element` are the content of the mask in `content_units`. `mask` is the mask
+/// of the `mask` element, which masks it in turn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mask<'a> {
+    pub element: Rc<Element<'a>>,
+    pub units: Units,
+    pub content_units: Units,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub mask_type: MaskType,
+    pub mask: Option<Box<Mask<'a>>>,
+}
+
+impl<'a> Mask<'a> {
+    /// the region of the mask in the user space of a element with the bounding
+    /// box `bbox`, outside of which nothing is drawn. `None` for a region without area.
+    pub fn region(&self, bbox: &BBox) -> Option<BBox> {
+        if self.width <= 0.0 || self.height <= 0.0 {
+            return None;
+        }
+        let m = self.units.to_user_space(bbox)?;
+        Some(BBox::new(self.x, self.y, self.x + self.width, self.y + self.height).transformed(&m))
+    }
+
+    /// the transform from the content of the mask to the user space of a element
+    /// with the bounding box `bbox`
+    pub fn content_transform(&self, bbox: &BBox) -> Option<Matrix> {
+        self.content_units.to_user_space(bbox)
+    }
+}
+
+/// the element referenced by the `url()` of a property, if it has the type `ele_type`
+fn referenced<'a>(
+    document: &Document<'a>,
+    ele: &Element<'a>,
+    key: &str,
+    ele_type: &str,
+) -> Option<Rc<Element<'a>>> {
+    let value = property(ele, key)?;
+    let id = url_ids(&value).first().copied()?;
+    document
+        .element_by_id(id)
+        .filter(|target| target.ele_type == ele_type)
+}
+
+/// whether a element is not drawn because of its computed `display` or `visibility`
+fn is_hidden<'a>(styles: &ComputedStyles<'a>, ele: &Rc<Element<'a>>) -> bool {
+    let style = styles.style(ele);
+    style.get("display").map(str::trim) == Some("none")
+        || matches!(
+            style.get("visibility").map(str::trim),
+            Some("hidden" | "collapse")
+        )
+}
+
+impl<'a> Document<'a> {
+    /// resolve the `clip-path` of a element to the `clipPath` it references
+    ///
+    /// `None` without a clip path or if the reference is not a `clipPath`. a
+    /// reference back to a clip path which is being resolved is left out.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse_document, FillRule, Matrix, Units};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><clipPath id="c" clipPathUnits="objectBoundingBox" clip-rule="evenodd"><rect id="r" width=".5" height="1"/><use href="#r" x=".5"/></clipPath><circle id="a" r="5" clip-path="url(#c)"/></svg>"##).unwrap();
+    /// let clip_path = document.resolve_clip_path(&document.element_by_id("a").unwrap()).unwrap();
+    /// assert_eq!(clip_path.units, Units::ObjectBoundingBox);
+    /// assert_eq!(clip_path.shapes.len(), 2);
+    /// assert_eq!(clip_path.shapes[1].element.get_attribute("id").as_deref(), Some("r"));
+    /// assert_eq!(clip_path.shapes[1].transform, Matrix::translate(0.5, 0.0));
+    /// assert_eq!(clip_path.shapes[1].clip_rule, FillRule::EvenOdd);
+    /// ```
+    ///
+    pub fn resolve_clip_path(&self, ele: &Rc<Element<'a>>) -> Option<ClipPath<'a>> {
+        let styles = ComputedStyles::new(self.stylesheet());
+        self.resolve_clip_path_with(&styles, ele)
+    }
+
+    /// resolve a clip path like `Document::resolve_clip_path`, with the computed
+    /// styles of the tree
+    pub(crate) fn resolve_clip_path_with(
+        &self,
+        styles: &ComputedStyles<'a>,
+        ele: &Rc<Element<'a>>,
+    ) -> Option<ClipPath<'a>> {
+        self.clip_path_of(styles, ele, &mut vec![])
+    }
+
+    fn clip_path_of(
+        &self,
+        styles: &ComputedStyles<'a>,
+        ele: &Element<'a>,
+        visiting: &mut Vec<Rc<Element<'a>>>,
+    ) -> Option<ClipPath<'a>> {
+        let element = referenced(self, ele, "clip-path", "clipPath")?;
+        if visiting.iter().any(|other| Rc::ptr_eq(other, &element)) {
+            return None;
+        }
+        visiting.push(element.clone());
+        let children = element.children.borrow().clone();
+        let mut shapes = vec![];
+        for child in children.iter() {
+            let (shape, transform) = match child.ele_type {
+                "use" => {
+                    let target = element_href(child).and_then(|id| self.element_by_id(&id));
+                    let Some(target) = target else {
+                        continue;
+                    };
+                    let offset = Matrix::translate(
+                        number_or_zero(child, "x").unwrap_or(0.0),
+                        number_or_zero(child, "y").unwrap_or(0.0),
+                    );
+                    let transform = matrix_of(child) * offset * matrix_of(&target);
+                    (target, transform)
+                }
+                _ => (child.clone(), matrix_of(child)),
+            };
+            let is_shape =
+                SHAPES.contains(&shape.ele_type) || ["path", "text"].contains(&shape.ele_type);
+            if !is_shape || is_hidden(styles, child) || is_hidden(styles, &shape) {
+                continue;
+            }
+            shapes.push(ClipShape {
+                clip_rule: styles
+                    .get(&shape, "clip-rule")
+                    .and_then(|value| FillRule::parse(&value))
+                    .unwrap_or_default(),
+                clip_path: self.clip_path_of(styles, &shape, visiting),
+                element: shape,
+                transform,
+            });
+        }
+        let clip_path = self.clip_path_of(styles, &element, visiting).map(Box::new);
+        visiting.pop();
+        Some(ClipPath {
+            units: element
+                .get_attribute("clipPathUnits")
+                .and_then(|value| Units::parse(&value))
+                .unwrap_or(Units::UserSpaceOnUse),
+            transform: matrix_of(&element),
+            shapes,
+            clip_path,
+            element,
+        })
+    }
+
+    /// resolve the `mask` of a element to the `mask` it references
+    ///
+    /// `None` without a mask or if the reference is not a `mask`. lengths in user
+    /// space are resolved against `context`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse_document, BBox, LengthContext, MaskType};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><mask id="m" x="0" y="0" width="1" height=".5" style="mask-type:alpha"><rect width="10" height="10" fill="white"/></mask><rect id="a" width="10" height="20" mask="url(#m)"/></svg>"##).unwrap();
+    /// let mask = document.resolve_mask(&document.element_by_id("a").unwrap(), &LengthContext::default()).unwrap();
+    /// assert_eq!(mask.mask_type, MaskType::Alpha);
+    /// assert_eq!(mask.region(&BBox::new(0.0, 0.0, 10.0, 20.0)), Some(BBox::new(0.0, 0.0, 10.0, 10.0)));
+    /// ```
+    ///
+    pub fn resolve_mask(&self, ele: &Rc<Element<'a>>, context: &LengthContext) -> Option<Mask<'a>> {
+        self.mask_of(ele, context, &mut vec![])
+    }
+
+    fn mask_of(
+        &self,
+        ele: &Element<'a>,
+        context: &LengthContext,
+        visiting: &mut Vec<Rc<Element<'a>>>,
+    ) -> Option<Mask<'a>> {
+        let element = referenced(self, ele, "mask", "mask")?;
+        if visiting.iter().any(|other| Rc::ptr_eq(other, &element)) {
+            return None;
+        }
+        let units = element
+            .get_attribute("maskUnits")
+            .and_then(|value| Units::parse(&value))
+            .unwrap_or(Units::ObjectBoundingBox);
+        let length = |key: &str, default: &str| {
+            let length = element
+                .get_attribute(key)
+                .and_then(|value| Length::parse(&value).ok())
+                .unwrap_or_else(|| Length::parse(default).unwrap());
+            resolve_length(length, units, key, context)
+        };
+        visiting.push(element.clone());
+        let mask = self.mask_of(&element, context, visiting).map(Box::new);
+        visiting.pop();
+        Some(Mask {
+            units,
+            content_units: element
+                .get_attribute("maskContentUnits")
+                .and_then(|value| Units::parse(&value))
+                .unwrap_or(Units::UserSpaceOnUse),
+            x: length("x", "-10%"),
+            y: length("y", "-10%"),
+            width: length("width", "120%"),
+            height: length("height", "120%"),
+            mask_type: property(&element, "mask-type")
+                .and_then(|value| MaskType::parse(&value))
+                .unwrap_or_default(),
+            mask,
+            element,
+        })
+    }
+}
+
+/// the `transform` of a element as a matrix, the identity if it is missing or invalid
+fn matrix_of(ele: &Element) -> Matrix {
+    ele.transform().unwrap_or_default().to_matrix()
+}
+
+/// the rectangle of a clip path made of a single `rect`, in the user space of the
+/// element it clips
+fn clip_rect(clip_path: &ClipPath, bbox: &BBox) -> Option<BBox> {
+    let [shape] = clip_path.shapes.as_slice() else {
+        return None;
+    };
+    if clip_path.clip_path.is_some()
+        || shape.clip_path.is_some()
+        || shape.element.ele_type != "rect"
+        || radii(&shape.element)? != (0.0, 0.0)
+    {
+        return None;
+    }
+    let m = clip_path.to_user_space(bbox)? * shape.transform;
+    if m.b != 0.0 || m.c != 0.0 {
+        return None;
+    }
+    let number = |key| number_or_zero(&shape.element, key);
+    let (x, y) = (number("x")?, number("y")?);
+    let (width, height) = (number("width")?, number("height")?);
+    if width <= 0.0 || height <= 0.0 {
+        return Some(BBox::new(0.0, 0.0, 0.0, 0.0));
+    }
+    Some(BBox::new(x, y, x + width, y + height).transformed(&m))
+}
+
+/// apply the clip paths which are a single rectangle to the `rect`s they clip
+///
+/// a `rect` without rounded corners, stroke, paint server, mask or filter
+/// which is clipped by a `clipPath` holding a single axis aligned `rect`
+/// becomes the intersection of the two rectangles, and loses its `clip-path`.
+/// a rect clipped to nothing is removed. a rect which is referenced by another
+/// element and the `clipPath` elements are kept.
+///
+/// ## Example
+/// ```rust
+/// use svg_simple_parser::{flatten_clip_paths, parse_document, stringify};
+///
+/// let (_, document) = parse_document(r##"<svg><clipPath id="c" transform="translate(5 0)"><rect width="10" height="10"/></clipPath><rect width="20" height="5" fill="red" clip-path="url(#c)"/></svg>"##).unwrap();
+/// flatten_clip_paths(&document);
+/// assert_eq!(
+///     stringify(document.root.clone()),
+///     r##"<svg><clipPath id="c" transform="translate(5 0)"><rect height="10" width="10"/></clipPath><rect fill="red" height="5" width="10" x="5"/></svg>"##
+/// );
+/// ```
+///
+pub fn flatten_clip_paths(document: &Document) {
+    let styles = ComputedStyles::new(document.stylesheet());
+    let referenced: HashSet<String> = document
+        .references()
+        .into_iter()
+        .map(|reference| reference.id)
+        .collect();
+    for ele in document.root.descendants() {
+        if ele.ele_type != "rect" || radii(&ele) != Some((0.0, 0.0)) {
+            continue;
+        }
+        // a rect drawn elsewhere, e.g. by a use, keeps its geometry
+        if ele
+            .get_attribute("id")
+            .is_some_and(|id| referenced.contains(id.as_ref()))
+        {
+            continue;
+        }
+        let style = styles.style(&ele);
+        if style_stroke(&style).is_some()
+            || style.get("fill").is_some_and(|fill| fill.contains("url("))
+            || style.get("mask").is_some()
+            || style.get("filter").is_some()
+        {
+            continue;
+        }
+        let Some(clip_path) = document.resolve_clip_path_with(&styles, &ele) else {
+            continue;
+        };
+        let number = |key| number_or_zero(&ele, key);
+        let (Some(x), Some(y), Some(width), Some(height)) =
+            (number("x"), number("y"), number("width"), number("height"))
+        else {
+            continue;
+        };
+        if width <= 0.0 || height <= 0.0 {
+            continue;
+        }
+        let bbox = BBox::new(x, y, x + width, y + height);
+        let Some(clip) = clip_rect(&clip_path, &bbox) else {
+            continue;
+        };
+        let (min_x, min_y) = (bbox.min_x.max(clip.min_x), bbox.min_y.max(clip.min_y));
+        let (max_x, max_y) = (bbox.max_x.min(clip.max_x), bbox.max_y.min(clip.max_y));
+        if max_x <= min_x || max_y <= min_y {
+            let parent = ele.parent.borrow().upgrade();
+            if let Some(parent) = parent {
+                document.remove_child(&parent, &ele);
+            }
+            continue;
+        }
+        for (key, value) in [
+            ("x", min_x),
+            ("y", min_y),
+            ("width", max_x - min_x),
+            ("height", max_y - min_y),
+        ] {
+            let value = snap(value);
+            if value != 0.0 || ele.get_attribute(key).is_some() {
+                ele.set_attribute(key, shortest_number(value));
+            }
+        }
+        ele.remove_attribute("clip-path");
+        ele.remove_style_property("clip-path");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        flatten_clip_paths, parse_document, stringify, BBox, FillRule, LengthContext, MaskType,
+        Matrix, Units,
+    };
+
+    #[test]
+    fn test_resolve_clip_path() {
+        let (_, document) = parse_document(
+            r##"<svg><clipPath id="a" clip-path="url(#b)" transform="scale(2)"><rect width="1" height="1" clip-path="url(#b)" style="clip-rule:evenodd"/><circle r="1" display="none"/><g><rect/></g><text>a</text><use href="#missing"/></clipPath><clipPath id="b" clip-path="url(#a)" clipPathUnits="objectBoundingBox"><path/></clipPath><rect id="r" clip-path="url(#a)"/><rect id="m" clip-path="url(#r)"/></svg>"##,
+        )
+        .unwrap();
+        let ele = |id| document.element_by_id(id).unwrap();
+        let a = document.resolve_clip_path(&ele("r")).unwrap();
+        assert_eq!(a.transform, Matrix::scale(2.0, 2.0));
+        assert_eq!(a.units, Units::UserSpaceOnUse);
+        let types: Vec<_> = a
+            .shapes
+            .iter()
+            .map(|shape| shape.element.ele_type)
+            .collect();
+        assert_eq!(types, ["rect", "text"]);
+        assert_eq!(a.shapes[0].clip_rule, FillRule::EvenOdd);
+        assert_eq!(a.shapes[1].clip_rule, FillRule::NonZero);
+        // the reference of b back to a is left out
+        let b = a.clip_path.as_ref().unwrap();
+        assert_eq!(b.units, Units::ObjectBoundingBox);
+        assert_eq!(b.clip_path, None);
+        assert_eq!(a.shapes[0].clip_path.as_ref(), Some(&**b));
+        assert_eq!(
+            b.to_user_space(&BBox::new(1.0, 1.0, 3.0, 5.0)),
+            Some(Matrix::translate(1.0, 1.0) * Matrix::scale(2.0, 4.0))
+        );
+        assert_eq!(b.to_user_space(&BBox::new(1.0, 1.0, 1.0, 5.0)), None);
+        // a reference to a element which is not a clip path
+        assert_eq!(document.resolve_clip_path(&ele("m")), None);
+    }
+
+    #[test]
+    fn test_resolve_mask() {
+        let (_, document) = parse_document(
+            r##"<svg><mask id="a" mask="url(#b)" maskUnits="userSpaceOnUse" maskContentUnits="objectBoundingBox" x="10%" width="50"/><mask id="b" mask="url(#a)" mask-type="alpha"/><rect id="r" mask="url(#a)"/></svg>"##,
+        )
+        .unwrap();
+        let context = LengthContext::default();
+        let mask = document
+            .resolve_mask(&document.element_by_id("r").unwrap(), &context)
+            .unwrap();
+        assert_eq!(mask.units, Units::UserSpaceOnUse);
+        assert_eq!(mask.mask_type, MaskType::Luminance);
+        assert_eq!(
+            (mask.x, mask.y, mask.width, mask.height),
+            (10.0, -10.0, 50.0, 120.0)
+        );
+        let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(
+            mask.region(&bbox),
+            Some(BBox::new(10.0, -10.0, 60.0, 110.0))
+        );
+        assert_eq!(
+            mask.content_transform(&bbox),
+            Some(Matrix::scale(10.0, 10.0))
+        );
+        let inner = mask.mask.as_ref().unwrap();
+        assert_eq!(inner.mask_type, MaskType::Alpha);
+        assert_eq!(inner.mask, None);
+    }
+
+    fn flatten(source: &str) -> String {
+        let (_, document) = parse_document(source).unwrap();
+        flatten_clip_paths(&document);
+        stringify(document.root.clone())
+    }
+
+    #[test]
+    fn test_flatten_clip_paths() {
+        // bounding box units, and a use of the clip rect
+        assert_eq!(
+            flatten(
+                r##"<svg><clipPath id="c" clipPathUnits="objectBoundingBox"><rect id="s" x=".5" width=".5" height=".5"/></clipPath><rect x="10" y="10" width="10" height="10" style="clip-path:url(#c)"/></svg>"##
+            ),
+            r##"<svg><clipPath clipPathUnits="objectBoundingBox" id="c"><rect height=".5" id="s" width=".5" x=".5"/></clipPath><rect height="5" width="5" x="15" y="10"/></svg>"##
+        );
+        // clipped to nothing
+        assert_eq!(
+            flatten(
+                r##"<svg><clipPath id="c"><rect x="20" width="10" height="10"/></clipPath><rect width="10" height="10" clip-path="url(#c)"/></svg>"##
+            ),
+            r##"<svg><clipPath id="c"><rect height="10" width="10" x="20"/></clipPath></svg>"##
+        );
+        // clips which are not a single axis aligned rect are kept
+        for source in [
+            r##"<svg><clipPath id="c" transform="rotate(45)"><rect width="5" height="5"/></clipPath><rect width="10" height="10" clip-path="url(#c)"/></svg>"##,
+            r##"<svg><clipPath id="c"><rect width="5" height="5"/><rect x="5" width="5" height="5"/></clipPath><rect width="10" height="10" clip-path="url(#c)"/></svg>"##,
+            r##"<svg><clipPath id="c"><circle r="5"/></clipPath><rect width="10" height="10" clip-path="url(#c)"/></svg>"##,
+            r##"<svg><clipPath id="c"><rect width="5" height="5" rx="1"/></clipPath><rect width="10" height="10" clip-path="url(#c)"/></svg>"##,
+            r##"<svg><clipPath id="c"><rect width="5" height="5"/></clipPath><rect width="10" height="10" clip-path="url(#c)" stroke="red"/></svg>"##,
+            r##"<svg><clipPath id="c"><rect width="5" height="5"/></clipPath><circle r="10" clip-path="url(#c)"/></svg>"##,
+            r##"<svg><linearGradient id="g"/><clipPath id="c"><rect width="5" height="5"/></clipPath><rect width="10" height="10" clip-path="url(#c)" style="stroke:red;stroke-width:4;fill:url(#g)"/></svg>"##,
+            // the rect is also drawn by the use of the clip path
+            r##"<svg><clipPath id="c"><use href="#s" x="20"/></clipPath><rect id="s" width="10" height="10" clip-path="url(#c)"/></svg>"##,
+            r##"<svg><clipPath id="c"><rect width="5" height="5"/></clipPath><rect id="s" width="10" height="10" clip-path="url(#c)"/><use href="#s" x="20"/></svg>"##,
+        ] {
+            let (_, document) = parse_document(source).unwrap();
+            let expected = stringify(document.root.clone());
+            assert_eq!(flatten(source), expected);
+        }
+    }
+}
@@ -0,0 +1,641 @@
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::ast::Element;
+use crate::css::ComputedStyles;
+use crate::path::shortest_number;
+use crate::style::Style;
+
+/// The 147 named colors of SVG and CSS
+const NAMED_COLORS: [(&str, u32); 147] = [
+    ("aliceblue", 0xf0f8ff),
+    ("antiquewhite", 0xfaebd7),
+    ("aqua", 0x00ffff),
+    ("aquamarine", 0x7fffd4),
+    ("azure", 0xf0ffff),
+    ("beige", 0xf5f5dc),
+    ("bisque", 0xffe4c4),
+    ("black", 0x000000),
+    ("blanchedalmond", 0xffebcd),
+    ("blue", 0x0000ff),
+    ("blueviolet", 0x8a2be2),
+    ("brown", 0xa52a2a),
+    ("burlywood", 0xdeb887),
+    ("cadetblue", 0x5f9ea0),
+    ("chartreuse", 0x7fff00),
+    ("chocolate", 0xd2691e),
+    ("coral", 0xff7f50),
+    ("cornflowerblue", 0x6495ed),
+    ("cornsilk", 0xfff8dc),
+    ("crimson", 0xdc143c),
+    ("cyan", 0x00ffff),
+    ("darkblue", 0x00008b),
+    ("darkcyan", 0x008b8b),
+    ("darkgoldenrod", 0xb8860b),
+    ("darkgray", 0xa9a9a9),
+    ("darkgreen", 0x006400),
+    ("darkgrey", 0xa9a9a9),
+    ("darkkhaki", 0xbdb76b),
+    ("darkmagenta", 0x8b008b),
+    ("darkolivegreen", 0x556b2f),
+    ("darkorange", 0xff8c00),
+    ("darkorchid", 0x9932cc),
+    ("darkred", 0x8b0000),
+    ("darksalmon", 0xe9967a),
+    ("darkseagreen", 0x8fbc8f),
+    ("darkslateblue", 0x483d8b),
+    ("darkslategray", 0x2f4f4f),
+    ("darkslategrey", 0x2f4f4f),
+    ("darkturquoise", 0x00ced1),
+    ("darkviolet", 0x9400d3),
+    ("deeppink", 0xff1493),
+    ("deepskyblue", 0x00bfff),
+    ("dimgray", 0x696969),
+    ("dimgrey", 0x696969),
+    ("dodgerblue", 0x1e90ff),
+    ("firebrick", 0xb22222),
+    ("floralwhite", 0xfffaf0),
+    ("forestgreen", 0x228b22),
+    ("fuchsia", 0xff00ff),
+    ("gainsboro", 0xdcdcdc),
+    ("ghostwhite", 0xf8f8ff),
+    ("gold", 0xffd700),
+    ("goldenrod", 0xdaa520),
+    ("gray", 0x808080),
+    ("green", 0x008000),
+    ("greenyellow", 0xadff2f),
+    ("grey", 0x808080),
+    ("honeydew", 0xf0fff0),
+    ("hotpink", 0xff69b4),
+    ("indianred", 0xcd5c5c),
+    ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0),
+    ("khaki", 0xf0e68c),
+    ("lavender", 0xe6e6fa),
+    ("lavenderblush", 0xfff0f5),
+    ("lawngreen", 0x7cfc00),
+    ("lemonchiffon", 0xfffacd),
+    ("lightblue", 0xadd8e6),
+    ("lightcoral", 0xf08080),
+    ("lightcyan", 0xe0ffff),
+    ("lightgoldenrodyellow", 0xfafad2),
+    ("lightgray", 0xd3d3d3),
+    ("lightgreen", 0x90ee90),
+    ("lightgrey", 0xd3d3d3),
+    ("lightpink", 0xffb6c1),
+    ("lightsalmon", 0xffa07a),
+    ("lightseagreen", 0x20b2aa),
+    ("lightskyblue", 0x87cefa),
+    ("lightslategray", 0x778899),
+    ("lightslategrey", 0x778899),
+    ("lightsteelblue", 0xb0c4de),
+    ("lightyellow", 0xffffe0),
+    ("lime", 0x00ff00),
+    ("limegreen", 0x32cd32),
+    ("linen", 0xfaf0e6),
+    ("magenta", 0xff00ff),
+    ("maroon", 0x800000),
+    ("mediumaquamarine", 0x66cdaa),
+    ("mediumblue", 0x0000cd),
+    ("mediumorchid", 0xba55d3),
+    ("mediumpurple", 0x9370db),
+    ("mediumseagreen", 0x3cb371),
+    ("mediumslateblue", 0x7b68ee),
+    ("mediumspringgreen", 0x00fa9a),
+    ("mediumturquoise", 0x48d1cc),
+    ("mediumvioletred", 0xc71585),
+    ("midnightblue", 0x191970),
+    ("mintcream", 0xf5fffa),
+    ("mistyrose", 0xffe4e1),
+    ("moccasin", 0xffe4b5),
+    ("navajowhite", 0xffdead),
+    ("navy", 0x000080),
+    ("oldlace", 0xfdf5e6),
+    ("olive", 0x808000),
+    ("olivedrab", 0x6b8e23),
+    ("orange", 0xffa500),
+    ("orangered", 0xff4500),
+    ("orchid", 0xda70d6),
+    ("palegoldenrod", 0xeee8aa),
+    ("palegreen", 0x98fb98),
+    ("paleturquoise", 0xafeeee),
+    ("palevioletred", 0xdb7093),
+    ("papayawhip", 0xffefd5),
+    ("peachpuff", 0xffdab9),
+    ("peru", 0xcd853f),
+    ("pink", 0xffc0cb),
+    ("plum", 0xdda0dd),
+    ("powderblue", 0xb0e0e6),
+    ("purple", 0x800080),
+    ("red", 0xff0000),
+    ("rosybrown", 0xbc8f8f),
+    ("royalblue", 0x4169e1),
+    ("saddlebrown", 0x8b4513),
+    ("salmon", 0xfa8072),
+    ("sandybrown", 0xf4a460),
+    ("seagreen", 0x2e8b57),
+    ("seashell", 0xfff5ee),
+    ("sienna", 0xa0522d),
+    ("silver", 0xc0c0c0),
+    ("skyblue", 0x87ceeb),
+    ("slateblue", 0x6a5acd),
+    ("slategray", 0x708090),
+    ("slategrey", 0x708090),
+    ("snow", 0xfffafa),
+    ("springgreen", 0x00ff7f),
+    ("steelblue", 0x4682b4),
+    ("tan", 0xd2b48c),
+    ("teal", 0x008080),
+    ("thistle", 0xd8bfd8),
+    ("tomato", 0xff6347),
+    ("turquoise", 0x40e0d0),
+    ("violet", 0xee82ee),
+    ("wheat", 0xf5deb3),
+    ("white", 0xffffff),
+    ("whitesmoke", 0xf5f5f5),
+    ("yellow", 0xffff00),
+    ("yellowgreen", 0x9acd32),
+];
+
+/// A color in sRGB with a alpha between `0` and `1`
+///
+/// `Display` writes the shortest equivalent form understood by svg 1.1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgba {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    pub alpha: f64,
+}
+
+/// A parsed color, `currentColor` refers to the `color` of the element
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Color {
+    CurrentColor,
+    Rgba(Rgba),
+}
+
+/// A value which is not a color
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorError {
+    pub value: String,
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid color: {:?}", self.value)
+    }
+}
+
+impl Error for ColorError {}
+
+impl Rgba {
+    pub const BLACK: Rgba = Rgba::new(0, 0, 0, 1.0);
+    pub const WHITE: Rgba = Rgba::new(255, 255, 255, 1.0);
+    pub const TRANSPARENT: Rgba = Rgba::new(0, 0, 0, 0.0);
+
+    pub const fn new(red: u8, green: u8, blue: u8, alpha: f64) -> Rgba {
+        Rgba {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// a color from a `0xrrggbb` number
+    pub const fn from_u32(rgb: u32, alpha: f64) -> Rgba {
+        Rgba::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, alpha)
+    }
+
+    /// a color from hue in degrees, saturation and lightness between `0` and `1`
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::Rgba;
+    ///
+    /// assert_eq!(Rgba::from_hsl(120.0, 1.0, 0.25, 1.0), Rgba::new(0, 128, 0, 1.0));
+    /// ```
+    ///
+    pub fn from_hsl(hue: f64, saturation: f64, lightness: f64, alpha: f64) -> Rgba {
+        let (s, l) = (saturation.clamp(0.0, 1.0), lightness.clamp(0.0, 1.0));
+        let h = hue.rem_euclid(360.0) / 30.0;
+        let a = s * l.min(1.0 - l);
+        let channel = |n: f64| {
+            let k = (n + h) % 12.0;
+            let value = l - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0);
+            (value * 255.0).round() as u8
+        };
+        Rgba::new(channel(0.0), channel(8.0), channel(4.0), alpha)
+    }
+
+    /// hue in degrees, saturation and lightness between `0` and `1`
+    pub fn to_hsl(&self) -> (f64, f64, f64) {
+        let (r, g, b) = (
+            self.red as f64 / 255.0,
+            self.green as f64 / 255.0,
+            self.blue as f64 / 255.0,
+        );
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let lightness = (max + min) / 2.0;
+        let delta = max - min;
+        if delta == 0.0 {
+            return (0.0, 0.0, lightness);
+        }
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            (b - r) / delta + 2.0
+        } else {
+            (r - g) / delta + 4.0
+        };
+        (hue * 60.0, saturation, lightness)
+    }
+
+    /// the `#rrggbb` form, or `#rrggbbaa` if the color is not opaque
+    pub fn to_hex(&self) -> String {
+        match self.alpha_byte() {
+            Some(255) => format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue),
+            alpha => format!(
+                "#{:02x}{:02x}{:02x}{:02x}",
+                self.red,
+                self.green,
+                self.blue,
+                alpha.unwrap_or((self.alpha.clamp(0.0, 1.0) * 255.0).round() as u8)
+            ),
+        }
+    }
+
+    /// the name of the color, if it has one
+    pub fn name(&self) -> Option<&'static str> {
+        if self.alpha != 1.0 {
+            return None;
+        }
+        let rgb = (self.red as u32) << 16 | (self.green as u32) << 8 | self.blue as u32;
+        NAMED_COLORS
+            .iter()
+            .filter(|(_, value)| *value == rgb)
+            .map(|(name, _)| *name)
+            .min_by_key(|name| name.len())
+    }
+
+    /// the alpha as a byte, if it is exactly one
+    fn alpha_byte(&self) -> Option<u8> {
+        let byte = self.alpha * 255.0;
+        if (byte - byte.round()).abs() < 1e-9 && (0.0..=255.0).contains(&byte) {
+            Some(byte.round() as u8)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Rgba {
+    /// write the shortest of the hex forms and the name, or `rgba()` if the
+    /// color is not opaque as svg 1.1 has no hex form with alpha
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.alpha != 1.0 {
+            return write!(
+                f,
+                "rgba({},{},{},{})",
+                self.red,
+                self.green,
+                self.blue,
+                shortest_number(self.alpha.clamp(0.0, 1.0))
+            );
+        }
+        let bytes = [self.red, self.green, self.blue];
+        let mut candidates = vec![];
+        if bytes.iter().all(|b| b >> 4 == b & 0xf) {
+            let digits: String = bytes.iter().map(|b| format!("{:x}", b & 0xf)).collect();
+            candidates.push(format!("#{}", digits));
+        } else {
+            let digits: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            candidates.push(format!("#{}", digits));
+        }
+        if let Some(name) = self.name() {
+            candidates.push(name.to_owned());
+        }
+        // the first candidate wins a tie, so a hex form over a name
+        let shortest =
+            candidates
+                .iter()
+                .fold(&candidates[0], |a, b| if b.len() < a.len() { b } else { a });
+        write!(f, "{}", shortest)
+    }
+}
+
+/// the arguments of a color function, legacy comma separated or modern space
+/// separated with `/` before the alpha
+fn function_arguments(input: &str) -> Option<Vec<&str>> {
+    let mut args: Vec<&str> = if input.contains(',') {
+        input.split(',').map(str::trim).collect()
+    } else {
+        let (color, alpha) = match input.split_once('/') {
+            Some((color, alpha)) => (color, Some(alpha.trim())),
+            None => (input, None),
+        };
+        let mut args: Vec<&str> = color.split_whitespace().collect();
+        if args.len() != 3 {
+            return None;
+        }
+        args.extend(alpha);
+        args
+    };
+    args.retain(|arg| !arg.is_empty());
+    Some(args)
+}
+
+/// a number, or a percentage of `percent_of`
+fn number_or_percentage(arg: &str, percent_of: f64) -> Option<f64> {
+    let value = match arg.strip_suffix('%') {
+        Some(n) => n.parse::<f64>().ok()? / 100.0 * percent_of,
+        None => arg.parse::<f64>().ok()?,
+    };
+    Some(value).filter(|n| n.is_finite())
+}
+
+fn alpha_argument(arg: Option<&&str>) -> Option<f64> {
+    match arg {
+        Some(arg) => number_or_percentage(arg, 1.0).map(|a| a.clamp(0.0, 1.0)),
+        None => Some(1.0),
+    }
+}
+
+/// a angle in degrees, with an optional angle unit
+pub(crate) fn parse_angle(arg: &str) -> Option<f64> {
+    let units = [
+        ("deg", 1.0),
+        ("grad", 0.9),
+        ("rad", 180.0 / std::f64::consts::PI),
+        ("turn", 360.0),
+    ];
+    for (unit, factor) in units {
+        if let Some(n) = arg.strip_suffix(unit) {
+            return n.parse::<f64>().ok().map(|n| n * factor);
+        }
+    }
+    arg.parse::<f64>().ok()
+}
+
+fn parse_hex(hex: &str) -> Option<Rgba> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).unwrap() * 17;
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+    match hex.len() {
+        3 => Some(Rgba::new(digit(0), digit(1), digit(2), 1.0)),
+        4 => Some(Rgba::new(
+            digit(0),
+            digit(1),
+            digit(2),
+            digit(3) as f64 / 255.0,
+        )),
+        6 => Some(Rgba::new(byte(0), byte(2), byte(4), 1.0)),
+        8 => Some(Rgba::new(byte(0), byte(2), byte(4), byte(6) as f64 / 255.0)),
+        _ => None,
+    }
+}
+
+impl Color {
+    /// parse a color, keywords and function names are case-insensitive
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{Color, Rgba};
+    ///
+    /// let red = Color::Rgba(Rgba::new(255, 0, 0, 1.0));
+    /// assert_eq!(Color::parse("red").unwrap(), red);
+    /// assert_eq!(Color::parse("#f00").unwrap(), red);
+    /// assert_eq!(Color::parse("rgb(100%, 0%, 0%)").unwrap(), red);
+    /// assert_eq!(Color::parse("hsl(0 100% 50%)").unwrap(), red);
+    /// assert_eq!(Color::parse("rgb(255 0 0 / 50%)").unwrap(), Color::Rgba(Rgba::new(255, 0, 0, 0.5)));
+    /// assert_eq!(Color::parse("currentColor").unwrap(), Color::CurrentColor);
+    /// assert!(Color::parse("reddish").is_err());
+    /// ```
+    ///
+    pub fn parse(value: &str) -> Result<Color, ColorError> {
+        let error = || ColorError {
+            value: value.to_owned(),
+        };
+        let lower = value.trim().to_ascii_lowercase();
+        if lower == "currentcolor" {
+            return Ok(Color::CurrentColor);
+        }
+        if lower == "transparent" {
+            return Ok(Color::Rgba(Rgba::TRANSPARENT));
+        }
+        if let Some(hex) = lower.strip_prefix('#') {
+            return parse_hex(hex).map(Color::Rgba).ok_or_else(error);
+        }
+        if let Some((_, rgb)) = NAMED_COLORS.iter().find(|(name, _)| *name == lower) {
+            return Ok(Color::Rgba(Rgba::from_u32(*rgb, 1.0)));
+        }
+        let (name, args) = lower
+            .strip_suffix(')')
+            .and_then(|s| s.split_once('('))
+            .ok_or_else(error)?;
+        let args = function_arguments(args).ok_or_else(error)?;
+        if args.len() != 3 && args.len() != 4 {
+            return Err(error());
+        }
+        let alpha = alpha_argument(args.get(3)).ok_or_else(error)?;
+        let rgba = match name.trim_end() {
+            "rgb" | "rgba" => {
+                let channel = |arg: &str| {
+                    number_or_percentage(arg, 255.0).map(|n| n.clamp(0.0, 255.0).round() as u8)
+                };
+                match (channel(args[0]), channel(args[1]), channel(args[2])) {
+                    (Some(r), Some(g), Some(b)) => Rgba::new(r, g, b, alpha),
+                    _ => return Err(error()),
+                }
+            }
+            "hsl" | "hsla" => {
+                let fraction = |arg: &str| match arg.strip_suffix('%') {
+                    Some(_) => number_or_percentage(arg, 1.0),
+                    None => number_or_percentage(arg, 1.0).map(|n| n / 100.0),
+                };
+                match (parse_angle(args[0]), fraction(args[1]), fraction(args[2])) {
+                    (Some(h), Some(s), Some(l)) => Rgba::from_hsl(h, s, l, alpha),
+                    _ => return Err(error()),
+                }
+            }
+            _ => return Err(error()),
+        };
+        Ok(Color::Rgba(rgba))
+    }
+
+    /// the color, with `currentColor` replaced by `current`
+    pub fn resolve(&self, current: Rgba) -> Rgba {
+        match self {
+            Color::CurrentColor => current,
+            Color::Rgba(rgba) => *rgba,
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = ColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Color::parse(s)
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Color::CurrentColor => write!(f, "currentColor"),
+            Color::Rgba(rgba) => write!(f, "{}", rgba),
+        }
+    }
+}
+
+/// a opacity attribute, a number or a percentage clamped to `0..=1`
+pub(crate) fn opacity(value: &str) -> Option<f64> {
+    number_or_percentage(value.trim(), 1.0).map(|n| n.clamp(0.0, 1.0))
+}
+
+/// the resolved color of a paint property, `fill` or `stroke`, from the
+/// computed style of a element, see `Stylesheet::computed_style`
+///
+/// `currentColor` takes the computed `color` and the alpha includes
+/// `fill-opacity` or `stroke-opacity`. a paint server reference resolves to
+/// its fallback color. `None` for no paint.
+pub(crate) fn paint_color(style: &Style, key: &str, default: &str) -> Option<Rgba> {
+    let value = style.get(key).unwrap_or(default).trim();
+    let value = match value.strip_prefix("url(") {
+        Some(rest) => rest.split_once(')').map(|(_, fallback)| fallback.trim())?,
+        None => value,
+    };
+    if value == "none" || value.is_empty() {
+        return None;
+    }
+    let current = style
+        .get("color")
+        .and_then(|color| Color::parse(color).ok())
+        .map_or(Rgba::BLACK, |color| color.resolve(Rgba::BLACK));
+    let mut rgba = Color::parse(value).ok()?.resolve(current);
+    let opacity = style
+        .get(&format!("{}-opacity", key))
+        .and_then(opacity)
+        .unwrap_or(1.0);
+    rgba.alpha *= opacity;
+    Some(rgba)
+}
+
+impl<'a> Element<'a> {
+    /// the resolved fill color, black by default
+    ///
+    /// the properties are read from the computed style of the element, its
+    /// presentation attributes, `style` and the rules of the `<style>` elements
+    /// of its tree with those of its ancestors, see `Stylesheet::computed_style`.
+    /// `currentColor` takes the computed `color` and the alpha includes
+    /// `fill-opacity`. a paint server reference resolves to its fallback color.
+    /// `None` for no paint.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, Rgba};
+    ///
+    /// let (_, root) = parse(r##"<g color="#00f" fill-opacity="0.5"><rect fill="currentColor" stroke="url(#a) red"/><circle/><path style="fill:white;stroke:red" fill="blue"/></g>"##).unwrap();
+    /// let rect = root.children.borrow()[0].clone();
+    /// let circle = root.children.borrow()[1].clone();
+    /// let path = root.children.borrow()[2].clone();
+    /// assert_eq!(rect.fill_color(), Some(Rgba::new(0, 0, 255, 0.5)));
+    /// assert_eq!(rect.stroke_color(), Some(Rgba::new(255, 0, 0, 1.0)));
+    /// assert_eq!(circle.fill_color(), Some(Rgba::new(0, 0, 0, 0.5)));
+    /// assert_eq!(circle.stroke_color(), None);
+    /// // `style` wins over the presentation attributes
+    /// assert_eq!(path.fill_color(), Some(Rgba::new(255, 255, 255, 0.5)));
+    /// assert_eq!(path.stroke_color(), Some(Rgba::new(255, 0, 0, 1.0)));
+    /// ```
+    ///
+    pub fn fill_color(self: &Rc<Self>) -> Option<Rgba> {
+        paint_color(&ComputedStyles::of_tree(self).style(self), "fill", "black")
+    }
+
+    /// the resolved stroke color, see `Element::fill_color`. none by default.
+    pub fn stroke_color(self: &Rc<Self>) -> Option<Rgba> {
+        paint_color(&ComputedStyles::of_tree(self).style(self), "stroke", "none")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::color::NAMED_COLORS;
+    use crate::{Color, Rgba};
+
+    fn rgba(value: &str) -> Rgba {
+        Color::parse(value).unwrap().resolve(Rgba::BLACK)
+    }
+
+    #[test]
+    fn test_named_colors() {
+        for window in NAMED_COLORS.windows(2) {
+            assert!(window[0].0 < window[1].0);
+        }
+        assert_eq!(rgba("Purple"), rgba("#800080"));
+        assert_eq!(rgba("grey").name(), Some("gray"));
+        assert_eq!(
+            rgba(" LightGoldenrodYellow "),
+            Rgba::new(250, 250, 210, 1.0)
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(rgba("#ff0000cc"), Rgba::new(255, 0, 0, 0.8));
+        assert_eq!(rgba("#f00c"), Rgba::new(255, 0, 0, 0.8));
+        assert_eq!(rgba("transparent"), Rgba::TRANSPARENT);
+        assert_eq!(rgba("rgba(255, 128, 0, .25)"), Rgba::new(255, 128, 0, 0.25));
+        assert_eq!(rgba("RGB(300 -5 12.6)"), Rgba::new(255, 0, 13, 1.0));
+        assert_eq!(rgba("hsla(240, 100%, 50%, 0.5)"), Rgba::new(0, 0, 255, 0.5));
+        assert_eq!(
+            rgba("hsl(0.5turn 100% 50% / 20%)"),
+            Rgba::new(0, 255, 255, 0.2)
+        );
+        for invalid in [
+            "#ff",
+            "#ggg",
+            "rgb(1, 2)",
+            "rgb(1 2 3 4)",
+            "foo(1, 2, 3)",
+            "rgb(a, b, c)",
+            "",
+        ] {
+            assert!(Color::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_hsl_round_trip() {
+        for value in ["#ff8000", "#336699", "#808080", "#fff", "#0f0"] {
+            let color = rgba(value);
+            let (h, s, l) = color.to_hsl();
+            assert_eq!(Rgba::from_hsl(h, s, l, 1.0), color, "{}", value);
+        }
+        assert_eq!(rgba("#00f").to_hsl(), (240.0, 1.0, 0.5));
+    }
+
+    #[test]
+    fn test_display() {
+        let display = |value: &str| Color::parse(value).unwrap().to_string();
+        assert_eq!(display("#ff0000"), "red");
+        assert_eq!(display("rgb(255, 255, 255)"), "#fff");
+        assert_eq!(display("blue"), "#00f");
+        assert_eq!(display("#d2b48c"), "tan");
+        assert_eq!(display("#123456"), "#123456");
+        assert_eq!(display("#11223380"), "rgba(17,34,51,.5019607843137255)");
+        assert_eq!(display("rgba(17,34,51,.5)"), "rgba(17,34,51,.5)");
+        assert_eq!(display("transparent"), "rgba(0,0,0,0)");
+        assert_eq!(display("rgb(255 0 0 / 50%)"), "rgba(255,0,0,.5)");
+        assert_eq!(display("currentcolor"), "currentColor");
+        assert_eq!(rgba("#11223344").to_hex(), "#11223344");
+        assert_eq!(rgba("red").to_hex(), "#ff0000");
+    }
+}
@@ -0,0 +1,162 @@
+use std::rc::Rc;
+
+use crate::ast::Element;
+use crate::bbox::{element_bbox, BBox};
+use crate::css::ComputedStyles;
+use crate::document::Document;
+use crate::expand_use::expand_use;
+use crate::length::LengthContext;
+use crate::number::snap;
+use crate::path::shortest_number;
+use crate::transform::Matrix;
+use crate::view_box::ViewBox;
+
+impl<'a> Element<'a> {
+    /// fit the canvas to the painted content of a root `svg`, see `Element::stroke_bbox`
+    ///
+    /// the `viewBox` becomes the box of the content grown by `padding` on each
+    /// side. a `width` or `height` which is present is resized in user units,
+    /// keeping the scale of the current viewport. returns the new view box,
+    /// `None` if the element is not a `svg` or draws nothing.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, stringify};
+    ///
+    /// let (_, root) = parse(r#"<svg width="48" height="48" viewBox="0 0 24 24"><rect x="4" y="6" width="10" height="8"/></svg>"#).unwrap();
+    /// root.crop_to_content(1.0);
+    /// assert_eq!(
+    ///     stringify(root),
+    ///     r#"<svg height="20" viewBox="3 5 12 10" width="24"><rect height="8" width="10" x="4" y="6"/></svg>"#
+    /// );
+    /// ```
+    ///
+    pub fn crop_to_content(self: &Rc<Self>, padding: f64) -> Option<ViewBox> {
+        let bbox = self.content_bbox()?;
+        Some(self.crop(ViewBox::new(
+            bbox.min_x - padding,
+            bbox.min_y - padding,
+            bbox.width() + 2.0 * padding,
+            bbox.height() + 2.0 * padding,
+        )))
+    }
+
+    /// like `Element::crop_to_content`, but the view box is the smallest square
+    /// with the content in its center
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, ViewBox};
+    ///
+    /// let (_, root) = parse(r#"<svg><circle cx="10" cy="10" r="2"/><circle cx="20" cy="10" r="2"/></svg>"#).unwrap();
+    /// assert_eq!(root.crop_to_square(0.0), Some(ViewBox::new(8.0, 3.0, 14.0, 14.0)));
+    /// assert_eq!(root.get_attribute("width"), None);
+    /// ```
+    ///
+    pub fn crop_to_square(self: &Rc<Self>, padding: f64) -> Option<ViewBox> {
+        let bbox = self.content_bbox()?;
+        let size = bbox.width().max(bbox.height()) + 2.0 * padding;
+        let (center_x, center_y) = (
+            (bbox.min_x + bbox.max_x) / 2.0,
+            (bbox.min_y + bbox.max_y) / 2.0,
+        );
+        Some(self.crop(ViewBox::new(
+            center_x - size / 2.0,
+            center_y - size / 2.0,
+            size,
+            size,
+        )))
+    }
+
+    /// the painted box of the content of a `svg`, with its `<use>` expanded
+    fn content_bbox(self: &Rc<Self>) -> Option<BBox> {
+        if self.ele_type != "svg" {
+            return None;
+        }
+        let document = Document::new(self.deep_clone());
+        // a recursive use is left in place and draws nothing
+        let _ = expand_use(&document);
+        element_bbox(
+            &document.root,
+            &Matrix::default(),
+            &ComputedStyles::new(document.stylesheet()),
+            true,
+        )
+        .filter(|bbox| bbox.width() > 0.0 || bbox.height() > 0.0)
+    }
+
+    /// write the view box, and resize the present `width` and `height`
+    fn crop(&self, view_box: ViewBox) -> ViewBox {
+        let view_box = ViewBox::new(
+            snap(view_box.min_x),
+            snap(view_box.min_y),
+            snap(view_box.width),
+            snap(view_box.height),
+        );
+        // the scale of the current viewport, or user units without a view box
+        let (scale_x, scale_y) = match self.view_box() {
+            Ok(Some(_)) => self
+                .viewport_transform(&LengthContext::default())
+                .map_or((1.0, 1.0), |m| (m.a.abs(), m.d.abs())),
+            _ => (1.0, 1.0),
+        };
+        for (key, size) in [
+            ("width", view_box.width * scale_x),
+            ("height", view_box.height * scale_y),
+        ] {
+            if self.get_attribute(key).is_some() {
+                self.set_attribute(key, shortest_number(snap(size)));
+            }
+        }
+        self.set_attribute("viewBox", view_box.to_string());
+        view_box
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, ViewBox};
+
+    #[test]
+    fn test_crop_to_content() {
+        let (_, root) = parse(
+            r#"<svg width="1in" height="10"><g transform="translate(10 10)"><path d="M0 0 H10" stroke="red" stroke-width="2" stroke-linecap="square"/></g></svg>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            root.crop_to_content(0.5),
+            Some(ViewBox::new(8.5, 8.5, 13.0, 3.0))
+        );
+        assert_eq!(root.get_attribute("width").as_deref(), Some("13"));
+        assert_eq!(root.get_attribute("height").as_deref(), Some("3"));
+        // a square keeps the content centered
+        assert_eq!(
+            root.crop_to_square(0.0),
+            Some(ViewBox::new(9.0, 4.0, 12.0, 12.0))
+        );
+        assert_eq!(root.get_attribute("width").as_deref(), Some("12"));
+
+        let (_, root) =
+            parse(r#"<svg viewBox="0 0 10 10"><defs><rect width="5" height="5"/></defs></svg>"#)
+                .unwrap();
+        assert_eq!(root.crop_to_content(1.0), None);
+        assert_eq!(root.get_attribute("viewBox").as_deref(), Some("0 0 10 10"));
+        let (_, group) = parse(r#"<g><rect width="5" height="5"/></g>"#).unwrap();
+        assert_eq!(group.crop_to_content(0.0), None);
+    }
+
+    #[test]
+    fn test_crop_to_referenced_content() {
+        // the content drawn by a use and a image, and a stroke from a stylesheet
+        let (_, root) = parse(
+            r##"<svg><style>.a{stroke:red;stroke-width:2}</style><defs><rect id="r" width="4" height="4"/></defs><use href="#r" x="20" y="20"/><image x="-5" y="0" width="5" height="5" href="a.png"/><rect class="a" x="10" width="2" height="2"/></svg>"##,
+        )
+        .unwrap();
+        assert_eq!(
+            root.crop_to_content(0.0),
+            Some(ViewBox::new(-5.0, -1.0, 29.0, 25.0))
+        );
+        // the original tree keeps its use
+        assert_eq!(root.children.borrow()[2].ele_type, "use");
+    }
+}
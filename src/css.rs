@@ -0,0 +1,1231 @@
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::Element;
+use crate::document::Document;
+use crate::style::{is_presentation_attribute, split_top_level, unescape, Declaration, Style};
+
+/// the properties which a element takes from its parent when it does not set them
+const INHERITED_PROPERTIES: [&str; 44] = [
+    "clip-rule",
+    "color",
+    "color-interpolation",
+    "color-interpolation-filters",
+    "color-profile",
+    "color-rendering",
+    "cursor",
+    "direction",
+    "dominant-baseline",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "font",
+    "font-family",
+    "font-size",
+    "font-size-adjust",
+    "font-stretch",
+    "font-style",
+    "font-variant",
+    "font-weight",
+    "glyph-orientation-horizontal",
+    "glyph-orientation-vertical",
+    "image-rendering",
+    "kerning",
+    "letter-spacing",
+    "marker-end",
+    "marker-mid",
+    "marker-start",
+    "paint-order",
+    "pointer-events",
+    "shape-rendering",
+    "stroke",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-opacity",
+    "stroke-width",
+    "text-anchor",
+    "text-rendering",
+    "visibility",
+    "word-spacing",
+    "writing-mode",
+];
+
+fn is_inherited(name: &str) -> bool {
+    INHERITED_PROPERTIES.contains(&name)
+}
+
+/// the initial values of the inherited properties, but the fonts and markers
+const INITIAL_VALUES: [(&str, &str); 39] = [
+    ("clip-rule", "nonzero"),
+    ("color", "black"),
+    ("color-interpolation", "sRGB"),
+    ("color-interpolation-filters", "linearRGB"),
+    ("color-profile", "auto"),
+    ("color-rendering", "auto"),
+    ("cursor", "auto"),
+    ("direction", "ltr"),
+    ("dominant-baseline", "auto"),
+    ("fill", "black"),
+    ("fill-opacity", "1"),
+    ("fill-rule", "nonzero"),
+    ("font-size", "medium"),
+    ("font-size-adjust", "none"),
+    ("font-stretch", "normal"),
+    ("font-style", "normal"),
+    ("font-variant", "normal"),
+    ("font-weight", "normal"),
+    ("glyph-orientation-horizontal", "0deg"),
+    ("glyph-orientation-vertical", "auto"),
+    ("image-rendering", "auto"),
+    ("kerning", "auto"),
+    ("letter-spacing", "normal"),
+    ("paint-order", "normal"),
+    ("pointer-events", "visiblePainted"),
+    ("shape-rendering", "auto"),
+    ("stroke", "none"),
+    ("stroke-dasharray", "none"),
+    ("stroke-dashoffset", "0"),
+    ("stroke-linecap", "butt"),
+    ("stroke-linejoin", "miter"),
+    ("stroke-miterlimit", "4"),
+    ("stroke-opacity", "1"),
+    ("stroke-width", "1"),
+    ("text-anchor", "start"),
+    ("text-rendering", "auto"),
+    ("visibility", "visible"),
+    ("word-spacing", "normal"),
+    ("writing-mode", "lr-tb"),
+];
+
+/// the value of a inherited property when neither the element nor its
+/// ancestors set it, `None` if it depends on the user agent
+pub(crate) fn initial_value(name: &str) -> Option<&'static str> {
+    INITIAL_VALUES
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| *value)
+}
+
+/// the inherited properties which a computed style may hold
+pub(crate) fn inherited_properties() -> impl Iterator<Item = &'static str> {
+    INHERITED_PROPERTIES.iter().copied()
+}
+
+/// the longhands set by a declaration, the `marker` shorthand sets the three
+/// marker properties
+fn longhands(declaration: Declaration) -> Vec<Declaration> {
+    match declaration.name.as_str() {
+        "marker" => ["marker-start", "marker-mid", "marker-end"]
+            .iter()
+            .map(|name| Declaration {
+                name: name.to_string(),
+                ..declaration.clone()
+            })
+            .collect(),
+        _ => vec![declaration],
+    }
+}
+
+/// How a attribute selector compares the value of the attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeOperator {
+    /// `[name]`
+    Exists,
+    /// `[name=value]`
+    Equals,
+    /// `[name~=value]`, one of the whitespace separated words
+    Includes,
+    /// `[name|=value]`, the value or the value followed by `-`
+    DashMatch,
+    /// `[name^=value]`
+    Prefix,
+    /// `[name$=value]`
+    Suffix,
+    /// `[name*=value]`
+    Substring,
+}
+
+/// A simple selector, one condition on a element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimpleSelector {
+    Universal,
+    Type(String),
+    Id(String),
+    Class(String),
+    Attribute {
+        name: String,
+        operator: AttributeOperator,
+        value: String,
+    },
+    Root,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+}
+
+/// How two compound selectors are related
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Combinator {
+    /// `a b`
+    Descendant,
+    /// `a > b`
+    Child,
+    /// `a + b`
+    NextSibling,
+    /// `a ~ b`
+    SubsequentSibling,
+}
+
+/// A complex selector, like `g.icon > rect:first-child`
+///
+/// `compounds` the compound selectors from left to right, each a list of simple selectors
+///
+/// `combinators` the combinator between each compound and the next one
+///
+/// `Display` writes the selector in a normalized form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    pub compounds: Vec<Vec<SimpleSelector>>,
+    pub combinators: Vec<Combinator>,
+}
+
+/// What went wrong when parsing a selector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectorErrorKind {
+    ExpectedSelector,
+    InvalidAttribute,
+    /// a pseudo-class which depends on user interaction, or a pseudo-element
+    UnsupportedPseudoClass,
+}
+
+/// A error in a selector
+///
+/// `position` the byte offset of the error in the selector
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectorError {
+    pub position: usize,
+    pub kind: SelectorErrorKind,
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            SelectorErrorKind::ExpectedSelector => "expected a selector",
+            SelectorErrorKind::InvalidAttribute => "invalid attribute selector",
+            SelectorErrorKind::UnsupportedPseudoClass => "unsupported pseudo-class",
+        };
+        write!(f, "{} at position {}", message, self.position)
+    }
+}
+
+impl Error for SelectorError {}
+
+/// a scanner over the characters of a selector
+struct Scanner<'s> {
+    input: &'s str,
+    chars: Vec<(usize, char)>,
+    index: usize,
+}
+
+impl<'s> Scanner<'s> {
+    fn new(input: &'s str) -> Self {
+        Scanner {
+            input,
+            chars: input.char_indices().collect(),
+            index: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).map(|(_, c)| *c)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.index += 1;
+        c
+    }
+
+    fn error(&self, kind: SelectorErrorKind) -> SelectorError {
+        let position = self
+            .chars
+            .get(self.index)
+            .map_or(self.input.len(), |(i, _)| *i);
+        SelectorError { position, kind }
+    }
+
+    /// skip whitespace, `true` if there was some
+    fn whitespace(&mut self) -> bool {
+        let start = self.index;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.index += 1;
+        }
+        self.index > start
+    }
+
+    /// a identifier with its escapes resolved, `None` if there is none
+    fn identifier(&mut self) -> Option<String> {
+        let mut raw = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\\' {
+                raw.push(c);
+                self.index += 1;
+                raw.extend(self.next());
+            } else if c.is_alphanumeric() || c == '-' || c == '_' || !c.is_ascii() {
+                raw.push(c);
+                self.index += 1;
+            } else {
+                break;
+            }
+        }
+        Some(unescape(&raw)).filter(|ident| !ident.is_empty())
+    }
+
+    /// a quoted string without its quotes, or a identifier
+    fn value(&mut self) -> Option<String> {
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.index += 1;
+                let mut value = String::new();
+                loop {
+                    match self.next()? {
+                        c if c == quote => return Some(value),
+                        '\\' => value.extend(self.next()),
+                        c => value.push(c),
+                    }
+                }
+            }
+            _ => self.identifier(),
+        }
+    }
+
+    fn attribute(&mut self) -> Result<SimpleSelector, SelectorError> {
+        let invalid = |scanner: &Self| scanner.error(SelectorErrorKind::InvalidAttribute);
+        self.whitespace();
+        let name = self.identifier().ok_or_else(|| invalid(self))?;
+        self.whitespace();
+        let operator = match self.next() {
+            Some(']') => {
+                return Ok(SimpleSelector::Attribute {
+                    name,
+                    operator: AttributeOperator::Exists,
+                    value: String::new(),
+                })
+            }
+            Some('=') => AttributeOperator::Equals,
+            Some(c) if self.peek() == Some('=') => {
+                self.index += 1;
+                match c {
+                    '~' => AttributeOperator::Includes,
+                    '|' => AttributeOperator::DashMatch,
+                    '^' => AttributeOperator::Prefix,
+                    '$' => AttributeOperator::Suffix,
+                    '*' => AttributeOperator::Substring,
+                    _ => return Err(invalid(self)),
+                }
+            }
+            _ => return Err(invalid(self)),
+        };
+        self.whitespace();
+        let value = self.value().ok_or_else(|| invalid(self))?;
+        self.whitespace();
+        match self.next() {
+            Some(']') => Ok(SimpleSelector::Attribute {
+                name,
+                operator,
+                value,
+            }),
+            _ => Err(invalid(self)),
+        }
+    }
+
+    fn compound(&mut self) -> Result<Vec<SimpleSelector>, SelectorError> {
+        let mut compound = vec![];
+        while let Some(c) = self.peek() {
+            let simple = match c {
+                '*' if compound.is_empty() => {
+                    self.index += 1;
+                    SimpleSelector::Universal
+                }
+                '#' | '.' => {
+                    self.index += 1;
+                    let name = self
+                        .identifier()
+                        .ok_or_else(|| self.error(SelectorErrorKind::ExpectedSelector))?;
+                    if c == '#' {
+                        SimpleSelector::Id(name)
+                    } else {
+                        SimpleSelector::Class(name)
+                    }
+                }
+                '[' => {
+                    self.index += 1;
+                    self.attribute()?
+                }
+                ':' => {
+                    let error = self.error(SelectorErrorKind::UnsupportedPseudoClass);
+                    self.index += 1;
+                    let name = self.identifier().map(|name| name.to_ascii_lowercase());
+                    match name.as_deref() {
+                        Some("root") => SimpleSelector::Root,
+                        Some("first-child") => SimpleSelector::FirstChild,
+                        Some("last-child") => SimpleSelector::LastChild,
+                        Some("only-child") => SimpleSelector::OnlyChild,
+                        _ => return Err(error),
+                    }
+                }
+                _ if compound.is_empty() => match self.identifier() {
+                    Some(name) => SimpleSelector::Type(name),
+                    None => break,
+                },
+                _ => break,
+            };
+            compound.push(simple);
+        }
+        if compound.is_empty() {
+            return Err(self.error(SelectorErrorKind::ExpectedSelector));
+        }
+        Ok(compound)
+    }
+}
+
+/// the element siblings before a element, the closest first
+fn previous_siblings<'a>(ele: &Rc<Element<'a>>) -> Vec<Rc<Element<'a>>> {
+    let parent = match ele.parent.borrow().upgrade() {
+        Some(parent) => parent,
+        None => return vec![],
+    };
+    let children = parent.children.borrow();
+    let index = children
+        .iter()
+        .position(|child| Rc::ptr_eq(child, ele))
+        .unwrap_or(0);
+    children[..index].iter().rev().cloned().collect()
+}
+
+fn matches_simple(simple: &SimpleSelector, ele: &Rc<Element>) -> bool {
+    let sibling_count = || {
+        ele.parent.borrow().upgrade().map(|parent| {
+            let children = parent.children.borrow();
+            let index = children.iter().position(|child| Rc::ptr_eq(child, ele));
+            (index.unwrap_or(0), children.len())
+        })
+    };
+    match simple {
+        SimpleSelector::Universal => true,
+        SimpleSelector::Type(name) => ele.ele_type == name,
+        SimpleSelector::Id(id) => ele.get_attribute("id").is_some_and(|value| value == *id),
+        SimpleSelector::Class(class) => ele
+            .get_attribute("class")
+            .is_some_and(|value| value.split_whitespace().any(|c| c == class)),
+        SimpleSelector::Attribute {
+            name,
+            operator,
+            value,
+        } => {
+            let attribute = match ele.get_attribute(name) {
+                Some(attribute) => attribute,
+                None => return false,
+            };
+            match operator {
+                AttributeOperator::Exists => true,
+                AttributeOperator::Equals => attribute == value.as_str(),
+                AttributeOperator::Includes => attribute.split_whitespace().any(|w| w == value),
+                AttributeOperator::DashMatch => {
+                    attribute == value.as_str() || attribute.starts_with(&format!("{}-", value))
+                }
+                AttributeOperator::Prefix => {
+                    !value.is_empty() && attribute.starts_with(value.as_str())
+                }
+                AttributeOperator::Suffix => {
+                    !value.is_empty() && attribute.ends_with(value.as_str())
+                }
+                AttributeOperator::Substring => {
+                    !value.is_empty() && attribute.contains(value.as_str())
+                }
+            }
+        }
+        SimpleSelector::Root => ele.parent.borrow().upgrade().is_none(),
+        SimpleSelector::FirstChild => sibling_count().is_some_and(|(index, _)| index == 0),
+        SimpleSelector::LastChild => {
+            sibling_count().is_some_and(|(index, count)| index + 1 == count)
+        }
+        SimpleSelector::OnlyChild => sibling_count().is_some_and(|(_, count)| count == 1),
+    }
+}
+
+/// escape the characters of a identifier which can not be written as they are
+fn escape_identifier(ident: &str) -> String {
+    let mut escaped = String::new();
+    for (i, c) in ident.chars().enumerate() {
+        let digit_first = i == 0 && c.is_ascii_digit();
+        if digit_first {
+            escaped.push_str(&format!("\\{:x} ", c as u32));
+        } else if c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii() {
+            escaped.push(c);
+        } else {
+            escaped.push('\\');
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+impl fmt::Display for SimpleSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimpleSelector::Universal => write!(f, "*"),
+            SimpleSelector::Type(name) => write!(f, "{}", escape_identifier(name)),
+            SimpleSelector::Id(id) => write!(f, "#{}", escape_identifier(id)),
+            SimpleSelector::Class(class) => write!(f, ".{}", escape_identifier(class)),
+            SimpleSelector::Attribute {
+                name,
+                operator,
+                value,
+            } => {
+                let operator = match operator {
+                    AttributeOperator::Exists => return write!(f, "[{}]", escape_identifier(name)),
+                    AttributeOperator::Equals => "=",
+                    AttributeOperator::Includes => "~=",
+                    AttributeOperator::DashMatch => "|=",
+                    AttributeOperator::Prefix => "^=",
+                    AttributeOperator::Suffix => "$=",
+                    AttributeOperator::Substring => "*=",
+                };
+                let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+                write!(f, "[{}{}\"{}\"]", escape_identifier(name), operator, value)
+            }
+            SimpleSelector::Root => write!(f, ":root"),
+            SimpleSelector::FirstChild => write!(f, ":first-child"),
+            SimpleSelector::LastChild => write!(f, ":last-child"),
+            SimpleSelector::OnlyChild => write!(f, ":only-child"),
+        }
+    }
+}
+
+impl Selector {
+    /// parse a complex selector
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{Combinator, Selector, SimpleSelector};
+    ///
+    /// let selector = Selector::parse("g.icon>rect:first-child").unwrap();
+    /// assert_eq!(selector.combinators, vec![Combinator::Child]);
+    /// assert_eq!(selector.compounds[0], vec![
+    ///     SimpleSelector::Type("g".to_owned()),
+    ///     SimpleSelector::Class("icon".to_owned()),
+    /// ]);
+    /// assert_eq!(selector.specificity(), (0, 2, 2));
+    /// assert_eq!(selector.to_string(), "g.icon > rect:first-child");
+    ///
+    /// let error = Selector::parse("a:hover").unwrap_err();
+    /// assert_eq!(error.to_string(), "unsupported pseudo-class at position 1");
+    /// ```
+    ///
+    pub fn parse(input: &str) -> Result<Selector, SelectorError> {
+        let mut scanner = Scanner::new(input);
+        let mut selector = Selector {
+            compounds: vec![],
+            combinators: vec![],
+        };
+        scanner.whitespace();
+        loop {
+            selector.compounds.push(scanner.compound()?);
+            let spaced = scanner.whitespace();
+            let combinator = match scanner.peek() {
+                None => return Ok(selector),
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::NextSibling,
+                Some('~') => Combinator::SubsequentSibling,
+                Some(_) if spaced => Combinator::Descendant,
+                Some(_) => return Err(scanner.error(SelectorErrorKind::ExpectedSelector)),
+            };
+            if combinator != Combinator::Descendant {
+                scanner.index += 1;
+                scanner.whitespace();
+            }
+            selector.combinators.push(combinator);
+        }
+    }
+
+    /// parse a comma separated list of selectors
+    pub fn parse_list(input: &str) -> Result<Vec<Selector>, SelectorError> {
+        let mut offset = 0;
+        let mut selectors = vec![];
+        for part in split_top_level(input, ',') {
+            let selector = Selector::parse(&part).map_err(|error| SelectorError {
+                position: offset + error.position,
+                ..error
+            })?;
+            selectors.push(selector);
+            offset += part.len() + 1;
+        }
+        Ok(selectors)
+    }
+
+    /// the specificity, the number of id selectors, of class, attribute and
+    /// pseudo-class selectors and of type selectors
+    pub fn specificity(&self) -> (u32, u32, u32) {
+        let mut specificity = (0, 0, 0);
+        for simple in self.compounds.iter().flatten() {
+            match simple {
+                SimpleSelector::Universal => {}
+                SimpleSelector::Id(_) => specificity.0 += 1,
+                SimpleSelector::Type(_) => specificity.2 += 1,
+                _ => specificity.1 += 1,
+            }
+        }
+        specificity
+    }
+
+    /// whether the selector matches a element, in the tree the element is in
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, Selector};
+    ///
+    /// let (_, root) = parse(r#"<svg><g class="icon big"><rect/><circle/></g></svg>"#).unwrap();
+    /// let circle = root.descendants()[2].clone();
+    /// assert!(Selector::parse("svg .big circle").unwrap().matches(&circle));
+    /// assert!(Selector::parse("rect + circle:last-child").unwrap().matches(&circle));
+    /// assert!(!Selector::parse("svg > circle").unwrap().matches(&circle));
+    /// ```
+    ///
+    pub fn matches(&self, ele: &Rc<Element>) -> bool {
+        self.matches_at(ele, self.compounds.len() - 1)
+    }
+
+    fn matches_at(&self, ele: &Rc<Element>, index: usize) -> bool {
+        if !self.compounds[index]
+            .iter()
+            .all(|simple| matches_simple(simple, ele))
+        {
+            return false;
+        }
+        if index == 0 {
+            return true;
+        }
+        match self.combinators[index - 1] {
+            Combinator::Child => ele
+                .parent
+                .borrow()
+                .upgrade()
+                .is_some_and(|parent| self.matches_at(&parent, index - 1)),
+            Combinator::Descendant => {
+                let mut current = ele.parent.borrow().upgrade();
+                while let Some(ancestor) = current {
+                    if self.matches_at(&ancestor, index - 1) {
+                        return true;
+                    }
+                    current = ancestor.parent.borrow().upgrade();
+                }
+                false
+            }
+            Combinator::NextSibling => previous_siblings(ele)
+                .first()
+                .is_some_and(|sibling| self.matches_at(sibling, index - 1)),
+            Combinator::SubsequentSibling => previous_siblings(ele)
+                .iter()
+                .any(|sibling| self.matches_at(sibling, index - 1)),
+        }
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, compound) in self.compounds.iter().enumerate() {
+            if i > 0 {
+                let combinator = match self.combinators[i - 1] {
+                    Combinator::Descendant => " ",
+                    Combinator::Child => " > ",
+                    Combinator::NextSibling => " + ",
+                    Combinator::SubsequentSibling => " ~ ",
+                };
+                write!(f, "{}", combinator)?;
+            }
+            for simple in compound {
+                write!(f, "{}", simple)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A style rule, the declarations which apply to the elements matching any of the selectors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub selectors: Vec<Selector>,
+    pub style: Style,
+}
+
+/// A parsed stylesheet
+///
+/// `rules` the style rules in source order
+///
+/// `unsupported` the source of the at-rules and of the rules whose selectors
+/// are not supported, like `@media` or `a:hover`
+///
+/// `Display` writes the rules followed by the unsupported source.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stylesheet {
+    pub rules: Vec<Rule>,
+    pub unsupported: Vec<String>,
+}
+
+/// split a stylesheet into its statements, a prelude with a optional block.
+/// comments are dropped.
+fn statements(input: &str) -> Vec<(String, Option<String>)> {
+    let input = split_top_level(input, '\0').concat();
+    let mut statements = vec![];
+    let mut prelude = String::new();
+    let mut chars = input.chars();
+    let mut quote: Option<char> = None;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                prelude.push(c);
+                prelude.extend(chars.next());
+            }
+            c if quote == Some(c) => {
+                quote = None;
+                prelude.push(c);
+            }
+            _ if quote.is_some() => prelude.push(c),
+            '"' | '\'' => {
+                quote = Some(c);
+                prelude.push(c);
+            }
+            ';' if prelude.trim_start().starts_with('@') => {
+                statements.push((std::mem::take(&mut prelude), None));
+            }
+            '{' => {
+                let mut block = String::new();
+                let mut depth = 1;
+                let mut block_quote: Option<char> = None;
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            block.push(c);
+                            block.extend(chars.next());
+                            continue;
+                        }
+                        c if block_quote == Some(c) => block_quote = None,
+                        _ if block_quote.is_some() => {}
+                        '"' | '\'' => block_quote = Some(c),
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    block.push(c);
+                }
+                statements.push((std::mem::take(&mut prelude), Some(block)));
+            }
+            _ => prelude.push(c),
+        }
+    }
+    statements
+}
+
+/// a declaration which applies to a element, with its origin, the specificity
+/// of its rule and its position among its origin
+type CascadedDeclaration = (Origin, (u32, u32, u32), usize, Declaration);
+
+/// the place of a declaration in the cascade, the greater wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Origin {
+    PresentationAttribute,
+    Stylesheet,
+    Inline,
+    ImportantStylesheet,
+    ImportantInline,
+}
+
+impl Stylesheet {
+    /// parse a stylesheet, a `<!--`, `-->` or CDATA markup around it is ignored
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::Stylesheet;
+    ///
+    /// let sheet = Stylesheet::parse(".st0{fill:#F00} .st1, #a{stroke:blue;stroke-width:2} a:hover{fill:red} @media print{.st0{fill:none}}");
+    /// assert_eq!(sheet.rules.len(), 2);
+    /// assert_eq!(sheet.rules[1].style.get("stroke-width"), Some("2"));
+    /// assert_eq!(sheet.unsupported, vec!["a:hover{fill:red}", "@media print{.st0{fill:none}}"]);
+    /// ```
+    ///
+    pub fn parse(input: &str) -> Stylesheet {
+        let mut sheet = Stylesheet::default();
+        for (prelude, block) in statements(input) {
+            let prelude = prelude
+                .trim()
+                .trim_start_matches("<![CDATA[")
+                .trim_start_matches("<!--")
+                .trim_start_matches("-->")
+                .trim_start_matches("]]>")
+                .trim();
+            let source = match &block {
+                Some(block) => format!("{}{{{}}}", prelude, block),
+                None => format!("{};", prelude),
+            };
+            if prelude.starts_with('@') {
+                sheet.unsupported.push(source);
+                continue;
+            }
+            match (Selector::parse_list(prelude), block) {
+                (Ok(selectors), Some(block)) => sheet.rules.push(Rule {
+                    selectors,
+                    style: Style::parse(&block),
+                }),
+                _ if prelude.is_empty() => {}
+                _ => sheet.unsupported.push(source),
+            }
+        }
+        sheet
+    }
+
+    /// the declarations which apply to a element, in increasing precedence
+    fn declarations(&self, ele: &Rc<Element>) -> Vec<CascadedDeclaration> {
+        let mut declarations = vec![];
+        for (key, value) in ele.attributes.borrow().iter() {
+            if is_presentation_attribute(key) {
+                let declaration = Declaration::new(key, value.trim());
+                declarations.push((Origin::PresentationAttribute, (0, 0, 0), 0, declaration));
+            }
+        }
+        let mut order = 0;
+        for rule in &self.rules {
+            let specificity = rule
+                .selectors
+                .iter()
+                .filter(|selector| selector.matches(ele))
+                .map(Selector::specificity)
+                .max();
+            if let Some(specificity) = specificity {
+                for declaration in &rule.style.declarations {
+                    let origin = match declaration.important {
+                        true => Origin::ImportantStylesheet,
+                        false => Origin::Stylesheet,
+                    };
+                    for declaration in longhands(declaration.clone()) {
+                        declarations.push((origin, specificity, order, declaration));
+                    }
+                    order += 1;
+                }
+            }
+        }
+        for (order, declaration) in ele.style().declarations.into_iter().enumerate() {
+            let origin = match declaration.important {
+                true => Origin::ImportantInline,
+                false => Origin::Inline,
+            };
+            for declaration in longhands(declaration) {
+                declarations.push((origin, (0, 0, 0), order, declaration));
+            }
+        }
+        declarations.sort_by_key(|a| (a.0, a.1, a.2));
+        declarations
+    }
+
+    /// the winning value of each property set on a element, from its presentation
+    /// attributes, the rules of the stylesheet and its `style` attribute
+    ///
+    /// the declarations are sorted by property and none is `!important`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, Stylesheet};
+    ///
+    /// let (_, rect) = parse(r#"<rect id="a" class="b" fill="red" stroke="red" style="stroke:green"/>"#).unwrap();
+    /// let sheet = Stylesheet::parse("#a{fill:blue} .b{fill:black;stroke:black!important} rect{opacity:.5}");
+    /// assert_eq!(sheet.cascaded_style(&rect).to_string(), "fill:blue;opacity:.5;stroke:black");
+    /// ```
+    ///
+    pub fn cascaded_style(&self, ele: &Rc<Element>) -> Style {
+        let mut properties = BTreeMap::new();
+        for (_, _, _, declaration) in self.declarations(ele) {
+            properties.insert(declaration.name, declaration.value);
+        }
+        Style {
+            declarations: properties
+                .into_iter()
+                .map(|(name, value)| Declaration::new(&name, &value))
+                .collect(),
+        }
+    }
+
+    /// the cascaded style of a element with the inherited properties of its
+    /// ancestors, and `inherit` replaced by the value of the parent
+    ///
+    /// the ancestors are computed once each, from the root down. to compute the
+    /// styles of a whole tree, walk it with `Stylesheet::computed_style_from`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, Stylesheet};
+    ///
+    /// let (_, root) = parse(r#"<g class="a" opacity=".5"><rect fill="inherit" opacity="inherit"/></g>"#).unwrap();
+    /// let rect = root.children.borrow()[0].clone();
+    /// let sheet = Stylesheet::parse(".a{fill:red;font-size:12px}");
+    /// assert_eq!(sheet.computed_style(&rect).to_string(), "fill:red;font-size:12px;opacity:.5");
+    /// ```
+    ///
+    pub fn computed_style(&self, ele: &Rc<Element>) -> Style {
+        let mut ancestors = vec![ele.clone()];
+        loop {
+            let parent = ancestors.last().unwrap().parent.borrow().upgrade();
+            match parent {
+                Some(parent) => ancestors.push(parent),
+                None => break,
+            }
+        }
+        ancestors
+            .iter()
+            .rev()
+            .fold(Style::default(), |parent, ele| {
+                self.computed_style_from(ele, &parent)
+            })
+    }
+
+    /// the computed style of a element from the computed style of its parent
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, Stylesheet};
+    ///
+    /// let (_, root) = parse(r#"<g stroke="red"><rect stroke-width="inherit"/></g>"#).unwrap();
+    /// let rect = root.children.borrow()[0].clone();
+    /// let sheet = Stylesheet::default();
+    /// let parent = sheet.computed_style(&root);
+    /// assert_eq!(sheet.computed_style_from(&rect, &parent).to_string(), "stroke:red");
+    /// ```
+    ///
+    pub fn computed_style_from(&self, ele: &Rc<Element>, parent: &Style) -> Style {
+        let mut properties = BTreeMap::new();
+        for declaration in &parent.declarations {
+            if is_inherited(&declaration.name) {
+                properties.insert(declaration.name.clone(), declaration.value.clone());
+            }
+        }
+        for declaration in self.cascaded_style(ele).declarations {
+            if declaration.value.eq_ignore_ascii_case("inherit") {
+                match parent.get(&declaration.name) {
+                    Some(value) => properties.insert(declaration.name, value.to_owned()),
+                    None => properties.remove(&declaration.name),
+                };
+            } else {
+                properties.insert(declaration.name, declaration.value);
+            }
+        }
+        Style {
+            declarations: properties
+                .into_iter()
+                .map(|(name, value)| Declaration::new(&name, &value))
+                .collect(),
+        }
+    }
+}
+
+/// a computed style with its element, kept alive so its address is not reused
+type StyleEntry<'a> = (Rc<Element<'a>>, Rc<Style>);
+
+/// The computed styles of the elements of a tree, each computed once from the
+/// computed style of its parent
+///
+/// the styles are not updated when the tree changes.
+pub(crate) struct ComputedStyles<'a> {
+    sheet: Stylesheet,
+    styles: RefCell<HashMap<*const Element<'a>, StyleEntry<'a>>>,
+}
+
+impl<'a> ComputedStyles<'a> {
+    pub(crate) fn new(sheet: Stylesheet) -> Self {
+        ComputedStyles {
+            sheet,
+            styles: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// the styles of the tree of a element, with the rules of its `<style>` elements
+    pub(crate) fn of_tree(ele: &Rc<Element<'a>>) -> Self {
+        ComputedStyles::new(tree_stylesheet(ele))
+    }
+
+    /// the computed style of a element
+    pub(crate) fn style(&self, ele: &Rc<Element<'a>>) -> Rc<Style> {
+        let key = Rc::as_ptr(ele);
+        if let Some((_, style)) = self.styles.borrow().get(&key) {
+            return style.clone();
+        }
+        let parent = ele.parent.borrow().upgrade();
+        let parent = match parent {
+            Some(parent) => self.style(&parent),
+            None => Rc::new(Style::default()),
+        };
+        let style = Rc::new(self.sheet.computed_style_from(ele, &parent));
+        self.styles
+            .borrow_mut()
+            .insert(key, (ele.clone(), style.clone()));
+        style
+    }
+
+    /// the computed value of a property of a element
+    pub(crate) fn get(&self, ele: &Rc<Element<'a>>, name: &str) -> Option<String> {
+        self.style(ele).get(name).map(String::from)
+    }
+
+    /// the value a element gives to a property itself, from the cascade without
+    /// inheritance
+    pub(crate) fn specified(&self, ele: &Rc<Element<'a>>, name: &str) -> Option<String> {
+        self.sheet.cascaded_style(ele).get(name).map(String::from)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, selector) in self.selectors.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", selector)?;
+        }
+        write!(f, "{{{}}}", self.style)
+    }
+}
+
+impl fmt::Display for Stylesheet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for rule in &self.rules {
+            write!(f, "{}", rule)?;
+        }
+        for source in &self.unsupported {
+            write!(f, "{}", source)?;
+        }
+        Ok(())
+    }
+}
+
+/// whether a `<style>` element holds css
+fn is_css(ele: &Element) -> bool {
+    ele.ele_type == "style"
+        && ele.get_attribute("type").is_none_or(|value| {
+            value.trim().is_empty() || value.trim().eq_ignore_ascii_case("text/css")
+        })
+}
+
+/// the rules of every `<style>` element in the tree of a element, in document order
+pub(crate) fn tree_stylesheet(ele: &Rc<Element>) -> Stylesheet {
+    let mut root = ele.clone();
+    loop {
+        let parent = root.parent.borrow().upgrade();
+        match parent {
+            Some(parent) => root = parent,
+            None => break,
+        }
+    }
+    let mut sheet = Stylesheet::default();
+    let mut elements = vec![root.clone()];
+    elements.append(&mut root.descendants());
+    for ele in elements.iter().filter(|ele| is_css(ele)) {
+        let mut part = Stylesheet::parse(&ele.text_content());
+        sheet.rules.append(&mut part.rules);
+        sheet.unsupported.append(&mut part.unsupported);
+    }
+    sheet
+}
+
+impl<'a> Document<'a> {
+    /// the rules of every `<style>` element of the document, in document order
+    pub fn stylesheet(&self) -> Stylesheet {
+        tree_stylesheet(&self.root)
+    }
+}
+
+/// write the rules of the `<style>` elements into the `style` attribute of the
+/// elements they match, and remove the `<style>` elements
+///
+/// the presentation attributes are left as they are. the unsupported rules, like
+/// `@media` or `a:hover`, are kept in the first `<style>` element.
+///
+/// ## Example
+/// ```rust
+/// use svg_simple_parser::{inline_styles, parse_document, stringify};
+///
+/// let (_, document) = parse_document(r#"<svg><style>.st0{fill:#F00} rect{stroke:blue}</style><rect class="st0" fill="red" style="stroke:green"/></svg>"#).unwrap();
+/// inline_styles(&document);
+/// assert_eq!(
+///     stringify(document.root.clone()),
+///     r#"<svg><rect class="st0" fill="red" style="fill:#F00;stroke:green"/></svg>"#
+/// );
+/// ```
+///
+pub fn inline_styles(document: &Document) {
+    let sheet = document.stylesheet();
+    let mut elements = vec![document.root.clone()];
+    elements.append(&mut document.root.descendants());
+    for ele in &elements {
+        if is_css(ele) {
+            continue;
+        }
+        let declarations = sheet.declarations(ele);
+        let matched = declarations
+            .iter()
+            .any(|(origin, ..)| matches!(origin, Origin::Stylesheet | Origin::ImportantStylesheet));
+        if !matched {
+            continue;
+        }
+        let mut properties = BTreeMap::new();
+        for (origin, _, _, declaration) in declarations {
+            properties.insert(declaration.name, (origin, declaration.value));
+        }
+        let declarations = properties
+            .into_iter()
+            .filter(|(_, (origin, _))| *origin != Origin::PresentationAttribute)
+            .map(|(name, (_, value))| Declaration::new(&name, &value))
+            .collect();
+        ele.set_style(&Style { declarations });
+    }
+    let mut kept = false;
+    for ele in elements.iter().filter(|ele| is_css(ele)) {
+        if !kept && !sheet.unsupported.is_empty() {
+            ele.set_text_content(&sheet.unsupported.join("\n"));
+            kept = true;
+            continue;
+        }
+        let parent = ele.parent.borrow().upgrade();
+        if let Some(parent) = parent {
+            parent.remove_child(ele);
+        }
+    }
+    document.reindex();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::css::{statements, ComputedStyles, Selector, SelectorErrorKind, SimpleSelector};
+    use crate::{inline_styles, parse, parse_document, stringify, AttributeOperator, Stylesheet};
+
+    #[test]
+    fn test_parse_selector() {
+        let selector = Selector::parse(r#"svg  *[data-a|="x"] ~ .a\:b#c[d]"#).unwrap();
+        assert_eq!(
+            selector.compounds[1],
+            vec![
+                SimpleSelector::Universal,
+                SimpleSelector::Attribute {
+                    name: "data-a".to_owned(),
+                    operator: AttributeOperator::DashMatch,
+                    value: "x".to_owned(),
+                },
+            ]
+        );
+        assert_eq!(
+            selector.compounds[2][0],
+            SimpleSelector::Class("a:b".to_owned())
+        );
+        assert_eq!(selector.specificity(), (1, 3, 1));
+        assert_eq!(selector.to_string(), r#"svg *[data-a|="x"] ~ .a\:b#c[d]"#);
+        let kind = |input| Selector::parse(input).unwrap_err().kind;
+        assert_eq!(kind(""), SelectorErrorKind::ExpectedSelector);
+        assert_eq!(kind("a >"), SelectorErrorKind::ExpectedSelector);
+        assert_eq!(kind("a[b"), SelectorErrorKind::InvalidAttribute);
+        assert_eq!(kind("a::before"), SelectorErrorKind::UnsupportedPseudoClass);
+        let error = Selector::parse_list("a, b c, d:focus").unwrap_err();
+        assert_eq!(error.position, 9);
+    }
+
+    #[test]
+    fn test_matches() {
+        let (_, root) =
+            parse(r#"<svg><g id="g" data-x="a b"><rect class="r"/><circle/><path/></g></svg>"#)
+                .unwrap();
+        let nodes = root.descendants();
+        let matches = |selector: &str, index: usize| {
+            Selector::parse(selector).unwrap().matches(&nodes[index])
+        };
+        assert!(matches(":root > g", 0));
+        assert!(matches("[data-x~=b] > .r:first-child", 1));
+        assert!(!matches("[data-x^=b] .r", 1));
+        assert!(matches(".r ~ path:last-child", 3));
+        assert!(!matches(".r + path", 3));
+        assert!(matches("#g *", 2));
+        assert!(!matches("circle:only-child", 2));
+    }
+
+    #[test]
+    fn test_statements() {
+        assert_eq!(
+            statements(r#"@import "a;b.css"; /* x{} */ a{content:"}"} @media x{b{c:d}}"#),
+            vec![
+                (r#"@import "a;b.css""#.to_owned(), None),
+                ("  a".to_owned(), Some(r#"content:"}""#.to_owned())),
+                (" @media x".to_owned(), Some("b{c:d}".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cascade() {
+        let (_, document) = parse_document(
+            r#"<svg><style><![CDATA[
+                g rect { fill: blue; stroke-width: 3 }
+                .a { fill: green }
+                rect { fill: red !important; stroke: black }
+            ]]></style><g stroke-width="1" font-family="x"><rect class="a" fill="yellow" stroke="none" style="stroke:white"/></g></svg>"#,
+        )
+        .unwrap();
+        let sheet = document.stylesheet();
+        assert_eq!(sheet.rules.len(), 3);
+        let rect = document.root.descendants()[2].clone();
+        assert_eq!(
+            sheet.cascaded_style(&rect).to_string(),
+            "fill:red;stroke:white;stroke-width:3"
+        );
+        assert_eq!(
+            sheet.computed_style(&rect).to_string(),
+            "fill:red;font-family:x;stroke:white;stroke-width:3"
+        );
+        let styles = ComputedStyles::new(sheet);
+        assert_eq!(
+            styles.style(&rect).to_string(),
+            "fill:red;font-family:x;stroke:white;stroke-width:3"
+        );
+    }
+
+    #[test]
+    fn test_style_type() {
+        let (_, document) = parse_document(
+            r#"<svg><style type=" TEXT/CSS ">rect{fill:red}</style><style type="text/x-other">rect{fill:blue}</style><rect/></svg>"#,
+        )
+        .unwrap();
+        assert_eq!(document.stylesheet().to_string(), "rect{fill:red}");
+    }
+
+    #[test]
+    fn test_inline_styles() {
+        let (_, document) = parse_document(
+            r#"<svg><style>.a{fill:red} a:hover{fill:blue}</style><style>circle{stroke:red}</style><circle class="a" r="1"/><rect/></svg>"#,
+        )
+        .unwrap();
+        inline_styles(&document);
+        assert_eq!(
+            stringify(document.root.clone()),
+            r#"<svg><style>a:hover{fill:blue}</style><circle class="a" r="1" style="fill:red;stroke:red"/><rect/></svg>"#
+        );
+        assert_eq!(
+            Stylesheet::parse("a:hover{fill:blue}").to_string(),
+            "a:hover{fill:blue}"
+        );
+    }
+}
@@ -0,0 +1,459 @@
+/// the base length and extra bits of the deflate length codes 257 to 285
+const LENGTH_CODES: [(u16, u8); 29] = [
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 1),
+    (13, 1),
+    (15, 1),
+    (17, 1),
+    (19, 2),
+    (23, 2),
+    (27, 2),
+    (31, 2),
+    (35, 3),
+    (43, 3),
+    (51, 3),
+    (59, 3),
+    (67, 4),
+    (83, 4),
+    (99, 4),
+    (115, 4),
+    (131, 5),
+    (163, 5),
+    (195, 5),
+    (227, 5),
+    (258, 0),
+];
+
+/// the base distance and extra bits of the deflate distance codes
+const DISTANCE_CODES: [(u16, u8); 30] = [
+    (1, 0),
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (5, 1),
+    (7, 1),
+    (9, 2),
+    (13, 2),
+    (17, 3),
+    (25, 3),
+    (33, 4),
+    (49, 4),
+    (65, 5),
+    (97, 5),
+    (129, 6),
+    (193, 6),
+    (257, 7),
+    (385, 7),
+    (513, 8),
+    (769, 8),
+    (1025, 9),
+    (1537, 9),
+    (2049, 10),
+    (3073, 10),
+    (4097, 11),
+    (6145, 11),
+    (8193, 12),
+    (12289, 12),
+    (16385, 13),
+    (24577, 13),
+];
+
+const WINDOW: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// how many earlier positions with the same hash are tried for a match
+const MAX_CHAIN: usize = 64;
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in bytes.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+/// Writes bits from the least significant one, like deflate wants
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u32) {
+        self.buffer |= value << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// write a huffman code, which is stored from its most significant bit
+    fn write_code(&mut self, code: u32, bits: u32) {
+        let reversed = code.reverse_bits() >> (32 - bits);
+        self.write(reversed, bits);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// write a literal or length symbol with the fixed huffman codes
+fn write_symbol(writer: &mut BitWriter, symbol: u32) {
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn write_match(writer: &mut BitWriter, length: usize, distance: usize) {
+    let index = LENGTH_CODES
+        .iter()
+        .rposition(|(base, _)| *base as usize <= length)
+        .unwrap();
+    let (base, extra) = LENGTH_CODES[index];
+    write_symbol(writer, 257 + index as u32);
+    writer.write((length - base as usize) as u32, extra as u32);
+    let index = DISTANCE_CODES
+        .iter()
+        .rposition(|(base, _)| *base as usize <= distance)
+        .unwrap();
+    let (base, extra) = DISTANCE_CODES[index];
+    writer.write_code(index as u32, 5);
+    writer.write((distance - base as usize) as u32, extra as u32);
+}
+
+/// The earlier positions of every three bytes, to find matches
+///
+/// `head` the last position plus one of each hash, `previous` the position
+/// plus one before each position with the same hash
+struct HashChains {
+    head: Vec<usize>,
+    previous: Vec<usize>,
+}
+
+impl HashChains {
+    fn hash(data: &[u8], i: usize) -> usize {
+        ((data[i] as usize) << 10 ^ (data[i + 1] as usize) << 5 ^ data[i + 2] as usize)
+            & (WINDOW - 1)
+    }
+
+    fn insert(&mut self, data: &[u8], i: usize) {
+        if i + MIN_MATCH <= data.len() {
+            let h = HashChains::hash(data, i);
+            self.previous[i] = self.head[h];
+            self.head[h] = i + 1;
+        }
+    }
+
+    /// the longest earlier match of the bytes at `i`, as `(length, distance)`
+    fn longest_match(&self, data: &[u8], i: usize) -> (usize, usize) {
+        let (mut best_length, mut best_distance) = (0, 0);
+        if i + MIN_MATCH > data.len() {
+            return (0, 0);
+        }
+        let limit = MAX_MATCH.min(data.len() - i);
+        let mut candidate = self.head[HashChains::hash(data, i)];
+        let mut chain = 0;
+        while candidate > 0 && chain < MAX_CHAIN {
+            let start = candidate - 1;
+            if i - start > WINDOW {
+                break;
+            }
+            let length = (0..limit)
+                .take_while(|k| data[start + k] == data[i + k])
+                .count();
+            if length > best_length {
+                (best_length, best_distance) = (length, i - start);
+                if length == limit {
+                    break;
+                }
+            }
+            candidate = self.previous[start];
+            chain += 1;
+        }
+        (best_length, best_distance)
+    }
+}
+
+/// compress with a single deflate block of fixed huffman codes and greedy
+/// matches found through hash chains
+fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter {
+        bytes: vec![],
+        buffer: 0,
+        count: 0,
+    };
+    // the final block, with fixed huffman codes
+    writer.write(1, 1);
+    writer.write(1, 2);
+    let mut chains = HashChains {
+        head: vec![0; WINDOW],
+        previous: vec![0; data.len()],
+    };
+    let mut i = 0;
+    while i < data.len() {
+        let (length, distance) = chains.longest_match(data, i);
+        if length >= MIN_MATCH {
+            write_match(&mut writer, length, distance);
+            for k in i..i + length {
+                chains.insert(data, k);
+            }
+            i += length;
+        } else {
+            write_symbol(&mut writer, data[i] as u32);
+            chains.insert(data, i);
+            i += 1;
+        }
+    }
+    write_symbol(&mut writer, 256);
+    writer.finish()
+}
+
+/// compress with deflate in a zlib stream
+pub(crate) fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0x78, 0x9c];
+    bytes.extend(deflate(data));
+    bytes.extend(adler32(data).to_be_bytes());
+    bytes
+}
+
+/// the order of the code length code lengths of a dynamic block
+#[cfg(feature = "pdf")]
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Reads bits from the least significant one, like deflate wants
+#[cfg(feature = "pdf")]
+struct BitReader<'d> {
+    data: &'d [u8],
+    /// the position in bits
+    position: usize,
+}
+
+#[cfg(feature = "pdf")]
+impl<'d> BitReader<'d> {
+    fn bits(&mut self, count: u32) -> Option<u32> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = *self.data.get(self.position / 8)?;
+            value |= ((byte >> (self.position % 8)) as u32 & 1) << i;
+            self.position += 1;
+        }
+        Some(value)
+    }
+
+    /// the bytes from the next byte boundary
+    fn bytes(&mut self, count: usize) -> Option<&'d [u8]> {
+        let start = self.position.div_ceil(8);
+        let bytes = self.data.get(start..start + count)?;
+        self.position = (start + count) * 8;
+        Some(bytes)
+    }
+}
+
+/// A canonical huffman code, `counts` the number of codes of each length and
+/// `symbols` ordered by their code
+#[cfg(feature = "pdf")]
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+#[cfg(feature = "pdf")]
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for i in 1..16 {
+            offsets[i] = offsets[i - 1] + counts[i - 1];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length > 0 {
+                symbols[offsets[*length as usize] as usize] = symbol as u16;
+                offsets[*length as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Option<u16> {
+        // the first code and the index of the first symbol of each length
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for count in &self.counts[1..] {
+            code |= reader.bits(1)? as i32;
+            let count = *count as i32;
+            if code - first < count {
+                return self.symbols.get((index + code - first) as usize).copied();
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
+
+/// decompress a zlib stream, `None` for a invalid stream or one which holds
+/// more than `limit` bytes
+#[cfg(feature = "pdf")]
+pub(crate) fn inflate(data: &[u8], limit: usize) -> Option<Vec<u8>> {
+    let [method, flags, ..] = *data else {
+        return None;
+    };
+    if method & 0x0f != 8
+        || !(method as u16 * 256 + flags as u16).is_multiple_of(31)
+        || flags & 0x20 != 0
+    {
+        return None;
+    }
+    let mut reader = BitReader {
+        data: &data[2..],
+        position: 0,
+    };
+    let mut out = vec![];
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                let header = reader.bytes(4)?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]])
+                    || out.len() + length as usize > limit
+                {
+                    return None;
+                }
+                out.extend(reader.bytes(length as usize)?);
+            }
+            1 => {
+                let mut lengths = [8u8; 288];
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                let literals = Huffman::new(&lengths);
+                let distances = Huffman::new(&[5; 30]);
+                inflate_block(&mut reader, &literals, &distances, &mut out, limit)?;
+            }
+            2 => {
+                let literal_count = reader.bits(5)? as usize + 257;
+                let distance_count = reader.bits(5)? as usize + 1;
+                let code_count = reader.bits(4)? as usize + 4;
+                let mut code_lengths = [0u8; 19];
+                for i in CODE_LENGTH_ORDER.iter().take(code_count) {
+                    code_lengths[*i] = reader.bits(3)? as u8;
+                }
+                let codes = Huffman::new(&code_lengths);
+                let mut lengths: Vec<u8> = vec![];
+                while lengths.len() < literal_count + distance_count {
+                    let (value, repeat) = match codes.decode(&mut reader)? {
+                        symbol @ 0..=15 => (symbol as u8, 1),
+                        16 => (*lengths.last()?, 3 + reader.bits(2)?),
+                        17 => (0, 3 + reader.bits(3)?),
+                        18 => (0, 11 + reader.bits(7)?),
+                        _ => return None,
+                    };
+                    lengths.extend(std::iter::repeat_n(value, repeat as usize));
+                }
+                if lengths.len() != literal_count + distance_count {
+                    return None;
+                }
+                let literals = Huffman::new(&lengths[..literal_count]);
+                let distances = Huffman::new(&lengths[literal_count..]);
+                inflate_block(&mut reader, &literals, &distances, &mut out, limit)?;
+            }
+            _ => return None,
+        }
+        if last {
+            return Some(out);
+        }
+    }
+}
+
+/// decompress the symbols of a huffman block up to its end, `None` past `limit` bytes
+#[cfg(feature = "pdf")]
+fn inflate_block(
+    reader: &mut BitReader,
+    literals: &Huffman,
+    distances: &Huffman,
+    out: &mut Vec<u8>,
+    limit: usize,
+) -> Option<()> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 if out.len() >= limit => return None,
+            0..=255 => out.push(symbol as u8),
+            256 => return Some(()),
+            _ => {
+                let (base, extra) = *LENGTH_CODES.get(symbol - 257)?;
+                let length = base as usize + reader.bits(extra as u32)? as usize;
+                let (base, extra) = *DISTANCE_CODES.get(distances.decode(reader)? as usize)?;
+                let distance = base as usize + reader.bits(extra as u32)? as usize;
+                if distance > out.len() || out.len() + length > limit {
+                    return None;
+                }
+                for _ in 0..length {
+                    out.push(out[out.len() - distance]);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::deflate::adler32;
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn test_inflate() {
+        use crate::deflate::{inflate, zlib};
+
+        let data: Vec<u8> = (0..2000u32).map(|i| (i * i % 251) as u8).collect();
+        assert_eq!(inflate(&zlib(&data), 2000), Some(data.clone()));
+        // the output is limited
+        assert_eq!(inflate(&zlib(&data), 1999), None);
+        assert_eq!(inflate(&zlib(&[0; 100_000]), 1000), None);
+        // a stored block
+        assert_eq!(
+            inflate(b"\x78\x01\x01\x03\x00\xfc\xffabc", 3),
+            Some(b"abc".to_vec())
+        );
+        // a dynamic block, from zlib
+        assert_eq!(
+            inflate(b"\x78\xda\x25\x8a\x81\x09\x00\x30\x0c\xc2\x6e\x4d\xf4\xff\x1b\xd6\x76\x20\x18\x8c\x4a\x91\x89\x64\x8b\x0f\x85\xd4\xae\xf1\xf6\xaa\xf3\x4c\x1f\xe7\x44\x13\x22", 100),
+            Some(b"bbadabaababacaabaaabacaadaacdbdbaabbcaabadbbbdabcd".to_vec())
+        );
+        assert_eq!(inflate(b"\x78\x9c\xff", 100), None);
+    }
+}
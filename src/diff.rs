@@ -0,0 +1,792 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::Element;
+use crate::stringify::stringify;
+
+/// A element of the old tree of a diff
+///
+/// `path` the indexes of the element from the root
+///
+/// `id` the `id` of the element, used first to find it when a patch is applied
+///
+/// `ele_type` the type of the element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeRef {
+    pub path: Vec<usize>,
+    pub id: Option<String>,
+    pub ele_type: String,
+}
+
+/// A attribute which differs between two matched elements
+///
+/// `old`/`new` are `None` when the attribute is added/removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeChange {
+    pub name: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// A change between two trees
+///
+/// `parent` and `index` give the position of a element in the new tree.
+#[derive(Debug, Clone)]
+pub enum Change<'a> {
+    /// a element of the new tree without counterpart, with its subtree and its tail
+    Inserted {
+        parent: NodeRef,
+        index: usize,
+        element: Rc<Element<'a>>,
+    },
+    /// a element of the old tree without counterpart, with its subtree
+    Removed { node: NodeRef },
+    /// a element which changed parent or order among its siblings
+    Moved {
+        node: NodeRef,
+        parent: NodeRef,
+        index: usize,
+    },
+    /// a element whose attributes changed
+    Modified {
+        node: NodeRef,
+        attributes: Vec<AttributeChange>,
+    },
+    /// a element whose `text` changed, or its `tail` when `tail` is set
+    TextModified {
+        node: NodeRef,
+        tail: bool,
+        old: String,
+        new: String,
+    },
+    /// the root, when the roots have different types
+    Replaced {
+        node: NodeRef,
+        element: Rc<Element<'a>>,
+    },
+}
+
+/// The changes from a old tree to a new tree
+///
+/// `Display` gives a human readable report, `to_json` a machine readable one.
+#[derive(Debug, Clone)]
+pub struct Diff<'a> {
+    pub changes: Vec<Change<'a>>,
+}
+
+/// A element of a patch which cannot be found in the patched tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchError {
+    pub node: NodeRef,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot find {} in the tree", self.node)
+    }
+}
+
+impl Error for PatchError {}
+
+impl fmt::Display for NodeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path: String = self.path.iter().map(|i| format!("/{}", i)).collect();
+        let path = if path.is_empty() { "/" } else { &path };
+        match &self.id {
+            Some(id) => write!(f, "{} {}#{}", path, self.ele_type, id),
+            None => write!(f, "{} {}", path, self.ele_type),
+        }
+    }
+}
+
+/// compare two trees and list the changes from `old` to `new`
+///
+/// elements are matched by `id` first, then the children of matched elements
+/// are matched in order by type. the roots are always matched. the `text` of
+/// matched elements and the `tail` of matched children are compared too.
+///
+/// ## Example
+/// ```rust
+/// use svg_simple_parser::{diff, parse};
+///
+/// let (_, old) = parse(r#"<svg><rect id="a" fill="red"/><circle r="1"/></svg>"#).unwrap();
+/// let (_, new) = parse(r#"<svg><circle r="1"/><rect id="a" fill="blue"/><path d="M0 0"/></svg>"#).unwrap();
+/// let changes = diff(&old, &new);
+/// assert_eq!(
+///     changes.to_string(),
+///     "> /1 circle -> / svg at 0\n+ / svg at 2 <path d=\"M0 0\"/>\n~ /0 rect#a\n    fill: \"red\" -> \"blue\"\n"
+/// );
+///
+/// // the changes turn a copy of the old tree into the new tree
+/// let patched = changes.apply(&old.deep_clone()).unwrap();
+/// assert_eq!(patched, new);
+/// ```
+///
+pub fn diff<'a>(old: &Rc<Element<'a>>, new: &Rc<Element<'a>>) -> Diff<'a> {
+    let mut differ = Differ {
+        old_refs: HashMap::new(),
+        id_pairs: HashMap::new(),
+        matched_old: HashMap::new(),
+        matched_new: HashMap::new(),
+        changes: vec![],
+    };
+    differ.index_old(old, vec![]);
+    if old.ele_type != new.ele_type {
+        return Diff {
+            changes: vec![Change::Replaced {
+                node: differ.node_ref(old),
+                element: new.clone(),
+            }],
+        };
+    }
+    differ.pair_ids(old, new);
+    differ.link(old, new);
+    differ.match_children(old, new);
+    differ.collect_changes(old, new);
+    Diff {
+        changes: differ.changes,
+    }
+}
+
+struct Differ<'a> {
+    old_refs: HashMap<*const Element<'a>, NodeRef>,
+    /// new element -> old element with the same unique id
+    id_pairs: HashMap<*const Element<'a>, Rc<Element<'a>>>,
+    /// old element -> new element
+    matched_old: HashMap<*const Element<'a>, Rc<Element<'a>>>,
+    /// new element -> old element
+    matched_new: HashMap<*const Element<'a>, Rc<Element<'a>>>,
+    changes: Vec<Change<'a>>,
+}
+
+impl<'a> Differ<'a> {
+    fn index_old(&mut self, ele: &Rc<Element<'a>>, path: Vec<usize>) {
+        for (i, child) in ele.children.borrow().iter().enumerate() {
+            let mut child_path = path.clone();
+            child_path.push(i);
+            self.index_old(child, child_path);
+        }
+        let node = NodeRef {
+            path,
+            id: ele.get_attribute("id").map(String::from),
+            ele_type: ele.ele_type.to_owned(),
+        };
+        self.old_refs.insert(Rc::as_ptr(ele), node);
+    }
+
+    fn node_ref(&self, ele: &Rc<Element<'a>>) -> NodeRef {
+        self.old_refs[&Rc::as_ptr(ele)].clone()
+    }
+
+    fn link(&mut self, old: &Rc<Element<'a>>, new: &Rc<Element<'a>>) {
+        self.matched_old.insert(Rc::as_ptr(old), new.clone());
+        self.matched_new.insert(Rc::as_ptr(new), old.clone());
+    }
+
+    fn is_id_paired_old(&self, old: &Rc<Element<'a>>) -> bool {
+        self.id_pairs.values().any(|ele| Rc::ptr_eq(ele, old))
+    }
+
+    /// pair the elements with the same unique id and type, a pair is linked
+    /// once the parent of its new element is matched
+    fn pair_ids(&mut self, old: &Rc<Element<'a>>, new: &Rc<Element<'a>>) {
+        let old_ids = unique_ids(old);
+        for (id, new_ele) in unique_ids(new) {
+            if let Some(old_ele) = old_ids.get(&id) {
+                if old_ele.ele_type == new_ele.ele_type
+                    && !Rc::ptr_eq(old_ele, old)
+                    && !Rc::ptr_eq(&new_ele, new)
+                {
+                    self.id_pairs.insert(Rc::as_ptr(&new_ele), old_ele.clone());
+                }
+            }
+        }
+    }
+
+    /// match the children of two matched elements, then their subtrees
+    fn match_children(&mut self, old: &Rc<Element<'a>>, new: &Rc<Element<'a>>) {
+        let old_children = old.children.borrow().clone();
+        let new_children = new.children.borrow().clone();
+        for new_child in new_children.iter() {
+            if let Some(old_child) = self.id_pairs.get(&Rc::as_ptr(new_child)).cloned() {
+                self.link(&old_child, new_child);
+            }
+        }
+        for new_child in new_children.iter() {
+            if self.id_pairs.contains_key(&Rc::as_ptr(new_child)) {
+                continue;
+            }
+            let old_child = old_children.iter().find(|old_child| {
+                old_child.ele_type == new_child.ele_type
+                    && !self.matched_old.contains_key(&Rc::as_ptr(old_child))
+                    && !self.is_id_paired_old(old_child)
+            });
+            if let Some(old_child) = old_child.cloned() {
+                self.link(&old_child, new_child);
+            }
+        }
+        for new_child in new_children.iter() {
+            if let Some(old_child) = self.matched_new.get(&Rc::as_ptr(new_child)).cloned() {
+                self.match_children(&old_child, new_child);
+            }
+        }
+    }
+
+    fn collect_changes(&mut self, old: &Rc<Element<'a>>, new: &Rc<Element<'a>>) {
+        let attributes = attribute_changes(old, new);
+        if !attributes.is_empty() {
+            self.changes.push(Change::Modified {
+                node: self.node_ref(old),
+                attributes,
+            });
+        }
+        self.text_change(old, new, false);
+        for old_child in old.children.borrow().iter() {
+            if !self.matched_old.contains_key(&Rc::as_ptr(old_child)) {
+                self.changes.push(Change::Removed {
+                    node: self.node_ref(old_child),
+                });
+            }
+        }
+
+        let new_children = new.children.borrow().clone();
+        let old_children = old.children.borrow();
+        let matched: Vec<Option<Rc<Element<'a>>>> = new_children
+            .iter()
+            .map(|child| self.matched_new.get(&Rc::as_ptr(child)).cloned())
+            .collect();
+        // the children which stay under the same parent keep their place if
+        // they are in the longest run of increasing old indexes
+        let staying: Vec<(usize, usize)> = matched
+            .iter()
+            .enumerate()
+            .filter_map(|(i, old_child)| {
+                let old_child = old_child.as_ref()?;
+                let old_index = old_children
+                    .iter()
+                    .position(|node| Rc::ptr_eq(node, old_child))?;
+                Some((i, old_index))
+            })
+            .collect();
+        let kept = longest_increasing(&staying.iter().map(|(_, o)| *o).collect::<Vec<_>>());
+        let kept: Vec<usize> = kept.into_iter().map(|k| staying[k].0).collect();
+        for (index, (new_child, old_child)) in new_children.iter().zip(matched.iter()).enumerate() {
+            match old_child {
+                Some(old_child) if !kept.contains(&index) => self.changes.push(Change::Moved {
+                    node: self.node_ref(old_child),
+                    parent: self.node_ref(old),
+                    index,
+                }),
+                Some(_) => {}
+                None => self.changes.push(Change::Inserted {
+                    parent: self.node_ref(old),
+                    index,
+                    element: new_child.clone(),
+                }),
+            }
+        }
+        drop(old_children);
+        for (new_child, old_child) in new_children.iter().zip(matched.iter()) {
+            if let Some(old_child) = old_child {
+                self.text_change(old_child, new_child, true);
+                self.collect_changes(old_child, new_child);
+            }
+        }
+    }
+
+    /// add a change if the `text`, or the `tail`, of two matched elements differs
+    fn text_change(&mut self, old: &Rc<Element<'a>>, new: &Rc<Element<'a>>, tail: bool) {
+        let (old_text, new_text) = match tail {
+            true => (old.tail.borrow(), new.tail.borrow()),
+            false => (old.text.borrow(), new.text.borrow()),
+        };
+        if *old_text != *new_text {
+            self.changes.push(Change::TextModified {
+                node: self.node_ref(old),
+                tail,
+                old: old_text.to_string(),
+                new: new_text.to_string(),
+            });
+        }
+    }
+}
+
+/// the positions of a longest strictly increasing subsequence
+fn longest_increasing(list: &[usize]) -> Vec<usize> {
+    let mut tails: Vec<usize> = vec![];
+    let mut prev: Vec<Option<usize>> = vec![None; list.len()];
+    for (i, value) in list.iter().enumerate() {
+        let pos = tails.partition_point(|&t| list[t] < *value);
+        prev[i] = pos.checked_sub(1).map(|p| tails[p]);
+        if pos == tails.len() {
+            tails.push(i);
+        } else {
+            tails[pos] = i;
+        }
+    }
+    let mut result = vec![];
+    let mut current = tails.last().copied();
+    while let Some(i) = current {
+        result.push(i);
+        current = prev[i];
+    }
+    result.reverse();
+    result
+}
+
+/// the attributes which differ between two elements, sorted by name
+fn attribute_changes(old: &Element, new: &Element) -> Vec<AttributeChange> {
+    let (old_attributes, new_attributes) = (old.attributes.borrow(), new.attributes.borrow());
+    let mut names: Vec<&String> = old_attributes.keys().chain(new_attributes.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let old = old_attributes.get(name).map(|v| v.to_string());
+            let new = new_attributes.get(name).map(|v| v.to_string());
+            (old != new).then(|| AttributeChange {
+                name: name.clone(),
+                old,
+                new,
+            })
+        })
+        .collect()
+}
+
+/// the ids used by exactly one element of the tree
+fn unique_ids<'a>(root: &Rc<Element<'a>>) -> HashMap<String, Rc<Element<'a>>> {
+    let mut ids: HashMap<String, Option<Rc<Element<'a>>>> = HashMap::new();
+    let mut elements = vec![root.clone()];
+    elements.append(&mut root.descendants());
+    for ele in elements {
+        if let Some(id) = ele.get_attribute("id") {
+            ids.entry(id.into_owned())
+                .and_modify(|entry| *entry = None)
+                .or_insert(Some(ele));
+        }
+    }
+    ids.into_iter()
+        .filter_map(|(id, ele)| Some((id, ele?)))
+        .collect()
+}
+
+/// find the element of a `NodeRef` in a tree, by id first, then by path
+fn find<'a>(root: &Rc<Element<'a>>, node: &NodeRef) -> Result<Rc<Element<'a>>, PatchError> {
+    let by_id = node
+        .id
+        .as_ref()
+        .and_then(|id| unique_ids(root).remove(id))
+        .filter(|ele| ele.ele_type == node.ele_type);
+    let found = by_id.or_else(|| {
+        let mut current = root.clone();
+        for i in node.path.iter() {
+            let child = current.children.borrow().get(*i).cloned()?;
+            current = child;
+        }
+        Some(current).filter(|ele| ele.ele_type == node.ele_type)
+    });
+    found.ok_or_else(|| PatchError { node: node.clone() })
+}
+
+impl<'a> Diff<'a> {
+    /// whether the two trees are the same
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// apply the changes as a patch to a tree similar to the old tree
+    ///
+    /// the elements of the patch are found by `id` first, then by path.
+    /// return the patched root, which is a new element only if the root is
+    /// replaced; return a error if a element cannot be found, in which case
+    /// the tree is unchanged.
+    pub fn apply(&self, root: &Rc<Element<'a>>) -> Result<Rc<Element<'a>>, PatchError> {
+        if let Some(Change::Replaced { node, element }) = self.changes.first() {
+            find(root, node)?;
+            return Ok(element.deep_clone());
+        }
+        let mut modified = vec![];
+        let mut texts = vec![];
+        let mut detached = vec![];
+        let mut placed = vec![];
+        for change in self.changes.iter() {
+            match change {
+                Change::Modified { node, attributes } => {
+                    modified.push((find(root, node)?, attributes));
+                }
+                Change::TextModified {
+                    node, tail, new, ..
+                } => texts.push((find(root, node)?, *tail, new)),
+                Change::Removed { node } => detached.push(find(root, node)?),
+                Change::Moved {
+                    node,
+                    parent,
+                    index,
+                } => {
+                    let ele = find(root, node)?;
+                    detached.push(ele.clone());
+                    placed.push((find(root, parent)?, *index, ele));
+                }
+                Change::Inserted {
+                    parent,
+                    index,
+                    element,
+                } => {
+                    let copy = element.deep_clone();
+                    *copy.tail.borrow_mut() = element.tail.borrow().clone();
+                    placed.push((find(root, parent)?, *index, copy));
+                }
+                Change::Replaced { node, .. } => return Err(PatchError { node: node.clone() }),
+            }
+        }
+
+        for (ele, attributes) in modified {
+            for attribute in attributes {
+                match &attribute.new {
+                    Some(value) => ele.set_attribute(&attribute.name, value.clone()),
+                    None => {
+                        ele.remove_attribute(&attribute.name);
+                    }
+                }
+            }
+        }
+        for (ele, tail, text) in texts {
+            let text = Cow::Owned(text.clone());
+            match tail {
+                true => *ele.tail.borrow_mut() = text,
+                false => *ele.text.borrow_mut() = text,
+            }
+        }
+        for ele in detached {
+            let parent = ele.parent.borrow().upgrade();
+            if let Some(parent) = parent {
+                parent.remove_child(&ele);
+            }
+        }
+        // inserting by increasing index puts every element at its final place
+        placed.sort_by_key(|(_, index, _)| *index);
+        for (parent, index, ele) in placed {
+            parent.insert_child(index, ele);
+        }
+        Ok(root.clone())
+    }
+
+    /// a machine readable JSON report of the changes
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{diff, parse};
+    ///
+    /// let (_, old) = parse(r#"<svg><rect fill="red"/></svg>"#).unwrap();
+    /// let (_, new) = parse(r#"<svg><rect/></svg>"#).unwrap();
+    /// assert_eq!(
+    ///     diff(&old, &new).to_json(),
+    ///     r#"{"changes":[{"type":"modified","node":{"path":[0],"id":null,"type":"rect"},"attributes":[{"name":"fill","old":"red","new":null}]}]}"#
+    /// );
+    /// ```
+    ///
+    pub fn to_json(&self) -> String {
+        let changes: Vec<String> = self.changes.iter().map(change_json).collect();
+        format!("{{\"changes\":[{}]}}", changes.join(","))
+    }
+}
+
+impl<'a> fmt::Display for Diff<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in self.changes.iter() {
+            match change {
+                Change::Inserted {
+                    parent,
+                    index,
+                    element,
+                } => writeln!(
+                    f,
+                    "+ {} at {} {}",
+                    parent,
+                    index,
+                    stringify(element.clone())
+                )?,
+                Change::Removed { node } => writeln!(f, "- {}", node)?,
+                Change::Moved {
+                    node,
+                    parent,
+                    index,
+                } => writeln!(f, "> {} -> {} at {}", node, parent, index)?,
+                Change::Modified { node, attributes } => {
+                    writeln!(f, "~ {}", node)?;
+                    for attribute in attributes {
+                        match (&attribute.old, &attribute.new) {
+                            (Some(old), Some(new)) => {
+                                writeln!(f, "    {}: {:?} -> {:?}", attribute.name, old, new)?
+                            }
+                            (None, Some(new)) => {
+                                writeln!(f, "    + {}: {:?}", attribute.name, new)?
+                            }
+                            (Some(old), None) => {
+                                writeln!(f, "    - {}: {:?}", attribute.name, old)?
+                            }
+                            (None, None) => {}
+                        }
+                    }
+                }
+                Change::TextModified {
+                    node,
+                    tail,
+                    old,
+                    new,
+                } => {
+                    writeln!(f, "~ {}", node)?;
+                    let part = if *tail { "#tail" } else { "#text" };
+                    writeln!(f, "    {}: {:?} -> {:?}", part, old, new)?
+                }
+                Change::Replaced { node, element } => {
+                    writeln!(f, "! {} -> {}", node, stringify(element.clone()))?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// escape a text as a JSON string
+fn json_string(value: &str) -> String {
+    let mut result = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn json_option(value: &Option<String>) -> String {
+    value
+        .as_deref()
+        .map_or_else(|| "null".to_owned(), json_string)
+}
+
+fn node_json(node: &NodeRef) -> String {
+    let path: Vec<String> = node.path.iter().map(|i| i.to_string()).collect();
+    format!(
+        "{{\"path\":[{}],\"id\":{},\"type\":{}}}",
+        path.join(","),
+        json_option(&node.id),
+        json_string(&node.ele_type)
+    )
+}
+
+fn change_json(change: &Change) -> String {
+    match change {
+        Change::Inserted {
+            parent,
+            index,
+            element,
+        } => format!(
+            "{{\"type\":\"inserted\",\"parent\":{},\"index\":{},\"element\":{}}}",
+            node_json(parent),
+            index,
+            json_string(&stringify(element.clone()))
+        ),
+        Change::Removed { node } => {
+            format!("{{\"type\":\"removed\",\"node\":{}}}", node_json(node))
+        }
+        Change::Moved {
+            node,
+            parent,
+            index,
+        } => format!(
+            "{{\"type\":\"moved\",\"node\":{},\"parent\":{},\"index\":{}}}",
+            node_json(node),
+            node_json(parent),
+            index
+        ),
+        Change::Modified { node, attributes } => {
+            let attributes: Vec<String> = attributes
+                .iter()
+                .map(|attribute| {
+                    format!(
+                        "{{\"name\":{},\"old\":{},\"new\":{}}}",
+                        json_string(&attribute.name),
+                        json_option(&attribute.old),
+                        json_option(&attribute.new)
+                    )
+                })
+                .collect();
+            format!(
+                "{{\"type\":\"modified\",\"node\":{},\"attributes\":[{}]}}",
+                node_json(node),
+                attributes.join(",")
+            )
+        }
+        Change::TextModified {
+            node,
+            tail,
+            old,
+            new,
+        } => format!(
+            "{{\"type\":\"text\",\"node\":{},\"part\":{},\"old\":{},\"new\":{}}}",
+            node_json(node),
+            json_string(if *tail { "tail" } else { "text" }),
+            json_string(old),
+            json_string(new)
+        ),
+        Change::Replaced { node, element } => format!(
+            "{{\"type\":\"replaced\",\"node\":{},\"element\":{}}}",
+            node_json(node),
+            json_string(&stringify(element.clone()))
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diff::{json_string, longest_increasing, Change};
+    use crate::{diff, parse, stringify};
+
+    #[test]
+    fn test_longest_increasing() {
+        assert_eq!(longest_increasing(&[]), Vec::<usize>::new());
+        assert_eq!(longest_increasing(&[0, 1, 2]), vec![0, 1, 2]);
+        assert_eq!(longest_increasing(&[2, 0, 1]), vec![1, 2]);
+        assert_eq!(longest_increasing(&[3, 1, 4, 2, 5]).len(), 3);
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c\n"), r#""a\"b\\c\n""#);
+    }
+
+    #[test]
+    fn test_same_tree() {
+        let svg = r#"<svg><g id="a"><rect x="1"/></g></svg>"#;
+        let (_, old) = parse(svg).unwrap();
+        let (_, new) = parse(svg).unwrap();
+        let changes = diff(&old, &new);
+        assert!(changes.is_empty());
+        assert_eq!(changes.to_string(), "");
+    }
+
+    #[test]
+    fn test_diff_changes() {
+        let (_, old) = parse(
+            r#"<svg><g id="layer"><rect id="r" x="1"/><circle r="2"/></g><path d="M0 0"/><ellipse/></svg>"#,
+        )
+        .unwrap();
+        let (_, new) = parse(
+            r#"<svg><g id="layer" opacity="0.5"><circle r="3"/></g><rect id="r" x="1"/><ellipse/><line/></svg>"#,
+        )
+        .unwrap();
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes.to_string(),
+            concat!(
+                "- /1 path\n",
+                "> /0/0 rect#r -> / svg at 1\n",
+                "+ / svg at 3 <line/>\n",
+                "~ /0 g#layer\n",
+                "    + opacity: \"0.5\"\n",
+                "~ /0/1 circle\n",
+                "    r: \"2\" -> \"3\"\n",
+            )
+        );
+        assert!(matches!(changes.changes[0], Change::Removed { .. }));
+        let patched = changes.apply(&old.deep_clone()).unwrap();
+        assert_eq!(stringify(patched.clone()), stringify(new.clone()));
+        assert_eq!(patched, new);
+    }
+
+    #[test]
+    fn test_diff_reorder_and_nesting() {
+        let (_, old) =
+            parse(r#"<svg><a id="1"/><b id="2"/><c id="3"/><g id="w"><d/></g></svg>"#).unwrap();
+        let (_, new) =
+            parse(r#"<svg><c id="3"/><a id="1"/><g id="w"><b id="2"/><d/></g></svg>"#).unwrap();
+        let patched = diff(&old, &new).apply(&old.deep_clone()).unwrap();
+        assert_eq!(patched, new);
+    }
+
+    #[test]
+    fn test_apply_to_another_tree() {
+        let (_, old) = parse(r#"<svg><rect id="r" fill="red"/></svg>"#).unwrap();
+        let (_, new) = parse(r#"<svg><rect id="r" fill="blue"/></svg>"#).unwrap();
+        // the element is found by id even though it moved in the patched tree
+        let (_, other) = parse(r#"<svg><title/><rect id="r" fill="red" x="1"/></svg>"#).unwrap();
+        let patched = diff(&old, &new).apply(&other).unwrap();
+        assert_eq!(
+            stringify(patched),
+            r#"<svg><title/><rect fill="blue" id="r" x="1"/></svg>"#
+        );
+
+        let (_, unrelated) = parse(r#"<svg><g/></svg>"#).unwrap();
+        let error = diff(&old, &new).apply(&unrelated).unwrap_err();
+        assert_eq!(error.to_string(), "cannot find /0 rect#r in the tree");
+    }
+
+    #[test]
+    fn test_replaced_root() {
+        let (_, old) = parse(r#"<svg/>"#).unwrap();
+        let (_, new) = parse(r#"<g x="1"/>"#).unwrap();
+        let changes = diff(&old, &new);
+        assert_eq!(changes.to_string(), "! / svg -> <g x=\"1\"/>\n");
+        assert_eq!(
+            changes.to_json(),
+            r#"{"changes":[{"type":"replaced","node":{"path":[],"id":null,"type":"svg"},"element":"<g x=\"1\"/>"}]}"#
+        );
+        assert_eq!(changes.apply(&old).unwrap(), new);
+    }
+
+    #[test]
+    fn test_diff_text() {
+        let (_, old) = parse(r#"<svg><style>.a{fill:red}</style><text>a</text></svg>"#).unwrap();
+        let (_, new) = parse(r#"<svg><style>.a{fill:blue}</style><text>b</text></svg>"#).unwrap();
+        assert_ne!(old, new);
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes.to_string(),
+            concat!(
+                "~ /0 style\n",
+                "    #text: \".a{fill:red}\" -> \".a{fill:blue}\"\n",
+                "~ /1 text\n",
+                "    #text: \"a\" -> \"b\"\n",
+            )
+        );
+        assert_eq!(
+            diff(&old.children.borrow()[1], &new.children.borrow()[1]).to_json(),
+            r#"{"changes":[{"type":"text","node":{"path":[],"id":null,"type":"text"},"part":"text","old":"a","new":"b"}]}"#
+        );
+        assert_eq!(changes.apply(&old.deep_clone()).unwrap(), new);
+
+        // the text after a element is its tail, which follows it when it is inserted
+        let (_, old) = parse(r#"<text>a <tspan>c</tspan> d</text>"#).unwrap();
+        let (_, new) = parse(r#"<text>a <tspan>c</tspan> e<tspan>f</tspan>g</text>"#).unwrap();
+        let changes = diff(&old, &new);
+        assert_eq!(
+            changes.to_string(),
+            concat!(
+                "+ / text at 1 <tspan>f</tspan>\n",
+                "~ /0 tspan\n",
+                "    #tail: \" d\" -> \" e\"\n",
+            )
+        );
+        assert!(changes
+            .to_json()
+            .contains(r#""part":"tail","old":" d","new":" e""#));
+        let patched = changes.apply(&old.deep_clone()).unwrap();
+        assert_eq!(patched, new);
+        assert_eq!(
+            stringify(patched),
+            r#"<text>a <tspan>c</tspan> e<tspan>f</tspan>g</text>"#
+        );
+    }
+}
@@ -0,0 +1,304 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::Element;
+#[cfg(any(feature = "render", feature = "pdf"))]
+use crate::css::inline_styles;
+#[cfg(any(feature = "render", feature = "pdf"))]
+use crate::expand_use::expand_use;
+#[cfg(any(feature = "render", feature = "pdf"))]
+use crate::text::{text_to_paths, Fonts};
+
+/// A duplicate `id` found in a document
+///
+/// `id` the duplicated value
+///
+/// `elements` every element carrying the id, in document order
+#[derive(Debug, Clone)]
+pub struct DuplicateId<'a> {
+    pub id: String,
+    pub elements: Vec<Rc<Element<'a>>>,
+}
+
+/// A parsed svg tree with an index of its `id` attributes
+///
+/// `root` the root element of the tree
+///
+/// the index is built when the document is created and kept up to date
+/// by the mutation methods of the document. if the tree is changed
+/// directly through the `RefCell`s of its elements, call `reindex`.
+#[derive(Debug)]
+pub struct Document<'a> {
+    pub root: Rc<Element<'a>>,
+    ids: RefCell<HashMap<String, Vec<Rc<Element<'a>>>>>,
+}
+
+impl<'a> Document<'a> {
+    /// new a document and index the ids of the tree
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use std::collections::HashMap;
+    /// use svg_simple_parser::{Document, Element};
+    ///
+    /// let rect = Element::new(("rect",HashMap::from([("id".to_owned(), "r1")])));
+    /// let document = Document::new(Element::new_width_children(("svg",HashMap::new(),vec![rect])));
+    /// assert_eq!(document.element_by_id("r1").unwrap().ele_type, "rect");
+    /// ```
+    ///
+    pub fn new(root: Rc<Element<'a>>) -> Self {
+        let document = Document {
+            root,
+            ids: RefCell::new(HashMap::new()),
+        };
+        document.reindex();
+        document
+    }
+
+    /// rebuild the id index from the whole tree
+    pub fn reindex(&self) {
+        self.ids.borrow_mut().clear();
+        self.index_subtree(&self.root.clone());
+    }
+
+    /// find the element whose `id` attribute is `id`.
+    ///
+    /// if the id is duplicated, the first element in document order wins.
+    pub fn element_by_id(&self, id: &str) -> Option<Rc<Element<'a>>> {
+        self.ids
+            .borrow()
+            .get(id)
+            .and_then(|elements| elements.first().cloned())
+    }
+
+    /// every id which is used by more than one element, sorted by id
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use svg_simple_parser::parse_document;
+    ///
+    /// let (_, document) = parse_document(r#"<svg><rect id="a"/><circle id="a"/></svg>"#).unwrap();
+    /// let duplicates = document.duplicate_ids();
+    /// assert_eq!(duplicates.len(), 1);
+    /// assert_eq!(duplicates[0].id, "a");
+    /// assert_eq!(duplicates[0].elements.len(), 2);
+    /// ```
+    ///
+    pub fn duplicate_ids(&self) -> Vec<DuplicateId<'a>> {
+        let mut duplicates: Vec<DuplicateId<'a>> = self
+            .ids
+            .borrow()
+            .iter()
+            .filter(|(_, elements)| elements.len() > 1)
+            .map(|(id, elements)| DuplicateId {
+                id: id.clone(),
+                elements: elements.clone(),
+            })
+            .collect();
+        duplicates.sort_by(|a, b| a.id.cmp(&b.id));
+        duplicates
+    }
+
+    /// set a attribute of a element in the document, updating the index for `id`
+    pub fn set_attribute<V>(&self, ele: &Rc<Element<'a>>, key: &str, value: V)
+    where
+        V: Into<Cow<'a, str>>,
+    {
+        if key == "id" {
+            self.unindex(ele);
+        }
+        ele.set_attribute(key, value);
+        if key == "id" {
+            self.index(ele);
+        }
+    }
+
+    /// remove a attribute of a element in the document, updating the index for `id`
+    pub fn remove_attribute(&self, ele: &Rc<Element<'a>>, key: &str) -> Option<Cow<'a, str>> {
+        if key == "id" {
+            self.unindex(ele);
+        }
+        ele.remove_attribute(key)
+    }
+
+    /// add a element to the children of a element in the document and index its subtree
+    pub fn add_child(&self, parent: &Rc<Element<'a>>, child: Rc<Element<'a>>) {
+        parent.add_child(child.clone());
+        self.index_subtree(&child);
+    }
+
+    /// remove a element from the children of a element in the document and unindex its subtree
+    pub fn remove_child(
+        &self,
+        parent: &Rc<Element<'a>>,
+        child: &Rc<Element<'a>>,
+    ) -> Option<Rc<Element<'a>>> {
+        let node = parent.remove_child(child)?;
+        self.unindex(&node);
+        node.descendants().iter().for_each(|ele| self.unindex(ele));
+        Some(node)
+    }
+
+    fn index_subtree(&self, ele: &Rc<Element<'a>>) {
+        self.index(ele);
+        ele.descendants().iter().for_each(|ele| self.index(ele));
+    }
+
+    fn index(&self, ele: &Rc<Element<'a>>) {
+        let id = match ele.attributes.borrow().get("id") {
+            Some(id) => id.to_string(),
+            None => return,
+        };
+        let mut ids = self.ids.borrow_mut();
+        let elements = ids.entry(id).or_default();
+        // keep document order so the first element of a duplicated id wins
+        let position = elements
+            .iter()
+            .position(|other| is_before(ele, other))
+            .unwrap_or(elements.len());
+        elements.insert(position, ele.clone());
+    }
+
+    fn unindex(&self, ele: &Rc<Element<'a>>) {
+        let id = match ele.attributes.borrow().get("id") {
+            Some(id) => id.to_string(),
+            None => return,
+        };
+        let mut ids = self.ids.borrow_mut();
+        if let Some(elements) = ids.get_mut(&id) {
+            elements.retain(|other| !Rc::ptr_eq(other, ele));
+            if elements.is_empty() {
+                ids.remove(&id);
+            }
+        }
+    }
+}
+
+impl<'a> From<Rc<Element<'a>>> for Document<'a> {
+    fn from(root: Rc<Element<'a>>) -> Self {
+        Document::new(root)
+    }
+}
+
+/// a copy of a tree to be drawn, with its `<use>` expanded, its stylesheets
+/// applied, its text as paths and every style as presentation attributes
+#[cfg(any(feature = "render", feature = "pdf"))]
+pub(crate) fn drawing_copy<'a>(root: &Rc<Element<'a>>, fonts: &Fonts) -> Document<'a> {
+    let document = Document::new(root.deep_clone());
+    // a recursive use is left in place and not drawn
+    let _ = expand_use(&document);
+    inline_styles(&document);
+    text_to_paths(&document, fonts);
+    document.root.style_to_attributes();
+    for ele in document.root.descendants() {
+        ele.style_to_attributes();
+    }
+    document
+}
+
+/// the indexes of the element from the root, used to compare document order
+fn tree_path(ele: &Rc<Element>) -> Vec<usize> {
+    let mut path = vec![];
+    let mut current = ele.clone();
+    loop {
+        let parent = match current.parent.borrow().upgrade() {
+            Some(parent) => parent,
+            None => break,
+        };
+        let index = parent
+            .children
+            .borrow()
+            .iter()
+            .position(|node| Rc::ptr_eq(node, &current))
+            .unwrap_or(0);
+        path.push(index);
+        current = parent;
+    }
+    path.reverse();
+    path
+}
+
+fn is_before(a: &Rc<Element>, b: &Rc<Element>) -> bool {
+    tree_path(a) < tree_path(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::rc::Rc;
+
+    use crate::{parse_document, Document, Element};
+
+    #[test]
+    fn test_element_by_id() {
+        let (_, document) = parse_document(
+            r#"<svg><defs><linearGradient id="g1"/></defs><g id="layer"><rect id="r1"/></g></svg>"#,
+        )
+        .unwrap();
+        assert_eq!(
+            document.element_by_id("g1").unwrap().ele_type,
+            "linearGradient"
+        );
+        assert_eq!(document.element_by_id("r1").unwrap().ele_type, "rect");
+        assert_eq!(document.element_by_id("layer").unwrap().ele_type, "g");
+        assert!(document.element_by_id("missing").is_none());
+        assert!(document.duplicate_ids().is_empty());
+    }
+
+    #[test]
+    fn test_duplicate_ids() {
+        let (_, document) =
+            parse_document(r#"<svg><g><rect id="a"/></g><circle id="a"/><path id="b"/></svg>"#)
+                .unwrap();
+        let duplicates = document.duplicate_ids();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].id, "a");
+        assert_eq!(duplicates[0].elements[0].ele_type, "rect");
+        assert_eq!(duplicates[0].elements[1].ele_type, "circle");
+        assert_eq!(document.element_by_id("a").unwrap().ele_type, "rect");
+    }
+
+    #[test]
+    fn test_mutation() {
+        let document = Document::new(Element::new(("svg", HashMap::new())));
+        let rect = Element::new(("rect", HashMap::from([("id".to_owned(), "r1")])));
+        let group = Element::new_width_children((
+            "g",
+            HashMap::from([("id".to_owned(), "g1")]),
+            vec![rect.clone()],
+        ));
+        document.add_child(&document.root.clone(), group.clone());
+        assert!(Rc::ptr_eq(&document.element_by_id("r1").unwrap(), &rect));
+
+        document.set_attribute(&rect, "id", "r2");
+        assert!(document.element_by_id("r1").is_none());
+        assert!(Rc::ptr_eq(&document.element_by_id("r2").unwrap(), &rect));
+
+        // a earlier element takes over a duplicated id
+        document.set_attribute(&group, "id", "r2");
+        assert!(Rc::ptr_eq(&document.element_by_id("r2").unwrap(), &group));
+        assert_eq!(document.duplicate_ids().len(), 1);
+
+        document.remove_attribute(&group, "id");
+        assert!(Rc::ptr_eq(&document.element_by_id("r2").unwrap(), &rect));
+
+        document.remove_child(&document.root.clone(), &group);
+        assert!(document.element_by_id("r2").is_none());
+    }
+
+    #[test]
+    fn test_reindex() {
+        let document = Document::new(Element::new(("svg", HashMap::new())));
+        document.root.add_child(Element::new((
+            "rect",
+            HashMap::from([("id".to_owned(), "r1")]),
+        )));
+        assert!(document.element_by_id("r1").is_none());
+        document.reindex();
+        assert_eq!(document.element_by_id("r1").unwrap().ele_type, "rect");
+    }
+}
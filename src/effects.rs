@@ -0,0 +1,1151 @@
+use std::collections::HashMap;
+
+use crate::bbox::BBox;
+use crate::color::Rgba;
+use crate::filter::{
+    BlendMode, ColorInterpolation, CompositeOperator, Filter, FilterInput, FilterKind,
+    FilterPrimitive, LightSource, MorphologyOperator,
+};
+use crate::raster::{premultiplied, Canvas};
+use crate::render::Pixmap;
+use crate::transform::Matrix;
+
+type Pixels = Vec<[f32; 4]>;
+
+/// A intermediate result of a filter, premultiplied in a color space
+#[derive(Clone)]
+struct Image {
+    pixels: Pixels,
+    space: ColorInterpolation,
+}
+
+fn to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// apply a function to the straight color channels of premultiplied pixels
+fn map_straight(pixels: &mut Pixels, f: impl Fn([f32; 4]) -> [f32; 4]) {
+    for pixel in pixels.iter_mut() {
+        let alpha = pixel[3];
+        let straight = if alpha > 0.0 {
+            [pixel[0] / alpha, pixel[1] / alpha, pixel[2] / alpha, alpha]
+        } else {
+            [0.0, 0.0, 0.0, 0.0]
+        };
+        let [r, g, b, a] = f(straight).map(|c| c.clamp(0.0, 1.0));
+        *pixel = [r * a, g * a, b * a, a];
+    }
+}
+
+impl Image {
+    fn converted(mut self, space: ColorInterpolation) -> Image {
+        if self.space != space {
+            let convert = match space {
+                ColorInterpolation::LinearRgb => to_linear,
+                ColorInterpolation::Srgb => to_srgb,
+            };
+            map_straight(&mut self.pixels, |[r, g, b, a]| {
+                [convert(r), convert(g), convert(b), a]
+            });
+            self.space = space;
+        }
+        self
+    }
+}
+
+/// a sRGB color as premultiplied channels in a color space
+fn color_in(color: Rgba, space: ColorInterpolation) -> [f32; 4] {
+    let [r, g, b, a] = premultiplied(color);
+    match space {
+        ColorInterpolation::Srgb => [r, g, b, a],
+        ColorInterpolation::LinearRgb if a > 0.0 => [
+            to_linear(r / a) * a,
+            to_linear(g / a) * a,
+            to_linear(b / a) * a,
+            a,
+        ],
+        ColorInterpolation::LinearRgb => [0.0; 4],
+    }
+}
+
+/// The state of a filter being applied to a image of `width` by `height` pixels
+struct Effects<'f, 'a> {
+    filter: &'f Filter<'a>,
+    width: usize,
+    height: usize,
+    /// the user space of the filtered element to pixels
+    matrix: Matrix,
+    /// the primitive units to pixels
+    primitive: Matrix,
+    bbox: BBox,
+    source: Image,
+    results: HashMap<String, Image>,
+    previous: Option<Image>,
+}
+
+/// the pixels whose center is inside a box in pixel coordinates
+fn pixel_range(bbox: &BBox, width: usize, height: usize) -> (usize, usize, usize, usize) {
+    let clamp = |n: f64, max: usize| (n - 0.5).ceil().clamp(0.0, max as f64) as usize;
+    (
+        clamp(bbox.min_x, width),
+        clamp(bbox.min_y, height),
+        clamp(bbox.max_x, width),
+        clamp(bbox.max_y, height),
+    )
+}
+
+/// clear the pixels outside of a box in pixel coordinates
+fn clip(pixels: &mut Pixels, width: usize, height: usize, bbox: &BBox) {
+    let (x0, y0, x1, y1) = pixel_range(bbox, width, height);
+    for y in 0..height {
+        for x in 0..width {
+            if x < x0 || x >= x1 || y < y0 || y >= y1 {
+                pixels[y * width + x] = [0.0; 4];
+            }
+        }
+    }
+}
+
+/// apply a filter to premultiplied pixels, `matrix` maps the user space of the
+/// filtered element with the bounding box `bbox` to pixels. `None` if the
+/// element is not drawn.
+pub(crate) fn apply_filter(
+    filter: &Filter,
+    source: &Canvas,
+    matrix: &Matrix,
+    bbox: Option<BBox>,
+) -> Option<Canvas> {
+    let bbox = bbox.unwrap_or(BBox::new(0.0, 0.0, 0.0, 0.0));
+    let region = filter.region(&bbox)?.transformed(matrix);
+    if filter.primitives.is_empty() {
+        return None;
+    }
+    let (width, height) = (source.width, source.height);
+    let mut pixels = source.pixels.clone();
+    clip(&mut pixels, width, height, &region);
+    let mut effects = Effects {
+        filter,
+        width,
+        height,
+        matrix: *matrix,
+        primitive: *matrix * filter.primitive_units.to_user_space(&bbox)?,
+        bbox,
+        source: Image {
+            pixels,
+            space: ColorInterpolation::Srgb,
+        },
+        results: HashMap::new(),
+        previous: None,
+    };
+    for primitive in &filter.primitives {
+        let mut image = effects.apply(primitive);
+        match filter.subregion(primitive, &bbox) {
+            Some(subregion) => clip(
+                &mut image.pixels,
+                width,
+                height,
+                &subregion.transformed(matrix),
+            ),
+            None => image.pixels.fill([0.0; 4]),
+        }
+        if let Some(result) = &primitive.result {
+            effects.results.insert(result.clone(), image.clone());
+        }
+        effects.previous = Some(image);
+    }
+    let image = effects.previous?.converted(ColorInterpolation::Srgb);
+    Some(Canvas {
+        width,
+        height,
+        pixels: image.pixels,
+    })
+}
+
+impl<'f, 'a> Effects<'f, 'a> {
+    fn input(&self, input: &Option<FilterInput>, space: ColorInterpolation) -> Image {
+        let transparent = || Image {
+            pixels: vec![[0.0; 4]; self.width * self.height],
+            space,
+        };
+        let image = match input {
+            Some(FilterInput::SourceGraphic) => self.source.clone(),
+            Some(FilterInput::SourceAlpha) => {
+                return Image {
+                    pixels: self
+                        .source
+                        .pixels
+                        .iter()
+                        .map(|p| [0.0, 0.0, 0.0, p[3]])
+                        .collect(),
+                    space,
+                }
+            }
+            Some(FilterInput::Reference(name)) if self.results.contains_key(name) => {
+                self.results[name].clone()
+            }
+            // a missing result is like a missing input
+            None | Some(FilterInput::Reference(_)) => {
+                self.previous.clone().unwrap_or_else(|| self.source.clone())
+            }
+            Some(_) => return transparent(),
+        };
+        image.converted(space)
+    }
+
+    /// the horizontal and vertical scale of primitive units to pixels
+    fn scale(&self) -> (f64, f64) {
+        let m = &self.primitive;
+        (m.a.hypot(m.b), m.c.hypot(m.d))
+    }
+
+    /// a vector in primitive units in pixels
+    fn vector(&self, dx: f64, dy: f64) -> (f64, f64) {
+        let m = &self.primitive;
+        (m.a * dx + m.c * dy, m.b * dx + m.d * dy)
+    }
+
+    fn apply(&self, primitive: &FilterPrimitive) -> Image {
+        let space = primitive.color_interpolation;
+        let (width, height) = (self.width, self.height);
+        let pixels = match &primitive.kind {
+            FilterKind::GaussianBlur {
+                input,
+                std_dev_x,
+                std_dev_y,
+            } => {
+                let (sx, sy) = self.scale();
+                let image = self.input(input, space);
+                blur(image.pixels, width, height, std_dev_x * sx, std_dev_y * sy)
+            }
+            FilterKind::Offset { input, dx, dy } => {
+                let (dx, dy) = self.vector(*dx, *dy);
+                offset(&self.input(input, space).pixels, width, height, dx, dy)
+            }
+            FilterKind::ColorMatrix { input, kind } => {
+                let m = kind.to_matrix().map(|value| value as f32);
+                let mut pixels = self.input(input, space).pixels;
+                map_straight(&mut pixels, |c| {
+                    let row = |i: usize| {
+                        m[i] * c[0] + m[i + 1] * c[1] + m[i + 2] * c[2] + m[i + 3] * c[3] + m[i + 4]
+                    };
+                    [row(0), row(5), row(10), row(15)]
+                });
+                pixels
+            }
+            FilterKind::Composite {
+                input,
+                input2,
+                operator,
+            } => {
+                let a = self.input(input, space).pixels;
+                let b = self.input(input2, space).pixels;
+                a.iter()
+                    .zip(&b)
+                    .map(|(a, b)| composite(*operator, a, b))
+                    .collect()
+            }
+            FilterKind::Merge { inputs } => {
+                let mut pixels = vec![[0.0; 4]; width * height];
+                for input in inputs {
+                    let layer = self.input(input, space).pixels;
+                    for (destination, source) in pixels.iter_mut().zip(&layer) {
+                        *destination = composite(CompositeOperator::Over, source, destination);
+                    }
+                }
+                pixels
+            }
+            FilterKind::Flood { color } => vec![color_in(*color, space); width * height],
+            FilterKind::Blend {
+                input,
+                input2,
+                mode,
+            } => {
+                let a = self.input(input, space).pixels;
+                let b = self.input(input2, space).pixels;
+                a.iter().zip(&b).map(|(a, b)| blend(*mode, a, b)).collect()
+            }
+            FilterKind::Morphology {
+                input,
+                operator,
+                radius_x,
+                radius_y,
+            } => {
+                let pixels = self.input(input, space).pixels;
+                let (sx, sy) = self.scale();
+                let (rx, ry) = (radius_x * sx, radius_y * sy);
+                // a radius which is not positive disables the primitive
+                if *radius_x <= 0.0 || *radius_y <= 0.0 {
+                    pixels
+                } else {
+                    morphology(pixels, width, height, *operator, rx, ry)
+                }
+            }
+            FilterKind::Turbulence {
+                base_frequency_x,
+                base_frequency_y,
+                num_octaves,
+                seed,
+                stitch_tiles,
+                fractal_noise,
+            } => self.turbulence(
+                primitive,
+                (base_frequency_x.max(0.0), base_frequency_y.max(0.0)),
+                *num_octaves,
+                *seed,
+                *stitch_tiles,
+                *fractal_noise,
+            ),
+            FilterKind::ComponentTransfer { input, functions } => {
+                let mut pixels = self.input(input, space).pixels;
+                map_straight(&mut pixels, |c| {
+                    [0, 1, 2, 3].map(|i| functions[i].apply(c[i] as f64) as f32)
+                });
+                pixels
+            }
+            FilterKind::DropShadow {
+                input,
+                std_dev_x,
+                std_dev_y,
+                dx,
+                dy,
+                color,
+            } => {
+                let image = self.input(input, space).pixels;
+                let (sx, sy) = self.scale();
+                let (dx, dy) = self.vector(*dx, *dy);
+                let alpha: Pixels = image.iter().map(|p| [0.0, 0.0, 0.0, p[3]]).collect();
+                let shadow = blur(alpha, width, height, std_dev_x * sx, std_dev_y * sy);
+                let shadow = offset(&shadow, width, height, dx, dy);
+                let color = color_in(*color, space);
+                image
+                    .iter()
+                    .zip(&shadow)
+                    .map(|(source, shadow)| {
+                        let shadow = color.map(|c| c * shadow[3]);
+                        composite(CompositeOperator::Over, source, &shadow)
+                    })
+                    .collect()
+            }
+            FilterKind::DiffuseLighting {
+                input,
+                surface_scale,
+                diffuse_constant,
+                color,
+                light,
+            } => self.lighting(
+                &self.input(input, space).pixels,
+                *surface_scale,
+                light,
+                color_in(*color, space),
+                Lighting::Diffuse(*diffuse_constant),
+            ),
+            FilterKind::SpecularLighting {
+                input,
+                surface_scale,
+                specular_constant,
+                specular_exponent,
+                color,
+                light,
+            } => self.lighting(
+                &self.input(input, space).pixels,
+                *surface_scale,
+                light,
+                color_in(*color, space),
+                Lighting::Specular(*specular_constant, *specular_exponent),
+            ),
+            FilterKind::Unsupported { .. } => vec![[0.0; 4]; width * height],
+        };
+        Image { pixels, space }
+    }
+
+    fn turbulence(
+        &self,
+        primitive: &FilterPrimitive,
+        base_frequency: (f64, f64),
+        num_octaves: u32,
+        seed: f64,
+        stitch_tiles: bool,
+        fractal_noise: bool,
+    ) -> Pixels {
+        let (width, height) = (self.width, self.height);
+        let mut pixels = vec![[0.0; 4]; width * height];
+        let Some(inverse) = self.primitive.invert() else {
+            return pixels;
+        };
+        // the tile to stitch, in primitive units
+        let to_primitive = inverse * self.matrix;
+        let tile = self
+            .filter
+            .subregion(primitive, &self.bbox)
+            .map(|subregion| subregion.transformed(&to_primitive));
+        let noise = Noise::new(seed.round() as i64);
+        let stitch = tile.filter(|_| stitch_tiles);
+        for y in 0..height {
+            for x in 0..width {
+                let point = inverse.apply(x as f64 + 0.5, y as f64 + 0.5);
+                let channel = |channel| {
+                    let sum = noise.turbulence(
+                        channel,
+                        point,
+                        base_frequency,
+                        num_octaves,
+                        fractal_noise,
+                        stitch,
+                    );
+                    let value = if fractal_noise {
+                        (sum + 1.0) / 2.0
+                    } else {
+                        sum
+                    };
+                    value.clamp(0.0, 1.0) as f32
+                };
+                let [r, g, b, a] = [0, 1, 2, 3].map(channel);
+                pixels[y * width + x] = [r * a, g * a, b * a, a];
+            }
+        }
+        pixels
+    }
+
+    fn lighting(
+        &self,
+        input: &Pixels,
+        surface_scale: f64,
+        light: &Option<LightSource>,
+        color: [f32; 4],
+        lighting: Lighting,
+    ) -> Pixels {
+        let (width, height) = (self.width, self.height);
+        let Some(light) = light else {
+            return vec![[0.0; 4]; width * height];
+        };
+        let (sx, sy) = self.scale();
+        let z_scale = (sx * sy).sqrt();
+        let light = match *light {
+            LightSource::Distant { .. } => *light,
+            LightSource::Point { x, y, z } => {
+                let (x, y) = self.primitive.apply(x, y);
+                LightSource::Point {
+                    x,
+                    y,
+                    z: z * z_scale,
+                }
+            }
+            LightSource::Spot {
+                x,
+                y,
+                z,
+                points_at_x,
+                points_at_y,
+                points_at_z,
+                specular_exponent,
+                limiting_cone_angle,
+            } => {
+                let (x, y) = self.primitive.apply(x, y);
+                let (points_at_x, points_at_y) = self.primitive.apply(points_at_x, points_at_y);
+                LightSource::Spot {
+                    x,
+                    y,
+                    z: z * z_scale,
+                    points_at_x,
+                    points_at_y,
+                    points_at_z: points_at_z * z_scale,
+                    specular_exponent,
+                    limiting_cone_angle,
+                }
+            }
+        };
+        // the light color without its alpha
+        let rgb = if color[3] > 0.0 {
+            [
+                color[0] / color[3],
+                color[1] / color[3],
+                color[2] / color[3],
+            ]
+        } else {
+            [0.0; 3]
+        };
+        let alpha = |x: isize, y: isize| {
+            let x = x.clamp(0, width as isize - 1) as usize;
+            let y = y.clamp(0, height as isize - 1) as usize;
+            input[y * width + x][3] as f64
+        };
+        let mut pixels = vec![[0.0; 4]; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let (xi, yi) = (x as isize, y as isize);
+                let a = |dx, dy| alpha(xi + dx, yi + dy);
+                // the sobel kernels of the spec
+                let nx = -surface_scale / 4.0
+                    * ((a(1, -1) + 2.0 * a(1, 0) + a(1, 1))
+                        - (a(-1, -1) + 2.0 * a(-1, 0) + a(-1, 1)));
+                let ny = -surface_scale / 4.0
+                    * ((a(-1, 1) + 2.0 * a(0, 1) + a(1, 1))
+                        - (a(-1, -1) + 2.0 * a(0, -1) + a(1, -1)));
+                let normal = normalize((nx, ny, 1.0));
+                let surface = (x as f64 + 0.5, y as f64 + 0.5, surface_scale * a(0, 0));
+                let (l, factor) = light_vector(&light, surface);
+                let light_color = rgb.map(|c| c as f64 * factor);
+                let pixel = match lighting {
+                    Lighting::Diffuse(kd) => {
+                        let n_dot_l = dot(normal, l);
+                        let [r, g, b] =
+                            light_color.map(|c| (kd * n_dot_l * c).clamp(0.0, 1.0) as f32);
+                        [r, g, b, 1.0]
+                    }
+                    Lighting::Specular(ks, exponent) => {
+                        let h = normalize((l.0, l.1, l.2 + 1.0));
+                        let n_dot_h = dot(normal, h).max(0.0);
+                        let [r, g, b] = light_color
+                            .map(|c| (ks * n_dot_h.powf(exponent) * c).clamp(0.0, 1.0) as f32);
+                        let a = r.max(g).max(b);
+                        // premultiplied, the alpha is the largest channel
+                        [r, g, b, a]
+                    }
+                };
+                pixels[y * width + x] = pixel;
+            }
+        }
+        pixels
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Lighting {
+    Diffuse(f64),
+    /// the specular constant and exponent
+    Specular(f64, f64),
+}
+
+type Vector = (f64, f64, f64);
+
+fn dot(a: Vector, b: Vector) -> f64 {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+fn normalize(v: Vector) -> Vector {
+    let length = dot(v, v).sqrt();
+    if length == 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+    (v.0 / length, v.1 / length, v.2 / length)
+}
+
+/// the unit vector from a point of the surface to the light, and the factor of
+/// the light color there
+fn light_vector(light: &LightSource, surface: Vector) -> (Vector, f64) {
+    match *light {
+        LightSource::Distant { azimuth, elevation } => {
+            let (azimuth, elevation) = (azimuth.to_radians(), elevation.to_radians());
+            (
+                (
+                    azimuth.cos() * elevation.cos(),
+                    azimuth.sin() * elevation.cos(),
+                    elevation.sin(),
+                ),
+                1.0,
+            )
+        }
+        LightSource::Point { x, y, z } => (
+            normalize((x - surface.0, y - surface.1, z - surface.2)),
+            1.0,
+        ),
+        LightSource::Spot {
+            x,
+            y,
+            z,
+            points_at_x,
+            points_at_y,
+            points_at_z,
+            specular_exponent,
+            limiting_cone_angle,
+        } => {
+            let l = normalize((x - surface.0, y - surface.1, z - surface.2));
+            let s = normalize((points_at_x - x, points_at_y - y, points_at_z - z));
+            let minus_l_dot_s = -dot(l, s);
+            let outside = limiting_cone_angle
+                .is_some_and(|angle| minus_l_dot_s < angle.abs().to_radians().cos());
+            let factor = if minus_l_dot_s <= 0.0 || outside {
+                0.0
+            } else {
+                minus_l_dot_s.powf(specular_exponent)
+            };
+            (l, factor)
+        }
+    }
+}
+
+/// a porter duff operator on premultiplied pixels, `a` onto `b`
+fn composite(operator: CompositeOperator, a: &[f32; 4], b: &[f32; 4]) -> [f32; 4] {
+    let (aa, ab) = (a[3], b[3]);
+    let mut out = [0.0; 4];
+    for i in 0..4 {
+        out[i] = match operator {
+            CompositeOperator::Over => a[i] + b[i] * (1.0 - aa),
+            CompositeOperator::In => a[i] * ab,
+            CompositeOperator::Out => a[i] * (1.0 - ab),
+            CompositeOperator::Atop => a[i] * ab + b[i] * (1.0 - aa),
+            CompositeOperator::Xor => a[i] * (1.0 - ab) + b[i] * (1.0 - aa),
+            CompositeOperator::Lighter => (a[i] + b[i]).min(1.0),
+            CompositeOperator::Arithmetic { k1, k2, k3, k4 } => {
+                (k1 as f32 * a[i] * b[i] + k2 as f32 * a[i] + k3 as f32 * b[i] + k4 as f32)
+                    .clamp(0.0, 1.0)
+            }
+        };
+    }
+    // a arithmetic result may have more color than alpha
+    for i in 0..3 {
+        out[i] = out[i].min(out[3]);
+    }
+    out
+}
+
+/// blend premultiplied pixels, the source `a` onto the backdrop `b`
+fn blend(mode: BlendMode, a: &[f32; 4], b: &[f32; 4]) -> [f32; 4] {
+    let (aa, ab) = (a[3], b[3]);
+    let mut out = [0.0, 0.0, 0.0, aa + ab - aa * ab];
+    for i in 0..3 {
+        let cs = if aa > 0.0 { a[i] / aa } else { 0.0 };
+        let cb = if ab > 0.0 { b[i] / ab } else { 0.0 };
+        let hard_light = |cb: f32, cs: f32| {
+            if cs <= 0.5 {
+                cb * 2.0 * cs
+            } else {
+                let s = 2.0 * cs - 1.0;
+                cb + s - cb * s
+            }
+        };
+        let mixed = match mode {
+            BlendMode::Normal => cs,
+            BlendMode::Multiply => cs * cb,
+            BlendMode::Screen => cb + cs - cb * cs,
+            BlendMode::Overlay => hard_light(cs, cb),
+            BlendMode::Darken => cs.min(cb),
+            BlendMode::Lighten => cs.max(cb),
+            BlendMode::ColorDodge if cb == 0.0 => 0.0,
+            BlendMode::ColorDodge if cs >= 1.0 => 1.0,
+            BlendMode::ColorDodge => (cb / (1.0 - cs)).min(1.0),
+            BlendMode::ColorBurn if cb >= 1.0 => 1.0,
+            BlendMode::ColorBurn if cs <= 0.0 => 0.0,
+            BlendMode::ColorBurn => 1.0 - ((1.0 - cb) / cs).min(1.0),
+            BlendMode::HardLight => hard_light(cb, cs),
+            BlendMode::SoftLight => {
+                if cs <= 0.5 {
+                    cb - (1.0 - 2.0 * cs) * cb * (1.0 - cb)
+                } else {
+                    let d = if cb <= 0.25 {
+                        ((16.0 * cb - 12.0) * cb + 4.0) * cb
+                    } else {
+                        cb.sqrt()
+                    };
+                    cb + (2.0 * cs - 1.0) * (d - cb)
+                }
+            }
+            BlendMode::Difference => (cb - cs).abs(),
+            BlendMode::Exclusion => cb + cs - 2.0 * cb * cs,
+        };
+        out[i] = a[i] * (1.0 - ab) + b[i] * (1.0 - aa) + aa * ab * mixed;
+    }
+    out
+}
+
+/// a box blur of each row or column, averaging `size` pixels from `left`
+/// pixels before each pixel
+fn box_blur(
+    pixels: &Pixels,
+    width: usize,
+    height: usize,
+    horizontal: bool,
+    size: usize,
+    left: usize,
+) -> Pixels {
+    let (lines, length) = if horizontal {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    let index = |line: usize, i: usize| {
+        if horizontal {
+            line * width + i
+        } else {
+            i * width + line
+        }
+    };
+    let mut out = vec![[0.0; 4]; pixels.len()];
+    let mut sums = vec![[0.0f32; 4]; length + 1];
+    for line in 0..lines {
+        for i in 0..length {
+            let pixel = pixels[index(line, i)];
+            for c in 0..4 {
+                sums[i + 1][c] = sums[i][c] + pixel[c];
+            }
+        }
+        for i in 0..length {
+            let start = (i as isize - left as isize).clamp(0, length as isize) as usize;
+            let end =
+                (i as isize - left as isize + size as isize).clamp(0, length as isize) as usize;
+            let pixel = &mut out[index(line, i)];
+            for c in 0..4 {
+                pixel[c] = ((sums[end][c] - sums[start][c]) / size as f32).max(0.0);
+            }
+        }
+    }
+    out
+}
+
+/// a gaussian blur along the rows or columns
+fn blur_direction(
+    pixels: Pixels,
+    width: usize,
+    height: usize,
+    horizontal: bool,
+    std_dev: f64,
+) -> Pixels {
+    if std_dev <= 0.0 {
+        return pixels;
+    }
+    if std_dev >= 2.0 {
+        // three box blurs approximate the gaussian, as the spec suggests
+        let d = (std_dev * 3.0 * (2.0 * std::f64::consts::PI).sqrt() / 4.0 + 0.5).floor() as usize;
+        let passes = if d % 2 == 1 {
+            [(d, d / 2), (d, d / 2), (d, d / 2)]
+        } else {
+            [(d, d / 2), (d, d / 2 - 1), (d + 1, d / 2)]
+        };
+        return passes.iter().fold(pixels, |pixels, (size, left)| {
+            box_blur(&pixels, width, height, horizontal, *size, *left)
+        });
+    }
+    let radius = (std_dev * 3.0).ceil() as isize;
+    let kernel: Vec<f32> = (-radius..=radius)
+        .map(|i| (-(i * i) as f64 / (2.0 * std_dev * std_dev)).exp() as f32)
+        .collect();
+    let total: f32 = kernel.iter().sum();
+    let (lines, length) = if horizontal {
+        (height, width)
+    } else {
+        (width, height)
+    };
+    let index = |line: usize, i: usize| {
+        if horizontal {
+            line * width + i
+        } else {
+            i * width + line
+        }
+    };
+    let mut out = vec![[0.0; 4]; pixels.len()];
+    for line in 0..lines {
+        for i in 0..length {
+            let mut sum = [0.0; 4];
+            for (k, weight) in kernel.iter().enumerate() {
+                let j = i as isize + k as isize - radius;
+                if j < 0 || j >= length as isize {
+                    continue;
+                }
+                let pixel = pixels[index(line, j as usize)];
+                for c in 0..4 {
+                    sum[c] += pixel[c] * weight;
+                }
+            }
+            out[index(line, i)] = sum.map(|c| c / total);
+        }
+    }
+    out
+}
+
+fn blur(pixels: Pixels, width: usize, height: usize, std_dev_x: f64, std_dev_y: f64) -> Pixels {
+    // a negative deviation is a error, which disables the primitive like zero does
+    if std_dev_x < 0.0 || std_dev_y < 0.0 {
+        return pixels;
+    }
+    let pixels = blur_direction(pixels, width, height, true, std_dev_x);
+    blur_direction(pixels, width, height, false, std_dev_y)
+}
+
+/// move the pixels by a vector rounded to whole pixels
+fn offset(pixels: &Pixels, width: usize, height: usize, dx: f64, dy: f64) -> Pixels {
+    let (dx, dy) = (dx.round() as isize, dy.round() as isize);
+    let mut out = vec![[0.0; 4]; pixels.len()];
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let (sx, sy) = (x - dx, y - dy);
+            if sx >= 0 && sy >= 0 && sx < width as isize && sy < height as isize {
+                out[(y * width as isize + x) as usize] =
+                    pixels[(sy * width as isize + sx) as usize];
+            }
+        }
+    }
+    out
+}
+
+/// the minimum or maximum of each channel in a rectangle of radius `rx` by `ry`
+fn morphology(
+    pixels: Pixels,
+    width: usize,
+    height: usize,
+    operator: MorphologyOperator,
+    rx: f64,
+    ry: f64,
+) -> Pixels {
+    let pick = |a: f32, b: f32| match operator {
+        MorphologyOperator::Erode => a.min(b),
+        MorphologyOperator::Dilate => a.max(b),
+    };
+    let pass = |pixels: &Pixels, radius: usize, horizontal: bool| {
+        let mut out = pixels.clone();
+        for y in 0..height {
+            for x in 0..width {
+                let (position, length) = if horizontal { (x, width) } else { (y, height) };
+                let start = position.saturating_sub(radius);
+                let end = (position + radius).min(length - 1);
+                let mut value = pixels[y * width + x];
+                for i in start..=end {
+                    let other = if horizontal {
+                        pixels[y * width + i]
+                    } else {
+                        pixels[i * width + x]
+                    };
+                    for c in 0..4 {
+                        value[c] = pick(value[c], other[c]);
+                    }
+                }
+                out[y * width + x] = value;
+            }
+        }
+        out
+    };
+    let pixels = pass(&pixels, rx.round() as usize, true);
+    pass(&pixels, ry.round() as usize, false)
+}
+
+const B_SIZE: usize = 0x100;
+const B_MASK: i64 = 0xff;
+const PERLIN_N: f64 = 4096.0;
+
+/// The lattice of the perlin noise of the spec for `feTurbulence`
+struct Noise {
+    lattice: Vec<usize>,
+    gradient: Vec<Vec<(f64, f64)>>,
+}
+
+/// The tile the noise wraps around, in lattice cells
+#[derive(Clone, Copy)]
+struct StitchInfo {
+    width: i64,
+    height: i64,
+    wrap_x: i64,
+    wrap_y: i64,
+}
+
+fn random(seed: i64) -> i64 {
+    const M: i64 = 2147483647;
+    const A: i64 = 16807;
+    const Q: i64 = 127773;
+    const R: i64 = 2836;
+    let result = A * (seed % Q) - R * (seed / Q);
+    if result <= 0 {
+        result + M
+    } else {
+        result
+    }
+}
+
+impl Noise {
+    fn new(seed: i64) -> Noise {
+        let mut seed = if seed <= 0 {
+            -(seed % (2147483647 - 1)) + 1
+        } else {
+            seed
+        };
+        if seed > 2147483647 - 1 {
+            seed = 2147483647 - 1;
+        }
+        let mut lattice = vec![0; B_SIZE + B_SIZE + 2];
+        let mut gradient = vec![vec![(0.0, 0.0); B_SIZE + B_SIZE + 2]; 4];
+        for channel in gradient.iter_mut() {
+            for (i, entry) in lattice.iter_mut().enumerate().take(B_SIZE) {
+                *entry = i;
+                let mut component = || {
+                    seed = random(seed);
+                    ((seed % (B_SIZE as i64 * 2)) - B_SIZE as i64) as f64 / B_SIZE as f64
+                };
+                let (x, y) = (component(), component());
+                let length = x.hypot(y);
+                channel[i] = if length == 0.0 {
+                    (0.0, 0.0)
+                } else {
+                    (x / length, y / length)
+                };
+            }
+        }
+        for i in (1..B_SIZE).rev() {
+            seed = random(seed);
+            let j = (seed % B_SIZE as i64) as usize;
+            lattice.swap(i, j);
+        }
+        for i in 0..B_SIZE + 2 {
+            lattice[B_SIZE + i] = lattice[i];
+            for channel in gradient.iter_mut() {
+                channel[B_SIZE + i] = channel[i];
+            }
+        }
+        Noise { lattice, gradient }
+    }
+
+    fn noise2(&self, channel: usize, (x, y): (f64, f64), stitch: Option<StitchInfo>) -> f64 {
+        let t = x + PERLIN_N;
+        let (mut bx0, rx0) = (t as i64, t - (t as i64) as f64);
+        let mut bx1 = bx0 + 1;
+        let t = y + PERLIN_N;
+        let (mut by0, ry0) = (t as i64, t - (t as i64) as f64);
+        let mut by1 = by0 + 1;
+        let (rx1, ry1) = (rx0 - 1.0, ry0 - 1.0);
+        if let Some(stitch) = stitch {
+            if bx0 >= stitch.wrap_x {
+                bx0 -= stitch.width;
+            }
+            if bx1 >= stitch.wrap_x {
+                bx1 -= stitch.width;
+            }
+            if by0 >= stitch.wrap_y {
+                by0 -= stitch.height;
+            }
+            if by1 >= stitch.wrap_y {
+                by1 -= stitch.height;
+            }
+        }
+        let (bx0, bx1) = ((bx0 & B_MASK) as usize, (bx1 & B_MASK) as usize);
+        let (by0, by1) = ((by0 & B_MASK) as usize, (by1 & B_MASK) as usize);
+        let i = self.lattice[bx0];
+        let j = self.lattice[bx1];
+        let gradient = &self.gradient[channel];
+        let s_curve = |t: f64| t * t * (3.0 - 2.0 * t);
+        let lerp = |t: f64, a: f64, b: f64| a + t * (b - a);
+        let (sx, sy) = (s_curve(rx0), s_curve(ry0));
+        let at = |b: usize, rx: f64, ry: f64| {
+            let q = gradient[b];
+            rx * q.0 + ry * q.1
+        };
+        let a = lerp(
+            sx,
+            at(self.lattice[i + by0], rx0, ry0),
+            at(self.lattice[j + by0], rx1, ry0),
+        );
+        let b = lerp(
+            sx,
+            at(self.lattice[i + by1], rx0, ry1),
+            at(self.lattice[j + by1], rx1, ry1),
+        );
+        lerp(sy, a, b)
+    }
+
+    fn turbulence(
+        &self,
+        channel: usize,
+        point: (f64, f64),
+        base_frequency: (f64, f64),
+        num_octaves: u32,
+        fractal_noise: bool,
+        tile: Option<BBox>,
+    ) -> f64 {
+        let (mut fx, mut fy) = base_frequency;
+        let mut stitch = None;
+        if let Some(tile) = tile {
+            let adjust = |frequency: f64, size: f64| {
+                if frequency == 0.0 {
+                    return frequency;
+                }
+                let low = (size * frequency).floor() / size;
+                let high = (size * frequency).ceil() / size;
+                if frequency / low < high / frequency {
+                    low
+                } else {
+                    high
+                }
+            };
+            fx = adjust(fx, tile.width());
+            fy = adjust(fy, tile.height());
+            let width = (tile.width() * fx + 0.5) as i64;
+            let height = (tile.height() * fy + 0.5) as i64;
+            stitch = Some(StitchInfo {
+                width,
+                height,
+                wrap_x: (tile.min_x * fx + PERLIN_N) as i64 + width,
+                wrap_y: (tile.min_y * fy + PERLIN_N) as i64 + height,
+            });
+        }
+        let mut sum = 0.0;
+        let mut vector = (point.0 * fx, point.1 * fy);
+        let mut ratio = 1.0;
+        for _ in 0..num_octaves {
+            let noise = self.noise2(channel, vector, stitch);
+            sum += if fractal_noise { noise } else { noise.abs() } / ratio;
+            vector = (vector.0 * 2.0, vector.1 * 2.0);
+            ratio *= 2.0;
+            if let Some(stitch) = &mut stitch {
+                stitch.width *= 2;
+                stitch.wrap_x = 2 * stitch.wrap_x - PERLIN_N as i64;
+                stitch.height *= 2;
+                stitch.wrap_y = 2 * stitch.wrap_y - PERLIN_N as i64;
+            }
+        }
+        sum
+    }
+}
+
+impl<'a> Filter<'a> {
+    /// apply the filter to a image, `matrix` maps the user space of the
+    /// filtered element to pixels and `bbox` is its bounding box in user space.
+    /// `None` if the element is not drawn, like for a filter without primitives.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, parse_document, render, BBox, LengthContext, Matrix, Rgba};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><filter id="f" width="2"><feOffset dx="2"/></filter><rect id="r" width="4" height="4" filter="url(#f)"/></svg>"##).unwrap();
+    /// let filter = document.resolve_filter(&document.element_by_id("r").unwrap(), &LengthContext::default()).unwrap();
+    /// let (_, square) = parse(r#"<svg width="8" height="8"><rect width="4" height="4"/></svg>"#).unwrap();
+    /// let pixmap = render(&square, 8, 8);
+    /// let moved = filter.apply(&pixmap, &Matrix::default(), Some(BBox::new(0.0, 0.0, 4.0, 4.0))).unwrap();
+    /// assert_eq!(moved.pixel(0, 0), Some(Rgba::TRANSPARENT));
+    /// assert_eq!(moved.pixel(4, 2), Some(Rgba::BLACK));
+    /// ```
+    ///
+    pub fn apply(&self, pixmap: &Pixmap, matrix: &Matrix, bbox: Option<BBox>) -> Option<Pixmap> {
+        let canvas =
+            Canvas::from_rgba8(pixmap.width as usize, pixmap.height as usize, &pixmap.data);
+        let canvas = apply_filter(self, &canvas, matrix, bbox)?;
+        Some(Pixmap {
+            width: pixmap.width,
+            height: pixmap.height,
+            data: canvas.to_rgba8(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::effects::{blend, blur, composite, morphology, offset, Noise, Pixels};
+    use crate::filter::{BlendMode, CompositeOperator, MorphologyOperator};
+    use crate::{parse, render, Rgba};
+
+    fn dot(width: usize, height: usize, x: usize, y: usize) -> Pixels {
+        let mut pixels = vec![[0.0; 4]; width * height];
+        pixels[y * width + x] = [1.0; 4];
+        pixels
+    }
+
+    #[test]
+    fn test_blur() {
+        for std_dev in [0.5, 3.0] {
+            let pixels = blur(dot(41, 41, 20, 20), 41, 41, std_dev, std_dev);
+            let total: f32 = pixels.iter().map(|p| p[3]).sum();
+            assert!((total - 1.0).abs() < 1e-4);
+            // symmetric around the dot
+            let close = |a: f32, b: f32| (a - b).abs() < 1e-6;
+            assert!(close(pixels[20 * 41 + 18][3], pixels[20 * 41 + 22][3]));
+            assert!(close(pixels[18 * 41 + 20][3], pixels[22 * 41 + 20][3]));
+            assert!(pixels[20 * 41 + 20][3] > pixels[20 * 41 + 21][3]);
+        }
+        // a deviation of zero in one direction blurs along the other only
+        let pixels = blur(dot(5, 5, 2, 2), 5, 5, 1.0, 0.0);
+        assert_eq!(pixels[3 * 5 + 2][3], 0.0);
+        assert!(pixels[2 * 5 + 3][3] > 0.0);
+    }
+
+    #[test]
+    fn test_offset_and_morphology() {
+        let pixels = offset(&dot(5, 5, 1, 1), 5, 5, 2.4, -0.6);
+        assert_eq!(pixels[3], [1.0; 4]);
+        let pixels = morphology(dot(5, 5, 2, 2), 5, 5, MorphologyOperator::Dilate, 1.0, 2.0);
+        assert_eq!(pixels.iter().filter(|p| p[3] == 1.0).count(), 15);
+        let pixels = morphology(pixels, 5, 5, MorphologyOperator::Erode, 1.0, 0.0);
+        assert_eq!(pixels.iter().filter(|p| p[3] == 1.0).count(), 5);
+    }
+
+    #[test]
+    fn test_composite_and_blend() {
+        let red = [0.5, 0.0, 0.0, 0.5];
+        let blue = [0.0, 0.0, 1.0, 1.0];
+        assert_eq!(
+            composite(CompositeOperator::Over, &red, &blue),
+            [0.5, 0.0, 0.5, 1.0]
+        );
+        assert_eq!(composite(CompositeOperator::In, &red, &blue), red);
+        assert_eq!(
+            composite(CompositeOperator::Out, &red, &blue),
+            [0.0, 0.0, 0.0, 0.0]
+        );
+        assert_eq!(
+            composite(CompositeOperator::Xor, &red, &blue),
+            [0.0, 0.0, 0.5, 0.5]
+        );
+        let arithmetic = CompositeOperator::Arithmetic {
+            k1: 0.0,
+            k2: 1.0,
+            k3: 1.0,
+            k4: 0.0,
+        };
+        assert_eq!(composite(arithmetic, &red, &blue), [0.5, 0.0, 1.0, 1.0]);
+        assert_eq!(blend(BlendMode::Normal, &red, &blue), [0.5, 0.0, 0.5, 1.0]);
+        let white = [1.0; 4];
+        let gray = [0.5, 0.5, 0.5, 1.0];
+        assert_eq!(blend(BlendMode::Multiply, &gray, &white), gray);
+        assert_eq!(
+            blend(BlendMode::Screen, &gray, &gray),
+            [0.75, 0.75, 0.75, 1.0]
+        );
+        assert_eq!(blend(BlendMode::Difference, &gray, &white), gray);
+    }
+
+    #[test]
+    fn test_turbulence() {
+        let noise = Noise::new(0);
+        // the noise is zero on the lattice
+        assert_eq!(noise.noise2(0, (3.0, 5.0), None), 0.0);
+        let value = noise.turbulence(0, (3.3, 5.7), (1.0, 1.0), 2, true, None);
+        assert!(value.abs() < 1.0 && value != 0.0);
+        assert_eq!(
+            value,
+            Noise::new(0).turbulence(0, (3.3, 5.7), (1.0, 1.0), 2, true, None)
+        );
+        assert_ne!(
+            value,
+            Noise::new(7).turbulence(0, (3.3, 5.7), (1.0, 1.0), 2, true, None)
+        );
+    }
+
+    fn pixel(svg: &str, x: u32, y: u32) -> Rgba {
+        let (_, root) = parse(svg).unwrap();
+        render(&root, 20, 20).pixel(x, y).unwrap()
+    }
+
+    #[test]
+    fn test_render_filters() {
+        let svg = r##"<svg viewBox="0 0 20 20"><filter id="f" x="0" y="0" width="2" height="2"><feDropShadow dx="5" dy="5" stdDeviation="0" flood-color="blue"/></filter><rect x="2" y="2" width="8" height="8" fill="red" filter="url(#f)"/></svg>"##;
+        assert_eq!(pixel(svg, 5, 5), Rgba::new(255, 0, 0, 1.0));
+        assert_eq!(pixel(svg, 13, 13), Rgba::new(0, 0, 255, 1.0));
+        assert_eq!(pixel(svg, 1, 1), Rgba::TRANSPARENT);
+        // the default region stops the shadow at 10% outside of the box
+        let svg = svg.replace(r#" x="0" y="0" width="2" height="2""#, "");
+        assert_eq!(pixel(&svg, 13, 13), Rgba::TRANSPARENT);
+
+        let svg = r##"<svg viewBox="0 0 20 20"><filter id="f" color-interpolation-filters="sRGB"><feFlood flood-color="white" result="w"/><feColorMatrix in="SourceGraphic" type="matrix" values="0 0 0 0 0 0 0 0 0 0 1 0 0 0 0 0 0 0 1 0"/><feComposite in2="w" operator="over"/></filter><rect width="10" height="10" fill="red" filter="url(#f)"/></svg>"##;
+        assert_eq!(pixel(svg, 5, 5), Rgba::new(0, 0, 255, 1.0));
+        assert_eq!(pixel(svg, 10, 5), Rgba::WHITE);
+        // a filter without primitives hides the element
+        let svg = r##"<svg viewBox="0 0 20 20"><filter id="f"/><rect width="10" height="10" fill="red" filter="url(#f)"/></svg>"##;
+        assert_eq!(pixel(svg, 5, 5), Rgba::TRANSPARENT);
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::{clone_subtree, Element};
+use crate::document::Document;
+use crate::number::{format_number, number_attribute, parse_number};
+use crate::reference::{element_href, Reference};
+use crate::view_box::{PreserveAspectRatio, ViewBox};
+
+/// the attributes of `<use>` which are consumed by the expansion
+const USE_ATTRIBUTES: [&str; 7] = [
+    "x",
+    "y",
+    "width",
+    "height",
+    "href",
+    "xlink:href",
+    "transform",
+];
+
+/// the attributes of `<symbol>` which are consumed by the expansion
+const SYMBOL_ATTRIBUTES: [&str; 9] = [
+    "id",
+    "x",
+    "y",
+    "width",
+    "height",
+    "viewBox",
+    "preserveAspectRatio",
+    "refX",
+    "refY",
+];
+
+/// A `<use>` which references itself through its own subtree
+///
+/// `cycle` the references walked through, see `Document::reference_cycles`
+#[derive(Debug, Clone)]
+pub struct RecursiveUseError<'a> {
+    pub cycle: Vec<Reference<'a>>,
+}
+
+impl<'a> fmt::Display for RecursiveUseError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids: Vec<String> = self.cycle.iter().map(|r| format!("#{}", r.id)).collect();
+        write!(f, "recursive <use> reference: {}", ids.join(" -> "))
+    }
+}
+
+impl<'a> Error for RecursiveUseError<'a> {}
+
+/// replace every `<use>` of the document with a copy of the element it references
+///
+/// the `<use>` becomes a `<g>` carrying its attributes, with `x`/`y` applied as a
+/// `translate` after its own `transform`. the referenced element is copied
+/// without its `id`; a `<symbol>` becomes a `<g>` scaled into the viewport of the
+/// `<use>`, or a nested `<svg>` when the size of the viewport is not two plain numbers.
+/// `<use>` elements whose reference is dangling are left untouched.
+///
+/// return a error and leave the tree unchanged if a `<use>` is recursive.
+///
+/// ## Example
+/// ```rust
+/// use svg_simple_parser::{expand_use, parse_document, stringify};
+///
+/// let (_, document) = parse_document(r##"<svg><defs><rect id="r" width="10"/></defs><use href="#r" x="5" fill="red"/></svg>"##).unwrap();
+/// expand_use(&document).unwrap();
+/// assert_eq!(
+///     stringify(document.root.clone()),
+///     r#"<svg><defs><rect id="r" width="10"/></defs><g fill="red" transform="translate(5 0)"><rect width="10"/></g></svg>"#
+/// );
+/// ```
+///
+pub fn expand_use<'a>(document: &Document<'a>) -> Result<(), RecursiveUseError<'a>> {
+    if let Some(cycle) = document
+        .reference_cycles()
+        .into_iter()
+        .find(|cycle| cycle.iter().any(is_use_reference))
+    {
+        return Err(RecursiveUseError { cycle });
+    }
+    loop {
+        let uses: Vec<(Rc<Element<'a>>, Rc<Element<'a>>)> = document
+            .root
+            .descendants()
+            .into_iter()
+            .filter(|ele| ele.ele_type == "use")
+            .filter_map(|ele| {
+                let target = element_href(&ele).and_then(|id| document.element_by_id(&id))?;
+                Some((ele, target))
+            })
+            .collect();
+        if uses.is_empty() {
+            break;
+        }
+        for (ele, target) in uses {
+            let expanded = expand_one(&ele, &target);
+            let parent = ele.parent.borrow().upgrade();
+            if let Some(parent) = parent {
+                parent.replace_child(&ele, expanded);
+            }
+        }
+        document.reindex();
+    }
+    Ok(())
+}
+
+fn is_use_reference(reference: &Reference) -> bool {
+    reference.source.ele_type == "use"
+        && (reference.attribute == "href" || reference.attribute == "xlink:href")
+}
+
+/// build the `<g>` which replaces a `<use>`
+fn expand_one<'a>(ele: &Rc<Element<'a>>, target: &Rc<Element<'a>>) -> Rc<Element<'a>> {
+    let group = Element::new(("g", HashMap::new()));
+    for (key, value) in ele.attributes.borrow().iter() {
+        if !USE_ATTRIBUTES.contains(&key.as_str()) {
+            group.set_attribute(key, value.clone());
+        }
+    }
+    let x = number_attribute(ele, "x").unwrap_or(0.0);
+    let y = number_attribute(ele, "y").unwrap_or(0.0);
+    let mut transforms: Vec<String> = ele
+        .get_attribute("transform")
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if x != 0.0 || y != 0.0 {
+        transforms.push(format!(
+            "translate({} {})",
+            format_number(x),
+            format_number(y)
+        ));
+    }
+    if !transforms.is_empty() {
+        group.set_attribute("transform", transforms.join(" "));
+    }
+
+    let content = match target.ele_type {
+        "symbol" => expand_symbol(ele, target),
+        "svg" => {
+            let content = clone_subtree(target);
+            for key in ["width", "height"] {
+                if let Some(value) = ele.get_attribute(key) {
+                    content.set_attribute(key, value);
+                }
+            }
+            content
+        }
+        _ => clone_subtree(target),
+    };
+    group.add_child(content);
+    group
+}
+
+/// convert a `<symbol>` to a `<g>` or a nested `<svg>` in the viewport of a `<use>`
+fn expand_symbol<'a>(ele: &Rc<Element<'a>>, symbol: &Rc<Element<'a>>) -> Rc<Element<'a>> {
+    let size = |key: &str| ele.get_attribute(key).or_else(|| symbol.get_attribute(key));
+    let view_box = symbol
+        .view_box()
+        .ok()
+        .flatten()
+        .filter(|view_box| view_box.width > 0.0 && view_box.height > 0.0);
+    let viewport = (
+        size("width").and_then(|v| parse_number(&v)),
+        size("height").and_then(|v| parse_number(&v)),
+    );
+
+    let content = match (&view_box, viewport) {
+        (Some(view_box), (Some(width), Some(height))) => {
+            let aspect = symbol.preserve_aspect_ratio().unwrap_or_default();
+            let content = Element::new(("g", HashMap::new()));
+            content.set_attribute(
+                "transform",
+                view_box_transform(view_box, &aspect, width, height),
+            );
+            content
+        }
+        (Some(_), _) => {
+            let content = Element::new(("svg", HashMap::new()));
+            for key in ["viewBox", "preserveAspectRatio"] {
+                if let Some(value) = symbol.get_attribute(key) {
+                    content.set_attribute(key, value);
+                }
+            }
+            for key in ["width", "height"] {
+                content.set_attribute(key, size(key).unwrap_or_else(|| "100%".into()));
+            }
+            content
+        }
+        (None, _) => Element::new(("g", HashMap::new())),
+    };
+    for (key, value) in symbol.attributes.borrow().iter() {
+        if !SYMBOL_ATTRIBUTES.contains(&key.as_str()) {
+            content.set_attribute(key, value.clone());
+        }
+    }
+    for child in symbol.children.borrow().iter() {
+        content.add_child(clone_subtree(child));
+    }
+    content
+}
+
+/// the transform mapping a `viewBox` into a viewport of `width` x `height`
+fn view_box_transform(
+    view_box: &ViewBox,
+    aspect: &PreserveAspectRatio,
+    width: f64,
+    height: f64,
+) -> String {
+    let matrix = view_box.transform((0.0, 0.0, width, height), aspect);
+    let (sx, sy, tx, ty) = (matrix.a, matrix.d, matrix.e, matrix.f);
+    let mut transforms = vec![];
+    if tx != 0.0 || ty != 0.0 {
+        transforms.push(format!(
+            "translate({} {})",
+            format_number(tx),
+            format_number(ty)
+        ));
+    }
+    if sx == sy {
+        transforms.push(format!("scale({})", format_number(sx)));
+    } else {
+        transforms.push(format!(
+            "scale({} {})",
+            format_number(sx),
+            format_number(sy)
+        ));
+    }
+    transforms.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expand_use::view_box_transform;
+    use crate::{expand_use, parse_document, stringify, PreserveAspectRatio, ViewBox};
+
+    #[test]
+    fn test_view_box_transform() {
+        let view_box_transform = |view_box: [f64; 4], aspect: &str, width, height| {
+            let view_box = ViewBox::new(view_box[0], view_box[1], view_box[2], view_box[3]);
+            let aspect = PreserveAspectRatio::parse(aspect).unwrap_or_default();
+            view_box_transform(&view_box, &aspect, width, height)
+        };
+        assert_eq!(
+            view_box_transform([0.0, 0.0, 10.0, 10.0], "", 20.0, 20.0),
+            "scale(2)"
+        );
+        assert_eq!(
+            view_box_transform([0.0, 0.0, 10.0, 20.0], "", 20.0, 20.0),
+            "translate(5 0) scale(1)"
+        );
+        assert_eq!(
+            view_box_transform([0.0, 0.0, 10.0, 20.0], "xMinYMin slice", 20.0, 20.0),
+            "scale(2)"
+        );
+        assert_eq!(
+            view_box_transform([5.0, 5.0, 10.0, 20.0], "none", 20.0, 20.0),
+            "translate(-10 -5) scale(2 1)"
+        );
+    }
+
+    #[test]
+    fn test_expand_symbol() {
+        let (_, document) = parse_document(
+            r##"<svg>
+                <symbol id="icon" viewBox="0 0 10 10" fill="blue"><circle r="5"/></symbol>
+                <use id="u" href="#icon" x="1" y="2" width="20" height="20" stroke="red"/>
+                <use xlink:href="#icon" width="50%"/>
+            </svg>"##,
+        )
+        .unwrap();
+        expand_use(&document).unwrap();
+        let children = document.root.children.borrow();
+        assert_eq!(
+            stringify(children[1].clone()),
+            r#"<g id="u" stroke="red" transform="translate(1 2)"><g fill="blue" transform="scale(2)"><circle r="5"/></g></g>"#
+        );
+        assert_eq!(
+            stringify(children[2].clone()),
+            r#"<g><svg fill="blue" height="100%" viewBox="0 0 10 10" width="50%"><circle r="5"/></svg></g>"#
+        );
+        assert_eq!(document.element_by_id("u").unwrap().ele_type, "g");
+    }
+
+    #[test]
+    fn test_expand_nested_use() {
+        let (_, document) = parse_document(
+            r##"<svg>
+                <rect id="r" width="10"/>
+                <g id="pair"><use href="#r"/><use href="#r" x="10"/></g>
+                <use href="#pair" transform="rotate(90)" y="20"/>
+                <use href="#missing"/>
+            </svg>"##,
+        )
+        .unwrap();
+        expand_use(&document).unwrap();
+        assert_eq!(
+            stringify(document.root.clone()),
+            concat!(
+                r#"<svg><rect id="r" width="10"/>"#,
+                r#"<g id="pair"><g><rect width="10"/></g><g transform="translate(10 0)"><rect width="10"/></g></g>"#,
+                r#"<g transform="rotate(90) translate(0 20)"><g><g><rect width="10"/></g><g transform="translate(10 0)"><rect width="10"/></g></g></g>"#,
+                r##"<use href="#missing"/></svg>"##
+            )
+        );
+    }
+
+    #[test]
+    fn test_expand_use_keeps_tail() {
+        let (_, document) =
+            parse_document(r##"<svg><rect id="r"/><text>a<use href="#r"/> keep me</text></svg>"##)
+                .unwrap();
+        expand_use(&document).unwrap();
+        assert_eq!(
+            stringify(document.root.clone()),
+            r#"<svg><rect id="r"/><text>a<g><rect/></g> keep me</text></svg>"#
+        );
+    }
+
+    #[test]
+    fn test_expand_recursive_use() {
+        let (_, document) = parse_document(
+            r##"<svg><g id="a"><use href="#b"/></g><g id="b"><use href="#a"/></g></svg>"##,
+        )
+        .unwrap();
+        let error = expand_use(&document).unwrap_err();
+        assert_eq!(error.to_string(), "recursive <use> reference: #b -> #a");
+        assert_eq!(document.root.descendants().len(), 4);
+    }
+}
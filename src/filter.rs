@@ -0,0 +1,967 @@
+use std::rc::Rc;
+
+use crate::ast::Element;
+use crate::bbox::BBox;
+use crate::color::Rgba;
+use crate::css::ComputedStyles;
+use crate::document::Document;
+use crate::length::{Length, LengthContext};
+use crate::number::{number_attribute, parse_number_list};
+use crate::paint::{color_property, property, resolve_length, Units};
+use crate::reference::url_ids;
+
+/// A input of a filter primitive, `in` or `in2`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterInput {
+    SourceGraphic,
+    SourceAlpha,
+    BackgroundImage,
+    BackgroundAlpha,
+    FillPaint,
+    StrokePaint,
+    /// the `result` of a earlier primitive
+    Reference(String),
+}
+
+impl FilterInput {
+    /// parse a `in` value, `None` if it is empty
+    pub fn parse(value: &str) -> Option<FilterInput> {
+        match value.trim() {
+            "" => None,
+            "SourceGraphic" => Some(FilterInput::SourceGraphic),
+            "SourceAlpha" => Some(FilterInput::SourceAlpha),
+            "BackgroundImage" => Some(FilterInput::BackgroundImage),
+            "BackgroundAlpha" => Some(FilterInput::BackgroundAlpha),
+            "FillPaint" => Some(FilterInput::FillPaint),
+            "StrokePaint" => Some(FilterInput::StrokePaint),
+            name => Some(FilterInput::Reference(name.to_owned())),
+        }
+    }
+}
+
+/// The color space a filter primitive works in, `color-interpolation-filters`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorInterpolation {
+    #[default]
+    LinearRgb,
+    Srgb,
+}
+
+/// The operation of a `feColorMatrix`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorMatrixKind {
+    /// a 4 by 5 matrix by rows, applied to the straight RGBA and `1`
+    Matrix([f64; 20]),
+    Saturate(f64),
+    /// a rotation of the hue in degrees
+    HueRotate(f64),
+    LuminanceToAlpha,
+}
+
+impl ColorMatrixKind {
+    /// the equivalent 4 by 5 matrix
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::ColorMatrixKind;
+    ///
+    /// let identity = ColorMatrixKind::Saturate(1.0).to_matrix();
+    /// assert_eq!(identity[0], 1.0);
+    /// assert_eq!(identity[6], 1.0);
+    /// assert_eq!(identity[1], 0.0);
+    /// ```
+    ///
+    pub fn to_matrix(&self) -> [f64; 20] {
+        match self {
+            ColorMatrixKind::Matrix(values) => *values,
+            ColorMatrixKind::Saturate(s) => [
+                0.213 + 0.787 * s,
+                0.715 - 0.715 * s,
+                0.072 - 0.072 * s,
+                0.0,
+                0.0,
+                0.213 - 0.213 * s,
+                0.715 + 0.285 * s,
+                0.072 - 0.072 * s,
+                0.0,
+                0.0,
+                0.213 - 0.213 * s,
+                0.715 - 0.715 * s,
+                0.072 + 0.928 * s,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                0.0,
+                1.0,
+                0.0,
+            ],
+            ColorMatrixKind::HueRotate(degrees) => {
+                let (sin, cos) = degrees.to_radians().sin_cos();
+                [
+                    0.213 + cos * 0.787 - sin * 0.213,
+                    0.715 - cos * 0.715 - sin * 0.715,
+                    0.072 - cos * 0.072 + sin * 0.928,
+                    0.0,
+                    0.0,
+                    0.213 - cos * 0.213 + sin * 0.143,
+                    0.715 + cos * 0.285 + sin * 0.140,
+                    0.072 - cos * 0.072 - sin * 0.283,
+                    0.0,
+                    0.0,
+                    0.213 - cos * 0.213 - sin * 0.787,
+                    0.715 - cos * 0.715 + sin * 0.715,
+                    0.072 + cos * 0.928 + sin * 0.072,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    1.0,
+                    0.0,
+                ]
+            }
+            ColorMatrixKind::LuminanceToAlpha => {
+                let mut values = [0.0; 20];
+                values[15] = 0.2125;
+                values[16] = 0.7154;
+                values[17] = 0.0721;
+                values
+            }
+        }
+    }
+}
+
+/// The operator of a `feComposite`, `in` is composited onto `in2`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompositeOperator {
+    Over,
+    In,
+    Out,
+    Atop,
+    Xor,
+    Lighter,
+    /// `k1 * in * in2 + k2 * in + k3 * in2 + k4` for each premultiplied channel
+    Arithmetic {
+        k1: f64,
+        k2: f64,
+        k3: f64,
+        k4: f64,
+    },
+}
+
+/// The mode of a `feBlend`, `in` is blended onto `in2`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+}
+
+impl BlendMode {
+    /// parse a `mode` value
+    pub fn parse(value: &str) -> Option<BlendMode> {
+        match value.trim() {
+            "normal" => Some(BlendMode::Normal),
+            "multiply" => Some(BlendMode::Multiply),
+            "screen" => Some(BlendMode::Screen),
+            "overlay" => Some(BlendMode::Overlay),
+            "darken" => Some(BlendMode::Darken),
+            "lighten" => Some(BlendMode::Lighten),
+            "color-dodge" => Some(BlendMode::ColorDodge),
+            "color-burn" => Some(BlendMode::ColorBurn),
+            "hard-light" => Some(BlendMode::HardLight),
+            "soft-light" => Some(BlendMode::SoftLight),
+            "difference" => Some(BlendMode::Difference),
+            "exclusion" => Some(BlendMode::Exclusion),
+            _ => None,
+        }
+    }
+}
+
+/// The operator of a `feMorphology`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MorphologyOperator {
+    /// the minimum of the neighbourhood, which thins
+    #[default]
+    Erode,
+    /// the maximum of the neighbourhood, which fattens
+    Dilate,
+}
+
+/// A `feFuncR`, `feFuncG`, `feFuncB` or `feFuncA` of a `feComponentTransfer`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum TransferFunction {
+    #[default]
+    Identity,
+    Table(Vec<f64>),
+    Discrete(Vec<f64>),
+    Linear {
+        slope: f64,
+        intercept: f64,
+    },
+    Gamma {
+        amplitude: f64,
+        exponent: f64,
+        offset: f64,
+    },
+}
+
+impl TransferFunction {
+    /// the transferred value of a straight channel between `0` and `1`
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::TransferFunction;
+    ///
+    /// assert_eq!(TransferFunction::Table(vec![1.0, 0.0]).apply(0.25), 0.75);
+    /// assert_eq!(TransferFunction::Discrete(vec![0.2, 0.8]).apply(0.25), 0.2);
+    /// assert_eq!(TransferFunction::Linear { slope: 2.0, intercept: 0.5 }.apply(0.25), 1.0);
+    /// ```
+    ///
+    pub fn apply(&self, c: f64) -> f64 {
+        let value = match self {
+            TransferFunction::Identity => c,
+            TransferFunction::Table(values) if values.len() > 1 => {
+                let n = (values.len() - 1) as f64;
+                let k = ((c * n).floor() as usize).min(values.len() - 2);
+                values[k] + (c - k as f64 / n) * n * (values[k + 1] - values[k])
+            }
+            TransferFunction::Discrete(values) if !values.is_empty() => {
+                let n = values.len();
+                values[((c * n as f64).floor() as usize).min(n - 1)]
+            }
+            TransferFunction::Table(_) | TransferFunction::Discrete(_) => c,
+            TransferFunction::Linear { slope, intercept } => slope * c + intercept,
+            TransferFunction::Gamma {
+                amplitude,
+                exponent,
+                offset,
+            } => amplitude * c.powf(*exponent) + offset,
+        };
+        value.clamp(0.0, 1.0)
+    }
+}
+
+/// The light of a `feDiffuseLighting` or `feSpecularLighting`, in primitive units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightSource {
+    /// a light from far away, angles in degrees
+    Distant {
+        azimuth: f64,
+        elevation: f64,
+    },
+    Point {
+        x: f64,
+        y: f64,
+        z: f64,
+    },
+    Spot {
+        x: f64,
+        y: f64,
+        z: f64,
+        points_at_x: f64,
+        points_at_y: f64,
+        points_at_z: f64,
+        specular_exponent: f64,
+        /// the angle in degrees around the axis of the light outside of which it is dark
+        limiting_cone_angle: Option<f64>,
+    },
+}
+
+/// The operation of a filter primitive
+///
+/// a input which is `None` is the result of the previous primitive, or the
+/// `SourceGraphic` for the first one. numbers like a standard deviation or a
+/// offset are in primitive units.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterKind {
+    GaussianBlur {
+        input: Option<FilterInput>,
+        std_dev_x: f64,
+        std_dev_y: f64,
+    },
+    Offset {
+        input: Option<FilterInput>,
+        dx: f64,
+        dy: f64,
+    },
+    ColorMatrix {
+        input: Option<FilterInput>,
+        kind: ColorMatrixKind,
+    },
+    Composite {
+        input: Option<FilterInput>,
+        input2: Option<FilterInput>,
+        operator: CompositeOperator,
+    },
+    Merge {
+        inputs: Vec<Option<FilterInput>>,
+    },
+    /// a color with the alpha of `flood-opacity`
+    Flood {
+        color: Rgba,
+    },
+    Blend {
+        input: Option<FilterInput>,
+        input2: Option<FilterInput>,
+        mode: BlendMode,
+    },
+    Morphology {
+        input: Option<FilterInput>,
+        operator: MorphologyOperator,
+        radius_x: f64,
+        radius_y: f64,
+    },
+    Turbulence {
+        base_frequency_x: f64,
+        base_frequency_y: f64,
+        num_octaves: u32,
+        seed: f64,
+        stitch_tiles: bool,
+        fractal_noise: bool,
+    },
+    /// the functions of the red, green, blue and alpha channels
+    ComponentTransfer {
+        input: Option<FilterInput>,
+        functions: [TransferFunction; 4],
+    },
+    DropShadow {
+        input: Option<FilterInput>,
+        std_dev_x: f64,
+        std_dev_y: f64,
+        dx: f64,
+        dy: f64,
+        color: Rgba,
+    },
+    DiffuseLighting {
+        input: Option<FilterInput>,
+        surface_scale: f64,
+        diffuse_constant: f64,
+        color: Rgba,
+        light: Option<LightSource>,
+    },
+    SpecularLighting {
+        input: Option<FilterInput>,
+        surface_scale: f64,
+        specular_constant: f64,
+        specular_exponent: f64,
+        color: Rgba,
+        light: Option<LightSource>,
+    },
+    /// a primitive which is not supported, its result is transparent
+    Unsupported {
+        name: String,
+    },
+}
+
+/// A filter primitive of a `filter`
+///
+/// the subregion `x`, `y`, `width` and `height` is in primitive units, a missing
+/// one takes the value of the filter region.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterPrimitive {
+    pub kind: FilterKind,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    pub result: Option<String>,
+    pub color_interpolation: ColorInterpolation,
+}
+
+/// A resolved `filter`
+///
+/// the filter region `x`, `y`, `width` and `height` is in `units`, the
+/// primitives use `primitive_units`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter<'a> {
+    pub element: Rc<Element<'a>>,
+    pub units: Units,
+    pub primitive_units: Units,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub primitives: Vec<FilterPrimitive>,
+}
+
+impl<'a> Filter<'a> {
+    /// the filter region in the user space of a element with the bounding box
+    /// `bbox`, outside of which the result is transparent. `None` for a region
+    /// without area.
+    pub fn region(&self, bbox: &BBox) -> Option<BBox> {
+        if self.width <= 0.0 || self.height <= 0.0 {
+            return None;
+        }
+        let m = self.units.to_user_space(bbox)?;
+        Some(BBox::new(self.x, self.y, self.x + self.width, self.y + self.height).transformed(&m))
+    }
+
+    /// the subregion of a primitive in the user space of a element with the
+    /// bounding box `bbox`, clipped to the filter region
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse_document, BBox, LengthContext};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><filter id="f" x="0" y="0" width="1" height="1" primitiveUnits="objectBoundingBox"><feFlood x="0.5" height="2"/></filter><rect id="r" width="10" height="10" filter="url(#f)"/></svg>"##).unwrap();
+    /// let filter = document.resolve_filter(&document.element_by_id("r").unwrap(), &LengthContext::default()).unwrap();
+    /// let bbox = BBox::new(0.0, 0.0, 10.0, 10.0);
+    /// assert_eq!(filter.region(&bbox), Some(bbox));
+    /// assert_eq!(filter.subregion(&filter.primitives[0], &bbox), Some(BBox::new(5.0, 0.0, 10.0, 10.0)));
+    /// ```
+    ///
+    pub fn subregion(&self, primitive: &FilterPrimitive, bbox: &BBox) -> Option<BBox> {
+        let region = self.region(bbox)?;
+        let m = self.primitive_units.to_user_space(bbox)?;
+        let (x, y) = m.apply(primitive.x.unwrap_or(0.0), primitive.y.unwrap_or(0.0));
+        let min_x = primitive.x.map_or(region.min_x, |_| x);
+        let min_y = primitive.y.map_or(region.min_y, |_| y);
+        let max_x = primitive
+            .width
+            .map_or(region.max_x, |width| min_x + width * m.a);
+        let max_y = primitive
+            .height
+            .map_or(region.max_y, |height| min_y + height * m.d);
+        let (min_x, min_y) = (min_x.max(region.min_x), min_y.max(region.min_y));
+        let (max_x, max_y) = (max_x.min(region.max_x), max_y.min(region.max_y));
+        if max_x <= min_x || max_y <= min_y {
+            return None;
+        }
+        Some(BBox::new(min_x, min_y, max_x, max_y))
+    }
+}
+
+/// the `in` or `in2` of a primitive
+fn input(ele: &Element, key: &str) -> Option<FilterInput> {
+    ele.get_attribute(key)
+        .and_then(|value| FilterInput::parse(&value))
+}
+
+/// a number attribute, `default` if it is missing or invalid
+fn number(ele: &Element, key: &str, default: f64) -> f64 {
+    number_attribute(ele, key).unwrap_or(default)
+}
+
+/// a attribute of one or two numbers, the second one defaults to the first
+fn number_pair(ele: &Element, key: &str, default: f64) -> (f64, f64) {
+    let numbers = ele
+        .get_attribute(key)
+        .and_then(|value| parse_number_list(&value));
+    match numbers.as_deref() {
+        Some([n]) => (*n, *n),
+        Some([x, y]) => (*x, *y),
+        _ => (default, default),
+    }
+}
+
+fn light_source(ele: &Element) -> Option<LightSource> {
+    let light = ele
+        .children
+        .borrow()
+        .iter()
+        .find(|child| ["feDistantLight", "fePointLight", "feSpotLight"].contains(&child.ele_type))?
+        .clone();
+    let n = |key, default| number(&light, key, default);
+    Some(match light.ele_type {
+        "feDistantLight" => LightSource::Distant {
+            azimuth: n("azimuth", 0.0),
+            elevation: n("elevation", 0.0),
+        },
+        "fePointLight" => LightSource::Point {
+            x: n("x", 0.0),
+            y: n("y", 0.0),
+            z: n("z", 0.0),
+        },
+        _ => LightSource::Spot {
+            x: n("x", 0.0),
+            y: n("y", 0.0),
+            z: n("z", 0.0),
+            points_at_x: n("pointsAtX", 0.0),
+            points_at_y: n("pointsAtY", 0.0),
+            points_at_z: n("pointsAtZ", 0.0),
+            specular_exponent: n("specularExponent", 1.0),
+            limiting_cone_angle: number_attribute(&light, "limitingConeAngle"),
+        },
+    })
+}
+
+fn transfer_function(ele: &Element) -> TransferFunction {
+    let n = |key, default| number(ele, key, default);
+    let table = || {
+        ele.get_attribute("tableValues")
+            .and_then(|value| parse_number_list(&value))
+            .unwrap_or_default()
+    };
+    match ele.get_attribute("type").as_deref().map(str::trim) {
+        Some("table") => TransferFunction::Table(table()),
+        Some("discrete") => TransferFunction::Discrete(table()),
+        Some("linear") => TransferFunction::Linear {
+            slope: n("slope", 1.0),
+            intercept: n("intercept", 0.0),
+        },
+        Some("gamma") => TransferFunction::Gamma {
+            amplitude: n("amplitude", 1.0),
+            exponent: n("exponent", 1.0),
+            offset: n("offset", 0.0),
+        },
+        _ => TransferFunction::Identity,
+    }
+}
+
+fn filter_kind<'a>(styles: &ComputedStyles<'a>, ele: &Rc<Element<'a>>) -> FilterKind {
+    let n = |key, default| number(ele, key, default);
+    let style = styles.style(ele);
+    let color = |key, opacity_key, default| color_property(&style, key, opacity_key, default);
+    let keyword = |key: &str| ele.get_attribute(key).map(|value| value.trim().to_owned());
+    match ele.ele_type {
+        "feGaussianBlur" => {
+            let (std_dev_x, std_dev_y) = number_pair(ele, "stdDeviation", 0.0);
+            FilterKind::GaussianBlur {
+                input: input(ele, "in"),
+                std_dev_x,
+                std_dev_y,
+            }
+        }
+        "feOffset" => FilterKind::Offset {
+            input: input(ele, "in"),
+            dx: n("dx", 0.0),
+            dy: n("dy", 0.0),
+        },
+        "feColorMatrix" => {
+            let values = ele
+                .get_attribute("values")
+                .and_then(|value| parse_number_list(&value));
+            let kind = match keyword("type").as_deref() {
+                Some("saturate") => ColorMatrixKind::Saturate(
+                    values
+                        .as_deref()
+                        .map_or(1.0, |v| v.first().copied().unwrap_or(1.0)),
+                ),
+                Some("hueRotate") => ColorMatrixKind::HueRotate(
+                    values
+                        .as_deref()
+                        .map_or(0.0, |v| v.first().copied().unwrap_or(0.0)),
+                ),
+                Some("luminanceToAlpha") => ColorMatrixKind::LuminanceToAlpha,
+                _ => match values
+                    .as_deref()
+                    .and_then(|v| <[f64; 20]>::try_from(v).ok())
+                {
+                    Some(values) => ColorMatrixKind::Matrix(values),
+                    // the identity
+                    None => ColorMatrixKind::Saturate(1.0),
+                },
+            };
+            FilterKind::ColorMatrix {
+                input: input(ele, "in"),
+                kind,
+            }
+        }
+        "feComposite" => FilterKind::Composite {
+            input: input(ele, "in"),
+            input2: input(ele, "in2"),
+            operator: match keyword("operator").as_deref() {
+                Some("in") => CompositeOperator::In,
+                Some("out") => CompositeOperator::Out,
+                Some("atop") => CompositeOperator::Atop,
+                Some("xor") => CompositeOperator::Xor,
+                Some("lighter") => CompositeOperator::Lighter,
+                Some("arithmetic") => CompositeOperator::Arithmetic {
+                    k1: n("k1", 0.0),
+                    k2: n("k2", 0.0),
+                    k3: n("k3", 0.0),
+                    k4: n("k4", 0.0),
+                },
+                _ => CompositeOperator::Over,
+            },
+        },
+        "feMerge" => FilterKind::Merge {
+            inputs: ele
+                .children
+                .borrow()
+                .iter()
+                .filter(|child| child.ele_type == "feMergeNode")
+                .map(|child| input(child, "in"))
+                .collect(),
+        },
+        "feFlood" => FilterKind::Flood {
+            color: color("flood-color", Some("flood-opacity"), Rgba::BLACK),
+        },
+        "feBlend" => FilterKind::Blend {
+            input: input(ele, "in"),
+            input2: input(ele, "in2"),
+            mode: keyword("mode")
+                .and_then(|value| BlendMode::parse(&value))
+                .unwrap_or_default(),
+        },
+        "feMorphology" => {
+            let (radius_x, radius_y) = number_pair(ele, "radius", 0.0);
+            FilterKind::Morphology {
+                input: input(ele, "in"),
+                operator: match keyword("operator").as_deref() {
+                    Some("dilate") => MorphologyOperator::Dilate,
+                    _ => MorphologyOperator::Erode,
+                },
+                radius_x,
+                radius_y,
+            }
+        }
+        "feTurbulence" => {
+            let (base_frequency_x, base_frequency_y) = number_pair(ele, "baseFrequency", 0.0);
+            FilterKind::Turbulence {
+                base_frequency_x,
+                base_frequency_y,
+                num_octaves: n("numOctaves", 1.0).max(0.0) as u32,
+                seed: n("seed", 0.0),
+                stitch_tiles: keyword("stitchTiles").as_deref() == Some("stitch"),
+                fractal_noise: keyword("type").as_deref() == Some("fractalNoise"),
+            }
+        }
+        "feComponentTransfer" => {
+            let mut functions: [TransferFunction; 4] = Default::default();
+            for child in ele.children.borrow().iter() {
+                let index = match child.ele_type {
+                    "feFuncR" => 0,
+                    "feFuncG" => 1,
+                    "feFuncB" => 2,
+                    "feFuncA" => 3,
+                    _ => continue,
+                };
+                functions[index] = transfer_function(child);
+            }
+            FilterKind::ComponentTransfer {
+                input: input(ele, "in"),
+                functions,
+            }
+        }
+        "feDropShadow" => {
+            let (std_dev_x, std_dev_y) = number_pair(ele, "stdDeviation", 2.0);
+            FilterKind::DropShadow {
+                input: input(ele, "in"),
+                std_dev_x,
+                std_dev_y,
+                dx: n("dx", 2.0),
+                dy: n("dy", 2.0),
+                color: color("flood-color", Some("flood-opacity"), Rgba::BLACK),
+            }
+        }
+        "feDiffuseLighting" => FilterKind::DiffuseLighting {
+            input: input(ele, "in"),
+            surface_scale: n("surfaceScale", 1.0),
+            diffuse_constant: n("diffuseConstant", 1.0),
+            color: color("lighting-color", None, Rgba::WHITE),
+            light: light_source(ele),
+        },
+        "feSpecularLighting" => FilterKind::SpecularLighting {
+            input: input(ele, "in"),
+            surface_scale: n("surfaceScale", 1.0),
+            specular_constant: n("specularConstant", 1.0),
+            specular_exponent: n("specularExponent", 1.0),
+            color: color("lighting-color", None, Rgba::WHITE),
+            light: light_source(ele),
+        },
+        name => FilterKind::Unsupported {
+            name: name.to_owned(),
+        },
+    }
+}
+
+impl<'a> Document<'a> {
+    /// resolve the `filter` of a element to the `filter` it references
+    ///
+    /// `None` without a filter, if the reference is not a `filter` or for a list
+    /// of filter functions like `blur(2px)`, which is not supported. lengths in
+    /// user space are resolved against `context`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse_document, FilterInput, FilterKind, LengthContext, Units};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><filter id="f" filterUnits="userSpaceOnUse" width="50"><feGaussianBlur in="SourceAlpha" stdDeviation="2 1" result="blur"/><feOffset in="blur" dx="3"/></filter><rect id="r" filter="url(#f)"/></svg>"##).unwrap();
+    /// let filter = document.resolve_filter(&document.element_by_id("r").unwrap(), &LengthContext::default()).unwrap();
+    /// assert_eq!(filter.units, Units::UserSpaceOnUse);
+    /// assert_eq!((filter.x, filter.width), (-10.0, 50.0));
+    /// assert_eq!(
+    ///     filter.primitives[0].kind,
+    ///     FilterKind::GaussianBlur { input: Some(FilterInput::SourceAlpha), std_dev_x: 2.0, std_dev_y: 1.0 }
+    /// );
+    /// assert_eq!(filter.primitives[0].result.as_deref(), Some("blur"));
+    /// ```
+    ///
+    pub fn resolve_filter(
+        &self,
+        ele: &Rc<Element<'a>>,
+        context: &LengthContext,
+    ) -> Option<Filter<'a>> {
+        let value = property(ele, "filter")?;
+        let id = url_ids(&value).first().copied()?;
+        let element = self
+            .element_by_id(id)
+            .filter(|target| target.ele_type == "filter")?;
+        let styles = ComputedStyles::new(self.stylesheet());
+        let units = element
+            .get_attribute("filterUnits")
+            .and_then(|value| Units::parse(&value))
+            .unwrap_or(Units::ObjectBoundingBox);
+        let primitive_units = element
+            .get_attribute("primitiveUnits")
+            .and_then(|value| Units::parse(&value))
+            .unwrap_or(Units::UserSpaceOnUse);
+        let length = |ele: &Element, key: &str, units: Units| {
+            ele.get_attribute(key)
+                .and_then(|value| Length::parse(&value).ok())
+                .map(|length| resolve_length(length, units, key, context))
+        };
+        let region = |key, default: f64| length(&element, key, units).unwrap_or(default);
+        let (x, y, width, height) = match units {
+            Units::ObjectBoundingBox => (
+                region("x", -0.1),
+                region("y", -0.1),
+                region("width", 1.2),
+                region("height", 1.2),
+            ),
+            Units::UserSpaceOnUse => {
+                let (w, h) = context.viewport;
+                (
+                    region("x", -0.1 * w),
+                    region("y", -0.1 * h),
+                    region("width", 1.2 * w),
+                    region("height", 1.2 * h),
+                )
+            }
+        };
+        let primitives = element
+            .children
+            .borrow()
+            .iter()
+            .filter(|child| child.ele_type.starts_with("fe"))
+            .map(|child| FilterPrimitive {
+                kind: filter_kind(&styles, child),
+                x: length(child, "x", primitive_units),
+                y: length(child, "y", primitive_units),
+                width: length(child, "width", primitive_units),
+                height: length(child, "height", primitive_units),
+                result: child
+                    .get_attribute("result")
+                    .map(|value| value.trim().to_owned())
+                    .filter(|value| !value.is_empty()),
+                color_interpolation: match styles
+                    .get(child, "color-interpolation-filters")
+                    .as_deref()
+                    .map(str::trim)
+                {
+                    Some("sRGB") => ColorInterpolation::Srgb,
+                    _ => ColorInterpolation::LinearRgb,
+                },
+            })
+            .collect();
+        Some(Filter {
+            element,
+            units,
+            primitive_units,
+            x,
+            y,
+            width,
+            height,
+            primitives,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse_document, BlendMode, ColorInterpolation, ColorMatrixKind, CompositeOperator,
+        FilterInput, FilterKind, LengthContext, LightSource, MorphologyOperator, Rgba,
+        TransferFunction, Units,
+    };
+
+    #[test]
+    fn test_resolve_filter() {
+        let (_, document) = parse_document(
+            r##"<svg color="lime"><filter id="f" x="0" width="50%" primitiveUnits="objectBoundingBox" color-interpolation-filters="sRGB">
+<feColorMatrix type="saturate" values="0.5" color-interpolation-filters="linearRGB"/>
+<feColorMatrix values="1 2 3"/>
+<feComposite in="SourceGraphic" in2="a" operator="arithmetic" k2="1" k3=".5"/>
+<feMerge><feMergeNode in="a"/><feMergeNode/></feMerge>
+<feFlood flood-color="currentColor" flood-opacity="50%" x="10%" result=" "/>
+<feBlend in2="BackgroundImage" mode="screen"/>
+<feMorphology operator="dilate" radius="1 2"/>
+<feTurbulence baseFrequency="0.05" numOctaves="3" seed="4" stitchTiles="stitch" type="fractalNoise"/>
+<feComponentTransfer><feFuncR type="table" tableValues="0 1"/><feFuncA type="gamma" exponent="2"/></feComponentTransfer>
+<feDropShadow stdDeviation="1" style="flood-color:red"/>
+<feDiffuseLighting lighting-color="blue"><feDistantLight azimuth="45"/></feDiffuseLighting>
+<feSpecularLighting specularExponent="20"><feSpotLight x="1" pointsAtZ="-1" limitingConeAngle="30"/></feSpecularLighting>
+<feTile/>
+</filter><rect id="r" filter="url(#f)"/><rect id="n" filter="blur(2px)"/></svg>"##,
+        )
+        .unwrap();
+        let context = LengthContext::default();
+        let ele = |id| document.element_by_id(id).unwrap();
+        assert_eq!(document.resolve_filter(&ele("n"), &context), None);
+        let filter = document.resolve_filter(&ele("r"), &context).unwrap();
+        assert_eq!(filter.units, Units::ObjectBoundingBox);
+        assert_eq!(filter.primitive_units, Units::ObjectBoundingBox);
+        assert_eq!(
+            (filter.x, filter.y, filter.width, filter.height),
+            (0.0, -0.1, 0.5, 1.2)
+        );
+        let kinds: Vec<_> = filter.primitives.iter().map(|p| p.kind.clone()).collect();
+        assert_eq!(
+            kinds[0],
+            FilterKind::ColorMatrix {
+                input: None,
+                kind: ColorMatrixKind::Saturate(0.5)
+            }
+        );
+        assert_eq!(
+            filter.primitives[0].color_interpolation,
+            ColorInterpolation::LinearRgb
+        );
+        assert_eq!(
+            filter.primitives[1].color_interpolation,
+            ColorInterpolation::Srgb
+        );
+        // a matrix without 20 values is the identity
+        assert_eq!(
+            kinds[1],
+            FilterKind::ColorMatrix {
+                input: None,
+                kind: ColorMatrixKind::Saturate(1.0)
+            }
+        );
+        assert_eq!(
+            kinds[2],
+            FilterKind::Composite {
+                input: Some(FilterInput::SourceGraphic),
+                input2: Some(FilterInput::Reference("a".to_owned())),
+                operator: CompositeOperator::Arithmetic {
+                    k1: 0.0,
+                    k2: 1.0,
+                    k3: 0.5,
+                    k4: 0.0
+                }
+            }
+        );
+        assert_eq!(
+            kinds[3],
+            FilterKind::Merge {
+                inputs: vec![Some(FilterInput::Reference("a".to_owned())), None]
+            }
+        );
+        assert_eq!(
+            kinds[4],
+            FilterKind::Flood {
+                color: Rgba::new(0, 255, 0, 0.5)
+            }
+        );
+        assert_eq!(filter.primitives[4].x, Some(0.1));
+        assert_eq!(filter.primitives[4].result, None);
+        assert_eq!(
+            kinds[5],
+            FilterKind::Blend {
+                input: None,
+                input2: Some(FilterInput::BackgroundImage),
+                mode: BlendMode::Screen
+            }
+        );
+        assert_eq!(
+            kinds[6],
+            FilterKind::Morphology {
+                input: None,
+                operator: MorphologyOperator::Dilate,
+                radius_x: 1.0,
+                radius_y: 2.0
+            }
+        );
+        assert_eq!(
+            kinds[7],
+            FilterKind::Turbulence {
+                base_frequency_x: 0.05,
+                base_frequency_y: 0.05,
+                num_octaves: 3,
+                seed: 4.0,
+                stitch_tiles: true,
+                fractal_noise: true
+            }
+        );
+        assert_eq!(
+            kinds[8],
+            FilterKind::ComponentTransfer {
+                input: None,
+                functions: [
+                    TransferFunction::Table(vec![0.0, 1.0]),
+                    TransferFunction::Identity,
+                    TransferFunction::Identity,
+                    TransferFunction::Gamma {
+                        amplitude: 1.0,
+                        exponent: 2.0,
+                        offset: 0.0
+                    }
+                ]
+            }
+        );
+        assert_eq!(
+            kinds[9],
+            FilterKind::DropShadow {
+                input: None,
+                std_dev_x: 1.0,
+                std_dev_y: 1.0,
+                dx: 2.0,
+                dy: 2.0,
+                color: Rgba::new(255, 0, 0, 1.0)
+            }
+        );
+        assert_eq!(
+            kinds[10],
+            FilterKind::DiffuseLighting {
+                input: None,
+                surface_scale: 1.0,
+                diffuse_constant: 1.0,
+                color: Rgba::new(0, 0, 255, 1.0),
+                light: Some(LightSource::Distant {
+                    azimuth: 45.0,
+                    elevation: 0.0
+                })
+            }
+        );
+        assert_eq!(
+            kinds[11],
+            FilterKind::SpecularLighting {
+                input: None,
+                surface_scale: 1.0,
+                specular_constant: 1.0,
+                specular_exponent: 20.0,
+                color: Rgba::WHITE,
+                light: Some(LightSource::Spot {
+                    x: 1.0,
+                    y: 0.0,
+                    z: 0.0,
+                    points_at_x: 0.0,
+                    points_at_y: 0.0,
+                    points_at_z: -1.0,
+                    specular_exponent: 1.0,
+                    limiting_cone_angle: Some(30.0)
+                })
+            }
+        );
+        assert_eq!(
+            kinds[12],
+            FilterKind::Unsupported {
+                name: "feTile".to_owned()
+            }
+        );
+    }
+}
@@ -0,0 +1,1343 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::path::{PathData, PathSegment};
+use crate::transform::Matrix;
+
+/// the deepest nesting of composite glyphs or charstring subroutines
+const MAX_DEPTH: usize = 10;
+
+/// What went wrong when reading a font
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontErrorKind {
+    /// the file could not be read
+    Io(io::ErrorKind),
+    /// not a TrueType or OpenType font, or a collection of them
+    UnknownFormat,
+    /// a table the font needs is missing
+    MissingTable,
+    /// a table is cut short or holds values out of range
+    Malformed,
+}
+
+/// A error in a font file
+///
+/// `table` the tag of the table the error is in, if any
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FontError {
+    pub kind: FontErrorKind,
+    pub table: Option<&'static str>,
+}
+
+impl FontError {
+    fn new(kind: FontErrorKind, table: &'static str) -> FontError {
+        FontError {
+            kind,
+            table: Some(table),
+        }
+    }
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            FontErrorKind::Io(kind) => return write!(f, "can not read the font: {}", kind),
+            FontErrorKind::UnknownFormat => "not a TrueType or OpenType font",
+            FontErrorKind::MissingTable => "missing table",
+            FontErrorKind::Malformed => "malformed table",
+        };
+        match self.table {
+            Some(table) => write!(f, "{} {}", message, table.trim()),
+            None => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for FontError {}
+
+impl From<io::Error> for FontError {
+    fn from(e: io::Error) -> Self {
+        FontError {
+            kind: FontErrorKind::Io(e.kind()),
+            table: None,
+        }
+    }
+}
+
+fn read_u8(data: &[u8], offset: usize) -> Option<u8> {
+    data.get(offset).copied()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset.checked_add(2)?)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_i16(data: &[u8], offset: usize) -> Option<i16> {
+    read_u16(data, offset).map(|n| n as i16)
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// a unsigned number of `size` bytes, the offsets of a CFF `INDEX`
+fn read_offset(data: &[u8], offset: usize, size: usize) -> Option<usize> {
+    let bytes = data.get(offset..offset.checked_add(size)?)?;
+    Some(bytes.iter().fold(0, |n, byte| n << 8 | *byte as usize))
+}
+
+/// a signed 2.14 fixed point number
+fn read_f2dot14(data: &[u8], offset: usize) -> Option<f64> {
+    read_i16(data, offset).map(|n| n as f64 / 16384.0)
+}
+
+/// The outlines of a font
+#[derive(Debug, Clone)]
+enum Outlines {
+    /// quadratic outlines in `glyf`, located by `loca`
+    TrueType {
+        glyf: (usize, usize),
+        loca: usize,
+        long_offsets: bool,
+    },
+    /// cubic outlines in a `CFF ` table
+    Cff(Cff),
+}
+
+/// The parts of a CFF table needed to draw its glyphs
+#[derive(Debug, Clone)]
+struct Cff {
+    /// the offset of the table in the font
+    start: usize,
+    char_strings: Index,
+    global_subrs: Index,
+    /// the local subroutines of each font dict, a single one for a font which is not CID keyed
+    local_subrs: Vec<Index>,
+    /// the font dict of each glyph, for a CID keyed font
+    fd_select: Option<usize>,
+    /// the scale from the font matrix to font units
+    scale: f64,
+}
+
+/// A CFF `INDEX`, offsets relative to the table
+#[derive(Debug, Clone, Copy, Default)]
+struct Index {
+    count: usize,
+    offset_size: usize,
+    /// the offset of the offset array
+    offsets: usize,
+    /// the offset before the first object, offsets count from 1
+    data: usize,
+    /// the offset after the index
+    end: usize,
+}
+
+impl Index {
+    fn parse(table: &[u8], offset: usize) -> Option<Index> {
+        let count = read_u16(table, offset)? as usize;
+        if count == 0 {
+            return Some(Index {
+                end: offset + 2,
+                ..Default::default()
+            });
+        }
+        let offset_size = read_u8(table, offset + 2)? as usize;
+        if !(1..=4).contains(&offset_size) {
+            return None;
+        }
+        let offsets = offset + 3;
+        let data = offsets + (count + 1) * offset_size - 1;
+        let end = data + read_offset(table, offsets + count * offset_size, offset_size)?;
+        if end > table.len() {
+            return None;
+        }
+        Some(Index {
+            count,
+            offset_size,
+            offsets,
+            data,
+            end,
+        })
+    }
+
+    /// the bytes of the object `i`
+    fn get<'t>(&self, table: &'t [u8], i: usize) -> Option<&'t [u8]> {
+        if i >= self.count {
+            return None;
+        }
+        let start = read_offset(table, self.offsets + i * self.offset_size, self.offset_size)?;
+        let end = read_offset(
+            table,
+            self.offsets + (i + 1) * self.offset_size,
+            self.offset_size,
+        )?;
+        table.get(self.data + start..self.data + end)
+    }
+
+    /// the bias added to the number of a subroutine
+    fn bias(&self) -> i32 {
+        match self.count {
+            0..=1239 => 107,
+            1240..=33899 => 1131,
+            _ => 32768,
+        }
+    }
+}
+
+/// parse a CFF `DICT` to its operators and their operands, a two byte
+/// operator is `1200` plus its second byte
+fn parse_dict(data: &[u8]) -> Option<HashMap<u16, Vec<f64>>> {
+    let mut dict = HashMap::new();
+    let mut operands = vec![];
+    let mut i = 0;
+    while i < data.len() {
+        let b0 = data[i];
+        match b0 {
+            0..=21 => {
+                let operator = if b0 == 12 {
+                    i += 1;
+                    1200 + *data.get(i)? as u16
+                } else {
+                    b0 as u16
+                };
+                dict.insert(operator, std::mem::take(&mut operands));
+                i += 1;
+            }
+            28 => {
+                operands.push(read_i16(data, i + 1)? as f64);
+                i += 3;
+            }
+            29 => {
+                operands.push(read_u32(data, i + 1)? as i32 as f64);
+                i += 5;
+            }
+            30 => {
+                // a real number, as a string of nibbles
+                let mut text = String::new();
+                i += 1;
+                'nibbles: while let Some(byte) = data.get(i) {
+                    i += 1;
+                    for nibble in [byte >> 4, byte & 15] {
+                        match nibble {
+                            0..=9 => text.push((b'0' + nibble) as char),
+                            10 => text.push('.'),
+                            11 => text.push('E'),
+                            12 => text.push_str("E-"),
+                            14 => text.push('-'),
+                            15 => break 'nibbles,
+                            _ => {}
+                        }
+                    }
+                }
+                operands.push(text.parse().unwrap_or(0.0));
+            }
+            32..=246 => {
+                operands.push(b0 as f64 - 139.0);
+                i += 1;
+            }
+            247..=250 => {
+                operands.push((b0 as f64 - 247.0) * 256.0 + *data.get(i + 1)? as f64 + 108.0);
+                i += 2;
+            }
+            251..=254 => {
+                operands.push(-(b0 as f64 - 251.0) * 256.0 - *data.get(i + 1)? as f64 - 108.0);
+                i += 2;
+            }
+            _ => return None,
+        }
+    }
+    Some(dict)
+}
+
+impl Cff {
+    fn parse(table: &[u8], start: usize, units_per_em: f64) -> Option<Cff> {
+        let header_size = read_u8(table, 2)? as usize;
+        let names = Index::parse(table, header_size)?;
+        let top_dicts = Index::parse(table, names.end)?;
+        let strings = Index::parse(table, top_dicts.end)?;
+        let global_subrs = Index::parse(table, strings.end)?;
+        let top = parse_dict(top_dicts.get(table, 0)?)?;
+        let offset = |operator: u16| -> Option<usize> {
+            let value = *top.get(&operator)?.first()?;
+            (value >= 0.0).then_some(value as usize)
+        };
+        let char_strings = Index::parse(table, offset(17)?)?;
+        let private_subrs = |operands: &Vec<f64>| -> Option<Index> {
+            let [size, offset] = operands.as_slice() else {
+                return None;
+            };
+            let (size, offset) = (*size as usize, *offset as usize);
+            let private = parse_dict(table.get(offset..offset + size)?)?;
+            let subrs = *private.get(&19)?.first()? as usize;
+            Index::parse(table, offset + subrs)
+        };
+        let (local_subrs, fd_select) = match offset(1236) {
+            // a CID keyed font has a private dict for each font dict
+            Some(fd_array) => {
+                let fd_array = Index::parse(table, fd_array)?;
+                let subrs = (0..fd_array.count)
+                    .map(|i| {
+                        let dict = parse_dict(fd_array.get(table, i)?)?;
+                        dict.get(&18).and_then(private_subrs)
+                    })
+                    .map(Option::unwrap_or_default)
+                    .collect();
+                (subrs, offset(1237))
+            }
+            None => (
+                vec![top.get(&18).and_then(private_subrs).unwrap_or_default()],
+                None,
+            ),
+        };
+        let scale = top
+            .get(&1207)
+            .and_then(|matrix| matrix.first())
+            .map_or(1.0, |a| a * units_per_em);
+        let scale = if scale > 0.0 && (scale - 1.0).abs() > 1e-6 {
+            scale
+        } else {
+            1.0
+        };
+        Some(Cff {
+            start,
+            char_strings,
+            global_subrs,
+            local_subrs,
+            fd_select,
+            scale,
+        })
+    }
+
+    /// the font dict of a glyph
+    fn font_dict(&self, table: &[u8], glyph: u16) -> Option<usize> {
+        let Some(fd_select) = self.fd_select else {
+            return Some(0);
+        };
+        match read_u8(table, fd_select)? {
+            0 => read_u8(table, fd_select + 1 + glyph as usize).map(usize::from),
+            3 => {
+                let count = read_u16(table, fd_select + 1)? as usize;
+                (0..count).find_map(|i| {
+                    let range = fd_select + 3 + i * 3;
+                    let first = read_u16(table, range)?;
+                    let next = read_u16(table, range + 3)?;
+                    (first..next)
+                        .contains(&glyph)
+                        .then(|| read_u8(table, range + 2).map(usize::from))
+                        .flatten()
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn outline(&self, table: &[u8], glyph: u16) -> Option<PathData> {
+        let char_string = self.char_strings.get(table, glyph as usize)?;
+        let local_subrs = self
+            .local_subrs
+            .get(self.font_dict(table, glyph)?)
+            .copied()
+            .unwrap_or_default();
+        let mut interpreter = CharString {
+            table,
+            global_subrs: self.global_subrs,
+            local_subrs,
+            stack: vec![],
+            stems: 0,
+            width_parsed: false,
+            point: (0.0, 0.0),
+            open: false,
+            segments: vec![],
+        };
+        interpreter.run(char_string, 0)?;
+        interpreter.close();
+        let path = PathData {
+            segments: interpreter.segments,
+        };
+        if self.scale != 1.0 {
+            return Some(path.transformed(&Matrix::scale(self.scale, self.scale)));
+        }
+        Some(path)
+    }
+}
+
+/// A interpreter of Type 2 charstrings
+struct CharString<'t> {
+    table: &'t [u8],
+    global_subrs: Index,
+    local_subrs: Index,
+    stack: Vec<f64>,
+    stems: usize,
+    /// whether the optional width before the first operator is consumed
+    width_parsed: bool,
+    point: (f64, f64),
+    open: bool,
+    segments: Vec<PathSegment>,
+}
+
+impl<'t> CharString<'t> {
+    fn close(&mut self) {
+        if self.open {
+            self.segments.push(PathSegment::ClosePath { abs: true });
+            self.open = false;
+        }
+    }
+
+    fn move_to(&mut self, dx: f64, dy: f64) {
+        self.close();
+        self.point = (self.point.0 + dx, self.point.1 + dy);
+        let (x, y) = self.point;
+        self.segments.push(PathSegment::MoveTo { abs: true, x, y });
+        self.open = true;
+    }
+
+    fn line_to(&mut self, dx: f64, dy: f64) {
+        self.point = (self.point.0 + dx, self.point.1 + dy);
+        let (x, y) = self.point;
+        self.segments.push(PathSegment::LineTo { abs: true, x, y });
+    }
+
+    fn curve_to(&mut self, [dx1, dy1, dx2, dy2, dx, dy]: [f64; 6]) {
+        let (x0, y0) = self.point;
+        let (x1, y1) = (x0 + dx1, y0 + dy1);
+        let (x2, y2) = (x1 + dx2, y1 + dy2);
+        self.point = (x2 + dx, y2 + dy);
+        let (x, y) = self.point;
+        self.segments.push(PathSegment::CurveTo {
+            abs: true,
+            x1,
+            y1,
+            x2,
+            y2,
+            x,
+            y,
+        });
+    }
+
+    /// drop the width in front of the arguments of the first stack clearing
+    /// operator, which has `expected` arguments or a multiple of them
+    fn parse_width(&mut self, odd: bool) {
+        if !self.width_parsed && odd && !self.stack.is_empty() {
+            self.stack.remove(0);
+        }
+        self.width_parsed = true;
+    }
+
+    /// run a charstring, `Some(true)` once `endchar` is reached and `None` if
+    /// it is malformed
+    fn run(&mut self, code: &[u8], depth: usize) -> Option<bool> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let mut i = 0;
+        while i < code.len() {
+            let b0 = code[i];
+            i += 1;
+            match b0 {
+                // hstem, vstem, hstemhm, vstemhm
+                1 | 3 | 18 | 23 => {
+                    self.parse_width(self.stack.len() % 2 == 1);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                }
+                // hintmask, cntrmask
+                19 | 20 => {
+                    self.parse_width(self.stack.len() % 2 == 1);
+                    self.stems += self.stack.len() / 2;
+                    self.stack.clear();
+                    i += self.stems.div_ceil(8);
+                }
+                // rmoveto
+                21 => {
+                    self.parse_width(self.stack.len() > 2);
+                    let n = self.stack.len();
+                    let (dx, dy) = (*self.stack.get(n.checked_sub(2)?)?, self.stack[n - 1]);
+                    self.move_to(dx, dy);
+                    self.stack.clear();
+                }
+                // hmoveto, vmoveto
+                22 | 4 => {
+                    self.parse_width(self.stack.len() > 1);
+                    let d = *self.stack.last()?;
+                    if b0 == 22 {
+                        self.move_to(d, 0.0);
+                    } else {
+                        self.move_to(0.0, d);
+                    }
+                    self.stack.clear();
+                }
+                // rlineto
+                5 => {
+                    for pair in std::mem::take(&mut self.stack).chunks_exact(2) {
+                        self.line_to(pair[0], pair[1]);
+                    }
+                }
+                // hlineto, vlineto
+                6 | 7 => {
+                    let mut horizontal = b0 == 6;
+                    for d in std::mem::take(&mut self.stack) {
+                        if horizontal {
+                            self.line_to(d, 0.0);
+                        } else {
+                            self.line_to(0.0, d);
+                        }
+                        horizontal = !horizontal;
+                    }
+                }
+                // rrcurveto
+                8 => {
+                    for c in std::mem::take(&mut self.stack).chunks_exact(6) {
+                        self.curve_to([c[0], c[1], c[2], c[3], c[4], c[5]]);
+                    }
+                }
+                // rcurveline
+                24 => {
+                    let stack = std::mem::take(&mut self.stack);
+                    let curves = stack.len().checked_sub(2)? / 6;
+                    for c in stack.chunks_exact(6).take(curves) {
+                        self.curve_to([c[0], c[1], c[2], c[3], c[4], c[5]]);
+                    }
+                    let n = stack.len();
+                    self.line_to(stack[n - 2], stack[n - 1]);
+                }
+                // rlinecurve
+                25 => {
+                    let stack = std::mem::take(&mut self.stack);
+                    let lines = stack.len().checked_sub(6)? / 2;
+                    for pair in stack.chunks_exact(2).take(lines) {
+                        self.line_to(pair[0], pair[1]);
+                    }
+                    let c = &stack[lines * 2..];
+                    self.curve_to([c[0], c[1], c[2], c[3], c[4], c[5]]);
+                }
+                // vvcurveto, hhcurveto
+                26 | 27 => {
+                    let mut stack = std::mem::take(&mut self.stack);
+                    let mut first = if stack.len() % 4 == 1 {
+                        stack.remove(0)
+                    } else {
+                        0.0
+                    };
+                    for c in stack.chunks_exact(4) {
+                        if b0 == 26 {
+                            self.curve_to([first, c[0], c[1], c[2], 0.0, c[3]]);
+                        } else {
+                            self.curve_to([c[0], first, c[1], c[2], c[3], 0.0]);
+                        }
+                        first = 0.0;
+                    }
+                }
+                // vhcurveto, hvcurveto
+                30 | 31 => {
+                    let stack = std::mem::take(&mut self.stack);
+                    let mut horizontal = b0 == 31;
+                    let count = stack.len() / 4;
+                    for (k, c) in stack.chunks_exact(4).enumerate() {
+                        let last = if k + 1 == count && stack.len() % 4 == 1 {
+                            stack[stack.len() - 1]
+                        } else {
+                            0.0
+                        };
+                        if horizontal {
+                            self.curve_to([c[0], 0.0, c[1], c[2], last, c[3]]);
+                        } else {
+                            self.curve_to([0.0, c[0], c[1], c[2], c[3], last]);
+                        }
+                        horizontal = !horizontal;
+                    }
+                }
+                // callsubr, callgsubr
+                10 | 29 => {
+                    let subrs = if b0 == 10 {
+                        self.local_subrs
+                    } else {
+                        self.global_subrs
+                    };
+                    let n = *self.stack.last()? as i32 + subrs.bias();
+                    self.stack.pop();
+                    let subr = subrs.get(self.table, usize::try_from(n).ok()?)?;
+                    if self.run(subr, depth + 1)? {
+                        return Some(true);
+                    }
+                }
+                // return
+                11 => return Some(false),
+                // endchar
+                14 => {
+                    self.parse_width(self.stack.len() == 1 || self.stack.len() == 5);
+                    self.close();
+                    self.stack.clear();
+                    return Some(true);
+                }
+                12 => {
+                    let operator = *code.get(i)?;
+                    i += 1;
+                    let s = std::mem::take(&mut self.stack);
+                    match operator {
+                        // flex
+                        35 if s.len() >= 12 => {
+                            self.curve_to([s[0], s[1], s[2], s[3], s[4], s[5]]);
+                            self.curve_to([s[6], s[7], s[8], s[9], s[10], s[11]]);
+                        }
+                        // hflex
+                        34 if s.len() >= 7 => {
+                            let y = self.point.1;
+                            self.curve_to([s[0], 0.0, s[1], s[2], s[3], 0.0]);
+                            let dy = y - (self.point.1);
+                            self.curve_to([s[4], 0.0, s[5], dy, s[6], 0.0]);
+                        }
+                        // hflex1
+                        36 if s.len() >= 9 => {
+                            let y = self.point.1;
+                            self.curve_to([s[0], s[1], s[2], s[3], s[4], 0.0]);
+                            let dy = y - (self.point.1 + s[7]);
+                            self.curve_to([s[5], 0.0, s[6], s[7], s[8], dy]);
+                        }
+                        // flex1
+                        37 if s.len() >= 11 => {
+                            let dx: f64 = s[0] + s[2] + s[4] + s[6] + s[8];
+                            let dy: f64 = s[1] + s[3] + s[5] + s[7] + s[9];
+                            let (x, y) = self.point;
+                            self.curve_to([s[0], s[1], s[2], s[3], s[4], s[5]]);
+                            let (x5, y5) = (self.point.0 + s[6] + s[8], self.point.1 + s[7] + s[9]);
+                            let last = if dx.abs() > dy.abs() {
+                                [s[10], y - y5]
+                            } else {
+                                [x - x5, s[10]]
+                            };
+                            self.curve_to([s[6], s[7], s[8], s[9], last[0], last[1]]);
+                        }
+                        // the arithmetic and storage operators are not supported
+                        _ => {}
+                    }
+                }
+                28 => {
+                    self.stack.push(read_i16(code, i)? as f64);
+                    i += 2;
+                }
+                32..=246 => self.stack.push(b0 as f64 - 139.0),
+                247..=250 => {
+                    let b1 = *code.get(i)? as f64;
+                    self.stack.push((b0 as f64 - 247.0) * 256.0 + b1 + 108.0);
+                    i += 1;
+                }
+                251..=254 => {
+                    let b1 = *code.get(i)? as f64;
+                    self.stack.push(-(b0 as f64 - 251.0) * 256.0 - b1 - 108.0);
+                    i += 1;
+                }
+                255 => {
+                    self.stack.push(read_u32(code, i)? as i32 as f64 / 65536.0);
+                    i += 4;
+                }
+                _ => self.stack.clear(),
+            }
+        }
+        Some(false)
+    }
+}
+
+/// A TrueType or OpenType font
+///
+/// `family` the family name, `weight` the `usWeightClass` from 100 to 900 and
+/// `italic` whether the font is italic or oblique. the metrics are in font
+/// units, `units_per_em` to the em, with the y axis going up: `ascender` is
+/// above the baseline and `descender` below it, so usually negative.
+#[derive(Clone)]
+pub struct Font {
+    pub family: String,
+    pub weight: u16,
+    pub italic: bool,
+    pub units_per_em: f64,
+    pub ascender: f64,
+    pub descender: f64,
+    pub x_height: f64,
+    data: Vec<u8>,
+    glyph_count: u16,
+    /// the offset of the `hmtx` table and its number of long metrics
+    hmtx: (usize, u16),
+    /// the offset and the format of the chosen `cmap` subtable
+    cmap: Option<(usize, u16)>,
+    /// the offset and the number of the pairs of the first `kern` subtable
+    kern: Option<(usize, usize)>,
+    outlines: Outlines,
+}
+
+impl fmt::Debug for Font {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Font")
+            .field("family", &self.family)
+            .field("weight", &self.weight)
+            .field("italic", &self.italic)
+            .field("units_per_em", &self.units_per_em)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Font {
+    /// read a font from the bytes of a `.ttf`, `.otf` or the first font of a `.ttc`
+    ///
+    /// a font needs the `head`, `hhea`, `hmtx`, `maxp` and `cmap` tables, and
+    /// outlines in `glyf` or `CFF `.
+    pub fn parse(data: Vec<u8>) -> Result<Font, FontError> {
+        let unknown = FontError {
+            kind: FontErrorKind::UnknownFormat,
+            table: None,
+        };
+        let mut start = 0;
+        if data.get(0..4) == Some(b"ttcf") {
+            start = read_u32(&data, 12).ok_or(unknown)? as usize;
+        }
+        match read_u32(&data, start) {
+            Some(0x00010000) | Some(0x74727565) | Some(0x4f54544f) => {}
+            _ => return Err(unknown),
+        }
+        let count = read_u16(&data, start + 4).ok_or(unknown)? as usize;
+        let mut tables = HashMap::new();
+        for i in 0..count {
+            let record = start + 12 + i * 16;
+            let tag = data.get(record..record + 4).ok_or(unknown)?;
+            let offset = read_u32(&data, record + 8).ok_or(unknown)? as usize;
+            let length = read_u32(&data, record + 12).ok_or(unknown)? as usize;
+            if offset
+                .checked_add(length)
+                .is_none_or(|end| end > data.len())
+            {
+                return Err(unknown);
+            }
+            tables.insert(tag.to_vec(), (offset, length));
+        }
+        let table = |tag: &'static str| -> Result<usize, FontError> {
+            tables
+                .get(tag.as_bytes())
+                .map(|(offset, _)| *offset)
+                .ok_or(FontError::new(FontErrorKind::MissingTable, tag))
+        };
+        let malformed = |tag: &'static str| FontError::new(FontErrorKind::Malformed, tag);
+        let head = table("head")?;
+        let units_per_em = read_u16(&data, head + 18)
+            .filter(|n| *n > 0)
+            .ok_or(malformed("head"))? as f64;
+        let mac_style = read_u16(&data, head + 44).ok_or(malformed("head"))?;
+        let long_offsets = read_i16(&data, head + 50).ok_or(malformed("head"))? != 0;
+        let hhea = table("hhea")?;
+        let mut ascender = read_i16(&data, hhea + 4).ok_or(malformed("hhea"))? as f64;
+        let mut descender = read_i16(&data, hhea + 6).ok_or(malformed("hhea"))? as f64;
+        let long_metrics = read_u16(&data, hhea + 34).ok_or(malformed("hhea"))?;
+        let glyph_count = read_u16(&data, table("maxp")? + 4).ok_or(malformed("maxp"))?;
+        let hmtx = table("hmtx")?;
+        if long_metrics == 0 || read_u16(&data, hmtx + long_metrics as usize * 4 - 2).is_none() {
+            return Err(malformed("hmtx"));
+        }
+        let mut weight = 400;
+        let mut italic = mac_style & 2 != 0;
+        let mut x_height = None;
+        if let Ok(os2) = table("OS/2") {
+            weight = read_u16(&data, os2 + 4).unwrap_or(weight).clamp(1, 1000);
+            italic |= read_u16(&data, os2 + 62).is_some_and(|selection| selection & 0x201 != 0);
+            if ascender == 0.0 && descender == 0.0 {
+                ascender = read_i16(&data, os2 + 68).unwrap_or(0) as f64;
+                descender = read_i16(&data, os2 + 70).unwrap_or(0) as f64;
+            }
+            if read_u16(&data, os2).is_some_and(|version| version >= 2) {
+                x_height = read_i16(&data, os2 + 86)
+                    .map(f64::from)
+                    .filter(|h| *h > 0.0);
+            }
+        }
+        let cmap = choose_cmap(&data, table("cmap")?);
+        let kern = tables.get(b"kern".as_slice()).and_then(|(offset, _)| {
+            // the first subtable, if it is a horizontal kerning table of format 0
+            let (version, subtable) = (read_u16(&data, *offset)?, offset + 4);
+            let coverage = read_u16(&data, subtable + 4)?;
+            (version == 0 && coverage & 0xff07 == 1)
+                .then(|| Some((subtable + 14, read_u16(&data, subtable + 6)? as usize)))
+                .flatten()
+        });
+        let outlines = match (table("glyf"), table("loca"), table("CFF ")) {
+            (Ok(glyf), Ok(loca), _) => Outlines::TrueType {
+                glyf: (glyf, tables[b"glyf".as_slice()].1),
+                loca,
+                long_offsets,
+            },
+            (_, _, Ok(cff)) => {
+                let length = tables[b"CFF ".as_slice()].1;
+                Outlines::Cff(
+                    Cff::parse(&data[cff..cff + length], cff, units_per_em)
+                        .ok_or(malformed("CFF "))?,
+                )
+            }
+            (Err(error), _, _) | (Ok(_), Err(error), _) => return Err(error),
+        };
+        let mut font = Font {
+            family: font_family(&data, table("name").ok()).unwrap_or_default(),
+            weight,
+            italic,
+            units_per_em,
+            ascender,
+            descender,
+            x_height: 0.0,
+            data,
+            glyph_count,
+            hmtx: (hmtx, long_metrics),
+            cmap,
+            kern,
+            outlines,
+        };
+        // the height of a `x` when the font does not tell it
+        font.x_height = x_height
+            .or_else(|| {
+                let glyph = font.glyph_index('x')?;
+                font.outline(glyph)?.bbox().map(|bbox| bbox.max_y)
+            })
+            .unwrap_or(ascender / 2.0);
+        Ok(font)
+    }
+
+    /// read a font file, see `Font::parse`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Font, FontError> {
+        Font::parse(fs::read(path)?)
+    }
+
+    /// the glyph of a character, `None` if the font has none
+    pub fn glyph_index(&self, c: char) -> Option<u16> {
+        let (subtable, format) = self.cmap?;
+        let data = &self.data;
+        let code = c as u32;
+        let glyph = match format {
+            0 => read_u8(
+                data,
+                subtable + 6 + usize::try_from(code).ok().filter(|c| *c < 256)?,
+            )? as u16,
+            4 => {
+                let code = u16::try_from(code).ok()?;
+                let segments = read_u16(data, subtable + 6)? as usize / 2;
+                let ends = subtable + 14;
+                let starts = ends + segments * 2 + 2;
+                let deltas = starts + segments * 2;
+                let range_offsets = deltas + segments * 2;
+                let (mut low, mut high) = (0, segments);
+                while low < high {
+                    let mid = (low + high) / 2;
+                    if read_u16(data, ends + mid * 2)? < code {
+                        low = mid + 1;
+                    } else {
+                        high = mid;
+                    }
+                }
+                let i = low;
+                if i >= segments {
+                    return None;
+                }
+                let start = read_u16(data, starts + i * 2)?;
+                if code < start {
+                    return None;
+                }
+                let delta = read_u16(data, deltas + i * 2)?;
+                let range_offset = read_u16(data, range_offsets + i * 2)? as usize;
+                if range_offset == 0 {
+                    code.wrapping_add(delta)
+                } else {
+                    let at = range_offsets + i * 2 + range_offset + (code - start) as usize * 2;
+                    match read_u16(data, at)? {
+                        0 => 0,
+                        glyph => glyph.wrapping_add(delta),
+                    }
+                }
+            }
+            6 => {
+                let first = read_u16(data, subtable + 6)? as u32;
+                let count = read_u16(data, subtable + 8)? as u32;
+                if code < first || code - first >= count {
+                    return None;
+                }
+                read_u16(data, subtable + 10 + (code - first) as usize * 2)?
+            }
+            12 => {
+                let groups = read_u32(data, subtable + 12)? as usize;
+                let (mut low, mut high) = (0, groups);
+                while low < high {
+                    let mid = (low + high) / 2;
+                    let group = subtable + 16 + mid * 12;
+                    if read_u32(data, group + 4)? < code {
+                        low = mid + 1;
+                    } else {
+                        high = mid;
+                    }
+                }
+                let group = subtable + 16 + low * 12;
+                let start = read_u32(data, group).filter(|_| low < groups)?;
+                if code < start {
+                    return None;
+                }
+                u16::try_from(read_u32(data, group + 8)? + (code - start)).ok()?
+            }
+            _ => return None,
+        };
+        (glyph != 0 && glyph < self.glyph_count).then_some(glyph)
+    }
+
+    /// the horizontal advance of a glyph, in font units
+    pub fn advance(&self, glyph: u16) -> f64 {
+        let (hmtx, long_metrics) = self.hmtx;
+        let i = glyph.min(long_metrics - 1) as usize;
+        read_u16(&self.data, hmtx + i * 4).unwrap_or(0) as f64
+    }
+
+    /// the kerning between two glyphs from the `kern` table, in font units
+    pub fn kerning(&self, left: u16, right: u16) -> f64 {
+        let Some((pairs, count)) = self.kern else {
+            return 0.0;
+        };
+        let key = (left as u32) << 16 | right as u32;
+        let (mut low, mut high) = (0, count);
+        while low < high {
+            let mid = (low + high) / 2;
+            match read_u32(&self.data, pairs + mid * 6) {
+                Some(pair) if pair < key => low = mid + 1,
+                Some(pair) if pair > key => high = mid,
+                Some(_) => return read_i16(&self.data, pairs + mid * 6 + 4).unwrap_or(0) as f64,
+                None => return 0.0,
+            }
+        }
+        0.0
+    }
+
+    /// the outline of a glyph in font units with the y axis going up, empty for
+    /// a glyph without contours like a space. `None` for a glyph which is not
+    /// in the font or can not be read.
+    pub fn outline(&self, glyph: u16) -> Option<PathData> {
+        if glyph >= self.glyph_count {
+            return None;
+        }
+        match &self.outlines {
+            Outlines::TrueType { .. } => self.glyf_outline(glyph, 0),
+            Outlines::Cff(cff) => {
+                let table = &self.data[cff.start..];
+                cff.outline(table, glyph)
+            }
+        }
+    }
+
+    /// the outline of a glyph of the `glyf` table, a composite glyph is the
+    /// union of its components
+    fn glyf_outline(&self, glyph: u16, depth: usize) -> Option<PathData> {
+        let Outlines::TrueType {
+            glyf: (glyf, glyf_length),
+            loca,
+            long_offsets,
+        } = self.outlines
+        else {
+            return None;
+        };
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        let data = &self.data;
+        let location = |i: usize| -> Option<usize> {
+            if long_offsets {
+                read_u32(data, loca + i * 4).map(|n| n as usize)
+            } else {
+                read_u16(data, loca + i * 2).map(|n| n as usize * 2)
+            }
+        };
+        let (start, end) = (location(glyph as usize)?, location(glyph as usize + 1)?);
+        if start >= end {
+            return Some(PathData { segments: vec![] });
+        }
+        if end > glyf_length {
+            return None;
+        }
+        let data = &data[glyf + start..glyf + end];
+        let contours = read_i16(data, 0)?;
+        if contours >= 0 {
+            return simple_glyph(data, contours as usize);
+        }
+        // a composite glyph
+        let mut segments = vec![];
+        let mut offset = 10;
+        loop {
+            let flags = read_u16(data, offset)?;
+            let component = read_u16(data, offset + 2)?;
+            offset += 4;
+            let (dx, dy) = if flags & 1 != 0 {
+                offset += 4;
+                (
+                    read_i16(data, offset - 4)? as f64,
+                    read_i16(data, offset - 2)? as f64,
+                )
+            } else {
+                offset += 2;
+                (
+                    read_u8(data, offset - 2)? as i8 as f64,
+                    read_u8(data, offset - 1)? as i8 as f64,
+                )
+            };
+            // components placed by matching points are put at the origin
+            let (dx, dy) = if flags & 2 != 0 { (dx, dy) } else { (0.0, 0.0) };
+            let (mut a, mut b, mut c, mut d) = (1.0, 0.0, 0.0, 1.0);
+            if flags & 8 != 0 {
+                a = read_f2dot14(data, offset)?;
+                d = a;
+                offset += 2;
+            } else if flags & 0x40 != 0 {
+                a = read_f2dot14(data, offset)?;
+                d = read_f2dot14(data, offset + 2)?;
+                offset += 4;
+            } else if flags & 0x80 != 0 {
+                a = read_f2dot14(data, offset)?;
+                b = read_f2dot14(data, offset + 2)?;
+                c = read_f2dot14(data, offset + 4)?;
+                d = read_f2dot14(data, offset + 6)?;
+                offset += 8;
+            }
+            if let Some(path) = self.glyf_outline(component, depth + 1) {
+                let matrix = Matrix::new(a, b, c, d, dx, dy);
+                segments.extend(path.transformed(&matrix).segments);
+            }
+            if flags & 0x20 == 0 {
+                break;
+            }
+        }
+        Some(PathData { segments })
+    }
+}
+
+/// the offset and format of the best `cmap` subtable, unicode full repertoire
+/// tables first
+fn choose_cmap(data: &[u8], cmap: usize) -> Option<(usize, u16)> {
+    let count = read_u16(data, cmap + 2)? as usize;
+    let mut best: Option<(u8, usize, u16)> = None;
+    for i in 0..count {
+        let record = cmap + 4 + i * 8;
+        let platform = read_u16(data, record)?;
+        let encoding = read_u16(data, record + 2)?;
+        let subtable = cmap + read_u32(data, record + 4)? as usize;
+        let format = read_u16(data, subtable)?;
+        let rank = match (platform, encoding, format) {
+            (3, 10, 12) | (0, 4 | 6, 12) => 5,
+            (3, 1, 4) | (0, 0..=3, 4) => 4,
+            (_, _, 12) => 3,
+            (_, _, 4) => 2,
+            (_, _, 6) => 1,
+            (_, _, 0) => 0,
+            _ => continue,
+        };
+        if best.is_none_or(|(best, _, _)| rank > best) {
+            best = Some((rank, subtable, format));
+        }
+    }
+    best.map(|(_, subtable, format)| (subtable, format))
+}
+
+/// the family of a font from its `name` table, the typographic family first
+fn font_family(data: &[u8], name: Option<usize>) -> Option<String> {
+    let name = name?;
+    let count = read_u16(data, name + 2)? as usize;
+    let strings = name + read_u16(data, name + 4)? as usize;
+    let mut best: Option<(u8, String)> = None;
+    for i in 0..count {
+        let record = name + 6 + i * 12;
+        let platform = read_u16(data, record)?;
+        let name_id = read_u16(data, record + 6)?;
+        let length = read_u16(data, record + 8)? as usize;
+        let offset = strings + read_u16(data, record + 10)? as usize;
+        let bytes = data.get(offset..offset + length)?;
+        let rank = match name_id {
+            16 => 2,
+            1 => 1,
+            _ => continue,
+        };
+        let text = match platform {
+            // UTF-16BE
+            0 | 3 => {
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            1 => bytes.iter().map(|byte| *byte as char).collect(),
+            _ => continue,
+        };
+        if best.as_ref().is_none_or(|(best, _)| rank > *best) {
+            best = Some((rank, text));
+        }
+    }
+    best.map(|(_, text)| text)
+}
+
+/// the path of a simple glyph with `contours` contours, quadratic curves
+/// between its points which are not on the curve
+fn simple_glyph(data: &[u8], contours: usize) -> Option<PathData> {
+    let mut ends = vec![];
+    for i in 0..contours {
+        ends.push(read_u16(data, 10 + i * 2)? as usize);
+    }
+    let count = ends.last().map_or(0, |end| end + 1);
+    let instructions = read_u16(data, 10 + contours * 2)? as usize;
+    let mut offset = 12 + contours * 2 + instructions;
+    let mut flags = Vec::with_capacity(count);
+    while flags.len() < count {
+        let flag = read_u8(data, offset)?;
+        offset += 1;
+        flags.push(flag);
+        if flag & 8 != 0 {
+            let repeat = read_u8(data, offset)?;
+            offset += 1;
+            for _ in 0..repeat {
+                flags.push(flag);
+            }
+        }
+    }
+    flags.truncate(count);
+    let mut coordinates = |short: u8, same: u8| -> Option<Vec<f64>> {
+        let mut value = 0.0;
+        let mut values = Vec::with_capacity(count);
+        for flag in &flags {
+            if flag & short != 0 {
+                let delta = read_u8(data, offset)? as f64;
+                offset += 1;
+                value += if flag & same != 0 { delta } else { -delta };
+            } else if flag & same == 0 {
+                value += read_i16(data, offset)? as f64;
+                offset += 2;
+            }
+            values.push(value);
+        }
+        Some(values)
+    };
+    let xs = coordinates(2, 16)?;
+    let ys = coordinates(4, 32)?;
+    let mut segments = vec![];
+    let mut first = 0;
+    for end in ends {
+        if end < first || end >= count {
+            return None;
+        }
+        let points: Vec<((f64, f64), bool)> = (first..=end)
+            .map(|i| ((xs[i], ys[i]), flags[i] & 1 != 0))
+            .collect();
+        first = end + 1;
+        contour_segments(&points, &mut segments);
+    }
+    Some(PathData { segments })
+}
+
+/// add a closed contour of TrueType points to a path, two points off the curve
+/// in a row have a implied point on the curve between them
+fn contour_segments(points: &[((f64, f64), bool)], segments: &mut Vec<PathSegment>) {
+    let n = points.len();
+    if n == 0 {
+        return;
+    }
+    let midpoint = |(a, b): ((f64, f64), (f64, f64))| ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+    // start on a point on the curve
+    let (start, skip) = match points.iter().position(|(_, on)| *on) {
+        Some(i) => (points[i].0, i),
+        None => (midpoint((points[0].0, points[n - 1].0)), n - 1),
+    };
+    segments.push(PathSegment::MoveTo {
+        abs: true,
+        x: start.0,
+        y: start.1,
+    });
+    let mut control: Option<(f64, f64)> = None;
+    for k in 1..=n {
+        let (point, on) = points[(skip + k) % n];
+        let target = match (on, control) {
+            // the close path draws the line back to the start
+            (true, None) if k == n && point == start => continue,
+            (true, None) => {
+                segments.push(PathSegment::LineTo {
+                    abs: true,
+                    x: point.0,
+                    y: point.1,
+                });
+                continue;
+            }
+            (true, Some(c)) => {
+                control = None;
+                (c, point)
+            }
+            (false, None) => {
+                control = Some(point);
+                continue;
+            }
+            (false, Some(c)) => {
+                control = Some(point);
+                (c, midpoint((c, point)))
+            }
+        };
+        let ((x1, y1), (x, y)) = target;
+        segments.push(PathSegment::Quadratic {
+            abs: true,
+            x1,
+            y1,
+            x,
+            y,
+        });
+    }
+    if let Some((x1, y1)) = control {
+        segments.push(PathSegment::Quadratic {
+            abs: true,
+            x1,
+            y1,
+            x: start.0,
+            y: start.1,
+        });
+    }
+    segments.push(PathSegment::ClosePath { abs: true });
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::{Font, FontErrorKind};
+
+    fn u16s(values: &[u16]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_be_bytes())
+            .collect()
+    }
+
+    /// a simple glyph of one contour of points on the curve
+    fn glyph(points: &[(i16, i16)]) -> Vec<u8> {
+        let xs = points.iter().map(|point| point.0);
+        let ys = points.iter().map(|point| point.1);
+        let bounds = [xs.clone().min(), ys.clone().min(), xs.max(), ys.max()];
+        let mut data = u16s(&[1]);
+        data.extend(bounds.iter().flat_map(|n| n.unwrap_or(0).to_be_bytes()));
+        data.extend(u16s(&[points.len() as u16 - 1, 0]));
+        data.extend(vec![1; points.len()]);
+        let mut previous = (0, 0);
+        let mut xs = vec![];
+        let mut ys = vec![];
+        for &(x, y) in points {
+            xs.extend((x - previous.0).to_be_bytes());
+            ys.extend((y - previous.1).to_be_bytes());
+            previous = (x, y);
+        }
+        data.extend(xs);
+        data.extend(ys);
+        data
+    }
+
+    /// a TrueType font "Test" of 1000 units to the em: a space, `A` a 500 by 700
+    /// square and `B` a triangle, with a kerning of -100 between them
+    pub(crate) fn test_font() -> Vec<u8> {
+        let glyphs = [
+            glyph(&[(50, 0), (50, 700), (450, 700), (450, 0)]),
+            vec![],
+            glyph(&[(0, 0), (0, 700), (500, 700), (500, 0)]),
+            glyph(&[(0, 0), (250, 700), (500, 0)]),
+        ];
+        let mut glyf = vec![];
+        let mut loca = vec![0u8; 4];
+        for glyph in &glyphs {
+            glyf.extend(glyph);
+            loca.extend((glyf.len() as u32).to_be_bytes());
+        }
+        let mut head = vec![0u8; 54];
+        head[0..4].copy_from_slice(&0x00010000u32.to_be_bytes());
+        head[12..16].copy_from_slice(&0x5f0f3cf5u32.to_be_bytes());
+        head[18..20].copy_from_slice(&1000u16.to_be_bytes());
+        head[50..52].copy_from_slice(&1u16.to_be_bytes());
+        let mut hhea = vec![0u8; 36];
+        hhea[0..4].copy_from_slice(&0x00010000u32.to_be_bytes());
+        hhea[4..6].copy_from_slice(&800i16.to_be_bytes());
+        hhea[6..8].copy_from_slice(&(-200i16).to_be_bytes());
+        hhea[34..36].copy_from_slice(&4u16.to_be_bytes());
+        let mut maxp = 0x00005000u32.to_be_bytes().to_vec();
+        maxp.extend(u16s(&[4]));
+        let hmtx = u16s(&[500, 0, 250, 0, 600, 0, 500, 0]);
+        let mut cmap = u16s(&[0, 1, 3, 1, 0, 12]);
+        cmap.extend(u16s(&[4, 40, 0, 6, 0, 0, 0]));
+        cmap.extend(u16s(&[32, 66, 0xffff, 0, 32, 65, 0xffff]));
+        cmap.extend(u16s(&[
+            1u16.wrapping_sub(32),
+            2u16.wrapping_sub(65),
+            1,
+            0,
+            0,
+            0,
+        ]));
+        let mut os2 = vec![0u8; 96];
+        os2[0..2].copy_from_slice(&2u16.to_be_bytes());
+        os2[4..6].copy_from_slice(&400u16.to_be_bytes());
+        os2[86..88].copy_from_slice(&500u16.to_be_bytes());
+        let mut name = u16s(&[0, 1, 18, 3, 1, 0x409, 1, 8, 0]);
+        name.extend("Test".encode_utf16().flat_map(|unit| unit.to_be_bytes()));
+        let kern = u16s(&[0, 1, 0, 20, 1, 1, 0, 0, 0, 2, 3, (-100i16) as u16]);
+        let tables: [(&[u8; 4], Vec<u8>); 10] = [
+            (b"OS/2", os2),
+            (b"cmap", cmap),
+            (b"glyf", glyf),
+            (b"head", head),
+            (b"hhea", hhea),
+            (b"hmtx", hmtx),
+            (b"kern", kern),
+            (b"loca", loca),
+            (b"maxp", maxp),
+            (b"name", name),
+        ];
+        let mut data = 0x00010000u32.to_be_bytes().to_vec();
+        data.extend(u16s(&[tables.len() as u16, 0, 0, 0]));
+        let mut offset = 12 + tables.len() * 16;
+        for (tag, table) in &tables {
+            data.extend(tag.as_slice());
+            data.extend(0u32.to_be_bytes());
+            data.extend((offset as u32).to_be_bytes());
+            data.extend((table.len() as u32).to_be_bytes());
+            offset += table.len().next_multiple_of(4);
+        }
+        for (_, table) in &tables {
+            data.extend(table);
+            data.resize(data.len().next_multiple_of(4), 0);
+        }
+        data
+    }
+
+    #[test]
+    fn test_parse() {
+        let font = Font::parse(test_font()).unwrap();
+        assert_eq!(font.family, "Test");
+        assert_eq!((font.weight, font.italic), (400, false));
+        assert_eq!(
+            (
+                font.units_per_em,
+                font.ascender,
+                font.descender,
+                font.x_height
+            ),
+            (1000.0, 800.0, -200.0, 500.0)
+        );
+        assert_eq!(font.glyph_index(' '), Some(1));
+        assert_eq!(font.glyph_index('A'), Some(2));
+        assert_eq!(font.glyph_index('B'), Some(3));
+        assert_eq!(font.glyph_index('C'), None);
+        assert_eq!(font.glyph_index('\u{1F600}'), None);
+        assert_eq!((font.advance(1), font.advance(2)), (250.0, 600.0));
+        assert_eq!((font.kerning(2, 3), font.kerning(3, 2)), (-100.0, 0.0));
+        assert_eq!(font.outline(1).unwrap().segments, vec![]);
+        assert_eq!(font.outline(3).unwrap().to_string(), "M0 0 250 700 500 0Z");
+        assert_eq!(font.outline(4), None);
+
+        let mut data = test_font();
+        data[0] = 9;
+        assert_eq!(
+            Font::parse(data).unwrap_err().kind,
+            FontErrorKind::UnknownFormat
+        );
+        assert_eq!(
+            Font::parse(test_font()[..200].to_vec()).unwrap_err().kind,
+            FontErrorKind::UnknownFormat
+        );
+    }
+}
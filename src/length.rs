@@ -0,0 +1,388 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ast::Element;
+use crate::path::{is_wsp, shortest_number};
+
+/// The unit of a length, `None` for a plain number in user units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthUnit {
+    None,
+    Px,
+    Em,
+    Ex,
+    Percent,
+    In,
+    Cm,
+    Mm,
+    Pt,
+    Pc,
+}
+
+impl LengthUnit {
+    /// the suffix of the unit in a attribute value
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            LengthUnit::None => "",
+            LengthUnit::Px => "px",
+            LengthUnit::Em => "em",
+            LengthUnit::Ex => "ex",
+            LengthUnit::Percent => "%",
+            LengthUnit::In => "in",
+            LengthUnit::Cm => "cm",
+            LengthUnit::Mm => "mm",
+            LengthUnit::Pt => "pt",
+            LengthUnit::Pc => "pc",
+        }
+    }
+
+    /// whether the unit is fixed by the DPI, like `mm` or `pt`
+    pub fn is_absolute(&self) -> bool {
+        matches!(
+            self,
+            LengthUnit::In | LengthUnit::Cm | LengthUnit::Mm | LengthUnit::Pt | LengthUnit::Pc
+        )
+    }
+}
+
+/// A length attribute, like `width="100%"` or `x="10mm"`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Length {
+    pub number: f64,
+    pub unit: LengthUnit,
+}
+
+/// What went wrong when parsing a length
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthErrorKind {
+    ExpectedNumber,
+    /// a suffix which is not a unit
+    InvalidUnit,
+}
+
+/// A error in a length
+///
+/// `position` the byte offset of the error in the value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LengthError {
+    pub position: usize,
+    pub kind: LengthErrorKind,
+}
+
+impl fmt::Display for LengthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            LengthErrorKind::ExpectedNumber => "expected a number",
+            LengthErrorKind::InvalidUnit => "invalid unit",
+        };
+        write!(f, "{} at position {}", message, self.position)
+    }
+}
+
+impl Error for LengthError {}
+
+/// The direction of a length, which decides what a percentage refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthDirection {
+    /// a percentage of the viewport width, like `x` or `width`
+    Horizontal,
+    /// a percentage of the viewport height, like `y` or `height`
+    Vertical,
+    /// a percentage of the normalized diagonal of the viewport, like `r` or `stroke-width`
+    Other,
+}
+
+impl LengthDirection {
+    /// the direction of a length attribute
+    pub fn of_attribute(key: &str) -> LengthDirection {
+        match key {
+            "x" | "cx" | "x1" | "x2" | "dx" | "fx" | "rx" | "width" | "refX" | "markerWidth" => {
+                LengthDirection::Horizontal
+            }
+            "y" | "cy" | "y1" | "y2" | "dy" | "fy" | "ry" | "height" | "refY" | "markerHeight" => {
+                LengthDirection::Vertical
+            }
+            _ => LengthDirection::Other,
+        }
+    }
+}
+
+/// What lengths are resolved against
+///
+/// `viewport` the width and height of the nearest viewport, for percentages
+///
+/// `font_size` the font size in user units, for `em` and `ex`
+///
+/// `dpi` the user units per inch, for absolute units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LengthContext {
+    pub viewport: (f64, f64),
+    pub font_size: f64,
+    pub dpi: f64,
+}
+
+impl Default for LengthContext {
+    /// a 100 by 100 viewport, a `16px` font and `96` DPI like CSS
+    fn default() -> Self {
+        LengthContext {
+            viewport: (100.0, 100.0),
+            font_size: 16.0,
+            dpi: 96.0,
+        }
+    }
+}
+
+/// the length of the number at the start of a length, the exponent needs a digit
+/// so that `1em` is not read as a broken `1e`
+fn number_length(input: &str) -> usize {
+    let bytes = input.as_bytes();
+    let digits = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+    let mut end = if input.starts_with(['+', '-']) { 1 } else { 0 };
+    let integer_end = digits(end);
+    let has_integer = integer_end > end;
+    end = integer_end;
+    if bytes.get(end) == Some(&b'.') {
+        let fraction_end = digits(end + 1);
+        if fraction_end > end + 1 || has_integer {
+            end = fraction_end;
+        }
+    }
+    if !has_integer && !input[..end].contains(|c: char| c.is_ascii_digit()) {
+        return 0;
+    }
+    if matches!(bytes.get(end), Some(b'e') | Some(b'E')) {
+        let mut i = end + 1;
+        if matches!(bytes.get(i), Some(b'+') | Some(b'-')) {
+            i += 1;
+        }
+        let exponent_end = digits(i);
+        if exponent_end > i {
+            end = exponent_end;
+        }
+    }
+    end
+}
+
+impl Length {
+    pub fn new(number: f64, unit: LengthUnit) -> Length {
+        Length { number, unit }
+    }
+
+    /// parse a length, surrounding whitespace is allowed and units are case-insensitive
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{Length, LengthUnit};
+    ///
+    /// assert_eq!(Length::parse("100%").unwrap(), Length::new(100.0, LengthUnit::Percent));
+    /// assert_eq!(Length::parse(" -1.5e1em ").unwrap(), Length::new(-15.0, LengthUnit::Em));
+    /// assert_eq!(Length::parse("10 px").unwrap_err().to_string(), "invalid unit at position 2");
+    /// ```
+    ///
+    pub fn parse(value: &str) -> Result<Length, LengthError> {
+        let trimmed = value.trim_start_matches(is_wsp);
+        let start = value.len() - trimmed.len();
+        let trimmed = trimmed.trim_end_matches(is_wsp);
+        let end = number_length(trimmed);
+        let number = trimmed[..end]
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite())
+            .ok_or(LengthError {
+                position: start,
+                kind: LengthErrorKind::ExpectedNumber,
+            })?;
+        let unit = match trimmed[end..].to_ascii_lowercase().as_str() {
+            "" => LengthUnit::None,
+            "px" => LengthUnit::Px,
+            "em" => LengthUnit::Em,
+            "ex" => LengthUnit::Ex,
+            "%" => LengthUnit::Percent,
+            "in" => LengthUnit::In,
+            "cm" => LengthUnit::Cm,
+            "mm" => LengthUnit::Mm,
+            "pt" => LengthUnit::Pt,
+            "pc" => LengthUnit::Pc,
+            _ => {
+                return Err(LengthError {
+                    position: start + end,
+                    kind: LengthErrorKind::InvalidUnit,
+                })
+            }
+        };
+        Ok(Length { number, unit })
+    }
+
+    /// convert the length to user units
+    ///
+    /// `ex` is taken as half of the font size.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{Length, LengthContext, LengthDirection};
+    ///
+    /// let context = LengthContext { viewport: (200.0, 100.0), ..Default::default() };
+    /// let resolve = |s: &str, direction| Length::parse(s).unwrap().to_user_units(&context, direction);
+    /// assert_eq!(resolve("50%", LengthDirection::Horizontal), 100.0);
+    /// assert_eq!(resolve("50%", LengthDirection::Vertical), 50.0);
+    /// assert_eq!(resolve("2em", LengthDirection::Other), 32.0);
+    /// assert_eq!(resolve("1in", LengthDirection::Other), 96.0);
+    /// assert_eq!(resolve("72pt", LengthDirection::Other), 96.0);
+    /// ```
+    ///
+    pub fn to_user_units(&self, context: &LengthContext, direction: LengthDirection) -> f64 {
+        let (width, height) = context.viewport;
+        let factor = match self.unit {
+            LengthUnit::None | LengthUnit::Px => 1.0,
+            LengthUnit::Em => context.font_size,
+            LengthUnit::Ex => context.font_size / 2.0,
+            LengthUnit::Percent => {
+                let reference = match direction {
+                    LengthDirection::Horizontal => width,
+                    LengthDirection::Vertical => height,
+                    LengthDirection::Other => ((width * width + height * height) / 2.0).sqrt(),
+                };
+                reference / 100.0
+            }
+            LengthUnit::In => context.dpi,
+            LengthUnit::Cm => context.dpi / 2.54,
+            LengthUnit::Mm => context.dpi / 25.4,
+            LengthUnit::Pt => context.dpi / 72.0,
+            LengthUnit::Pc => context.dpi / 6.0,
+        };
+        self.number * factor
+    }
+}
+
+impl FromStr for Length {
+    type Err = LengthError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Length::parse(s)
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", shortest_number(self.number), self.unit.suffix())
+    }
+}
+
+impl<'a> Element<'a> {
+    /// parse a length attribute of the element, `None` if it is missing
+    pub fn length_attribute(&self, key: &str) -> Result<Option<Length>, LengthError> {
+        self.get_attribute(key)
+            .map(|value| Length::parse(&value))
+            .transpose()
+    }
+
+    /// a length attribute of the element in user units, `None` if it is missing.
+    /// the direction of percentages follows the name of the attribute.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, LengthContext};
+    ///
+    /// let (_, rect) = parse(r#"<rect width="50%" height="1cm"/>"#).unwrap();
+    /// let context = LengthContext::default();
+    /// assert_eq!(rect.user_length("width", &context), Ok(Some(50.0)));
+    /// assert_eq!(rect.user_length("x", &context), Ok(None));
+    /// ```
+    ///
+    pub fn user_length(
+        &self,
+        key: &str,
+        context: &LengthContext,
+    ) -> Result<Option<f64>, LengthError> {
+        let direction = LengthDirection::of_attribute(key);
+        Ok(self
+            .length_attribute(key)?
+            .map(|length| length.to_user_units(context, direction)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::length::{number_length, LengthErrorKind};
+    use crate::{Length, LengthContext, LengthDirection, LengthError, LengthUnit};
+
+    #[test]
+    fn test_number_length() {
+        assert_eq!(number_length("10"), 2);
+        assert_eq!(number_length("1em"), 1);
+        assert_eq!(number_length("1e2ex"), 3);
+        assert_eq!(number_length("-.5%"), 3);
+        assert_eq!(number_length("5.px"), 2);
+        assert_eq!(number_length("+1E-2"), 5);
+        assert_eq!(number_length("."), 0);
+        assert_eq!(number_length("px"), 0);
+    }
+
+    #[test]
+    fn test_parse() {
+        let units = [
+            ("", LengthUnit::None),
+            ("px", LengthUnit::Px),
+            ("EM", LengthUnit::Em),
+            ("ex", LengthUnit::Ex),
+            ("%", LengthUnit::Percent),
+            ("in", LengthUnit::In),
+            ("cm", LengthUnit::Cm),
+            ("mm", LengthUnit::Mm),
+            ("pt", LengthUnit::Pt),
+            ("pc", LengthUnit::Pc),
+        ];
+        for (suffix, unit) in units {
+            assert_eq!(
+                Length::parse(&format!("1.5{}", suffix)),
+                Ok(Length::new(1.5, unit))
+            );
+        }
+        let error = |position, kind| Err(LengthError { position, kind });
+        assert_eq!(
+            Length::parse(" auto"),
+            error(1, LengthErrorKind::ExpectedNumber)
+        );
+        assert_eq!(Length::parse("1e"), error(1, LengthErrorKind::InvalidUnit));
+        assert_eq!(
+            Length::parse("12vw"),
+            error(2, LengthErrorKind::InvalidUnit)
+        );
+    }
+
+    #[test]
+    fn test_to_user_units() {
+        let context = LengthContext {
+            viewport: (300.0, 400.0),
+            font_size: 10.0,
+            dpi: 72.0,
+        };
+        let resolve =
+            |s: &str, direction| Length::parse(s).unwrap().to_user_units(&context, direction);
+        assert_eq!(resolve("10", LengthDirection::Other), 10.0);
+        assert_eq!(resolve("10%", LengthDirection::Horizontal), 30.0);
+        assert_eq!(resolve("10%", LengthDirection::Vertical), 40.0);
+        assert!((resolve("10%", LengthDirection::Other) - 35.355339).abs() < 1e-6);
+        assert_eq!(resolve("2ex", LengthDirection::Other), 10.0);
+        assert_eq!(resolve("1pt", LengthDirection::Other), 1.0);
+        assert_eq!(resolve("1pc", LengthDirection::Other), 12.0);
+        assert_eq!(resolve("2.54cm", LengthDirection::Other), 72.0);
+        assert_eq!(resolve("25.4mm", LengthDirection::Other), 72.0);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Length::new(0.5, LengthUnit::Em).to_string(), ".5em");
+        assert_eq!(Length::new(100.0, LengthUnit::Percent).to_string(), "100%");
+        assert_eq!(
+            LengthDirection::of_attribute("height"),
+            LengthDirection::Vertical
+        );
+    }
+}
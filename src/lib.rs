@@ -17,9 +17,86 @@
 //! }
 //! ```
 mod ast;
+mod bake;
+mod bbox;
+mod builder;
+mod clip;
+mod color;
+mod crop;
+mod css;
+#[cfg(any(feature = "render", feature = "pdf"))]
+mod deflate;
+mod diff;
+mod document;
+#[cfg(feature = "render")]
+mod effects;
+mod expand_use;
+mod filter;
+mod font;
+mod length;
+mod marker;
+mod number;
+mod paint;
 mod parse;
+mod path;
+mod path_normalize;
+#[cfg(feature = "pdf")]
+mod pdf;
+#[cfg(any(feature = "render", feature = "pdf"))]
+mod png;
+#[cfg(feature = "render")]
+mod raster;
+mod reference;
+#[cfg(feature = "render")]
+mod render;
+mod shape;
 mod stringify;
+mod style;
+mod text;
+mod transform;
+mod view_box;
 
 pub use ast::Element;
-pub use parse::parse;
+pub use bake::{bake_transforms, BakeError};
+pub use bbox::{BBox, LineCap, LineJoin, Stroke};
+#[doc(hidden)]
+pub use builder::check_closing_tag;
+pub use builder::ElementBuilder;
+pub use clip::{flatten_clip_paths, ClipPath, ClipShape, FillRule, Mask, MaskType};
+pub use color::{Color, ColorError, Rgba};
+pub use css::{
+    inline_styles, AttributeOperator, Combinator, Rule, Selector, SelectorError, SelectorErrorKind,
+    SimpleSelector, Stylesheet,
+};
+pub use diff::{diff, AttributeChange, Change, Diff, NodeRef, PatchError};
+pub use document::{Document, DuplicateId};
+pub use expand_use::{expand_use, RecursiveUseError};
+pub use filter::{
+    BlendMode, ColorInterpolation, ColorMatrixKind, CompositeOperator, Filter, FilterInput,
+    FilterKind, FilterPrimitive, LightSource, MorphologyOperator, TransferFunction,
+};
+pub use font::{Font, FontError, FontErrorKind};
+pub use length::{
+    Length, LengthContext, LengthDirection, LengthError, LengthErrorKind, LengthUnit,
+};
+pub use marker::{
+    expand_markers, Marker, MarkerInstance, MarkerOrient, MarkerPosition, MarkerUnits, Vertex,
+};
+pub use paint::{Gradient, GradientKind, GradientStop, Paint, Pattern, SpreadMethod, Units};
+pub use parse::{parse, parse_document};
+pub use path::{PathData, PathError, PathErrorKind, PathSegment};
+#[cfg(feature = "pdf")]
+pub use pdf::{render_pdf, render_pdf_with_files, render_pdf_with_fonts};
+pub use reference::Reference;
+#[cfg(feature = "render")]
+pub use render::{render, render_with_fonts, Pixmap};
+pub use shape::shapes_to_paths;
 pub use stringify::{stringify, stringify_pretty};
+pub use style::{Declaration, Style};
+pub use text::{text_to_paths, Fonts, TextGlyph};
+pub use transform::{
+    Decomposition, Matrix, Transform, TransformError, TransformErrorKind, TransformFunction,
+};
+pub use view_box::{
+    Align, MeetOrSlice, PreserveAspectRatio, ViewBox, ViewBoxError, ViewBoxErrorKind,
+};
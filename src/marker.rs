@@ -0,0 +1,863 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::{clone_subtree, Element};
+use crate::bbox::BBox;
+use crate::color::parse_angle;
+use crate::css::{inherited_properties, initial_value, ComputedStyles};
+use crate::document::Document;
+use crate::length::{Length, LengthContext, LengthDirection};
+use crate::number::snap;
+use crate::paint::property;
+use crate::path::{shortest_number, PathData, PathSegment};
+use crate::path_normalize::CenterArc;
+use crate::reference::url_ids;
+use crate::shape::shape_path_data;
+use crate::transform::Matrix;
+use crate::view_box::{PreserveAspectRatio, ViewBox};
+
+/// the elements which carry markers
+const MARKABLE: [&str; 4] = ["path", "line", "polyline", "polygon"];
+
+/// the properties of a shape which also apply to its markers, moved to the
+/// group holding both when the markers are expanded
+const GROUP_PROPERTIES: [&str; 5] = ["transform", "opacity", "clip-path", "mask", "filter"];
+
+/// A vertex of a path, where a marker can be placed
+///
+/// `angle` the direction of the path at the vertex in degrees, the bisector of
+/// the incoming and outgoing directions when there are both
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vertex {
+    pub x: f64,
+    pub y: f64,
+    pub angle: f64,
+}
+
+/// Which vertices of a path a marker is placed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerPosition {
+    Start,
+    Mid,
+    End,
+}
+
+impl MarkerPosition {
+    /// the property which references the marker for the position
+    pub fn property(&self) -> &'static str {
+        match self {
+            MarkerPosition::Start => "marker-start",
+            MarkerPosition::Mid => "marker-mid",
+            MarkerPosition::End => "marker-end",
+        }
+    }
+}
+
+/// The coordinate system of a marker, `markerUnits`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MarkerUnits {
+    #[default]
+    StrokeWidth,
+    UserSpaceOnUse,
+}
+
+impl MarkerUnits {
+    /// parse a `markerUnits` value
+    pub fn parse(value: &str) -> Option<MarkerUnits> {
+        match value.trim() {
+            "strokeWidth" => Some(MarkerUnits::StrokeWidth),
+            "userSpaceOnUse" => Some(MarkerUnits::UserSpaceOnUse),
+            _ => None,
+        }
+    }
+}
+
+/// The rotation of a marker, `orient`
+///
+/// `Angle` a fixed angle in degrees, `Auto` the direction of the path and
+/// `AutoStartReverse` the same but reversed for `marker-start`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkerOrient {
+    Angle(f64),
+    Auto,
+    AutoStartReverse,
+}
+
+impl Default for MarkerOrient {
+    fn default() -> Self {
+        MarkerOrient::Angle(0.0)
+    }
+}
+
+impl MarkerOrient {
+    /// parse a `orient` value, a angle may have a unit
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::MarkerOrient;
+    ///
+    /// assert_eq!(MarkerOrient::parse("auto-start-reverse"), Some(MarkerOrient::AutoStartReverse));
+    /// assert_eq!(MarkerOrient::parse("45"), Some(MarkerOrient::Angle(45.0)));
+    /// assert_eq!(MarkerOrient::parse("0.5turn"), Some(MarkerOrient::Angle(180.0)));
+    /// assert_eq!(MarkerOrient::parse("left"), None);
+    /// ```
+    ///
+    pub fn parse(value: &str) -> Option<MarkerOrient> {
+        match value.trim() {
+            "auto" => Some(MarkerOrient::Auto),
+            "auto-start-reverse" => Some(MarkerOrient::AutoStartReverse),
+            value => parse_angle(value)
+                .filter(|angle| angle.is_finite())
+                .map(MarkerOrient::Angle),
+        }
+    }
+
+    /// the rotation in degrees of a marker at `vertex`, placed at `position`
+    pub fn angle(&self, vertex: &Vertex, position: MarkerPosition) -> f64 {
+        match self {
+            MarkerOrient::Angle(angle) => *angle,
+            MarkerOrient::Auto => vertex.angle,
+            MarkerOrient::AutoStartReverse if position == MarkerPosition::Start => {
+                vertex.angle + 180.0
+            }
+            MarkerOrient::AutoStartReverse => vertex.angle,
+        }
+    }
+}
+
+/// A resolved `marker`
+///
+/// the children of `element` are drawn at each vertex, in a viewport of `width`
+/// by `height` which the `view_box` is fit into. `ref_x` and `ref_y` is the
+/// point of the content placed on the vertex. `clip` is whether the content is
+/// clipped to the viewport, which is the case unless `overflow` is visible.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker<'a> {
+    pub element: Rc<Element<'a>>,
+    pub units: MarkerUnits,
+    pub ref_x: f64,
+    pub ref_y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub orient: MarkerOrient,
+    pub view_box: Option<ViewBox>,
+    pub preserve_aspect_ratio: PreserveAspectRatio,
+    pub clip: bool,
+}
+
+impl<'a> Marker<'a> {
+    /// the transform from the content of the marker to the viewport, without
+    /// the `ref_x` and `ref_y`. `None` for a viewport or a view box without area.
+    fn view_box_transform(&self) -> Option<Matrix> {
+        if self.width <= 0.0 || self.height <= 0.0 {
+            return None;
+        }
+        match self.view_box {
+            Some(view_box) if view_box.width > 0.0 && view_box.height > 0.0 => {
+                Some(view_box.transform(
+                    (0.0, 0.0, self.width, self.height),
+                    &self.preserve_aspect_ratio,
+                ))
+            }
+            Some(_) => None,
+            None => Some(Matrix::default()),
+        }
+    }
+
+    /// the transform from the content of the marker to the user space of a path
+    /// with a stroke of `stroke_width`, for a marker at `vertex` placed at
+    /// `position`. `None` if nothing can be drawn.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse_document, LengthContext, Matrix, MarkerPosition, Vertex};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><marker id="m" refX="1" refY="2" orient="auto"/><path id="p" marker-end="url(#m)"/></svg>"##).unwrap();
+    /// let path = document.element_by_id("p").unwrap();
+    /// let marker = document.resolve_marker(&path, MarkerPosition::End, &LengthContext::default()).unwrap();
+    /// let vertex = Vertex { x: 10.0, y: 0.0, angle: 90.0 };
+    /// assert_eq!(
+    ///     marker.transform(&vertex, MarkerPosition::End, 2.0),
+    ///     Some(Matrix::translate(10.0, 0.0) * Matrix::rotate(90.0) * Matrix::scale(2.0, 2.0) * Matrix::translate(-1.0, -2.0))
+    /// );
+    /// ```
+    ///
+    pub fn transform(
+        &self,
+        vertex: &Vertex,
+        position: MarkerPosition,
+        stroke_width: f64,
+    ) -> Option<Matrix> {
+        let view_box = self.view_box_transform()?;
+        let scale = match self.units {
+            MarkerUnits::StrokeWidth => stroke_width,
+            MarkerUnits::UserSpaceOnUse => 1.0,
+        };
+        if scale <= 0.0 {
+            return None;
+        }
+        let (ref_x, ref_y) = view_box.apply(self.ref_x, self.ref_y);
+        Some(
+            Matrix::translate(vertex.x, vertex.y)
+                * Matrix::rotate(self.orient.angle(vertex, position))
+                * Matrix::scale(scale, scale)
+                * Matrix::translate(-ref_x, -ref_y)
+                * view_box,
+        )
+    }
+
+    /// the viewport in the coordinates of the content of the marker, `None` if
+    /// the content is not clipped or nothing can be drawn
+    pub fn clip_rect(&self) -> Option<BBox> {
+        if !self.clip {
+            return None;
+        }
+        let inverse = self.view_box_transform()?.invert()?;
+        Some(BBox::new(0.0, 0.0, self.width, self.height).transformed(&inverse))
+    }
+}
+
+/// A marker placed on a vertex of a path
+///
+/// `transform` maps the content of the marker to the user space of the path,
+/// see `Marker::transform`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkerInstance<'a> {
+    pub marker: Marker<'a>,
+    pub position: MarkerPosition,
+    pub transform: Matrix,
+}
+
+/// a direction, not normalized
+type Vector = (f64, f64);
+
+/// a vertex while the directions around it are collected
+struct Corner {
+    point: (f64, f64),
+    incoming: Option<Vector>,
+    outgoing: Option<Vector>,
+    /// reached by a segment without length, whose direction is its neighbours'
+    degenerate: bool,
+}
+
+/// the first vector which is not zero
+fn direction(vectors: &[Vector]) -> Option<Vector> {
+    vectors
+        .iter()
+        .copied()
+        .find(|(x, y)| *x != 0.0 || *y != 0.0)
+}
+
+/// the directions at the start and at the end of a absolute segment from `current`
+fn segment_directions(
+    segment: &PathSegment,
+    (cx, cy): (f64, f64),
+    start: (f64, f64),
+) -> (Option<Vector>, Option<Vector>) {
+    let line = |(x, y): (f64, f64)| {
+        let d = direction(&[(x - cx, y - cy)]);
+        (d, d)
+    };
+    match *segment {
+        PathSegment::CurveTo {
+            x1,
+            y1,
+            x2,
+            y2,
+            x,
+            y,
+            ..
+        } => (
+            direction(&[(x1 - cx, y1 - cy), (x2 - cx, y2 - cy), (x - cx, y - cy)]),
+            direction(&[(x - x2, y - y2), (x - x1, y - y1), (x - cx, y - cy)]),
+        ),
+        PathSegment::ArcTo {
+            rx,
+            ry,
+            x_axis_rotation,
+            large_arc,
+            sweep,
+            x,
+            y,
+            ..
+        } => {
+            match CenterArc::from_endpoints(
+                (cx, cy),
+                (rx, ry),
+                x_axis_rotation,
+                large_arc,
+                sweep,
+                (x, y),
+            ) {
+                Some(arc) => {
+                    let sign = arc.delta.signum();
+                    let tangent = |t: f64| {
+                        let (dx, dy) = arc.derivative(t);
+                        direction(&[(dx * sign, dy * sign)])
+                    };
+                    (tangent(arc.theta), tangent(arc.theta + arc.delta))
+                }
+                None => line((x, y)),
+            }
+        }
+        PathSegment::ClosePath { .. } => line(start),
+        PathSegment::MoveTo { x, y, .. } | PathSegment::LineTo { x, y, .. } => line((x, y)),
+        _ => (None, None),
+    }
+}
+
+/// the angle in degrees halfway between two directions
+fn bisector((ix, iy): Vector, (ox, oy): Vector) -> f64 {
+    let incoming = iy.atan2(ix);
+    let mut difference = oy.atan2(ox) - incoming;
+    if difference > std::f64::consts::PI {
+        difference -= 2.0 * std::f64::consts::PI;
+    } else if difference <= -std::f64::consts::PI {
+        difference += 2.0 * std::f64::consts::PI;
+    }
+    let angle = (incoming + difference / 2.0).to_degrees();
+    if angle > 180.0 {
+        angle - 360.0
+    } else {
+        angle
+    }
+}
+
+impl PathData {
+    /// the vertices of the path where markers are placed, with the direction of
+    /// the path at each of them
+    ///
+    /// every segment ends on a vertex and every subpath starts on one. the
+    /// direction at the start and the end of a closed subpath is the bisector of
+    /// the closing segment and the first segment. a segment without length takes
+    /// the direction of its neighbours.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{PathData, Vertex};
+    ///
+    /// let path = PathData::parse("M0 0 H10 V10").unwrap();
+    /// assert_eq!(
+    ///     path.vertices(),
+    ///     vec![
+    ///         Vertex { x: 0.0, y: 0.0, angle: 0.0 },
+    ///         Vertex { x: 10.0, y: 0.0, angle: 45.0 },
+    ///         Vertex { x: 10.0, y: 10.0, angle: 90.0 },
+    ///     ]
+    /// );
+    /// ```
+    ///
+    pub fn vertices(&self) -> Vec<Vertex> {
+        let mut corners: Vec<Corner> = vec![];
+        let (mut current, mut start) = ((0.0, 0.0), (0.0, 0.0));
+        // the corner which starts the current subpath
+        let mut first = 0;
+        for segment in self.simplified(None).segments {
+            if let PathSegment::MoveTo { x, y, .. } = segment {
+                current = (x, y);
+                start = current;
+                first = corners.len();
+                corners.push(Corner {
+                    point: current,
+                    incoming: None,
+                    outgoing: None,
+                    degenerate: false,
+                });
+                continue;
+            }
+            if corners.is_empty() {
+                corners.push(Corner {
+                    point: current,
+                    incoming: None,
+                    outgoing: None,
+                    degenerate: false,
+                });
+            }
+            let (outgoing, incoming) = segment_directions(&segment, current, start);
+            let previous = corners.last_mut().unwrap();
+            if outgoing.is_some() {
+                previous.outgoing = outgoing;
+            }
+            let previous_incoming = previous.incoming;
+            let end = match segment {
+                PathSegment::ClosePath { .. } => start,
+                PathSegment::LineTo { x, y, .. }
+                | PathSegment::CurveTo { x, y, .. }
+                | PathSegment::ArcTo { x, y, .. } => (x, y),
+                _ => current,
+            };
+            let degenerate = incoming.is_none();
+            corners.push(Corner {
+                point: end,
+                incoming: incoming.or(previous_incoming),
+                outgoing: None,
+                degenerate,
+            });
+            if let PathSegment::ClosePath { .. } = segment {
+                let last = corners.len() - 1;
+                let closing = corners[last].incoming;
+                let opening = corners[first].outgoing;
+                if corners[first].incoming.is_none() {
+                    corners[first].incoming = closing;
+                }
+                corners[last].outgoing = opening;
+                // a segment after the close starts a new subpath at the same point
+                first = last;
+            }
+            current = end;
+        }
+        for i in (0..corners.len().saturating_sub(1)).rev() {
+            if corners[i].outgoing.is_none() && corners[i + 1].degenerate {
+                corners[i].outgoing = corners[i + 1].outgoing;
+            }
+        }
+        corners
+            .into_iter()
+            .map(|corner| {
+                let angle = match (corner.incoming, corner.outgoing) {
+                    (Some(incoming), Some(outgoing)) => bisector(incoming, outgoing),
+                    (Some((x, y)), None) | (None, Some((x, y))) => y.atan2(x).to_degrees(),
+                    (None, None) => 0.0,
+                };
+                Vertex {
+                    x: corner.point.0,
+                    y: corner.point.1,
+                    angle,
+                }
+            })
+            .collect()
+    }
+}
+
+impl<'a> Document<'a> {
+    /// resolve the marker of a path, `line`, `polyline` or `polygon` at a position
+    ///
+    /// the marker properties are computed, the `marker` shorthand only counts
+    /// in a `style` attribute or a stylesheet. `None` without a marker or if the
+    /// reference is not a `marker`. lengths are resolved against `context`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse_document, LengthContext, MarkerOrient, MarkerPosition, MarkerUnits};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><marker id="m" markerWidth="10" orient="auto" markerUnits="userSpaceOnUse"/><g style="marker:url(#m)"><path id="p" d="M0 0 10 0"/></g></svg>"##).unwrap();
+    /// let path = document.element_by_id("p").unwrap();
+    /// let context = LengthContext::default();
+    /// let marker = document.resolve_marker(&path, MarkerPosition::Mid, &context).unwrap();
+    /// assert_eq!((marker.width, marker.height), (10.0, 3.0));
+    /// assert_eq!(marker.orient, MarkerOrient::Auto);
+    /// assert_eq!(marker.units, MarkerUnits::UserSpaceOnUse);
+    /// assert!(marker.clip);
+    /// ```
+    ///
+    pub fn resolve_marker(
+        &self,
+        ele: &Rc<Element<'a>>,
+        position: MarkerPosition,
+        context: &LengthContext,
+    ) -> Option<Marker<'a>> {
+        let styles = ComputedStyles::new(self.stylesheet());
+        self.resolve_marker_with(&styles, ele, position, context)
+    }
+
+    /// resolve a marker like `Document::resolve_marker`, with the computed styles of the tree
+    pub(crate) fn resolve_marker_with(
+        &self,
+        styles: &ComputedStyles<'a>,
+        ele: &Rc<Element<'a>>,
+        position: MarkerPosition,
+        context: &LengthContext,
+    ) -> Option<Marker<'a>> {
+        if !MARKABLE.contains(&ele.ele_type) {
+            return None;
+        }
+        let value = styles.get(ele, position.property())?;
+        let id = url_ids(&value).first().copied()?;
+        let element = self
+            .element_by_id(id)
+            .filter(|target| target.ele_type == "marker")?;
+        let length = |key: &str, default: f64| {
+            element
+                .get_attribute(key)
+                .and_then(|value| Length::parse(&value).ok())
+                .map_or(default, |length| {
+                    length.to_user_units(context, LengthDirection::of_attribute(key))
+                })
+        };
+        Some(Marker {
+            units: element
+                .get_attribute("markerUnits")
+                .and_then(|value| MarkerUnits::parse(&value))
+                .unwrap_or_default(),
+            ref_x: length("refX", 0.0),
+            ref_y: length("refY", 0.0),
+            width: length("markerWidth", 3.0),
+            height: length("markerHeight", 3.0),
+            orient: element
+                .get_attribute("orient")
+                .and_then(|value| MarkerOrient::parse(&value))
+                .unwrap_or_default(),
+            view_box: element.view_box().ok().flatten(),
+            preserve_aspect_ratio: element.preserve_aspect_ratio().unwrap_or_default(),
+            clip: !matches!(
+                styles.get(&element, "overflow").as_deref().map(str::trim),
+                Some("visible" | "auto")
+            ),
+            element,
+        })
+    }
+
+    /// the markers of a path, `line`, `polyline` or `polygon` at each of its
+    /// vertices, see `PathData::vertices`
+    ///
+    /// `marker-start` is placed on the first vertex, `marker-mid` on every other
+    /// one but the last, and `marker-end` on the last. a marker in `strokeWidth`
+    /// units is scaled by the `stroke-width` of the element, even without stroke.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse_document, LengthContext, MarkerPosition, Matrix};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><marker id="m" orient="auto"/><polyline id="p" points="0 0 10 0 10 10" marker-mid="url(#m)" marker-end="url(#m)"/></svg>"##).unwrap();
+    /// let markers = document.markers(&document.element_by_id("p").unwrap(), &LengthContext::default());
+    /// assert_eq!(markers.len(), 2);
+    /// assert_eq!(markers[0].position, MarkerPosition::Mid);
+    /// assert_eq!(markers[1].transform, Matrix::translate(10.0, 10.0) * Matrix::rotate(90.0));
+    /// ```
+    ///
+    pub fn markers(
+        &self,
+        ele: &Rc<Element<'a>>,
+        context: &LengthContext,
+    ) -> Vec<MarkerInstance<'a>> {
+        let styles = ComputedStyles::new(self.stylesheet());
+        self.markers_with(&styles, ele, context)
+    }
+
+    /// the markers of a shape like `Document::markers`, with the computed styles of the tree
+    pub(crate) fn markers_with(
+        &self,
+        styles: &ComputedStyles<'a>,
+        ele: &Rc<Element<'a>>,
+        context: &LengthContext,
+    ) -> Vec<MarkerInstance<'a>> {
+        let mut instances = vec![];
+        let path = match ele.ele_type {
+            "path" => ele.path_data().ok(),
+            "line" | "polyline" | "polygon" => shape_path_data(ele),
+            _ => None,
+        };
+        let vertices = path.map_or(vec![], |path| path.vertices());
+        let Some(last) = vertices.len().checked_sub(1) else {
+            return instances;
+        };
+        let stroke_width = styles
+            .get(ele, "stroke-width")
+            .and_then(|value| Length::parse(&value).ok())
+            .map_or(1.0, |length| {
+                length.to_user_units(context, LengthDirection::Other)
+            });
+        for position in [
+            MarkerPosition::Start,
+            MarkerPosition::Mid,
+            MarkerPosition::End,
+        ] {
+            let Some(marker) = self.resolve_marker_with(styles, ele, position, context) else {
+                continue;
+            };
+            let range = match position {
+                MarkerPosition::Start => 0..1,
+                MarkerPosition::Mid => 1..last.max(1),
+                MarkerPosition::End => last..last + 1,
+            };
+            for vertex in &vertices[range] {
+                if let Some(transform) = marker.transform(vertex, position, stroke_width) {
+                    instances.push(MarkerInstance {
+                        marker: marker.clone(),
+                        position,
+                        transform,
+                    });
+                }
+            }
+        }
+        instances
+    }
+}
+
+/// replace the markers of the document with copies of their content
+///
+/// a path, `line`, `polyline` or `polygon` with markers is wrapped in a `<g>`
+/// which takes over its `transform`, `opacity`, `clip-path`, `mask` and
+/// `filter`, followed by a `<g>` for each marker holding a copy of its
+/// children without `id`s, under the transform of the instance. the copies
+/// inherit from the marker instead of the shape, so the `<g>` sets each
+/// inherited property whose computed value differs, and lose their own markers.
+/// a marker which clips its content gets a `clipPath` of its viewport next to
+/// it, referenced by its copies. the marker properties are removed from every
+/// element outside of a `marker`, the `marker`s are kept.
+///
+/// ## Example
+/// ```rust
+/// use svg_simple_parser::{expand_markers, parse_document, stringify};
+///
+/// let (_, document) = parse_document(r##"<svg><marker id="m" orient="auto" refX="1" style="overflow:visible"><path d="M0 0 2 1 0 2"/></marker><g stroke="blue"><line x2="10" stroke-width="2" marker-end="url(#m)"/></g></svg>"##).unwrap();
+/// expand_markers(&document);
+/// assert_eq!(
+///     stringify(document.root.clone()),
+///     r##"<svg><marker id="m" orient="auto" refX="1" style="overflow:visible"><path d="M0 0 2 1 0 2"/></marker><g stroke="blue"><g><line stroke-width="2" x2="10"/><g stroke="none" transform="matrix(2 0 0 2 8 0)"><path d="M0 0 2 1 0 2"/></g></g></g></svg>"##
+/// );
+/// ```
+///
+pub fn expand_markers(document: &Document) {
+    let context = LengthContext::default();
+    let styles = ComputedStyles::new(document.stylesheet());
+    let marked: Vec<_> = document
+        .root
+        .descendants()
+        .into_iter()
+        .filter(|ele| !is_in_marker(ele))
+        .filter_map(|ele| {
+            let instances = document.markers_with(&styles, &ele, &context);
+            let parent = ele.parent.borrow().upgrade()?;
+            let parent_style = styles.style(&parent);
+            Some((ele, parent, parent_style, instances))
+        })
+        .collect();
+    // the clip path of the viewport of each marker which clips its content
+    let mut clip_paths: HashMap<*const Element, Option<String>> = HashMap::new();
+    for (ele, parent, parent_style, instances) in &marked {
+        if instances.is_empty() {
+            continue;
+        }
+        let group = Element::new(("g", HashMap::new()));
+        for key in GROUP_PROPERTIES {
+            if let Some(value) = property(ele, key) {
+                group.set_attribute(key, value);
+            }
+            ele.remove_attribute(key);
+            ele.remove_style_property(key);
+        }
+        parent.replace_child(ele, group.clone());
+        group.add_child(ele.clone());
+        for instance in instances {
+            let marker = &instance.marker;
+            let copy = Element::new(("g", HashMap::new()));
+            copy.set_attribute("transform", instance.transform.to_transform().to_string());
+            let marker_style = styles.style(&marker.element);
+            for key in inherited_properties().filter(|key| !key.starts_with("marker")) {
+                let value = marker_style.get(key).or(initial_value(key));
+                if value != parent_style.get(key).or(initial_value(key)) {
+                    if let Some(value) = value {
+                        copy.set_attribute(key, value.to_owned());
+                    }
+                }
+            }
+            let clip_path = clip_paths
+                .entry(Rc::as_ptr(&marker.element))
+                .or_insert_with(|| viewport_clip_path(document, marker));
+            if let Some(id) = clip_path {
+                copy.set_attribute("clip-path", format!("url(#{})", id));
+            }
+            for child in marker.element.children.borrow().iter() {
+                copy.add_child(clone_subtree(child));
+            }
+            group.add_child(copy);
+        }
+    }
+    for ele in document.root.descendants() {
+        if is_in_marker(&ele) {
+            continue;
+        }
+        for key in ["marker", "marker-start", "marker-mid", "marker-end"] {
+            ele.remove_attribute(key);
+            ele.remove_style_property(key);
+        }
+    }
+    document.reindex();
+}
+
+/// add a `clipPath` of the viewport of a marker next to it, return its id.
+/// `None` if the content is not clipped.
+fn viewport_clip_path<'a>(document: &Document<'a>, marker: &Marker<'a>) -> Option<String> {
+    let clip = marker.clip_rect()?;
+    let base = marker
+        .element
+        .get_attribute("id")
+        .map_or_else(|| "marker".to_owned(), |id| id.to_string());
+    let id = (1..)
+        .map(|n| format!("{}-clip-{}", base, n))
+        .find(|id| document.element_by_id(id).is_none())?;
+    let clip_path = Element::new(("clipPath", HashMap::new()));
+    let rect = Element::new(("rect", HashMap::new()));
+    for (key, value) in [
+        ("x", clip.min_x),
+        ("y", clip.min_y),
+        ("width", clip.width()),
+        ("height", clip.height()),
+    ] {
+        let value = snap(value);
+        if value != 0.0 || matches!(key, "width" | "height") {
+            rect.set_attribute(key, shortest_number(value));
+        }
+    }
+    clip_path.add_child(rect);
+    let parent = marker.element.parent.borrow().upgrade();
+    let parent = parent.unwrap_or_else(|| document.root.clone());
+    let index = parent
+        .children
+        .borrow()
+        .iter()
+        .position(|child| Rc::ptr_eq(child, &marker.element))
+        .map_or(usize::MAX, |index| index + 1);
+    parent.insert_child(index, clip_path.clone());
+    document.set_attribute(&clip_path, "id", id.clone());
+    Some(id)
+}
+
+/// whether a element is a `marker` or is in one
+fn is_in_marker(ele: &Rc<Element>) -> bool {
+    let mut current = Some(ele.clone());
+    while let Some(ele) = current {
+        if ele.ele_type == "marker" {
+            return true;
+        }
+        current = ele.parent.borrow().upgrade();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        expand_markers, parse_document, stringify, BBox, LengthContext, MarkerPosition, Matrix,
+        PathData,
+    };
+
+    fn angles(d: &str) -> Vec<f64> {
+        PathData::parse(d)
+            .unwrap()
+            .vertices()
+            .iter()
+            .map(|vertex| (vertex.angle * 1e6).round() / 1e6)
+            .collect()
+    }
+
+    #[test]
+    fn test_vertices() {
+        assert_eq!(angles("M0 0 10 0 10 10"), vec![0.0, 45.0, 90.0]);
+        // a closed subpath bisects the closing and the first segment
+        assert_eq!(
+            angles("M0 0 10 0 10 10 0 10Z"),
+            vec![-45.0, 45.0, 135.0, -135.0, -45.0]
+        );
+        // a reversal turns by a quarter
+        assert_eq!(angles("M0 0 10 0 0 0"), vec![0.0, 90.0, 180.0]);
+        // the tangents of curves and arcs
+        assert_eq!(angles("M0 0C0 10 10 10 10 0"), vec![90.0, -90.0]);
+        assert_eq!(angles("M0 0Q5 5 10 0"), vec![45.0, -45.0]);
+        assert_eq!(angles("M0 0A5 5 0 0 1 10 0"), vec![-90.0, 90.0]);
+        // a segment without length takes the directions around it
+        assert_eq!(angles("M0 0 10 0 10 0 10 10"), vec![0.0, 45.0, 45.0, 90.0]);
+        assert_eq!(angles("M0 0 0 0 10 0"), vec![0.0, 0.0, 0.0]);
+        // a new subpath starts without a incoming direction
+        assert_eq!(angles("M0 0 10 0M20 0 20 10"), vec![0.0, 0.0, 90.0, 90.0]);
+        assert_eq!(angles("M5 5"), vec![0.0]);
+        assert!(PathData::parse("").unwrap().vertices().is_empty());
+    }
+
+    #[test]
+    fn test_markers() {
+        let (_, document) = parse_document(
+            r##"<svg>
+                <marker id="a" orient="auto-start-reverse" markerWidth="4" markerHeight="2" viewBox="0 0 8 4" refX="8" refY="2"><path d="M0 0 8 2 0 4"/></marker>
+                <marker id="b" orient="30deg" markerUnits="userSpaceOnUse" style="overflow:visible"/>
+                <path id="p" d="M0 0 10 0 10 10" stroke-width="3" marker-start="url(#a)" marker-mid="url(#b)" marker-end="url(#a)"/>
+                <rect id="r" width="10" height="10" marker-start="url(#a)"/>
+                <path id="q" d="M0 0 10 0" marker-end="url(#p)"/>
+            </svg>"##,
+        )
+        .unwrap();
+        let context = LengthContext::default();
+        let markers = document.markers(&document.element_by_id("p").unwrap(), &context);
+        let positions: Vec<_> = markers.iter().map(|marker| marker.position).collect();
+        assert_eq!(
+            positions,
+            vec![
+                MarkerPosition::Start,
+                MarkerPosition::Mid,
+                MarkerPosition::End
+            ]
+        );
+        // the view box halves the content, the stroke width scales it by 3
+        let start = markers[0].transform;
+        let close = |(x1, y1): (f64, f64), (x2, y2): (f64, f64)| {
+            (x1 - x2).abs() < 1e-9 && (y1 - y2).abs() < 1e-9
+        };
+        assert!(close(start.apply(8.0, 2.0), (0.0, 0.0)));
+        // reversed at the start, pointing back along the path
+        assert!(close(start.apply(0.0, 2.0), (12.0, 0.0)));
+        let end = markers[2].transform;
+        assert!(close(end.apply(8.0, 2.0), (10.0, 10.0)));
+        assert!(close(end.apply(0.0, 2.0), (10.0, -2.0)));
+        assert_eq!(
+            markers[1].transform,
+            Matrix::translate(10.0, 0.0) * Matrix::rotate(30.0)
+        );
+        assert_eq!(
+            markers[0].marker.clip_rect(),
+            Some(BBox::new(0.0, 0.0, 8.0, 4.0))
+        );
+        assert_eq!(markers[1].marker.clip_rect(), None);
+        // only paths and lines carry markers, and only `marker` elements are markers
+        assert!(document
+            .markers(&document.element_by_id("r").unwrap(), &context)
+            .is_empty());
+        assert!(document
+            .markers(&document.element_by_id("q").unwrap(), &context)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_expand_markers() {
+        let (_, document) = parse_document(
+            r##"<svg><marker id="m" markerUnits="userSpaceOnUse"><circle r="1"/><path d="M0 0 1 1" marker-end="url(#m)"/></marker><g marker-mid="url(#m)"><polyline points="0 0 5 0 5 5 9 9" transform="translate(1 1)" style="opacity:.5;fill:none"/></g></svg>"##,
+        )
+        .unwrap();
+        expand_markers(&document);
+        assert_eq!(
+            stringify(document.root.clone()),
+            r##"<svg><marker id="m" markerUnits="userSpaceOnUse"><circle r="1"/><path d="M0 0 1 1" marker-end="url(#m)"/></marker><clipPath id="m-clip-1"><rect height="3" width="3"/></clipPath><g><g opacity=".5" transform="translate(1 1)"><polyline points="0 0 5 0 5 5 9 9" style="fill:none"/><g clip-path="url(#m-clip-1)" transform="translate(5)"><circle r="1"/><path d="M0 0 1 1"/></g><g clip-path="url(#m-clip-1)" transform="translate(5 5)"><circle r="1"/><path d="M0 0 1 1"/></g></g></g></svg>"##
+        );
+
+        // the copies inherit from the marker, not from the shape
+        let (_, document) = parse_document(
+            r##"<svg><marker id="m" fill="red" viewBox="0 0 10 10" style="overflow:visible"><rect width="1" height="1"/></marker><g stroke="blue" stroke-width="2" fill="red"><line x2="10" marker-end="url(#m)"/></g></svg>"##,
+        )
+        .unwrap();
+        expand_markers(&document);
+        assert_eq!(
+            stringify(document.root.clone()),
+            r##"<svg><marker fill="red" id="m" style="overflow:visible" viewBox="0 0 10 10"><rect height="1" width="1"/></marker><g fill="red" stroke="blue" stroke-width="2"><g><line x2="10"/><g stroke="none" stroke-width="1" transform="matrix(.6 0 0 .6 10 0)"><rect height="1" width="1"/></g></g></g></svg>"##
+        );
+    }
+
+    #[test]
+    fn test_marker_shorthand() {
+        // the shorthand counts in a style, not as a attribute
+        let (_, document) = parse_document(
+            r##"<svg><style>.a{marker:url(#m)}</style><marker id="m"/><path id="p" d="M0 0 10 0" marker="url(#m)"/><path id="q" class="a" d="M0 0 10 0" style="marker-start:none"/></svg>"##,
+        )
+        .unwrap();
+        let context = LengthContext::default();
+        let positions = |id| {
+            let ele = document.element_by_id(id).unwrap();
+            document
+                .markers(&ele, &context)
+                .iter()
+                .map(|marker| marker.position)
+                .collect::<Vec<_>>()
+        };
+        assert!(positions("p").is_empty());
+        assert_eq!(positions("q"), vec![MarkerPosition::End]);
+    }
+}
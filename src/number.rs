@@ -0,0 +1,108 @@
+use crate::ast::Element;
+
+/// parse a plain number attribute, an optional `px` suffix is allowed
+///
+/// ## Example
+/// ``` ignore
+/// "10" -> Some(10.0)
+/// " 1.5px " -> Some(1.5)
+/// "50%" -> None
+/// ```
+pub(crate) fn parse_number(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let value = value.strip_suffix("px").unwrap_or(value);
+    value.trim().parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+/// parse a list of numbers separated by whitespace and/or a comma
+///
+/// ## Example
+/// ``` ignore
+/// "0 0, 10 20" -> Some([0.0, 0.0, 10.0, 20.0])
+/// ```
+pub(crate) fn parse_number_list(value: &str) -> Option<Vec<f64>> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<f64>().ok().filter(|n| n.is_finite()))
+        .collect()
+}
+
+/// parse a plain number attribute of a element
+pub(crate) fn number_attribute(ele: &Element, key: &str) -> Option<f64> {
+    ele.get_attribute(key)
+        .and_then(|value| parse_number(&value))
+}
+
+/// round away the floating point noise of a computed number, to 9 decimals
+///
+/// ## Example
+/// ``` ignore
+/// 10.000000000000002 -> 10.0
+/// 6.123233995736766e-17 -> 0.0
+/// ```
+pub(crate) fn snap(n: f64) -> f64 {
+    let n = (n * 1e9).round() / 1e9;
+    if n == 0.0 {
+        0.0
+    } else {
+        n
+    }
+}
+
+/// format a number without trailing zeros, rounded to 6 decimals
+///
+/// ## Example
+/// ``` ignore
+/// 10.0 -> "10"
+/// 0.5 -> "0.5"
+/// 1.0 / 3.0 -> "0.333333"
+/// -0.0000001 -> "0"
+/// ```
+pub(crate) fn format_number(n: f64) -> String {
+    let s = format!("{:.6}", n);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    match s {
+        "-0" | "" => "0".to_owned(),
+        s => s.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::number::{format_number, parse_number, parse_number_list, snap};
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("10"), Some(10.0));
+        assert_eq!(parse_number(" 1.5px "), Some(1.5));
+        assert_eq!(parse_number("-2e1"), Some(-20.0));
+        assert_eq!(parse_number("50%"), None);
+        assert_eq!(parse_number("auto"), None);
+    }
+
+    #[test]
+    fn test_parse_number_list() {
+        assert_eq!(
+            parse_number_list("0 0, 10 20"),
+            Some(vec![0.0, 0.0, 10.0, 20.0])
+        );
+        assert_eq!(parse_number_list("0,a"), None);
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(10.0), "10");
+        assert_eq!(format_number(0.5), "0.5");
+        assert_eq!(format_number(-12.25), "-12.25");
+        assert_eq!(format_number(1.0 / 3.0), "0.333333");
+        assert_eq!(format_number(-0.0000001), "0");
+    }
+
+    #[test]
+    fn test_snap() {
+        assert_eq!(snap(10.000000000000002), 10.0);
+        assert_eq!(snap(-6.123233995736766e-17).to_string(), "0");
+        assert_eq!(snap(0.1 + 0.2), 0.3);
+    }
+}
@@ -0,0 +1,785 @@
+use std::rc::Rc;
+
+use crate::ast::Element;
+use crate::bbox::BBox;
+use crate::color::{opacity, paint_color, Color, Rgba};
+use crate::css::ComputedStyles;
+use crate::document::Document;
+use crate::length::{Length, LengthContext, LengthDirection, LengthUnit};
+use crate::reference::{element_href, url_ids};
+use crate::style::Style;
+use crate::transform::{Matrix, Transform};
+use crate::view_box::{PreserveAspectRatio, ViewBox};
+
+/// The coordinate system of the geometry of a paint server, clip path or mask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Units {
+    /// the user space of the element which references it
+    UserSpaceOnUse,
+    /// fractions of the bounding box of the element which references it
+    ObjectBoundingBox,
+}
+
+impl Units {
+    /// parse a `gradientUnits`-like value
+    pub fn parse(value: &str) -> Option<Units> {
+        match value.trim() {
+            "userSpaceOnUse" => Some(Units::UserSpaceOnUse),
+            "objectBoundingBox" => Some(Units::ObjectBoundingBox),
+            _ => None,
+        }
+    }
+
+    /// the transform from these units to user space, `None` for a bounding box
+    /// without area which can not be painted
+    pub fn to_user_space(&self, bbox: &BBox) -> Option<Matrix> {
+        match self {
+            Units::UserSpaceOnUse => Some(Matrix::default()),
+            Units::ObjectBoundingBox if bbox.width() > 0.0 && bbox.height() > 0.0 => Some(
+                Matrix::translate(bbox.min_x, bbox.min_y)
+                    * Matrix::scale(bbox.width(), bbox.height()),
+            ),
+            Units::ObjectBoundingBox => None,
+        }
+    }
+}
+
+/// How a gradient continues beyond its ends, `spreadMethod`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpreadMethod {
+    #[default]
+    Pad,
+    Reflect,
+    Repeat,
+}
+
+impl SpreadMethod {
+    /// parse a `spreadMethod` value
+    pub fn parse(value: &str) -> Option<SpreadMethod> {
+        match value.trim() {
+            "pad" => Some(SpreadMethod::Pad),
+            "reflect" => Some(SpreadMethod::Reflect),
+            "repeat" => Some(SpreadMethod::Repeat),
+            _ => None,
+        }
+    }
+}
+
+/// A color of a gradient at `offset` between `0` and `1`, the alpha includes `stop-opacity`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop {
+    pub offset: f64,
+    pub color: Rgba,
+}
+
+/// The geometry of a gradient, in the units of the gradient
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientKind {
+    /// from `(x1, y1)` at offset `0` to `(x2, y2)` at offset `1`
+    Linear { x1: f64, y1: f64, x2: f64, y2: f64 },
+    /// from the focal circle `(fx, fy, fr)` at offset `0` to the circle `(cx, cy, r)` at offset `1`
+    Radial {
+        cx: f64,
+        cy: f64,
+        r: f64,
+        fx: f64,
+        fy: f64,
+        fr: f64,
+    },
+}
+
+/// A resolved `linearGradient` or `radialGradient`
+///
+/// `transform` the `gradientTransform`, applied before the `units`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub units: Units,
+    pub transform: Matrix,
+    pub spread: SpreadMethod,
+    pub stops: Vec<GradientStop>,
+}
+
+/// A resolved `pattern`
+///
+/// the tile `x`, `y`, `width` and `height` are in `units`, `transform` is the
+/// `patternTransform`. `content` is the pattern whose children are drawn in each
+/// tile, in `content_units` or mapped by the `view_box`. `opacity` is the paint
+/// opacity of the element which references the pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern<'a> {
+    pub units: Units,
+    pub content_units: Units,
+    pub transform: Matrix,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub view_box: Option<ViewBox>,
+    pub preserve_aspect_ratio: PreserveAspectRatio,
+    pub content: Rc<Element<'a>>,
+    pub opacity: f64,
+}
+
+impl<'a> Pattern<'a> {
+    /// the first tile in the pattern space, for a element with the bounding box
+    /// `bbox`. `None` for a tile without area.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse_document, BBox, LengthContext};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><pattern id="p" width="0.5" height="0.25"><rect width="1" height="1"/></pattern></svg>"##).unwrap();
+    /// let pattern = document.pattern(&document.element_by_id("p").unwrap(), &LengthContext::default()).unwrap();
+    /// let bbox = BBox::new(10.0, 10.0, 30.0, 50.0);
+    /// assert_eq!(pattern.tile(&bbox), Some(BBox::new(10.0, 10.0, 20.0, 20.0)));
+    /// ```
+    ///
+    pub fn tile(&self, bbox: &BBox) -> Option<BBox> {
+        if self.width <= 0.0 || self.height <= 0.0 {
+            return None;
+        }
+        let m = self.units.to_user_space(bbox)?;
+        let (x, y) = m.apply(self.x, self.y);
+        let (width, height) = (self.width * m.a, self.height * m.d);
+        Some(BBox::new(x, y, x + width, y + height))
+    }
+
+    /// the transform from the content of the pattern to the pattern space, for a
+    /// element with the bounding box `bbox`. `None` if nothing can be drawn.
+    pub fn content_transform(&self, bbox: &BBox) -> Option<Matrix> {
+        let tile = self.tile(bbox)?;
+        match self.view_box {
+            Some(view_box) if view_box.width > 0.0 && view_box.height > 0.0 => {
+                Some(view_box.transform(
+                    (tile.min_x, tile.min_y, tile.width(), tile.height()),
+                    &self.preserve_aspect_ratio,
+                ))
+            }
+            Some(_) => None,
+            None => Some(
+                Matrix::translate(tile.min_x, tile.min_y)
+                    * match self.content_units {
+                        Units::UserSpaceOnUse => Matrix::default(),
+                        Units::ObjectBoundingBox => Matrix::scale(bbox.width(), bbox.height()),
+                    },
+            ),
+        }
+    }
+}
+
+/// A resolved `fill` or `stroke`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Paint<'a> {
+    Color(Rgba),
+    Gradient(Gradient),
+    Pattern(Pattern<'a>),
+}
+
+const GRADIENTS: [&str; 2] = ["linearGradient", "radialGradient"];
+
+/// a property from the `style` attribute or the attribute of the element
+pub(crate) fn property(ele: &Element, key: &str) -> Option<String> {
+    ele.style_property(key)
+        .or_else(|| ele.get_attribute(key).map(String::from))
+}
+
+impl Gradient {
+    /// the offset of a point in the units of the gradient, before the spread
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{Gradient, GradientKind, Matrix, SpreadMethod, Units};
+    ///
+    /// let gradient = Gradient {
+    ///     kind: GradientKind::Linear { x1: 0.0, y1: 0.0, x2: 10.0, y2: 0.0 },
+    ///     units: Units::UserSpaceOnUse,
+    ///     transform: Matrix::default(),
+    ///     spread: SpreadMethod::Pad,
+    ///     stops: vec![],
+    /// };
+    /// assert_eq!(gradient.offset_at(2.5, 7.0), 0.25);
+    /// ```
+    ///
+    pub fn offset_at(&self, x: f64, y: f64) -> f64 {
+        match self.kind {
+            GradientKind::Linear { x1, y1, x2, y2 } => {
+                let (dx, dy) = (x2 - x1, y2 - y1);
+                let length = dx * dx + dy * dy;
+                if length == 0.0 {
+                    // every point takes the last stop
+                    return 1.0;
+                }
+                ((x - x1) * dx + (y - y1) * dy) / length
+            }
+            GradientKind::Radial {
+                cx,
+                cy,
+                r,
+                fx,
+                fy,
+                fr,
+            } => {
+                if r <= 0.0 {
+                    return 1.0;
+                }
+                // a focal point outside the circle is moved onto it
+                let (mut fx, mut fy) = (fx, fy);
+                let distance = (fx - cx).hypot(fy - cy);
+                if distance > r * 0.999 {
+                    let scale = r * 0.999 / distance;
+                    fx = cx + (fx - cx) * scale;
+                    fy = cy + (fy - cy) * scale;
+                }
+                // the largest t whose circle, interpolated between the focal
+                // circle and the end circle, passes through the point
+                let fr = fr.clamp(0.0, r);
+                let (dx, dy, dr) = (cx - fx, cy - fy, r - fr);
+                let (qx, qy) = (x - fx, y - fy);
+                let a = dx * dx + dy * dy - dr * dr;
+                let b = qx * dx + qy * dy + fr * dr;
+                let c = qx * qx + qy * qy - fr * fr;
+                if a.abs() < 1e-12 {
+                    return if b == 0.0 { 0.0 } else { c / (2.0 * b) };
+                }
+                (b - (b * b - a * c).max(0.0).sqrt()) / a
+            }
+        }
+    }
+
+    /// the color at a offset, after the spread. `None` for a gradient without stops.
+    pub fn color_at(&self, offset: f64) -> Option<Rgba> {
+        let offset = match self.spread {
+            SpreadMethod::Pad => offset.clamp(0.0, 1.0),
+            SpreadMethod::Repeat => offset.rem_euclid(1.0),
+            SpreadMethod::Reflect => {
+                let offset = offset.rem_euclid(2.0);
+                if offset > 1.0 {
+                    2.0 - offset
+                } else {
+                    offset
+                }
+            }
+        };
+        let first = self.stops.first()?;
+        let last = self.stops.last()?;
+        if offset <= first.offset {
+            return Some(first.color);
+        }
+        if offset >= last.offset {
+            return Some(last.color);
+        }
+        let index = self.stops.iter().position(|stop| stop.offset > offset)?;
+        let (from, to) = (&self.stops[index - 1], &self.stops[index]);
+        let t = (offset - from.offset) / (to.offset - from.offset);
+        Some(mix(from.color, to.color, t))
+    }
+}
+
+/// interpolate two colors with premultiplied alpha
+fn mix(from: Rgba, to: Rgba, t: f64) -> Rgba {
+    let alpha = from.alpha + (to.alpha - from.alpha) * t;
+    let channel = |a: u8, b: u8| {
+        if alpha <= 0.0 {
+            return 0;
+        }
+        let premultiplied =
+            a as f64 * from.alpha + (b as f64 * to.alpha - a as f64 * from.alpha) * t;
+        (premultiplied / alpha).round().clamp(0.0, 255.0) as u8
+    };
+    Rgba::new(
+        channel(from.red, to.red),
+        channel(from.green, to.green),
+        channel(from.blue, to.blue),
+        alpha,
+    )
+}
+
+/// the `stop` children of a gradient, with offsets clamped to be increasing
+fn gradient_stops<'a>(styles: &ComputedStyles<'a>, ele: &Rc<Element<'a>>) -> Vec<GradientStop> {
+    let mut stops: Vec<GradientStop> = vec![];
+    for stop in ele.children.borrow().iter() {
+        if stop.ele_type != "stop" {
+            continue;
+        }
+        let offset = stop
+            .get_attribute("offset")
+            .and_then(|value| opacity(&value))
+            .unwrap_or(0.0);
+        let offset = stops.last().map_or(offset, |last| offset.max(last.offset));
+        let color = color_property(
+            &styles.style(stop),
+            "stop-color",
+            Some("stop-opacity"),
+            Rgba::BLACK,
+        );
+        stops.push(GradientStop { offset, color });
+    }
+    stops
+}
+
+/// a color property like `stop-color` of a computed style with the alpha of its
+/// opacity property if it has one, `currentColor` takes the computed `color`
+pub(crate) fn color_property(
+    style: &Style,
+    key: &str,
+    opacity_key: Option<&str>,
+    default: Rgba,
+) -> Rgba {
+    let current = style
+        .get("color")
+        .and_then(|color| Color::parse(color).ok())
+        .map_or(Rgba::BLACK, |color| color.resolve(Rgba::BLACK));
+    let mut color = style
+        .get(key)
+        .and_then(|color| Color::parse(color).ok())
+        .map_or(default, |color| color.resolve(current));
+    color.alpha *= opacity_key
+        .and_then(|key| style.get(key))
+        .and_then(opacity)
+        .unwrap_or(1.0);
+    color
+}
+
+impl<'a> Document<'a> {
+    /// a element followed by the elements of the same kind it inherits from
+    /// through `href`, up to a missing element or a cycle
+    fn href_chain(&self, ele: &Rc<Element<'a>>, kinds: &[&str]) -> Vec<Rc<Element<'a>>> {
+        let mut chain = vec![ele.clone()];
+        while let Some(next) = chain
+            .last()
+            .and_then(|last| element_href(last))
+            .and_then(|id| self.element_by_id(&id))
+        {
+            if !kinds.contains(&next.ele_type) || chain.iter().any(|ele| Rc::ptr_eq(ele, &next)) {
+                break;
+            }
+            chain.push(next);
+        }
+        chain
+    }
+
+    /// resolve a `linearGradient` or `radialGradient`, `None` for another element
+    ///
+    /// attributes which are missing are inherited from the gradients referenced
+    /// by `href`, the geometry only from a gradient of the same kind. the stops
+    /// are those of the first gradient of the chain which has any. lengths in
+    /// user space are resolved against `context`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse_document, GradientKind, LengthContext, SpreadMethod};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><linearGradient id="a" x2="50%" spreadMethod="repeat"><stop stop-color="red"/><stop offset="1" stop-color="blue"/></linearGradient><linearGradient id="b" href="#a" y2="1"/></svg>"##).unwrap();
+    /// let gradient = document.gradient(&document.element_by_id("b").unwrap(), &LengthContext::default()).unwrap();
+    /// assert_eq!(gradient.kind, GradientKind::Linear { x1: 0.0, y1: 0.0, x2: 0.5, y2: 1.0 });
+    /// assert_eq!(gradient.spread, SpreadMethod::Repeat);
+    /// assert_eq!(gradient.stops.len(), 2);
+    /// ```
+    ///
+    pub fn gradient(&self, ele: &Rc<Element<'a>>, context: &LengthContext) -> Option<Gradient> {
+        let styles = ComputedStyles::new(self.stylesheet());
+        self.gradient_with(&styles, ele, context)
+    }
+
+    /// resolve a gradient like `Document::gradient`, with the computed styles of the tree
+    pub(crate) fn gradient_with(
+        &self,
+        styles: &ComputedStyles<'a>,
+        ele: &Rc<Element<'a>>,
+        context: &LengthContext,
+    ) -> Option<Gradient> {
+        if !GRADIENTS.contains(&ele.ele_type) {
+            return None;
+        }
+        let chain = self.href_chain(ele, &GRADIENTS);
+        let inherited = |key: &str| chain.iter().find_map(|ele| ele.get_attribute(key));
+        let geometry = |key: &str| {
+            chain
+                .iter()
+                .filter(|other| other.ele_type == ele.ele_type)
+                .find_map(|ele| ele.get_attribute(key))
+        };
+        let units = inherited("gradientUnits")
+            .and_then(|value| Units::parse(&value))
+            .unwrap_or(Units::ObjectBoundingBox);
+        let length = |key: &str, default: &str| {
+            let length = geometry(key)
+                .and_then(|value| Length::parse(&value).ok())
+                .unwrap_or_else(|| Length::parse(default).unwrap());
+            resolve_length(length, units, key, context)
+        };
+        let kind = if ele.ele_type == "linearGradient" {
+            GradientKind::Linear {
+                x1: length("x1", "0%"),
+                y1: length("y1", "0%"),
+                x2: length("x2", "100%"),
+                y2: length("y2", "0%"),
+            }
+        } else {
+            let (cx, cy) = (length("cx", "50%"), length("cy", "50%"));
+            let fx = geometry("fx").map_or(cx, |_| length("fx", "50%"));
+            let fy = geometry("fy").map_or(cy, |_| length("fy", "50%"));
+            GradientKind::Radial {
+                cx,
+                cy,
+                r: length("r", "50%"),
+                fx,
+                fy,
+                fr: length("fr", "0%"),
+            }
+        };
+        Some(Gradient {
+            kind,
+            units,
+            transform: inherited("gradientTransform")
+                .and_then(|value| Transform::parse(&value).ok())
+                .map_or(Matrix::default(), |transform| transform.to_matrix()),
+            spread: inherited("spreadMethod")
+                .and_then(|value| SpreadMethod::parse(&value))
+                .unwrap_or_default(),
+            stops: chain
+                .iter()
+                .map(|ele| gradient_stops(styles, ele))
+                .find(|stops| !stops.is_empty())
+                .unwrap_or_default(),
+        })
+    }
+
+    /// resolve a `pattern`, `None` for another element or a pattern without content
+    ///
+    /// attributes which are missing are inherited from the patterns referenced by
+    /// `href`, and the content is the first pattern of the chain with children.
+    /// lengths in user space are resolved against `context`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse_document, LengthContext, Units};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><pattern id="a" width="10" height="10" patternUnits="userSpaceOnUse"><circle r="5"/></pattern><pattern id="b" href="#a" x="5"/></svg>"##).unwrap();
+    /// let pattern = document.pattern(&document.element_by_id("b").unwrap(), &LengthContext::default()).unwrap();
+    /// assert_eq!(pattern.units, Units::UserSpaceOnUse);
+    /// assert_eq!((pattern.x, pattern.width), (5.0, 10.0));
+    /// assert_eq!(pattern.content.get_attribute("id").as_deref(), Some("a"));
+    /// ```
+    ///
+    pub fn pattern(&self, ele: &Rc<Element<'a>>, context: &LengthContext) -> Option<Pattern<'a>> {
+        if ele.ele_type != "pattern" {
+            return None;
+        }
+        let chain = self.href_chain(ele, &["pattern"]);
+        let inherited = |key: &str| chain.iter().find_map(|ele| ele.get_attribute(key));
+        let units = inherited("patternUnits")
+            .and_then(|value| Units::parse(&value))
+            .unwrap_or(Units::ObjectBoundingBox);
+        let length = |key: &str| {
+            inherited(key)
+                .and_then(|value| Length::parse(&value).ok())
+                .map_or(0.0, |length| resolve_length(length, units, key, context))
+        };
+        let content = chain
+            .iter()
+            .find(|ele| !ele.children.borrow().is_empty())?
+            .clone();
+        Some(Pattern {
+            units,
+            content_units: inherited("patternContentUnits")
+                .and_then(|value| Units::parse(&value))
+                .unwrap_or(Units::UserSpaceOnUse),
+            transform: inherited("patternTransform")
+                .and_then(|value| Transform::parse(&value).ok())
+                .map_or(Matrix::default(), |transform| transform.to_matrix()),
+            x: length("x"),
+            y: length("y"),
+            width: length("width"),
+            height: length("height"),
+            view_box: inherited("viewBox").and_then(|value| ViewBox::parse(&value).ok()),
+            preserve_aspect_ratio: inherited("preserveAspectRatio")
+                .and_then(|value| PreserveAspectRatio::parse(&value).ok())
+                .unwrap_or_default(),
+            content,
+            opacity: 1.0,
+        })
+    }
+
+    /// resolve the paint of a element for `key`, `fill` or `stroke`, like `Element::fill_color`
+    /// from its computed style, with the `<style>` rules of the document
+    ///
+    /// a reference to a gradient or a pattern of the document resolves to the
+    /// paint server, see `Document::gradient` and `Document::pattern`. the paint
+    /// opacity is applied to the stops of a gradient, a gradient with a single
+    /// stop resolves to its color and a gradient without stops or a empty pattern
+    /// to no paint. a reference to anything else uses the fallback color.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse_document, LengthContext, Paint, Rgba};
+    ///
+    /// let (_, document) = parse_document(r##"<svg><linearGradient id="g"><stop stop-color="red"/></linearGradient><rect id="r" fill="url(#g)" stroke="blue"/></svg>"##).unwrap();
+    /// let rect = document.element_by_id("r").unwrap();
+    /// let context = LengthContext::default();
+    /// assert_eq!(document.resolve_paint(&rect, "fill", &context), Some(Paint::Color(Rgba::new(255, 0, 0, 1.0))));
+    /// assert_eq!(document.resolve_paint(&rect, "stroke", &context), Some(Paint::Color(Rgba::new(0, 0, 255, 1.0))));
+    /// ```
+    ///
+    pub fn resolve_paint(
+        &self,
+        ele: &Rc<Element<'a>>,
+        key: &str,
+        context: &LengthContext,
+    ) -> Option<Paint<'a>> {
+        let styles = ComputedStyles::new(self.stylesheet());
+        self.resolve_paint_with(&styles, ele, key, context)
+    }
+
+    /// resolve the paint of a element like `Document::resolve_paint`, with the
+    /// computed styles of the tree
+    pub(crate) fn resolve_paint_with(
+        &self,
+        styles: &ComputedStyles<'a>,
+        ele: &Rc<Element<'a>>,
+        key: &str,
+        context: &LengthContext,
+    ) -> Option<Paint<'a>> {
+        let default = if key == "fill" { "black" } else { "none" };
+        let style = styles.style(ele);
+        let server = style
+            .get(key)
+            .and_then(|value| url_ids(value).first().copied())
+            .and_then(|id| self.element_by_id(id))
+            .filter(|server| GRADIENTS.contains(&server.ele_type) || server.ele_type == "pattern");
+        let Some(server) = server else {
+            return paint_color(&style, key, default).map(Paint::Color);
+        };
+        let opacity = style
+            .get(&format!("{}-opacity", key))
+            .and_then(opacity)
+            .unwrap_or(1.0);
+        if let Some(mut pattern) = self.pattern(&server, context) {
+            pattern.opacity = opacity;
+            return Some(Paint::Pattern(pattern));
+        }
+        let mut gradient = self.gradient_with(styles, &server, context)?;
+        for stop in &mut gradient.stops {
+            stop.color.alpha *= opacity;
+        }
+        match gradient.stops.as_slice() {
+            [] => None,
+            [stop] => Some(Paint::Color(stop.color)),
+            _ => Some(Paint::Gradient(gradient)),
+        }
+    }
+}
+
+/// a length of a paint server in user space, or a fraction of the bounding box
+/// whatever its unit
+pub(crate) fn resolve_length(
+    length: Length,
+    units: Units,
+    key: &str,
+    context: &LengthContext,
+) -> f64 {
+    match units {
+        Units::ObjectBoundingBox if length.unit == LengthUnit::Percent => length.number / 100.0,
+        Units::ObjectBoundingBox => length.number,
+        Units::UserSpaceOnUse => length.to_user_units(context, LengthDirection::of_attribute(key)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        parse_document, BBox, Gradient, GradientKind, GradientStop, LengthContext, Matrix, Paint,
+        Rgba, SpreadMethod, Units,
+    };
+
+    #[test]
+    fn test_resolve_gradient() {
+        let (_, document) = parse_document(
+            r##"<svg><linearGradient id="g" x2="50%" gradientTransform="rotate(90)" spreadMethod="reflect"><stop offset="0" stop-color="red"/><stop offset="-1" style="stop-color:blue;stop-opacity:.5"/></linearGradient><radialGradient id="single"><stop stop-color="lime"/></radialGradient><rect id="a" fill="url(#g)" fill-opacity="0.5"/><rect id="b" fill="url(#single) red"/><rect id="c" fill="url(#missing) red"/></svg>"##,
+        )
+        .unwrap();
+        let context = LengthContext::default();
+        let paint = |id| {
+            let ele = document.element_by_id(id).unwrap();
+            document.resolve_paint(&ele, "fill", &context)
+        };
+        assert_eq!(
+            paint("a"),
+            Some(Paint::Gradient(Gradient {
+                kind: GradientKind::Linear {
+                    x1: 0.0,
+                    y1: 0.0,
+                    x2: 0.5,
+                    y2: 0.0
+                },
+                units: Units::ObjectBoundingBox,
+                transform: Matrix::rotate(90.0),
+                spread: SpreadMethod::Reflect,
+                stops: vec![
+                    GradientStop {
+                        offset: 0.0,
+                        color: Rgba::new(255, 0, 0, 0.5)
+                    },
+                    GradientStop {
+                        offset: 0.0,
+                        color: Rgba::new(0, 0, 255, 0.25)
+                    },
+                ],
+            }))
+        );
+        assert_eq!(paint("b"), Some(Paint::Color(Rgba::new(0, 255, 0, 1.0))));
+        assert_eq!(paint("c"), Some(Paint::Color(Rgba::new(255, 0, 0, 1.0))));
+    }
+
+    #[test]
+    fn test_styled_paint() {
+        // the paint from a stylesheet, and a stop color from the inherited color
+        let (_, document) = parse_document(
+            r##"<svg><style>.a{fill:url(#g)}</style><linearGradient id="g" style="color:red"><stop stop-color="currentColor"/><stop offset="1" stop-color="blue"/></linearGradient><rect id="r" class="a"/></svg>"##,
+        )
+        .unwrap();
+        let rect = document.element_by_id("r").unwrap();
+        let context = LengthContext::default();
+        let Some(Paint::Gradient(gradient)) = document.resolve_paint(&rect, "fill", &context)
+        else {
+            panic!("expected a gradient");
+        };
+        assert_eq!(
+            gradient.stops,
+            vec![
+                GradientStop {
+                    offset: 0.0,
+                    color: Rgba::new(255, 0, 0, 1.0)
+                },
+                GradientStop {
+                    offset: 1.0,
+                    color: Rgba::new(0, 0, 255, 1.0)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gradient_href() {
+        let (_, document) = parse_document(
+            r##"<svg><linearGradient id="a" href="#c" x1="1" gradientUnits="userSpaceOnUse"><stop stop-color="red"/><stop offset="1" stop-color="blue"/></linearGradient><radialGradient id="b" href="#a" fx="1" fr="2"/><linearGradient id="c" href="#b" y2="3" gradientTransform="scale(2)"/><pattern id="p" href="#a"/><rect id="r" fill="url(#p) lime"/></svg>"##,
+        )
+        .unwrap();
+        let context = LengthContext::default();
+        let gradient = |id| document.gradient(&document.element_by_id(id).unwrap(), &context);
+        // the cycle back to a ends the chain, the radial gradient gives no geometry
+        let a = gradient("a").unwrap();
+        assert_eq!(
+            a.kind,
+            GradientKind::Linear {
+                x1: 1.0,
+                y1: 0.0,
+                x2: 100.0,
+                y2: 3.0
+            }
+        );
+        assert_eq!(a.transform, Matrix::scale(2.0, 2.0));
+        let b = gradient("b").unwrap();
+        assert_eq!(
+            b.kind,
+            GradientKind::Radial {
+                cx: 50.0,
+                cy: 50.0,
+                r: 50.0,
+                fx: 1.0,
+                fy: 50.0,
+                fr: 2.0
+            }
+        );
+        assert_eq!(b.units, Units::UserSpaceOnUse);
+        assert_eq!(b.stops, a.stops);
+        assert_eq!(gradient("p"), None);
+        // a pattern does not inherit from a gradient and has no content
+        let rect = document.element_by_id("r").unwrap();
+        assert_eq!(document.resolve_paint(&rect, "fill", &context), None);
+    }
+
+    #[test]
+    fn test_pattern() {
+        let (_, document) = parse_document(
+            r##"<svg><pattern id="a" x="0.5" width="0.5" height="0.25" patternContentUnits="objectBoundingBox" patternTransform="rotate(45)"><rect width="1" height="1"/></pattern><pattern id="b" href="#a" viewBox="0 0 1 2" preserveAspectRatio="xMinYMin"/><rect id="r" fill="url(#b)" fill-opacity="0.5"/></svg>"##,
+        )
+        .unwrap();
+        let context = LengthContext::default();
+        let pattern = document
+            .pattern(&document.element_by_id("a").unwrap(), &context)
+            .unwrap();
+        let bbox = BBox::new(0.0, 0.0, 20.0, 40.0);
+        assert_eq!(pattern.transform, Matrix::rotate(45.0));
+        assert_eq!(pattern.tile(&bbox), Some(BBox::new(10.0, 0.0, 20.0, 10.0)));
+        assert_eq!(
+            pattern.content_transform(&bbox),
+            Some(Matrix::translate(10.0, 0.0) * Matrix::scale(20.0, 40.0))
+        );
+        assert_eq!(pattern.tile(&BBox::new(0.0, 0.0, 0.0, 10.0)), None);
+
+        let rect = document.element_by_id("r").unwrap();
+        let Some(Paint::Pattern(pattern)) = document.resolve_paint(&rect, "fill", &context) else {
+            panic!("expected a pattern");
+        };
+        assert_eq!(pattern.opacity, 0.5);
+        assert_eq!(pattern.content.get_attribute("id").as_deref(), Some("a"));
+        // the view box is fit into the tile
+        assert_eq!(
+            pattern.content_transform(&bbox),
+            Some(Matrix::translate(10.0, 0.0) * Matrix::scale(5.0, 5.0))
+        );
+    }
+
+    #[test]
+    fn test_gradient_color() {
+        let mut gradient = Gradient {
+            kind: GradientKind::Radial {
+                cx: 0.0,
+                cy: 0.0,
+                r: 10.0,
+                fx: 5.0,
+                fy: 0.0,
+                fr: 0.0,
+            },
+            units: Units::UserSpaceOnUse,
+            transform: Matrix::default(),
+            spread: SpreadMethod::Pad,
+            stops: vec![
+                GradientStop {
+                    offset: 0.0,
+                    color: Rgba::new(0, 0, 0, 1.0),
+                },
+                GradientStop {
+                    offset: 1.0,
+                    color: Rgba::new(255, 255, 255, 0.0),
+                },
+            ],
+        };
+        assert_eq!(gradient.offset_at(5.0, 0.0), 0.0);
+        assert!((gradient.offset_at(10.0, 0.0) - 1.0).abs() < 1e-9);
+        assert!((gradient.offset_at(-10.0, 0.0) - 1.0).abs() < 1e-9);
+        assert!((gradient.offset_at(0.0, 0.0) - 1.0 / 3.0).abs() < 1e-9);
+        // the color of a transparent stop does not bleed
+        assert_eq!(gradient.color_at(0.5), Some(Rgba::new(0, 0, 0, 0.5)));
+        assert_eq!(gradient.color_at(2.0), Some(Rgba::new(255, 255, 255, 0.0)));
+        gradient.spread = SpreadMethod::Reflect;
+        assert_eq!(gradient.color_at(2.0), Some(Rgba::new(0, 0, 0, 1.0)));
+        gradient.spread = SpreadMethod::Repeat;
+        assert_eq!(gradient.color_at(1.25), gradient.color_at(0.25));
+
+        // a focal circle is at offset 0 on its edge
+        gradient.kind = GradientKind::Radial {
+            cx: 0.0,
+            cy: 0.0,
+            r: 10.0,
+            fx: 0.0,
+            fy: 0.0,
+            fr: 2.0,
+        };
+        assert!(gradient.offset_at(1.0, 0.0) < 0.0);
+        assert!(gradient.offset_at(0.0, 2.0).abs() < 1e-9);
+        assert!((gradient.offset_at(6.0, 0.0) - 0.5).abs() < 1e-9);
+    }
+}
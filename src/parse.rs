@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
 
@@ -5,7 +7,7 @@ use nom::{
     branch::alt,
     bytes::complete::{is_not, tag, take_till, take_until, take_while},
     character::complete::{alphanumeric1, one_of, space1},
-    combinator::{cut, map, opt},
+    combinator::{cut, map, opt, recognize},
     error::{context, ContextError, ParseError},
     multi::{many0, separated_list0},
     sequence::{delimited, pair, preceded, separated_pair, terminated, tuple},
@@ -13,6 +15,10 @@ use nom::{
 };
 
 use crate::ast::Element;
+use crate::document::Document;
+
+/// the elements whose whitespace-only character data is kept
+const TEXT_ELEMENTS: [&str; 3] = ["text", "tspan", "textPath"];
 
 /// remove whitespace ` \t\r\n`
 #[inline(always)]
@@ -124,7 +130,7 @@ where
 /// }
 /// ```
 #[inline(always)]
-pub fn single_element<'a, E>(input: &'a str) -> IResult<&'a str, Rc<Element>, E>
+pub fn single_element<'a, E>(input: &'a str) -> IResult<&'a str, Rc<Element<'a>>, E>
 where
     E: ParseError<&'a str> + ContextError<&'a str>,
 {
@@ -167,23 +173,120 @@ where
 /// }
 /// ```
 #[inline(always)]
-pub fn double_element<'a, E>(input: &'a str) -> IResult<&'a str, Rc<Element>, E>
+pub fn double_element<'a, E>(input: &'a str) -> IResult<&'a str, Rc<Element<'a>>, E>
 where
     E: ParseError<&'a str> + ContextError<&'a str>,
 {
     let attributes_pattern = terminated(attribute_hash, tag(">"));
-    let children_pattern = terminated(element_list, terminated(take_until(">"), tag(">")));
+    let content_pattern = terminated(element_content, terminated(take_until(">"), tag(">")));
     context(
         "double_element",
         map(
-            tuple((element_start, attributes_pattern, children_pattern)),
-            Element::new_width_children,
+            tuple((element_start, attributes_pattern, content_pattern)),
+            |(ele_type, attributes, (children, text))| {
+                let ele = Element::new_width_children((ele_type, attributes, children));
+                *ele.text.borrow_mut() = text;
+                if !TEXT_ELEMENTS.contains(&ele_type) {
+                    // the whitespace between elements only matters in text
+                    let blank = |text: &RefCell<Cow<str>>| text.borrow().trim().is_empty();
+                    if blank(&ele.text) {
+                        *ele.text.borrow_mut() = Cow::Borrowed("");
+                    }
+                    for child in ele.children.borrow().iter() {
+                        if blank(&child.tail) {
+                            *child.tail.borrow_mut() = Cow::Borrowed("");
+                        }
+                    }
+                }
+                ele
+            },
         ),
     )(input)
 }
 
+type ElementContent<'a> = (Vec<Rc<Element<'a>>>, Cow<'a, str>);
+
+/// a part of the content of a element
+enum Content<'a> {
+    Element(Rc<Element<'a>>),
+    Text(&'a str),
+    Comment,
+}
+
+/// parse a run of character data or a CDATA section, which is kept with its markup
+///
+/// ## Example
+/// ``` ignore
+/// .a{fill:red}</style> -> ".a{fill:red}"
+/// <![CDATA[a<b]]> -> "<![CDATA[a<b]]>"
+/// ```
+fn character_data<'a, E>(input: &'a str) -> IResult<&'a str, &'a str, E>
+where
+    E: ParseError<&'a str>,
+{
+    alt((
+        recognize(delimited(tag("<![CDATA["), take_until("]]>"), tag("]]>"))),
+        is_not("<"),
+    ))(input)
+}
+
+/// parse a comment
+fn comment<'a, E>(input: &'a str) -> IResult<&'a str, &'a str, E>
+where
+    E: ParseError<&'a str>,
+{
+    delimited(tag("<!--"), take_until("-->"), tag("-->"))(input)
+}
+
+/// join runs of character data
+fn join_runs<'a>(runs: &mut Vec<&'a str>) -> Cow<'a, str> {
+    let text = match runs.len() {
+        0 => Cow::Borrowed(""),
+        1 => Cow::Borrowed(runs[0]),
+        _ => Cow::Owned(runs.concat()),
+    };
+    runs.clear();
+    text
+}
+
+/// parse the content of a element, its children and its text. the character
+/// data after a child is its `tail`, comments are dropped.
+fn element_content<'a, E>(input: &'a str) -> IResult<&'a str, ElementContent<'a>, E>
+where
+    E: ParseError<&'a str> + ContextError<&'a str>,
+{
+    let content = alt((
+        map(alt((double_element, single_element)), Content::Element),
+        map(comment, |_| Content::Comment),
+        map(character_data, Content::Text),
+    ));
+    map(many0(content), |contents| {
+        let mut children: Vec<Rc<Element<'a>>> = vec![];
+        let mut text = Cow::Borrowed("");
+        let mut runs = vec![];
+        for content in contents {
+            match content {
+                Content::Element(ele) => {
+                    match children.last() {
+                        Some(last) => *last.tail.borrow_mut() = join_runs(&mut runs),
+                        None => text = join_runs(&mut runs),
+                    }
+                    children.push(ele);
+                }
+                Content::Text(run) => runs.push(run),
+                Content::Comment => {}
+            }
+        }
+        match children.last() {
+            Some(last) => *last.tail.borrow_mut() = join_runs(&mut runs),
+            None => text = join_runs(&mut runs),
+        }
+        (children, text)
+    })(input)
+}
+
 /// parse a double element or a single element
-fn element<'a, E>(input: &'a str) -> IResult<&'a str, Rc<Element>, E>
+fn element<'a, E>(input: &'a str) -> IResult<&'a str, Rc<Element<'a>>, E>
 where
     E: ParseError<&'a str> + ContextError<&'a str>,
 {
@@ -193,13 +296,6 @@ where
     )(input)
 }
 
-/// parse a list of the element
-fn element_list<'a, E>(input: &'a str) -> IResult<&'a str, Vec<Rc<Element>>, E>
-where
-    E: ParseError<&'a str> + ContextError<&'a str>,
-{
-    context("element_list", many0(element))(input)
-}
 /// transform svg to a Element(AST struct)
 ///
 /// return a result.
@@ -220,34 +316,57 @@ where
 /// let (_, root) = parse(svg).unwrap();
 /// assert_eq!(root.ele_type, "svg");
 /// assert_eq!(*root.attributes.borrow(), HashMap::from([
-///     ("xmlns".to_owned(), "http://www.w3.org/2000/svg"),
-///     ("version".to_owned(), "1.1"),
+///     ("xmlns".to_owned(), "http://www.w3.org/2000/svg".into()),
+///     ("version".to_owned(), "1.1".into()),
 /// ]));
 /// let child = &*root.children.borrow()[0];
 /// assert_eq!(child.ele_type, "circle");
 /// assert_eq!(*child.attributes.borrow(), HashMap::from([
-///     ("cx".to_owned(), "100"),
-///     ("cy".to_owned(), "50"),
-///     ("r".to_owned(), "40"),
+///     ("cx".to_owned(), "100".into()),
+///     ("cy".to_owned(), "50".into()),
+///     ("r".to_owned(), "40".into()),
 /// ]));
 /// ```
 ///
-pub fn parse<'a>(input: &'a str) -> IResult<&'a str, Rc<Element>> {
+pub fn parse<'a>(input: &'a str) -> IResult<&'a str, Rc<Element<'a>>> {
     element(input)
 }
 
+/// transform svg to a Document which indexes the ids of the elements
+///
+/// return a result like `parse`.
+///
+/// ## Example
+/// ```rust
+/// use svg_simple_parser::parse_document;
+///
+/// let svg = r#"
+///     <svg xmlns="http://www.w3.org/2000/svg" version="1.1">
+///         <circle id="dot" cx="100" cy="50" r="40" />
+///     </svg>
+/// "#;
+/// let (_, document) = parse_document(svg).unwrap();
+/// assert_eq!(document.root.ele_type, "svg");
+/// assert_eq!(document.element_by_id("dot").unwrap().ele_type, "circle");
+/// ```
+///
+pub fn parse_document<'a>(input: &'a str) -> IResult<&'a str, Document<'a>> {
+    map(element, Document::new)(input)
+}
+
 #[cfg(test)]
 mod tests {
     use nom::error::ErrorKind;
     use std::collections::HashMap;
 
     use crate::parse::{
-        attribute, attribute_hash, attribute_value, double_element, element_list, single_element,
+        attribute, attribute_hash, attribute_value, double_element, element_content, single_element,
     };
+    use crate::{parse, stringify};
 
     #[test]
     fn test_elements() {
-        let (_, v) = element_list::<(&str, ErrorKind)>(
+        let (_, (v, _)) = element_content::<(&str, ErrorKind)>(
             r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1"/>"#,
         )
         .unwrap();
@@ -256,8 +375,8 @@ mod tests {
         assert_eq!(
             *one.attributes.borrow(),
             HashMap::from([
-                ("xmlns".to_owned(), "http://www.w3.org/2000/svg"),
-                ("version".to_owned(), "1.1"),
+                ("xmlns".to_owned(), "http://www.w3.org/2000/svg".into()),
+                ("version".to_owned(), "1.1".into()),
             ])
         );
     }
@@ -272,12 +391,52 @@ mod tests {
         assert_eq!(
             *root.attributes.borrow(),
             HashMap::from([
-                ("xmlns".to_owned(), "http://www.w3.org/2000/svg"),
-                ("version".to_owned(), "1.1"),
+                ("xmlns".to_owned(), "http://www.w3.org/2000/svg".into()),
+                ("version".to_owned(), "1.1".into()),
             ])
         );
     }
 
+    #[test]
+    fn test_element_text() {
+        let (_, root) = double_element::<(&str, ErrorKind)>(
+            "<text>a <!-- b --><tspan>c</tspan> d<![CDATA[<e>]]>\n</text>",
+        )
+        .unwrap();
+        assert_eq!(*root.text.borrow(), "a ");
+        assert_eq!(*root.children.borrow()[0].text.borrow(), "c");
+        assert_eq!(
+            *root.children.borrow()[0].tail.borrow(),
+            " d<![CDATA[<e>]]>\n"
+        );
+        let (_, root) = double_element::<(&str, ErrorKind)>("<g>\n  <rect/>\n</g>").unwrap();
+        assert_eq!(*root.text.borrow(), "");
+        assert_eq!(*root.children.borrow()[0].tail.borrow(), "");
+        // whitespace between elements is kept in text
+        let (_, root) =
+            double_element::<(&str, ErrorKind)>("<text><tspan>a</tspan> <tspan>b</tspan></text>")
+                .unwrap();
+        assert_eq!(*root.children.borrow()[0].tail.borrow(), " ");
+    }
+
+    #[test]
+    fn test_mixed_content_roundtrip() {
+        for source in [
+            "<text>a <tspan>c</tspan> d</text>",
+            "<text x=\"1\"><tspan>a</tspan> <tspan>b<tspan>c</tspan>d</tspan>e</text>",
+            "<svg><style>.a{fill:red}</style><text>a<a>b</a><tspan/>c</text></svg>",
+        ] {
+            let (_, root) = parse(source).unwrap();
+            assert_eq!(stringify(root.clone()), source);
+            let written = stringify(root.clone());
+            let (_, again) = parse(&written).unwrap();
+            assert_eq!(again, root);
+        }
+        let (_, a) = parse("<text>a <tspan>c</tspan> d</text>").unwrap();
+        let (_, b) = parse("<text>a  d<tspan>c</tspan></text>").unwrap();
+        assert_ne!(a, b);
+    }
+
     #[test]
     fn test_single_element() {
         let (_, root) = single_element::<(&str, ErrorKind)>(
@@ -288,8 +447,8 @@ mod tests {
         assert_eq!(
             *root.attributes.borrow(),
             HashMap::from([
-                ("xmlns".to_owned(), "http://www.w3.org/2000/svg"),
-                ("version".to_owned(), "1.1"),
+                ("xmlns".to_owned(), "http://www.w3.org/2000/svg".into()),
+                ("version".to_owned(), "1.1".into()),
             ])
         );
     }
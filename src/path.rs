@@ -0,0 +1,670 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use nom::{
+    character::complete::one_of,
+    combinator::map_res,
+    number::complete::recognize_float,
+    sequence::{pair, terminated},
+    IResult,
+};
+
+use crate::ast::Element;
+
+/// A segment of path data
+///
+/// `abs` whether the coordinates are absolute (upper case command) or
+/// relative to the current point (lower case command)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSegment {
+    MoveTo {
+        abs: bool,
+        x: f64,
+        y: f64,
+    },
+    LineTo {
+        abs: bool,
+        x: f64,
+        y: f64,
+    },
+    HorizontalLineTo {
+        abs: bool,
+        x: f64,
+    },
+    VerticalLineTo {
+        abs: bool,
+        y: f64,
+    },
+    CurveTo {
+        abs: bool,
+        x1: f64,
+        y1: f64,
+        x2: f64,
+        y2: f64,
+        x: f64,
+        y: f64,
+    },
+    SmoothCurveTo {
+        abs: bool,
+        x2: f64,
+        y2: f64,
+        x: f64,
+        y: f64,
+    },
+    Quadratic {
+        abs: bool,
+        x1: f64,
+        y1: f64,
+        x: f64,
+        y: f64,
+    },
+    SmoothQuadratic {
+        abs: bool,
+        x: f64,
+        y: f64,
+    },
+    ArcTo {
+        abs: bool,
+        rx: f64,
+        ry: f64,
+        x_axis_rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        x: f64,
+        y: f64,
+    },
+    ClosePath {
+        abs: bool,
+    },
+}
+
+impl PathSegment {
+    /// the command letter of the segment
+    pub fn command(&self) -> char {
+        let (letter, abs) = match *self {
+            PathSegment::MoveTo { abs, .. } => ('m', abs),
+            PathSegment::LineTo { abs, .. } => ('l', abs),
+            PathSegment::HorizontalLineTo { abs, .. } => ('h', abs),
+            PathSegment::VerticalLineTo { abs, .. } => ('v', abs),
+            PathSegment::CurveTo { abs, .. } => ('c', abs),
+            PathSegment::SmoothCurveTo { abs, .. } => ('s', abs),
+            PathSegment::Quadratic { abs, .. } => ('q', abs),
+            PathSegment::SmoothQuadratic { abs, .. } => ('t', abs),
+            PathSegment::ArcTo { abs, .. } => ('a', abs),
+            PathSegment::ClosePath { abs } => ('z', abs),
+        };
+        if abs {
+            letter.to_ascii_uppercase()
+        } else {
+            letter
+        }
+    }
+
+    /// whether the coordinates of the segment are absolute
+    pub fn is_absolute(&self) -> bool {
+        self.command().is_ascii_uppercase()
+    }
+}
+
+/// The parsed `d` attribute of a `<path>`
+///
+/// `Display` writes the shortest valid path data.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathData {
+    pub segments: Vec<PathSegment>,
+}
+
+/// What went wrong when parsing path data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathErrorKind {
+    /// a character which is not a path command
+    ExpectedCommand,
+    /// path data which does not start with `M` or `m`
+    ExpectedMoveTo,
+    ExpectedNumber,
+    /// a arc flag which is not `0` or `1`
+    ExpectedFlag,
+}
+
+/// A error in path data
+///
+/// `position` the byte offset of the error in the path data
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathError {
+    pub position: usize,
+    pub kind: PathErrorKind,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let expected = match self.kind {
+            PathErrorKind::ExpectedCommand => "a path command",
+            PathErrorKind::ExpectedMoveTo => "a moveto command",
+            PathErrorKind::ExpectedNumber => "a number",
+            PathErrorKind::ExpectedFlag => "a flag",
+        };
+        write!(f, "expected {} at position {}", expected, self.position)
+    }
+}
+
+impl Error for PathError {}
+
+#[inline(always)]
+pub(crate) fn is_wsp(c: char) -> bool {
+    " \t\r\n\x0C".contains(c)
+}
+
+/// skip whitespace and at most one comma
+#[inline(always)]
+pub(crate) fn comma_wsp(input: &str) -> &str {
+    let input = input.trim_start_matches(is_wsp);
+    input
+        .strip_prefix(',')
+        .unwrap_or(input)
+        .trim_start_matches(is_wsp)
+}
+
+/// parse a number with the compact syntax of path data
+///
+/// ## Example
+/// ``` ignore
+/// 1.5.5 -> (".5", 1.5)
+/// -1-2 -> ("-2", -1.0)
+/// 1e-3 -> ("", 0.001)
+/// ```
+#[inline(always)]
+pub(crate) fn number(input: &str) -> IResult<&str, f64> {
+    map_res(recognize_float, str::parse)(input)
+}
+
+/// parse a arc flag, which needs no separator
+#[inline(always)]
+fn flag(input: &str) -> IResult<&str, bool> {
+    let (rest, c) = one_of("01")(input)?;
+    Ok((rest, c == '1'))
+}
+
+/// skip the optional separator between two arguments
+#[inline(always)]
+fn separator(input: &str) -> IResult<&str, ()> {
+    Ok((comma_wsp(input), ()))
+}
+
+/// parse a coordinate pair
+#[inline(always)]
+fn pair_args(input: &str) -> IResult<&str, (f64, f64)> {
+    pair(terminated(number, separator), number)(input)
+}
+
+struct PathParser<'i> {
+    input: &'i str,
+    rest: &'i str,
+}
+
+impl<'i> PathParser<'i> {
+    fn error(&self, rest: &str, kind: PathErrorKind) -> PathError {
+        PathError {
+            position: self.input.len() - rest.len(),
+            kind,
+        }
+    }
+
+    /// run a argument parser at the current position
+    fn run<T>(
+        &mut self,
+        parser: fn(&'i str) -> IResult<&'i str, T>,
+        kind: PathErrorKind,
+    ) -> Result<T, PathError> {
+        match parser(self.rest) {
+            Ok((rest, value)) => {
+                self.rest = rest;
+                Ok(value)
+            }
+            Err(nom::Err::Error(e)) | Err(nom::Err::Failure(e)) => Err(self.error(e.input, kind)),
+            Err(nom::Err::Incomplete(_)) => Err(self.error("", kind)),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, PathError> {
+        let value = self.run(number, PathErrorKind::ExpectedNumber)?;
+        self.rest = comma_wsp(self.rest);
+        Ok(value)
+    }
+
+    fn flag(&mut self) -> Result<bool, PathError> {
+        let value = self.run(flag, PathErrorKind::ExpectedFlag)?;
+        self.rest = comma_wsp(self.rest);
+        Ok(value)
+    }
+
+    fn coordinates(&mut self) -> Result<(f64, f64), PathError> {
+        let value = self.run(pair_args, PathErrorKind::ExpectedNumber)?;
+        self.rest = comma_wsp(self.rest);
+        Ok(value)
+    }
+
+    /// whether a argument set follows, for implicit repeated commands
+    fn has_arguments(&self) -> bool {
+        self.rest
+            .starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c))
+    }
+
+    fn segment(&mut self, command: char, first: bool) -> Result<PathSegment, PathError> {
+        let abs = command.is_ascii_uppercase();
+        let segment = match command.to_ascii_lowercase() {
+            'm' if first => {
+                let (x, y) = self.coordinates()?;
+                PathSegment::MoveTo { abs, x, y }
+            }
+            'm' | 'l' => {
+                let (x, y) = self.coordinates()?;
+                PathSegment::LineTo { abs, x, y }
+            }
+            'h' => PathSegment::HorizontalLineTo {
+                abs,
+                x: self.number()?,
+            },
+            'v' => PathSegment::VerticalLineTo {
+                abs,
+                y: self.number()?,
+            },
+            'c' => {
+                let (x1, y1) = self.coordinates()?;
+                let (x2, y2) = self.coordinates()?;
+                let (x, y) = self.coordinates()?;
+                PathSegment::CurveTo {
+                    abs,
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                }
+            }
+            's' => {
+                let (x2, y2) = self.coordinates()?;
+                let (x, y) = self.coordinates()?;
+                PathSegment::SmoothCurveTo { abs, x2, y2, x, y }
+            }
+            'q' => {
+                let (x1, y1) = self.coordinates()?;
+                let (x, y) = self.coordinates()?;
+                PathSegment::Quadratic { abs, x1, y1, x, y }
+            }
+            't' => {
+                let (x, y) = self.coordinates()?;
+                PathSegment::SmoothQuadratic { abs, x, y }
+            }
+            'a' => PathSegment::ArcTo {
+                abs,
+                rx: self.number()?,
+                ry: self.number()?,
+                x_axis_rotation: self.number()?,
+                large_arc: self.flag()?,
+                sweep: self.flag()?,
+                x: self.number()?,
+                y: self.number()?,
+            },
+            _ => PathSegment::ClosePath { abs },
+        };
+        Ok(segment)
+    }
+
+    fn parse(mut self) -> Result<PathData, PathError> {
+        let mut segments = vec![];
+        self.rest = self.rest.trim_start_matches(is_wsp);
+        while let Some(command) = self.rest.chars().next() {
+            if !"MmZzLlHhVvCcSsQqTtAa".contains(command) {
+                return Err(self.error(self.rest, PathErrorKind::ExpectedCommand));
+            }
+            if segments.is_empty() && command != 'M' && command != 'm' {
+                return Err(self.error(self.rest, PathErrorKind::ExpectedMoveTo));
+            }
+            self.rest = self.rest[1..].trim_start_matches(is_wsp);
+            segments.push(self.segment(command, true)?);
+            if command != 'Z' && command != 'z' {
+                while self.has_arguments() {
+                    segments.push(self.segment(command, false)?);
+                }
+            }
+        }
+        Ok(PathData { segments })
+    }
+}
+
+impl PathData {
+    /// parse path data
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{PathData, PathSegment};
+    ///
+    /// let path = PathData::parse("M10-20l1.5.5z").unwrap();
+    /// assert_eq!(path.segments, vec![
+    ///     PathSegment::MoveTo { abs: true, x: 10.0, y: -20.0 },
+    ///     PathSegment::LineTo { abs: false, x: 1.5, y: 0.5 },
+    ///     PathSegment::ClosePath { abs: false },
+    /// ]);
+    ///
+    /// let error = PathData::parse("M 10 20 L 30").unwrap_err();
+    /// assert_eq!(error.to_string(), "expected a number at position 12");
+    /// ```
+    ///
+    pub fn parse(d: &str) -> Result<PathData, PathError> {
+        PathParser { input: d, rest: d }.parse()
+    }
+}
+
+impl FromStr for PathData {
+    type Err = PathError;
+
+    fn from_str(d: &str) -> Result<Self, Self::Err> {
+        PathData::parse(d)
+    }
+}
+
+/// A token of serialized path data
+enum Token {
+    Command(char),
+    Number(String),
+    Flag(bool),
+}
+
+/// format a number as short as possible without changing its value
+///
+/// ## Example
+/// ``` ignore
+/// 0.5 -> ".5"
+/// -0.25 -> "-.25"
+/// 1000000 -> "1e6"
+/// ```
+pub(crate) fn shortest_number(n: f64) -> String {
+    let n = if n == 0.0 { 0.0 } else { n };
+    let decimal = format!("{}", n);
+    let exponent = format!("{:e}", n);
+    let s = if exponent.len() < decimal.len() {
+        exponent
+    } else {
+        decimal
+    };
+    if let Some(s) = s.strip_prefix("0.") {
+        format!(".{}", s)
+    } else if let Some(s) = s.strip_prefix("-0.") {
+        format!("-.{}", s)
+    } else {
+        s
+    }
+}
+
+impl fmt::Display for PathData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut tokens = vec![];
+        let mut previous: Option<char> = None;
+        for segment in self.segments.iter() {
+            let command = segment.command();
+            // a repeated command is implicit, so is a lineto after a moveto.
+            // a repeated moveto is not, it would be read as a lineto.
+            let implicit = match previous {
+                Some(p) if p == command => !matches!(command, 'M' | 'm' | 'Z' | 'z'),
+                Some('M') => command == 'L',
+                Some('m') => command == 'l',
+                _ => false,
+            };
+            if !implicit {
+                tokens.push(Token::Command(command));
+            }
+            previous = Some(command);
+            let numbers: Vec<f64> = match *segment {
+                PathSegment::MoveTo { x, y, .. }
+                | PathSegment::LineTo { x, y, .. }
+                | PathSegment::SmoothQuadratic { x, y, .. } => vec![x, y],
+                PathSegment::HorizontalLineTo { x, .. } => vec![x],
+                PathSegment::VerticalLineTo { y, .. } => vec![y],
+                PathSegment::CurveTo {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                    ..
+                } => vec![x1, y1, x2, y2, x, y],
+                PathSegment::SmoothCurveTo { x2, y2, x, y, .. } => vec![x2, y2, x, y],
+                PathSegment::Quadratic { x1, y1, x, y, .. } => vec![x1, y1, x, y],
+                PathSegment::ArcTo {
+                    rx,
+                    ry,
+                    x_axis_rotation,
+                    large_arc,
+                    sweep,
+                    x,
+                    y,
+                    ..
+                } => {
+                    for n in [rx, ry, x_axis_rotation] {
+                        tokens.push(Token::Number(shortest_number(n)));
+                    }
+                    tokens.push(Token::Flag(large_arc));
+                    tokens.push(Token::Flag(sweep));
+                    vec![x, y]
+                }
+                PathSegment::ClosePath { .. } => vec![],
+            };
+            for n in numbers {
+                tokens.push(Token::Number(shortest_number(n)));
+            }
+        }
+
+        let mut last: Option<&Token> = None;
+        for token in tokens.iter() {
+            match (last, token) {
+                (Some(Token::Number(a)), Token::Number(b)) => {
+                    let needs_separator = !(b.starts_with('-')
+                        || (b.starts_with('.') && (a.contains('.') || a.contains('e'))));
+                    if needs_separator {
+                        write!(f, " ")?;
+                    }
+                }
+                (Some(Token::Number(_)), Token::Flag(_)) => write!(f, " ")?,
+                _ => {}
+            }
+            match token {
+                Token::Command(c) => write!(f, "{}", c)?,
+                Token::Number(n) => write!(f, "{}", n)?,
+                Token::Flag(flag) => write!(f, "{}", if *flag { 1 } else { 0 })?,
+            }
+            last = Some(token);
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Element<'a> {
+    /// parse the `d` attribute of the element, a missing `d` is a empty path
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::parse;
+    ///
+    /// let (_, path) = parse(r#"<path d="M 0 0 L 10 0 L 10 10"/>"#).unwrap();
+    /// assert_eq!(path.path_data().unwrap().to_string(), "M0 0 10 0 10 10");
+    /// ```
+    ///
+    pub fn path_data(&self) -> Result<PathData, PathError> {
+        match self.attributes.borrow().get("d") {
+            Some(d) => PathData::parse(d),
+            None => Ok(PathData::default()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::path::{number, shortest_number, PathErrorKind};
+    use crate::{PathData, PathError, PathSegment};
+
+    #[test]
+    fn test_number() {
+        assert_eq!(number("1.5.5"), Ok((".5", 1.5)));
+        assert_eq!(number("-1-2"), Ok(("-2", -1.0)));
+        assert_eq!(number("1e-3,"), Ok((",", 0.001)));
+        assert_eq!(number(".5"), Ok(("", 0.5)));
+        assert!(number("x").is_err());
+    }
+
+    #[test]
+    fn test_parse_all_commands() {
+        let path = PathData::parse(
+            "M1,2 m3 4 L5 6 l7 8 H9 h10 V11 v12 C1 2 3 4 5 6 c1 2 3 4 5 6 S1 2 3 4 s1 2 3 4 Q1 2 3 4 q1 2 3 4 T1 2 t1 2 A1 2 3 0 1 4 5 a1 2 3 1 0 4 5 Z z",
+        )
+        .unwrap();
+        let commands: String = path.segments.iter().map(|s| s.command()).collect();
+        assert_eq!(commands, "MmLlHhVvCcSsQqTtAaZz");
+        assert_eq!(
+            path.segments[16],
+            PathSegment::ArcTo {
+                abs: true,
+                rx: 1.0,
+                ry: 2.0,
+                x_axis_rotation: 3.0,
+                large_arc: false,
+                sweep: true,
+                x: 4.0,
+                y: 5.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_implicit_and_compact() {
+        let path = PathData::parse("m1 1 2 2-3-3zM0,0L1e1.5.5.5").unwrap();
+        assert_eq!(
+            path.segments,
+            vec![
+                PathSegment::MoveTo {
+                    abs: false,
+                    x: 1.0,
+                    y: 1.0
+                },
+                PathSegment::LineTo {
+                    abs: false,
+                    x: 2.0,
+                    y: 2.0
+                },
+                PathSegment::LineTo {
+                    abs: false,
+                    x: -3.0,
+                    y: -3.0
+                },
+                PathSegment::ClosePath { abs: false },
+                PathSegment::MoveTo {
+                    abs: true,
+                    x: 0.0,
+                    y: 0.0
+                },
+                PathSegment::LineTo {
+                    abs: true,
+                    x: 10.0,
+                    y: 0.5
+                },
+                PathSegment::LineTo {
+                    abs: true,
+                    x: 0.5,
+                    y: 0.5
+                },
+            ]
+        );
+        // arc flags without separators
+        let arc = PathData::parse("M0 0a25 25-30 0150-25").unwrap();
+        assert_eq!(
+            arc.segments[1],
+            PathSegment::ArcTo {
+                abs: false,
+                rx: 25.0,
+                ry: 25.0,
+                x_axis_rotation: -30.0,
+                large_arc: false,
+                sweep: true,
+                x: 50.0,
+                y: -25.0,
+            }
+        );
+        assert_eq!(PathData::parse("  ").unwrap(), PathData::default());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |d: &str| PathData::parse(d).unwrap_err();
+        assert_eq!(
+            error("L 10 10"),
+            PathError {
+                position: 0,
+                kind: PathErrorKind::ExpectedMoveTo
+            }
+        );
+        assert_eq!(
+            error("M 10 10 X"),
+            PathError {
+                position: 8,
+                kind: PathErrorKind::ExpectedCommand
+            }
+        );
+        assert_eq!(
+            error("M 10,"),
+            PathError {
+                position: 5,
+                kind: PathErrorKind::ExpectedNumber
+            }
+        );
+        assert_eq!(
+            error("M0 0 A1 1 0 2 0 1 1"),
+            PathError {
+                position: 12,
+                kind: PathErrorKind::ExpectedFlag
+            }
+        );
+        assert_eq!(error("M0 0 z 1").kind, PathErrorKind::ExpectedCommand);
+    }
+
+    #[test]
+    fn test_shortest_number() {
+        assert_eq!(shortest_number(0.5), ".5");
+        assert_eq!(shortest_number(-0.25), "-.25");
+        assert_eq!(shortest_number(10.0), "10");
+        assert_eq!(shortest_number(-0.0), "0");
+        assert_eq!(shortest_number(1000000.0), "1e6");
+        assert_eq!(shortest_number(0.0001), "1e-4");
+    }
+
+    #[test]
+    fn test_display() {
+        let roundtrip = |d: &str| PathData::parse(d).unwrap().to_string();
+        assert_eq!(
+            roundtrip("M 10 20 L 30 40 L 50 60 Z"),
+            "M10 20 30 40 50 60Z"
+        );
+        assert_eq!(
+            roundtrip("m 0.5 0.5 l -0.5 0.5 l 0.5 0.5"),
+            "m.5.5-.5.5.5.5"
+        );
+        assert_eq!(roundtrip("M0 0 H 10 H 20 V 5"), "M0 0H10 20V5");
+        assert_eq!(
+            roundtrip("M0 0 A 25 25 -30 0 1 50 -25 a 1 1 0 1 1 .5 .5"),
+            "M0 0A25 25-30 0150-25a1 1 0 11.5.5"
+        );
+        assert_eq!(roundtrip("M0 0 z m 1 1 z z"), "M0 0zm1 1zz");
+        assert_eq!(roundtrip("M0 0M10 10L5 5"), "M0 0M10 10 5 5");
+        assert_eq!(roundtrip("m0 0m10 10m1 1l5 5"), "m0 0m10 10m1 1 5 5");
+        for d in [
+            "M0 0M10 10L5 5M1 1M2 2",
+            "m0 0m10 10m-1-1l1 1",
+            "M1.5.5C1 2 3 4 5 6S1 2 3 4Q1 2 3 4T1 2",
+            "M0 0a25 25-30 0150-25",
+        ] {
+            let path = PathData::parse(d).unwrap();
+            assert_eq!(PathData::parse(&path.to_string()).unwrap(), path);
+        }
+    }
+}
@@ -0,0 +1,518 @@
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use crate::ast::Element;
+use crate::path::{PathData, PathSegment};
+
+/// A elliptical arc in center parameterization
+///
+/// `theta` the start angle and `delta` the sweep angle, in radians
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CenterArc {
+    pub cx: f64,
+    pub cy: f64,
+    pub rx: f64,
+    pub ry: f64,
+    pub phi: f64,
+    pub theta: f64,
+    pub delta: f64,
+}
+
+impl CenterArc {
+    /// convert a endpoint arc to center parameterization, see the implementation
+    /// notes of the SVG specification. return `None` for a arc which is a line.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_endpoints(
+        (x1, y1): (f64, f64),
+        (rx, ry): (f64, f64),
+        x_axis_rotation: f64,
+        large_arc: bool,
+        sweep: bool,
+        (x2, y2): (f64, f64),
+    ) -> Option<CenterArc> {
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if rx == 0.0 || ry == 0.0 || (x1 == x2 && y1 == y2) {
+            return None;
+        }
+        let phi = x_axis_rotation.to_radians();
+        let (sin, cos) = phi.sin_cos();
+        let (dx, dy) = ((x1 - x2) / 2.0, (y1 - y2) / 2.0);
+        let x1p = cos * dx + sin * dy;
+        let y1p = -sin * dx + cos * dy;
+        // scale up radii which are too small to reach the end point
+        let lambda = (x1p * x1p) / (rx * rx) + (y1p * y1p) / (ry * ry);
+        if lambda > 1.0 {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+        let numerator = rx * rx * ry * ry - rx * rx * y1p * y1p - ry * ry * x1p * x1p;
+        let denominator = rx * rx * y1p * y1p + ry * ry * x1p * x1p;
+        let mut coefficient = (numerator / denominator).max(0.0).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let cxp = coefficient * rx * y1p / ry;
+        let cyp = -coefficient * ry * x1p / rx;
+        let cx = cos * cxp - sin * cyp + (x1 + x2) / 2.0;
+        let cy = sin * cxp + cos * cyp + (y1 + y2) / 2.0;
+        let angle =
+            |ux: f64, uy: f64, vx: f64, vy: f64| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+        let (ux, uy) = ((x1p - cxp) / rx, (y1p - cyp) / ry);
+        let (vx, vy) = ((-x1p - cxp) / rx, (-y1p - cyp) / ry);
+        let theta = angle(1.0, 0.0, ux, uy);
+        let mut delta = angle(ux, uy, vx, vy);
+        if !sweep && delta > 0.0 {
+            delta -= 2.0 * PI;
+        } else if sweep && delta < 0.0 {
+            delta += 2.0 * PI;
+        }
+        Some(CenterArc {
+            cx,
+            cy,
+            rx,
+            ry,
+            phi,
+            theta,
+            delta,
+        })
+    }
+
+    /// the point of the arc at angle `t`
+    pub fn point(&self, t: f64) -> (f64, f64) {
+        let (sin, cos) = self.phi.sin_cos();
+        let (x, y) = (self.rx * t.cos(), self.ry * t.sin());
+        (self.cx + cos * x - sin * y, self.cy + sin * x + cos * y)
+    }
+
+    /// the derivative of the arc at angle `t`
+    pub fn derivative(&self, t: f64) -> (f64, f64) {
+        let (sin, cos) = self.phi.sin_cos();
+        let (x, y) = (-self.rx * t.sin(), self.ry * t.cos());
+        (cos * x - sin * y, sin * x + cos * y)
+    }
+
+    /// approximate the arc with cubic béziers whose distance to the arc is
+    /// below `tolerance`, as `(control 1, control 2, end)` triples
+    pub fn cubics(&self, tolerance: f64) -> Vec<[(f64, f64); 3]> {
+        let radius = self.rx.max(self.ry);
+        // the radial error of a cubic approximating a circular arc of angle a
+        let error =
+            |a: f64| radius * 4.0 / 27.0 * (a / 4.0).sin().powi(6) / (a / 4.0).cos().powi(2);
+        let mut count = (self.delta.abs() / FRAC_PI_2).ceil().max(1.0) as usize;
+        while count < 1024 && error(self.delta.abs() / count as f64) > tolerance {
+            count += 1;
+        }
+        let step = self.delta / count as f64;
+        let k = 4.0 / 3.0 * (step / 4.0).tan();
+        (0..count)
+            .map(|i| {
+                let (t1, t2) = (
+                    self.theta + step * i as f64,
+                    self.theta + step * (i + 1) as f64,
+                );
+                let (p1, p2) = (self.point(t1), self.point(t2));
+                let (d1, d2) = (self.derivative(t1), self.derivative(t2));
+                [
+                    (p1.0 + k * d1.0, p1.1 + k * d1.1),
+                    (p2.0 - k * d2.0, p2.1 - k * d2.1),
+                    p2,
+                ]
+            })
+            .collect()
+    }
+}
+
+impl PathData {
+    /// convert every segment to absolute coordinates
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::PathData;
+    ///
+    /// let path = PathData::parse("m10 10 l5 0 h5 v5 z m1 1 l1 1").unwrap();
+    /// assert_eq!(path.to_absolute().to_string(), "M10 10 15 10H20V15ZM11 11 12 12");
+    /// ```
+    ///
+    pub fn to_absolute(&self) -> PathData {
+        let (mut cx, mut cy) = (0.0, 0.0);
+        let (mut start_x, mut start_y) = (0.0, 0.0);
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| {
+                let (ox, oy) = if segment.is_absolute() {
+                    (0.0, 0.0)
+                } else {
+                    (cx, cy)
+                };
+                let segment = match *segment {
+                    PathSegment::MoveTo { x, y, .. } => {
+                        start_x = x + ox;
+                        start_y = y + oy;
+                        PathSegment::MoveTo {
+                            abs: true,
+                            x: start_x,
+                            y: start_y,
+                        }
+                    }
+                    PathSegment::LineTo { x, y, .. } => PathSegment::LineTo {
+                        abs: true,
+                        x: x + ox,
+                        y: y + oy,
+                    },
+                    PathSegment::HorizontalLineTo { x, .. } => PathSegment::HorizontalLineTo {
+                        abs: true,
+                        x: x + ox,
+                    },
+                    PathSegment::VerticalLineTo { y, .. } => PathSegment::VerticalLineTo {
+                        abs: true,
+                        y: y + oy,
+                    },
+                    PathSegment::CurveTo {
+                        x1,
+                        y1,
+                        x2,
+                        y2,
+                        x,
+                        y,
+                        ..
+                    } => PathSegment::CurveTo {
+                        abs: true,
+                        x1: x1 + ox,
+                        y1: y1 + oy,
+                        x2: x2 + ox,
+                        y2: y2 + oy,
+                        x: x + ox,
+                        y: y + oy,
+                    },
+                    PathSegment::SmoothCurveTo { x2, y2, x, y, .. } => PathSegment::SmoothCurveTo {
+                        abs: true,
+                        x2: x2 + ox,
+                        y2: y2 + oy,
+                        x: x + ox,
+                        y: y + oy,
+                    },
+                    PathSegment::Quadratic { x1, y1, x, y, .. } => PathSegment::Quadratic {
+                        abs: true,
+                        x1: x1 + ox,
+                        y1: y1 + oy,
+                        x: x + ox,
+                        y: y + oy,
+                    },
+                    PathSegment::SmoothQuadratic { x, y, .. } => PathSegment::SmoothQuadratic {
+                        abs: true,
+                        x: x + ox,
+                        y: y + oy,
+                    },
+                    PathSegment::ArcTo {
+                        rx,
+                        ry,
+                        x_axis_rotation,
+                        large_arc,
+                        sweep,
+                        x,
+                        y,
+                        ..
+                    } => PathSegment::ArcTo {
+                        abs: true,
+                        rx,
+                        ry,
+                        x_axis_rotation,
+                        large_arc,
+                        sweep,
+                        x: x + ox,
+                        y: y + oy,
+                    },
+                    PathSegment::ClosePath { .. } => PathSegment::ClosePath { abs: true },
+                };
+                match segment {
+                    PathSegment::HorizontalLineTo { x, .. } => cx = x,
+                    PathSegment::VerticalLineTo { y, .. } => cy = y,
+                    PathSegment::ClosePath { .. } => {
+                        cx = start_x;
+                        cy = start_y;
+                    }
+                    PathSegment::MoveTo { x, y, .. }
+                    | PathSegment::LineTo { x, y, .. }
+                    | PathSegment::CurveTo { x, y, .. }
+                    | PathSegment::SmoothCurveTo { x, y, .. }
+                    | PathSegment::Quadratic { x, y, .. }
+                    | PathSegment::SmoothQuadratic { x, y, .. }
+                    | PathSegment::ArcTo { x, y, .. } => {
+                        cx = x;
+                        cy = y;
+                    }
+                }
+                segment
+            })
+            .collect();
+        PathData { segments }
+    }
+
+    /// convert the path to absolute `M`, `L`, `C` and `Z` segments only
+    ///
+    /// `H`/`V` become `L`, `S`/`T` get explicit control points, quadratic
+    /// béziers are raised to cubic ones and elliptical arcs are approximated
+    /// by cubic béziers which stay within `tolerance` of the arc.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::PathData;
+    ///
+    /// let path = PathData::parse("M0 0 H9 Q9 9 0 9 T-9 0 Z").unwrap();
+    /// assert_eq!(
+    ///     path.to_curves(0.1).to_string(),
+    ///     "M0 0 9 0C9 6 6 9 0 9-6 9-9 6-9 0Z"
+    /// );
+    /// ```
+    ///
+    pub fn to_curves(&self, tolerance: f64) -> PathData {
+        self.simplified(Some(tolerance))
+    }
+
+    /// like `to_curves`, but elliptical arcs stay absolute `A` segments when
+    /// there is no `tolerance`
+    pub(crate) fn simplified(&self, tolerance: Option<f64>) -> PathData {
+        let mut segments = vec![];
+        let (mut cx, mut cy) = (0.0, 0.0);
+        let (mut start_x, mut start_y) = (0.0, 0.0);
+        // the last control points, to reflect for smooth segments
+        let mut last_cubic: Option<(f64, f64)> = None;
+        let mut last_quadratic: Option<(f64, f64)> = None;
+        let cubic =
+            |(x1, y1): (f64, f64), (x2, y2): (f64, f64), (x, y): (f64, f64)| PathSegment::CurveTo {
+                abs: true,
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            };
+        let line = |x: f64, y: f64| PathSegment::LineTo { abs: true, x, y };
+        for segment in self.to_absolute().segments {
+            let (mut next_cubic, mut next_quadratic) = (None, None);
+            match segment {
+                PathSegment::MoveTo { x, y, .. } => {
+                    segments.push(segment);
+                    start_x = x;
+                    start_y = y;
+                }
+                PathSegment::LineTo { x, y, .. } => segments.push(line(x, y)),
+                PathSegment::HorizontalLineTo { x, .. } => segments.push(line(x, cy)),
+                PathSegment::VerticalLineTo { y, .. } => segments.push(line(cx, y)),
+                PathSegment::CurveTo { x2, y2, .. } => {
+                    segments.push(segment);
+                    next_cubic = Some((x2, y2));
+                }
+                PathSegment::SmoothCurveTo { x2, y2, x, y, .. } => {
+                    let (x1, y1) =
+                        last_cubic.map_or((cx, cy), |(px, py)| (2.0 * cx - px, 2.0 * cy - py));
+                    segments.push(cubic((x1, y1), (x2, y2), (x, y)));
+                    next_cubic = Some((x2, y2));
+                }
+                PathSegment::Quadratic { x1, y1, x, y, .. } => {
+                    segments.push(quadratic_to_cubic((cx, cy), (x1, y1), (x, y)));
+                    next_quadratic = Some((x1, y1));
+                }
+                PathSegment::SmoothQuadratic { x, y, .. } => {
+                    let control =
+                        last_quadratic.map_or((cx, cy), |(px, py)| (2.0 * cx - px, 2.0 * cy - py));
+                    segments.push(quadratic_to_cubic((cx, cy), control, (x, y)));
+                    next_quadratic = Some(control);
+                }
+                PathSegment::ArcTo { .. } if tolerance.is_none() => segments.push(segment),
+                PathSegment::ArcTo {
+                    rx,
+                    ry,
+                    x_axis_rotation,
+                    large_arc,
+                    sweep,
+                    x,
+                    y,
+                    ..
+                } => {
+                    let tolerance = tolerance.unwrap_or_default();
+                    match CenterArc::from_endpoints(
+                        (cx, cy),
+                        (rx, ry),
+                        x_axis_rotation,
+                        large_arc,
+                        sweep,
+                        (x, y),
+                    ) {
+                        Some(arc) => segments.extend(
+                            arc.cubics(tolerance)
+                                .into_iter()
+                                .map(|[c1, c2, end]| cubic(c1, c2, end)),
+                        ),
+                        None if (cx, cy) != (x, y) => segments.push(line(x, y)),
+                        None => {}
+                    }
+                }
+                PathSegment::ClosePath { .. } => segments.push(segment),
+            }
+            (cx, cy) = end_point(&segment, (cx, cy), (start_x, start_y));
+            last_cubic = next_cubic;
+            last_quadratic = next_quadratic;
+        }
+        PathData { segments }
+    }
+}
+
+/// the current point after a absolute segment
+///
+/// `current` the current point before the segment, `start` the start of the subpath
+pub(crate) fn end_point(
+    segment: &PathSegment,
+    (cx, cy): (f64, f64),
+    start: (f64, f64),
+) -> (f64, f64) {
+    match *segment {
+        PathSegment::HorizontalLineTo { x, .. } => (x, cy),
+        PathSegment::VerticalLineTo { y, .. } => (cx, y),
+        PathSegment::ClosePath { .. } => start,
+        PathSegment::MoveTo { x, y, .. }
+        | PathSegment::LineTo { x, y, .. }
+        | PathSegment::CurveTo { x, y, .. }
+        | PathSegment::SmoothCurveTo { x, y, .. }
+        | PathSegment::Quadratic { x, y, .. }
+        | PathSegment::SmoothQuadratic { x, y, .. }
+        | PathSegment::ArcTo { x, y, .. } => (x, y),
+    }
+}
+
+/// raise a quadratic bézier to a cubic one
+fn quadratic_to_cubic(
+    (x0, y0): (f64, f64),
+    (qx, qy): (f64, f64),
+    (x, y): (f64, f64),
+) -> PathSegment {
+    PathSegment::CurveTo {
+        abs: true,
+        x1: x0 + 2.0 / 3.0 * (qx - x0),
+        y1: y0 + 2.0 / 3.0 * (qy - y0),
+        x2: x + 2.0 / 3.0 * (qx - x),
+        y2: y + 2.0 / 3.0 * (qy - y),
+        x,
+        y,
+    }
+}
+
+impl<'a> Element<'a> {
+    /// write path data to the `d` attribute of the element
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, stringify};
+    ///
+    /// let (_, path) = parse(r#"<path d="m 10 10 h 10 v 10 z"/>"#).unwrap();
+    /// path.set_path_data(&path.path_data().unwrap().to_curves(0.1));
+    /// assert_eq!(stringify(path), r#"<path d="M10 10 20 10 20 20Z"/>"#);
+    /// ```
+    ///
+    pub fn set_path_data(&self, path: &PathData) {
+        self.set_attribute("d", path.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use crate::path_normalize::CenterArc;
+    use crate::{PathData, PathSegment};
+
+    fn close(a: (f64, f64), b: (f64, f64)) -> bool {
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_to_absolute() {
+        let path =
+            PathData::parse("M1 1 c1 1 2 2 3 3 s1 1 2 2 q1 1 2 2 t1 1 a1 1 0 0 1 1 1 z l1 1")
+                .unwrap();
+        assert_eq!(
+            path.to_absolute().to_string(),
+            "M1 1C2 2 3 3 4 4S5 5 6 6Q7 7 8 8T9 9A1 1 0 0110 10ZL2 2"
+        );
+    }
+
+    #[test]
+    fn test_smooth_to_curves() {
+        let path = PathData::parse("M0 0 C0 10 10 10 10 0 S20 -10 20 0 L30 0 S40 10 40 0").unwrap();
+        let curves = path.to_curves(0.1);
+        assert_eq!(
+            curves.to_string(),
+            "M0 0C0 10 10 10 10 0 10-10 20-10 20 0L30 0C30 0 40 10 40 0"
+        );
+    }
+
+    #[test]
+    fn test_center_arc() {
+        let arc =
+            CenterArc::from_endpoints((0.0, 0.0), (10.0, 10.0), 0.0, false, true, (20.0, 0.0))
+                .unwrap();
+        assert!(close((arc.cx, arc.cy), (10.0, 0.0)));
+        assert!((arc.delta - PI).abs() < 1e-9);
+        // radii too small are scaled up
+        let arc = CenterArc::from_endpoints((0.0, 0.0), (1.0, 1.0), 0.0, false, false, (20.0, 0.0))
+            .unwrap();
+        assert!((arc.rx - 10.0).abs() < 1e-9);
+        assert!((arc.delta + PI).abs() < 1e-9);
+        assert!(
+            CenterArc::from_endpoints((0.0, 0.0), (0.0, 1.0), 0.0, false, false, (20.0, 0.0))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_arc_to_curves() {
+        let path = PathData::parse("M0 0 A10 10 0 0 1 20 0 A 0 5 0 0 0 30 0").unwrap();
+        for tolerance in [1.0, 0.01, 0.0001] {
+            let curves = path.to_curves(tolerance);
+            let mut previous = (0.0, 0.0);
+            for segment in curves.segments.iter() {
+                if let PathSegment::CurveTo {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                    ..
+                } = *segment
+                {
+                    // sample the bézier and check the distance to the circle
+                    for i in 0..=10 {
+                        let t = i as f64 / 10.0;
+                        let mt = 1.0 - t;
+                        let px = mt * mt * mt * previous.0
+                            + 3.0 * mt * mt * t * x1
+                            + 3.0 * mt * t * t * x2
+                            + t * t * t * x;
+                        let py = mt * mt * mt * previous.1
+                            + 3.0 * mt * mt * t * y1
+                            + 3.0 * mt * t * t * y2
+                            + t * t * t * y;
+                        let distance = ((px - 10.0).powi(2) + py.powi(2)).sqrt();
+                        assert!((distance - 10.0).abs() <= tolerance);
+                        assert!(py <= 1e-9);
+                    }
+                    previous = (x, y);
+                }
+            }
+            assert!(close(previous, (20.0, 0.0)));
+            // a arc with a zero radius is a line
+            assert_eq!(
+                curves.segments.last(),
+                Some(&PathSegment::LineTo {
+                    abs: true,
+                    x: 30.0,
+                    y: 0.0
+                })
+            );
+        }
+        assert_eq!(path.to_curves(1.0).segments.len(), 4);
+        assert!(path.to_curves(0.0001).segments.len() > 4);
+    }
+}
@@ -0,0 +1,1472 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::ast::Element;
+use crate::bbox::{
+    element_bbox, style_dashes, style_stroke, BBox, LineCap, LineJoin, Stroke, CONTAINERS,
+};
+use crate::clip::{ClipPath, FillRule, Mask, MaskType};
+use crate::color::{opacity, Rgba};
+use crate::css::ComputedStyles;
+use crate::deflate::zlib;
+use crate::document::{drawing_copy, Document};
+use crate::length::LengthContext;
+use crate::number::format_number;
+use crate::paint::{Gradient, GradientKind, Paint, Pattern, SpreadMethod, Units};
+use crate::path::{PathData, PathSegment};
+use crate::png::decode_png;
+use crate::shape::shape_path_data;
+use crate::text::Fonts;
+use crate::transform::Matrix;
+use crate::view_box::ViewBox;
+
+/// the size of a CSS pixel in PDF points
+const POINTS_PER_PIXEL: f64 = 0.75;
+
+/// the largest distance in points between a arc and its curves
+const TOLERANCE: f64 = 0.01;
+
+/// the most periods of a repeated or reflected gradient, more are padded
+const MAX_PERIODS: f64 = 256.0;
+
+/// the objects every file starts with, the shared resources come last
+const CATALOG: usize = 1;
+const PAGES: usize = 2;
+const PAGE: usize = 3;
+const RESOURCES: usize = 4;
+
+/// write a svg tree as a single page vector PDF file
+///
+/// the page is the `width` and `height` of the root, or the size of its
+/// `viewBox`, at 96 pixels to the inch, and its `viewBox` is fit into it
+/// following `preserveAspectRatio`. a copy of the tree is written with its
+/// `<use>` expanded and its stylesheets applied, see `expand_use` and
+/// `inline_styles`.
+///
+/// paths and basic shapes are filled and stroked with their dashes, with solid
+/// colors, linear and radial gradients as shading patterns, or patterns as
+/// tiling patterns. the paint opacities, the opacity of gradient stops, group
+/// `opacity` and masks go through ExtGState, as alphas or soft masks. clip paths
+/// clip, a clip path with several shapes is a soft mask of their union. markers are drawn, PNG and JPEG images from a `data:` URL
+/// are embedded, images from files only with `render_pdf_with_files`. filters
+/// are ignored and text is not written without fonts, see `render_pdf_with_fonts`.
+///
+/// ## Example
+/// ```rust
+/// use svg_simple_parser::{parse, render_pdf};
+///
+/// let (_, root) = parse(r#"<svg width="20" height="10"><rect width="5" height="10" fill="red" opacity="0.5"/></svg>"#).unwrap();
+/// let pdf = render_pdf(&root);
+/// assert!(pdf.starts_with(b"%PDF-1.4"));
+/// assert!(pdf.ends_with(b"%%EOF\n"));
+/// ```
+///
+pub fn render_pdf(root: &Rc<Element>) -> Vec<u8> {
+    render_pdf_with_fonts(root, &Fonts::default())
+}
+
+/// write a svg tree as a PDF file like `render_pdf`, with its text laid out
+/// with a set of fonts
+///
+/// the text is written as the outlines of its glyphs, see `text_to_paths`.
+///
+/// ## Example
+/// ```rust,no_run
+/// use svg_simple_parser::{parse, render_pdf_with_fonts, Fonts};
+///
+/// let mut fonts = Fonts::new();
+/// fonts.load("DejaVuSans.ttf").unwrap();
+/// let (_, root) = parse(r#"<svg width="40" height="20"><text y="15">Hi</text></svg>"#).unwrap();
+/// std::fs::write("hi.pdf", render_pdf_with_fonts(&root, &fonts)).unwrap();
+/// ```
+///
+pub fn render_pdf_with_fonts(root: &Rc<Element>, fonts: &Fonts) -> Vec<u8> {
+    write_pdf(root, fonts, None)
+}
+
+/// write a svg tree as a PDF file like `render_pdf_with_fonts`, embedding the
+/// images whose `href` is a file in the directory `base`
+///
+/// a relative path is resolved against `base`, a path which leads outside of
+/// it, e.g. with `..` or a link, is not read. no file is read if `base` does
+/// not exist.
+///
+/// ## Example
+/// ```rust,no_run
+/// use svg_simple_parser::{parse, render_pdf_with_files, Fonts};
+///
+/// let (_, root) = parse(r#"<svg width="40" height="20"><image width="40" height="20" href="photo.jpg"/></svg>"#).unwrap();
+/// std::fs::write("photo.pdf", render_pdf_with_files(&root, &Fonts::default(), "images".as_ref())).unwrap();
+/// ```
+///
+pub fn render_pdf_with_files(root: &Rc<Element>, fonts: &Fonts, base: &Path) -> Vec<u8> {
+    write_pdf(root, fonts, fs::canonicalize(base).ok())
+}
+
+/// write a PDF file, reading the images in the canonical directory `base`
+fn write_pdf(root: &Rc<Element>, fonts: &Fonts, base: Option<PathBuf>) -> Vec<u8> {
+    let document = drawing_copy(root, fonts);
+    let root = document.root.clone();
+    let context = LengthContext::default();
+    let view_box = root.view_box().ok().flatten();
+    let length = |key: &str, fallback: f64| match root.user_length(key, &context) {
+        Ok(Some(length)) if length > 0.0 => length,
+        _ => fallback,
+    };
+    let width = length("width", view_box.map_or(300.0, |view_box| view_box.width));
+    let height = length("height", view_box.map_or(150.0, |view_box| view_box.height));
+    let matrix = view_box.map_or(Matrix::default(), |view_box| {
+        let aspect = root.preserve_aspect_ratio().unwrap_or_default();
+        view_box.transform((0.0, 0.0, width, height), &aspect)
+    });
+    let (page_width, page_height) = (width * POINTS_PER_PIXEL, height * POINTS_PER_PIXEL);
+    // the page has the y axis going up
+    let page = Matrix::new(
+        POINTS_PER_PIXEL,
+        0.0,
+        0.0,
+        -POINTS_PER_PIXEL,
+        0.0,
+        page_height,
+    );
+    let writer = PdfWriter {
+        document: &document,
+        styles: ComputedStyles::new(document.stylesheet()),
+        context: LengthContext {
+            viewport: view_box.map_or((width, height), |view_box| {
+                (view_box.width, view_box.height)
+            }),
+            ..Default::default()
+        },
+        page: BBox::new(0.0, 0.0, page_width, page_height),
+        objects: RefCell::new(vec![vec![]; RESOURCES]),
+        resources: RefCell::new(vec![]),
+        states: RefCell::new(HashMap::new()),
+        base,
+        images: RefCell::new(HashMap::new()),
+        rendering: RefCell::new(vec![]),
+    };
+    let mut content = Content::new(Matrix::default());
+    writer.render_element(&root, &(page * matrix), &mut content);
+    let contents = writer.add_stream("", content.ops.as_bytes());
+    writer.set_object(
+        CATALOG,
+        format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES),
+    );
+    writer.set_object(
+        PAGES,
+        format!("<< /Type /Pages /Kids [{} 0 R] /Count 1 >>", PAGE),
+    );
+    writer.set_object(
+        PAGE,
+        format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources {} 0 R /Contents {} 0 R /Group << /S /Transparency /CS /DeviceRGB >> >>",
+            PAGES,
+            format_number(page_width),
+            format_number(page_height),
+            RESOURCES,
+            contents
+        ),
+    );
+    writer.write_resources();
+    writer.finish()
+}
+
+/// A content stream being written, `base` maps its default coordinate space
+/// to the page
+struct Content {
+    ops: String,
+    base: Matrix,
+}
+
+impl Content {
+    fn new(base: Matrix) -> Content {
+        Content {
+            ops: String::new(),
+            base,
+        }
+    }
+
+    /// the matrix from the page to the default space of the stream
+    fn page_inverse(&self) -> Matrix {
+        self.base.invert().unwrap_or_default()
+    }
+
+    /// concatenate a matrix from a user space to the page
+    fn transform(&mut self, matrix: &Matrix) {
+        let m = self.page_inverse() * *matrix;
+        writeln!(self.ops, "{} cm", numbers(&[m.a, m.b, m.c, m.d, m.e, m.f])).unwrap();
+    }
+
+    /// clip to a path, `matrix` maps it to the page
+    fn clip(&mut self, path: &PathData, matrix: &Matrix, rule: FillRule) {
+        let m = self.page_inverse() * *matrix;
+        self.ops.push_str(&path_ops(path, &m, tolerance(&m)));
+        self.ops.push_str(match rule {
+            FillRule::NonZero => "W n\n",
+            FillRule::EvenOdd => "W* n\n",
+        });
+    }
+}
+
+/// the name, width and height in pixels of a embedded image
+type Image = (String, f64, f64);
+
+struct PdfWriter<'d, 'a> {
+    document: &'d Document<'a>,
+    /// the computed styles of the prepared tree
+    styles: ComputedStyles<'a>,
+    context: LengthContext,
+    /// the page in the default space of the page
+    page: BBox,
+    /// the objects of the file, the object `n` at `n - 1`
+    objects: RefCell<Vec<Vec<u8>>>,
+    /// the resources shared by every content stream, as their category, their
+    /// name and their object
+    resources: RefCell<Vec<(&'static str, String, usize)>>,
+    /// the names of the graphics states by their dictionary
+    states: RefCell<HashMap<String, String>>,
+    /// the canonical directory of the image files which may be read
+    base: Option<PathBuf>,
+    /// the names, widths and heights of the embedded images by their `href`,
+    /// `None` for a image which can not be read
+    images: RefCell<HashMap<String, Option<Image>>>,
+    /// the patterns, masks and markers whose content is being written
+    rendering: RefCell<Vec<Rc<Element<'a>>>>,
+}
+
+impl<'d, 'a> PdfWriter<'d, 'a> {
+    fn add_object(&self, object: Vec<u8>) -> usize {
+        let mut objects = self.objects.borrow_mut();
+        objects.push(object);
+        objects.len()
+    }
+
+    fn set_object(&self, id: usize, object: String) {
+        self.objects.borrow_mut()[id - 1] = object.into_bytes();
+    }
+
+    /// add a stream compressed with deflate, `dictionary` the entries of its
+    /// dictionary besides its length and filter
+    fn add_stream(&self, dictionary: &str, data: &[u8]) -> usize {
+        let data = zlib(data);
+        let mut object = format!(
+            "<< {}/Length {} /Filter /FlateDecode >>\nstream\n",
+            dictionary,
+            data.len()
+        )
+        .into_bytes();
+        object.extend(data);
+        object.extend(b"\nendstream");
+        self.add_object(object)
+    }
+
+    /// add a object to the shared resources, returning its name
+    fn add_resource(&self, category: &'static str, prefix: &str, object: usize) -> String {
+        let mut resources = self.resources.borrow_mut();
+        let count = resources.iter().filter(|(c, _, _)| *c == category).count();
+        let name = format!("{}{}", prefix, count + 1);
+        resources.push((category, name.clone(), object));
+        name
+    }
+
+    /// the name of a graphics state, the same dictionary is written once
+    fn graphics_state(&self, dictionary: String) -> String {
+        if let Some(name) = self.states.borrow().get(&dictionary) {
+            return name.clone();
+        }
+        let object = self.add_object(format!("<< /Type /ExtGState {} >>", dictionary).into_bytes());
+        let name = self.add_resource("ExtGState", "GS", object);
+        self.states.borrow_mut().insert(dictionary, name.clone());
+        name
+    }
+
+    /// a form XObject drawing a content stream over the page, as a
+    /// transparency group in `color_space`
+    fn add_form(&self, content: &Content, color_space: &str) -> usize {
+        let bbox = self.page.transformed(&content.page_inverse());
+        self.add_stream(
+            &format!(
+                "/Type /XObject /Subtype /Form /BBox [{}] /Group << /S /Transparency /CS /{} >> /Resources {} 0 R ",
+                numbers(&[bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y]),
+                color_space,
+                RESOURCES
+            ),
+            content.ops.as_bytes(),
+        )
+    }
+
+    fn write_resources(&self) {
+        let mut dictionary = String::from("<<");
+        for category in ["ExtGState", "Pattern", "Shading", "XObject"] {
+            let resources = self.resources.borrow();
+            let entries: Vec<String> = resources
+                .iter()
+                .filter(|(c, _, _)| *c == category)
+                .map(|(_, name, object)| format!("/{} {} 0 R", name, object))
+                .collect();
+            if !entries.is_empty() {
+                write!(dictionary, " /{} << {} >>", category, entries.join(" ")).unwrap();
+            }
+        }
+        dictionary.push_str(" >>");
+        self.set_object(RESOURCES, dictionary);
+    }
+
+    /// the bytes of the file, with its cross reference table
+    fn finish(self) -> Vec<u8> {
+        let objects = self.objects.into_inner();
+        let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = vec![];
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n", i + 1).into_bytes());
+            pdf.extend(object);
+            pdf.extend(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            writeln!(table, "{:010} 00000 n ", offset).unwrap();
+        }
+        write!(
+            table,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            CATALOG,
+            xref
+        )
+        .unwrap();
+        pdf.extend(table.into_bytes());
+        pdf
+    }
+
+    /// write a element and its subtree, `matrix` maps its user space to the page
+    fn render_element(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, out: &mut Content) {
+        if ele.get_attribute("display").as_deref().map(str::trim) == Some("none") {
+            return;
+        }
+        let alpha = ele
+            .get_attribute("opacity")
+            .and_then(|value| opacity(&value))
+            .unwrap_or(1.0);
+        if alpha <= 0.0 {
+            return;
+        }
+        let mask = self.document.resolve_mask(ele, &self.context);
+        if alpha < 1.0 || mask.is_some() {
+            let mut state = String::new();
+            if alpha < 1.0 {
+                let alpha = format_number(alpha);
+                write!(state, "/ca {} /CA {}", alpha, alpha).unwrap();
+            }
+            if let Some(mask) = mask {
+                // a mask which can not be resolved hides everything
+                let Some(soft_mask) = self.soft_mask(
+                    &mask,
+                    matrix,
+                    element_bbox(ele, &Matrix::default(), &self.styles, false),
+                    out.base,
+                ) else {
+                    return;
+                };
+                write!(state, " /SMask {}", soft_mask).unwrap();
+            }
+            let mut group = Content::new(out.base);
+            self.render_clipped(ele, matrix, &mut group);
+            let form = self.add_form(&group, "DeviceRGB");
+            let name = self.add_resource("XObject", "X", form);
+            let state = self.graphics_state(state.trim().to_owned());
+            writeln!(out.ops, "q /{} gs /{} Do Q", state, name).unwrap();
+        } else {
+            self.render_clipped(ele, matrix, out);
+        }
+    }
+
+    /// write a element inside its clip path
+    fn render_clipped(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, out: &mut Content) {
+        let Some(clip_path) = self.document.resolve_clip_path_with(&self.styles, ele) else {
+            self.render_content(ele, matrix, out);
+            return;
+        };
+        out.ops.push_str("q\n");
+        let mut masks = vec![];
+        // a clip path which can not be resolved clips everything
+        if !self.clip(
+            &clip_path,
+            matrix,
+            element_bbox(ele, &Matrix::default(), &self.styles, false),
+            out,
+            &mut masks,
+        ) {
+            out.ops.push_str("0 0 0 0 re W n\n");
+        }
+        let mut content = Content::new(out.base);
+        self.render_content(ele, matrix, &mut content);
+        out.ops.push_str(&self.masked(content, masks).ops);
+        out.ops.push_str("Q\n");
+    }
+
+    /// a content drawn through soft masks, as nested groups
+    fn masked(&self, mut content: Content, masks: Vec<String>) -> Content {
+        for mask in masks {
+            let form = self.add_form(&content, "DeviceRGB");
+            let name = self.add_resource("XObject", "X", form);
+            let state = self.graphics_state(format!("/SMask {}", mask));
+            content = Content::new(content.base);
+            writeln!(content.ops, "q /{} gs /{} Do Q", state, name).unwrap();
+        }
+        content
+    }
+
+    fn render_content(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, out: &mut Content) {
+        match ele.ele_type {
+            "image" => self.draw_image(ele, matrix, out),
+            ele_type if CONTAINERS.contains(&ele_type) => self.render_children(ele, matrix, out),
+            _ => self.draw_shape(ele, matrix, out),
+        }
+    }
+
+    /// write the children of a element, a `switch` writes its first child only
+    fn render_children(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, out: &mut Content) {
+        let children = ele.children.borrow().clone();
+        let count = if ele.ele_type == "switch" {
+            1
+        } else {
+            children.len()
+        };
+        for child in children.iter().take(count) {
+            let mut m = *matrix * child.transform().unwrap_or_default().to_matrix();
+            if child.ele_type == "svg" {
+                m = m * child.viewport_transform(&self.context).unwrap_or_default();
+            }
+            self.render_element(child, &m, out);
+        }
+    }
+
+    /// clip to a clip path, `matrix` maps the user space of the clipped element
+    /// with the bounding box `bbox` to the page. `false` if it can not be resolved.
+    ///
+    /// a single shape clips with its own rule. the union of several shapes can
+    /// not be a clipping path, so they are filled in a alpha soft mask, each
+    /// with its own rule and clip path, which is pushed to `masks` for the
+    /// content to be drawn through.
+    fn clip(
+        &self,
+        clip_path: &ClipPath<'a>,
+        matrix: &Matrix,
+        bbox: Option<BBox>,
+        out: &mut Content,
+        masks: &mut Vec<String>,
+    ) -> bool {
+        let units = match bbox {
+            Some(bbox) => clip_path.to_user_space(&bbox),
+            None if clip_path.units == Units::UserSpaceOnUse => Some(clip_path.transform),
+            None => None,
+        };
+        let Some(units) = units else {
+            return false;
+        };
+        if let Some(inner) = &clip_path.clip_path {
+            if !self.clip(inner, matrix, bbox, out, masks) {
+                return false;
+            }
+        }
+        let shapes: Vec<(PathData, Matrix, &_)> = clip_path
+            .shapes
+            .iter()
+            .filter_map(|shape| {
+                let path = match shape.element.ele_type {
+                    "path" => shape.element.path_data().ok(),
+                    _ => shape_path_data(&shape.element),
+                }?;
+                Some((path, *matrix * units * shape.transform, shape))
+            })
+            .collect();
+        match shapes.as_slice() {
+            [] => false,
+            [(path, m, shape)] => {
+                if let Some(inner) = &shape.clip_path {
+                    if !self.clip(inner, m, path.bbox(), out, masks) {
+                        return false;
+                    }
+                }
+                out.clip(path, m, shape.clip_rule);
+                true
+            }
+            shapes => {
+                let mut group = Content::new(out.base);
+                for (path, m, shape) in shapes {
+                    let mut fill = Content::new(out.base);
+                    let mut shape_masks = vec![];
+                    if let Some(inner) = &shape.clip_path {
+                        if !self.clip(inner, m, path.bbox(), &mut fill, &mut shape_masks) {
+                            continue;
+                        }
+                    }
+                    let mut shape_fill = Content::new(out.base);
+                    let ops = path_ops(path, &(shape_fill.page_inverse() * *m), tolerance(m));
+                    shape_fill.ops.push_str(&ops);
+                    shape_fill.ops.push_str(match shape.clip_rule {
+                        FillRule::NonZero => "f\n",
+                        FillRule::EvenOdd => "f*\n",
+                    });
+                    fill.ops.push_str(&self.masked(shape_fill, shape_masks).ops);
+                    writeln!(group.ops, "q\n{}Q", fill.ops).unwrap();
+                }
+                let form = self.add_form(&group, "DeviceRGB");
+                masks.push(format!("<< /Type /Mask /S /Alpha /G {} 0 R >>", form));
+                true
+            }
+        }
+    }
+
+    /// the soft mask dictionary of a mask, `matrix` maps the user space of the
+    /// masked element with the bounding box `bbox` to the page, and `base` is
+    /// the default space of the stream where it is set
+    fn soft_mask(
+        &self,
+        mask: &Mask<'a>,
+        matrix: &Matrix,
+        bbox: Option<BBox>,
+        base: Matrix,
+    ) -> Option<String> {
+        // a mask drawn in its own content hides everything
+        if self.is_rendering(&mask.element) {
+            return None;
+        }
+        let bbox = bbox?;
+        let region = mask.region(&bbox)?;
+        let content = mask.content_transform(&bbox)?;
+        let mut group = Content::new(base);
+        group.ops.push_str("q\n");
+        group.clip(&rect_path(&region), matrix, FillRule::NonZero);
+        self.rendering.borrow_mut().push(mask.element.clone());
+        self.render_children(&mask.element, &(*matrix * content), &mut group);
+        self.rendering.borrow_mut().pop();
+        group.ops.push_str("Q\n");
+        if let Some(inner) = &mask.mask {
+            // the content of the mask is masked in turn, as a group of its own
+            let inner = self.soft_mask(inner, matrix, Some(bbox), base)?;
+            let form = self.add_form(&group, "DeviceRGB");
+            let name = self.add_resource("XObject", "X", form);
+            let state = self.graphics_state(format!("/SMask {}", inner));
+            group = Content::new(base);
+            writeln!(group.ops, "q /{} gs /{} Do Q", state, name).unwrap();
+        }
+        let form = self.add_form(&group, "DeviceRGB");
+        let kind = match mask.mask_type {
+            MaskType::Luminance => "Luminosity",
+            MaskType::Alpha => "Alpha",
+        };
+        Some(format!("<< /Type /Mask /S /{} /G {} 0 R >>", kind, form))
+    }
+
+    /// fill and stroke a path or basic shape
+    fn draw_shape(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, out: &mut Content) {
+        let path = match ele.ele_type {
+            "path" => ele.path_data().ok(),
+            _ => shape_path_data(ele),
+        };
+        let Some(path) = path else {
+            return;
+        };
+        let style = self.styles.style(ele);
+        if matches!(
+            style.get("visibility").map(str::trim),
+            Some("hidden" | "collapse")
+        ) {
+            return;
+        }
+        let bbox = path.bbox();
+        let ops = path_ops(&path, &Matrix::default(), tolerance(matrix));
+        if let Some(paint) =
+            self.document
+                .resolve_paint_with(&self.styles, ele, "fill", &self.context)
+        {
+            let rule = style
+                .get("fill-rule")
+                .and_then(FillRule::parse)
+                .unwrap_or_default();
+            let mut block = Content::new(out.base);
+            block.ops.push_str("q\n");
+            block.transform(matrix);
+            if self.set_paint(&paint, false, matrix, bbox, bbox, &mut block) {
+                block.ops.push_str(&ops);
+                block.ops.push_str(match rule {
+                    FillRule::NonZero => "f\nQ\n",
+                    FillRule::EvenOdd => "f*\nQ\n",
+                });
+                out.ops.push_str(&block.ops);
+            }
+        }
+        let stroke = self
+            .document
+            .resolve_paint_with(&self.styles, ele, "stroke", &self.context)
+            .zip(style_stroke(&style));
+        if let Some((paint, stroke)) = stroke {
+            let mut block = Content::new(out.base);
+            block.ops.push_str("q\n");
+            block.transform(matrix);
+            let area = path.stroke_bbox(&stroke);
+            if self.set_paint(&paint, true, matrix, bbox, area, &mut block) {
+                block.ops.push_str(&stroke_ops(&stroke));
+                if let Some((dashes, offset)) = style_dashes(&style, &self.context) {
+                    writeln!(
+                        block.ops,
+                        "[{}] {} d",
+                        numbers(&dashes),
+                        format_number(offset)
+                    )
+                    .unwrap();
+                }
+                block.ops.push_str(&ops);
+                block.ops.push_str("S\nQ\n");
+                out.ops.push_str(&block.ops);
+            }
+        }
+        self.draw_markers(ele, matrix, out);
+    }
+
+    /// draw the markers of a shape, clipped to their viewport
+    fn draw_markers(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, out: &mut Content) {
+        for instance in self.document.markers_with(&self.styles, ele, &self.context) {
+            let marker = &instance.marker;
+            // a marker drawn in its own content is left out
+            if self.is_rendering(&marker.element) {
+                continue;
+            }
+            let m = *matrix * instance.transform;
+            self.rendering.borrow_mut().push(marker.element.clone());
+            match marker.clip_rect() {
+                Some(clip) => {
+                    out.ops.push_str("q\n");
+                    out.clip(&rect_path(&clip), &m, FillRule::NonZero);
+                    self.render_children(&marker.element, &m, out);
+                    out.ops.push_str("Q\n");
+                }
+                None => self.render_children(&marker.element, &m, out),
+            }
+            self.rendering.borrow_mut().pop();
+        }
+    }
+
+    /// set the color of the fill or the stroke to a paint, `false` if nothing is
+    /// painted. `matrix` maps the user space of the shape to the page, `bbox` is
+    /// the box of the shape and `area` the box painted, both in user space.
+    fn set_paint(
+        &self,
+        paint: &Paint<'a>,
+        stroke: bool,
+        matrix: &Matrix,
+        bbox: Option<BBox>,
+        area: Option<BBox>,
+        out: &mut Content,
+    ) -> bool {
+        let (alpha_key, color_space, color) = if stroke {
+            ("CA", "CS", "SCN")
+        } else {
+            ("ca", "cs", "scn")
+        };
+        let set_alpha = |alpha: f64, out: &mut Content| {
+            if alpha < 1.0 {
+                let state = self.graphics_state(format!("/{} {}", alpha_key, format_number(alpha)));
+                writeln!(out.ops, "/{} gs", state).unwrap();
+            }
+        };
+        match paint {
+            Paint::Color(rgba) => {
+                writeln!(
+                    out.ops,
+                    "{} {}",
+                    rgb(*rgba),
+                    if stroke { "RG" } else { "rg" }
+                )
+                .unwrap();
+                set_alpha(rgba.alpha, out);
+                true
+            }
+            Paint::Gradient(gradient) => {
+                let Some(bbox) = bbox else {
+                    return false;
+                };
+                let Some(space) = gradient.units.to_user_space(&bbox) else {
+                    return false;
+                };
+                let space = space * gradient.transform;
+                let Some(inverse) = space.invert() else {
+                    return false;
+                };
+                let periods = periods(gradient, &area.unwrap_or(bbox).transformed(&inverse));
+                let Some(shading) = self.shading(gradient, periods, false) else {
+                    // a gradient without extent paints its last stop
+                    let Some(last) = gradient.stops.last() else {
+                        return false;
+                    };
+                    return self.set_paint(
+                        &Paint::Color(last.color),
+                        stroke,
+                        matrix,
+                        None,
+                        None,
+                        out,
+                    );
+                };
+                let pattern = out.page_inverse() * *matrix * space;
+                let object = self.add_object(
+                    format!(
+                        "<< /Type /Pattern /PatternType 2 /Shading {} 0 R /Matrix [{}] >>",
+                        shading,
+                        numbers(&[
+                            pattern.a, pattern.b, pattern.c, pattern.d, pattern.e, pattern.f
+                        ])
+                    )
+                    .into_bytes(),
+                );
+                let name = self.add_resource("Pattern", "P", object);
+                writeln!(out.ops, "/Pattern {} /{} {}", color_space, name, color).unwrap();
+                let first = gradient.stops[0].color.alpha;
+                if gradient.stops.iter().all(|stop| stop.color.alpha == first) {
+                    set_alpha(first, out);
+                } else if let Some(area) = area {
+                    // the alphas of the stops as a luminosity soft mask
+                    let Some(alpha) = self.shading(gradient, periods, true) else {
+                        return false;
+                    };
+                    let name = self.add_resource("Shading", "Sh", alpha);
+                    let mut group = Content::new(*matrix);
+                    group.ops.push_str("q\n");
+                    group.transform(&(*matrix * space));
+                    writeln!(group.ops, "/{} sh\nQ", name).unwrap();
+                    let bbox = area;
+                    let form = self.add_stream(
+                        &format!(
+                            "/Type /XObject /Subtype /Form /BBox [{}] /Group << /S /Transparency /CS /DeviceGray >> /Resources {} 0 R ",
+                            numbers(&[bbox.min_x, bbox.min_y, bbox.max_x, bbox.max_y]),
+                            RESOURCES
+                        ),
+                        group.ops.as_bytes(),
+                    );
+                    let state = self.graphics_state(format!(
+                        "/SMask << /Type /Mask /S /Luminosity /G {} 0 R >>",
+                        form
+                    ));
+                    writeln!(out.ops, "/{} gs", state).unwrap();
+                }
+                true
+            }
+            Paint::Pattern(pattern) => {
+                let Some(name) =
+                    bbox.and_then(|bbox| self.tiling_pattern(pattern, matrix, &bbox, out))
+                else {
+                    return false;
+                };
+                writeln!(out.ops, "/Pattern {} /{} {}", color_space, name, color).unwrap();
+                set_alpha(pattern.opacity, out);
+                true
+            }
+        }
+    }
+
+    /// a axial or radial shading of the colors of a gradient, or of the alphas
+    /// of its stops, over the `periods` of its offsets. `None` for a gradient
+    /// without extent.
+    fn shading(&self, gradient: &Gradient, periods: (f64, f64), alpha: bool) -> Option<usize> {
+        let (t0, t1) = periods;
+        let (shading_type, coords) = match gradient.kind {
+            GradientKind::Linear { x1, y1, x2, y2 } => {
+                if x1 == x2 && y1 == y2 {
+                    return None;
+                }
+                let point = |t: f64| [x1 + (x2 - x1) * t, y1 + (y2 - y1) * t];
+                (2, [point(t0), point(t1)].concat())
+            }
+            GradientKind::Radial {
+                cx,
+                cy,
+                r,
+                fx,
+                fy,
+                fr,
+            } => {
+                if r <= 0.0 {
+                    return None;
+                }
+                // a focal point outside the circle is moved onto it
+                let (mut fx, mut fy) = (fx, fy);
+                let distance = (fx - cx).hypot(fy - cy);
+                if distance > r * 0.999 {
+                    let scale = r * 0.999 / distance;
+                    fx = cx + (fx - cx) * scale;
+                    fy = cy + (fy - cy) * scale;
+                }
+                let fr = fr.clamp(0.0, r);
+                let circle = |t: f64| [fx + (cx - fx) * t, fy + (cy - fy) * t, fr + (r - fr) * t];
+                (3, [circle(t0), circle(t1)].concat())
+            }
+        };
+        let values = |rgba: Rgba| -> Vec<f64> {
+            if alpha {
+                vec![rgba.alpha]
+            } else {
+                [rgba.red, rgba.green, rgba.blue]
+                    .iter()
+                    .map(|channel| *channel as f64 / 255.0)
+                    .collect()
+            }
+        };
+        let function = stops_function(gradient, periods, values);
+        let object = format!(
+            "<< /ShadingType {} /ColorSpace /{} /Coords [{}] /Domain [{}] /Function {} /Extend [true true] >>",
+            shading_type,
+            if alpha { "DeviceGray" } else { "DeviceRGB" },
+            numbers(&coords),
+            numbers(&[t0, t1]),
+            function
+        );
+        Some(self.add_object(object.into_bytes()))
+    }
+
+    /// the name of a tiling pattern drawing the content of a pattern, for a
+    /// shape with the bounding box `bbox` whose user space `matrix` maps to the page
+    fn tiling_pattern(
+        &self,
+        pattern: &Pattern<'a>,
+        matrix: &Matrix,
+        bbox: &BBox,
+        out: &Content,
+    ) -> Option<String> {
+        // a pattern drawn in its own content paints nothing
+        if self.is_rendering(&pattern.content) {
+            return None;
+        }
+        let tile = pattern.tile(bbox)?;
+        let content = pattern.content_transform(bbox)?;
+        let space = *matrix * pattern.transform;
+        space.invert()?;
+        let mut cell = Content::new(space);
+        self.rendering.borrow_mut().push(pattern.content.clone());
+        self.render_children(&pattern.content, &(space * content), &mut cell);
+        self.rendering.borrow_mut().pop();
+        let m = out.page_inverse() * space;
+        let object = self.add_stream(
+            &format!(
+                "/Type /Pattern /PatternType 1 /PaintType 1 /TilingType 1 /BBox [{}] /XStep {} /YStep {} /Matrix [{}] /Resources {} 0 R ",
+                numbers(&[tile.min_x, tile.min_y, tile.max_x, tile.max_y]),
+                format_number(tile.width()),
+                format_number(tile.height()),
+                numbers(&[m.a, m.b, m.c, m.d, m.e, m.f]),
+                RESOURCES
+            ),
+            cell.ops.as_bytes(),
+        );
+        Some(self.add_resource("Pattern", "P", object))
+    }
+
+    /// draw a PNG or JPEG `image` in its viewport
+    fn draw_image(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, out: &mut Content) {
+        let style = self.styles.style(ele);
+        if matches!(
+            style.get("visibility").map(str::trim),
+            Some("hidden" | "collapse")
+        ) {
+            return;
+        }
+        let href = ele
+            .get_attribute("href")
+            .or_else(|| ele.get_attribute("xlink:href"));
+        let Some(href) = href else {
+            return;
+        };
+        let interpolate = !matches!(
+            style.get("image-rendering").map(str::trim),
+            Some("optimizeSpeed" | "pixelated" | "crisp-edges")
+        );
+        let Some((name, width, height)) = self.image(&href, interpolate) else {
+            return;
+        };
+        let length = |key: &str, fallback: f64| match ele.user_length(key, &self.context) {
+            Ok(Some(length)) => length,
+            _ => fallback,
+        };
+        let viewport = (
+            length("x", 0.0),
+            length("y", 0.0),
+            length("width", width),
+            length("height", height),
+        );
+        if viewport.2 <= 0.0 || viewport.3 <= 0.0 {
+            return;
+        }
+        let aspect = ele.preserve_aspect_ratio().unwrap_or_default();
+        let fit = ViewBox::new(0.0, 0.0, width, height).transform(viewport, &aspect);
+        let (x, y, w, h) = viewport;
+        out.ops.push_str("q\n");
+        out.clip(
+            &rect_path(&BBox::new(x, y, x + w, y + h)),
+            matrix,
+            FillRule::NonZero,
+        );
+        // the image space is the unit square with the first row at the top
+        out.transform(&(*matrix * fit * Matrix::new(width, 0.0, 0.0, -height, 0.0, height)));
+        writeln!(out.ops, "/{} Do\nQ", name).unwrap();
+    }
+
+    /// the name, width and height of a image XObject for a `href`, a `data:` URL
+    /// or the path of a file in the base directory
+    fn image(&self, href: &str, interpolate: bool) -> Option<Image> {
+        let key = format!("{}{}", interpolate, href);
+        let cached = self.images.borrow().get(&key).cloned();
+        match cached {
+            Some(image) => image,
+            None => {
+                let image = read_href(href, self.base.as_deref())
+                    .and_then(|data| self.add_image(&data, interpolate));
+                self.images.borrow_mut().insert(key, image.clone());
+                image
+            }
+        }
+    }
+
+    /// embed a PNG or JPEG file, returning its name, width and height
+    fn add_image(&self, data: &[u8], interpolate: bool) -> Option<Image> {
+        let (object, width, height) = if let Some(png) = decode_png(data) {
+            let size = format!("/Width {} /Height {}", png.width, png.height);
+            let soft_mask = png.alpha.map(|alpha| {
+                let mask = self.add_stream(
+                    &format!(
+                        "/Type /XObject /Subtype /Image {} /ColorSpace /DeviceGray /BitsPerComponent 8 /Interpolate {} ",
+                        size, interpolate
+                    ),
+                    &alpha,
+                );
+                format!("/SMask {} 0 R ", mask)
+            });
+            let object = self.add_stream(
+                &format!(
+                    "/Type /XObject /Subtype /Image {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Interpolate {} {}",
+                    size,
+                    interpolate,
+                    soft_mask.unwrap_or_default()
+                ),
+                &png.rgb,
+            );
+            (object, png.width as f64, png.height as f64)
+        } else {
+            let (width, height, components, adobe) = jpeg_info(data)?;
+            let (color_space, decode) = match components {
+                1 => ("DeviceGray", ""),
+                3 => ("DeviceRGB", ""),
+                // Adobe writes CMYK inverted
+                4 if adobe => ("DeviceCMYK", "/Decode [1 0 1 0 1 0 1 0] "),
+                4 => ("DeviceCMYK", ""),
+                _ => return None,
+            };
+            let mut object = format!(
+                "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /{} /BitsPerComponent 8 /Interpolate {} {}/Length {} /Filter /DCTDecode >>\nstream\n",
+                width, height, color_space, interpolate, decode, data.len()
+            )
+            .into_bytes();
+            object.extend(data);
+            object.extend(b"\nendstream");
+            (self.add_object(object), width as f64, height as f64)
+        };
+        let name = self.add_resource("XObject", "Im", object);
+        Some((name, width, height))
+    }
+
+    /// whether the content of a pattern, a mask or a marker is being written
+    fn is_rendering(&self, ele: &Rc<Element<'a>>) -> bool {
+        self.rendering
+            .borrow()
+            .iter()
+            .any(|other| Rc::ptr_eq(other, ele))
+    }
+}
+
+/// numbers separated by spaces
+fn numbers(values: &[f64]) -> String {
+    values
+        .iter()
+        .map(|value| format_number(*value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// the color operands of a color
+fn rgb(rgba: Rgba) -> String {
+    numbers(&[
+        rgba.red as f64 / 255.0,
+        rgba.green as f64 / 255.0,
+        rgba.blue as f64 / 255.0,
+    ])
+}
+
+/// the tolerance in user space for the curves of arcs drawn with a matrix
+fn tolerance(matrix: &Matrix) -> f64 {
+    let scale = matrix.determinant().abs().sqrt();
+    if scale > 0.0 && scale.is_finite() {
+        TOLERANCE / scale
+    } else {
+        TOLERANCE
+    }
+}
+
+/// the operators to construct a path, `matrix` maps it to the stream
+fn path_ops(path: &PathData, matrix: &Matrix, tolerance: f64) -> String {
+    let mut ops = String::new();
+    for segment in path.to_curves(tolerance).segments {
+        let (points, operator) = match segment {
+            PathSegment::MoveTo { x, y, .. } => (vec![(x, y)], "m"),
+            PathSegment::LineTo { x, y, .. } => (vec![(x, y)], "l"),
+            PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+                ..
+            } => (vec![(x1, y1), (x2, y2), (x, y)], "c"),
+            PathSegment::ClosePath { .. } => (vec![], "h"),
+            _ => continue,
+        };
+        let coordinates: Vec<f64> = points
+            .into_iter()
+            .flat_map(|(x, y)| {
+                let (x, y) = matrix.apply(x, y);
+                [x, y]
+            })
+            .collect();
+        if coordinates.is_empty() {
+            writeln!(ops, "{}", operator).unwrap();
+        } else {
+            writeln!(ops, "{} {}", numbers(&coordinates), operator).unwrap();
+        }
+    }
+    ops
+}
+
+/// the path of a rectangle
+fn rect_path(rect: &BBox) -> PathData {
+    let point = |x, y| PathSegment::LineTo { abs: true, x, y };
+    PathData {
+        segments: vec![
+            PathSegment::MoveTo {
+                abs: true,
+                x: rect.min_x,
+                y: rect.min_y,
+            },
+            point(rect.max_x, rect.min_y),
+            point(rect.max_x, rect.max_y),
+            point(rect.min_x, rect.max_y),
+            PathSegment::ClosePath { abs: true },
+        ],
+    }
+}
+
+/// the operators of the geometry of a stroke
+fn stroke_ops(stroke: &Stroke) -> String {
+    let cap = match stroke.line_cap {
+        LineCap::Butt => 0,
+        LineCap::Round => 1,
+        LineCap::Square => 2,
+    };
+    let join = match stroke.line_join {
+        LineJoin::Miter | LineJoin::MiterClip | LineJoin::Arcs => 0,
+        LineJoin::Round => 1,
+        LineJoin::Bevel => 2,
+    };
+    format!(
+        "{} w {} J {} j {} M\n",
+        format_number(stroke.width),
+        cap,
+        join,
+        format_number(stroke.miter_limit)
+    )
+}
+
+/// the whole periods of the offsets of a gradient which cover a area in the
+/// space of the gradient, from `0` to `1` for a padded gradient
+fn periods(gradient: &Gradient, area: &BBox) -> (f64, f64) {
+    if gradient.spread == SpreadMethod::Pad {
+        return (0.0, 1.0);
+    }
+    let offsets: Vec<f64> = [
+        (area.min_x, area.min_y),
+        (area.max_x, area.min_y),
+        (area.max_x, area.max_y),
+        (area.min_x, area.max_y),
+    ]
+    .iter()
+    .map(|(x, y)| gradient.offset_at(*x, *y))
+    .collect();
+    let mut t0 = offsets
+        .iter()
+        .copied()
+        .fold(f64::INFINITY, f64::min)
+        .floor()
+        .min(0.0);
+    let t1 = offsets
+        .iter()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max)
+        .ceil()
+        .max(1.0);
+    if matches!(gradient.kind, GradientKind::Radial { .. }) {
+        // circles before the focal circle have a negative radius
+        t0 = 0.0;
+    }
+    if !(t1 - t0).is_finite() || t1 - t0 > MAX_PERIODS {
+        return (0.0, 1.0);
+    }
+    (t0, t1)
+}
+
+/// a stitching function of the stops of a gradient over its periods, reversed
+/// in odd periods of a reflected gradient
+fn stops_function(
+    gradient: &Gradient,
+    (t0, t1): (f64, f64),
+    values: impl Fn(Rgba) -> Vec<f64>,
+) -> String {
+    // the pieces of the function as their start, end and colors
+    let mut pieces: Vec<(f64, f64, Vec<f64>, Vec<f64>)> = vec![];
+    let mut period = t0;
+    while period < t1 {
+        let reflected =
+            gradient.spread == SpreadMethod::Reflect && (period as i64).rem_euclid(2) == 1;
+        let mut stops: Vec<(f64, Vec<f64>)> = gradient
+            .stops
+            .iter()
+            .map(|stop| (stop.offset.clamp(0.0, 1.0), values(stop.color)))
+            .collect();
+        if reflected {
+            stops = stops
+                .into_iter()
+                .rev()
+                .map(|(offset, color)| (1.0 - offset, color))
+                .collect();
+        }
+        let first = stops[0].clone();
+        let last = stops[stops.len() - 1].clone();
+        stops.insert(0, (0.0, first.1));
+        stops.push((1.0, last.1));
+        for pair in stops.windows(2) {
+            let ((from, c0), (to, c1)) = (&pair[0], &pair[1]);
+            if to > from {
+                pieces.push((period + from, period + to, c0.clone(), c1.clone()));
+            }
+        }
+        period += 1.0;
+    }
+    let functions: Vec<String> = pieces
+        .iter()
+        .map(|(_, _, c0, c1)| {
+            format!(
+                "<< /FunctionType 2 /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >>",
+                numbers(c0),
+                numbers(c1)
+            )
+        })
+        .collect();
+    let bounds: Vec<f64> = pieces
+        .iter()
+        .skip(1)
+        .map(|(start, _, _, _)| *start)
+        .collect();
+    let encode: Vec<f64> = pieces.iter().flat_map(|_| [0.0, 1.0]).collect();
+    format!(
+        "<< /FunctionType 3 /Domain [{}] /Functions [{}] /Bounds [{}] /Encode [{}] >>",
+        numbers(&[t0, t1]),
+        functions.join(" "),
+        numbers(&bounds),
+        numbers(&encode)
+    )
+}
+
+/// the bytes of a `data:` URL, or of a file in the canonical directory `base`
+fn read_href(href: &str, base: Option<&Path>) -> Option<Vec<u8>> {
+    let href = href.trim();
+    let Some(url) = href.strip_prefix("data:") else {
+        let base = base?;
+        let path = href.strip_prefix("file://").unwrap_or(href);
+        let path = fs::canonicalize(base.join(path)).ok()?;
+        if !path.starts_with(base) {
+            return None;
+        }
+        return fs::read(path).ok();
+    };
+    let (header, data) = url.split_once(',')?;
+    if header.ends_with(";base64") {
+        base64(data)
+    } else {
+        percent_decode(data)
+    }
+}
+
+/// decode base64, with or without padding, in the standard or the URL alphabet
+fn base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            c if c.is_ascii_whitespace() => continue,
+            _ => return None,
+        };
+        buffer = (buffer << 6 | value as u32) & 0xffff;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// decode the `%` escapes of a URL
+fn percent_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut input = text.bytes();
+    while let Some(byte) = input.next() {
+        if byte == b'%' {
+            let hex = [input.next()?, input.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    Some(bytes)
+}
+
+/// the width, height and number of components of a JPEG file, and whether it
+/// has a Adobe marker
+fn jpeg_info(data: &[u8]) -> Option<(u16, u16, u8, bool)> {
+    if data.get(..2)? != [0xff, 0xd8] {
+        return None;
+    }
+    let mut adobe = false;
+    let mut offset = 2;
+    while offset + 4 <= data.len() {
+        if data[offset] != 0xff {
+            return None;
+        }
+        let marker = data[offset + 1];
+        if marker == 0xff {
+            // a fill byte
+            offset += 1;
+            continue;
+        }
+        let length = u16::from_be_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        match marker {
+            0xee => adobe |= data.get(offset + 4..offset + 9) == Some(b"Adobe"),
+            // the start of a frame, but not a huffman or arithmetic table
+            0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                let frame = data.get(offset + 5..offset + 10)?;
+                let height = u16::from_be_bytes([frame[0], frame[1]]);
+                let width = u16::from_be_bytes([frame[2], frame[3]]);
+                return Some((width, height, frame[4], adobe));
+            }
+            0xd9 | 0xda => return None,
+            _ => {}
+        }
+        offset += 2 + length;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::base64;
+    use crate::deflate::inflate;
+    use crate::{parse, render_pdf, render_pdf_with_files, Fonts};
+
+    /// the objects of a PDF file from its cross reference table, with their
+    /// streams inflated
+    fn objects(svg: &str) -> Vec<String> {
+        let (_, root) = parse(svg).unwrap();
+        pdf_objects(&render_pdf(&root))
+    }
+
+    fn pdf_objects(pdf: &[u8]) -> Vec<String> {
+        let text = String::from_utf8_lossy(pdf);
+        let start = text.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let xref: usize = text[start..].lines().next().unwrap().parse().unwrap();
+        let table = String::from_utf8_lossy(&pdf[xref..]);
+        let mut lines = table.lines().skip(1);
+        let count: usize = lines.next().unwrap()[2..].parse().unwrap();
+        let offsets: Vec<usize> = lines
+            .skip(1)
+            .take(count - 1)
+            .map(|line| line[..10].parse().unwrap())
+            .collect();
+        offsets
+            .iter()
+            .enumerate()
+            .map(|(i, offset)| {
+                let object = &pdf[*offset..];
+                let header = format!("{} 0 obj\n", i + 1);
+                assert!(object.starts_with(header.as_bytes()));
+                let end = object.windows(6).position(|w| w == b"endobj").unwrap();
+                let object = &object[header.len()..end];
+                let Some(stream) = object.windows(7).position(|w| w == b"stream\n") else {
+                    return String::from_utf8_lossy(object).into_owned();
+                };
+                let dictionary = String::from_utf8_lossy(&object[..stream]);
+                let data = &object[stream + 7..object.len() - "\nendstream\n".len()];
+                match dictionary.contains("/FlateDecode") {
+                    true => format!(
+                        "{}{}",
+                        dictionary,
+                        String::from_utf8_lossy(&inflate(data, usize::MAX).unwrap())
+                    ),
+                    false => dictionary.into_owned(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_render_pdf() {
+        let svg = r#"<svg width="20" height="10"><style>rect { fill: blue }</style><rect width="4" height="2"/><circle cx="10" cy="5" r="2" fill="lime" fill-opacity="0.5"/><path d="M0 5 H20" stroke="red" stroke-width="2" stroke-dasharray="1 2" stroke-linecap="round"/><rect width="1" height="1" display="none" fill="yellow"/></svg>"#;
+        let objects = objects(svg);
+        assert!(objects[0].contains("/Type /Catalog /Pages 2 0 R"));
+        assert!(objects[2].contains("/MediaBox [0 0 15 7.5]"));
+        let content = objects.last().unwrap();
+        // the page is flipped and scaled to points
+        assert!(content.contains("0.75 0 0 -0.75 0 7.5 cm\n0 0 1 rg\n0 0 m\n4 0 l\n"));
+        assert!(content.contains("0 1 0 rg\n/GS1 gs\n"));
+        assert!(content.contains("1 0 0 RG\n2 w 1 J 0 j 4 M\n[1 2] 0 d\n0 5 m\n20 5 l\nS\n"));
+        assert!(!content.contains("1 1 0 rg"));
+        assert!(objects[3].contains("/ExtGState << /GS1 5 0 R >>"));
+        assert!(objects[4].contains("/ca 0.5"));
+    }
+
+    #[test]
+    fn test_render_pdf_gradient() {
+        let svg = r##"<svg viewBox="0 0 10 10"><linearGradient id="l" spreadMethod="reflect" x2="0.5"><stop offset="0" stop-color="black"/><stop offset="1" stop-color="white"/></linearGradient><radialGradient id="r"><stop offset="0.5" stop-color="red"/><stop offset="1" stop-color="blue" stop-opacity="0"/></radialGradient><rect width="10" height="5" fill="url(#l)"/><rect y="5" width="10" height="5" fill="url(#r)"/></svg>"##;
+        let objects = objects(svg);
+        let shadings: Vec<&String> = objects
+            .iter()
+            .filter(|object| object.contains("/ShadingType"))
+            .collect();
+        // the reflected gradient covers two periods, the second one reversed
+        assert!(shadings[0]
+            .contains("/ShadingType 2 /ColorSpace /DeviceRGB /Coords [0 0 1 0] /Domain [0 2]"));
+        assert!(shadings[0].contains("/Bounds [1] /Encode [0 1 0 1]"));
+        assert!(shadings[0].contains("/C0 [1 1 1] /C1 [0 0 0]"));
+        assert!(shadings[1].contains(
+            "/ShadingType 3 /ColorSpace /DeviceRGB /Coords [0.5 0.5 0 0.5 0.5 0.5] /Domain [0 1]"
+        ));
+        // the alphas of the stops are a soft mask
+        assert!(shadings[2].contains("/ShadingType 3 /ColorSpace /DeviceGray"));
+        assert!(shadings[2].contains("/C0 [1] /C1 [0]"));
+        assert!(objects
+            .iter()
+            .any(|object| object.contains("/SMask << /Type /Mask /S /Luminosity")));
+        assert!(objects
+            .iter()
+            .any(|object| object.contains("/PatternType 2 /Shading")));
+    }
+
+    #[test]
+    fn test_render_pdf_clip_path_and_mask() {
+        let svg = r##"<svg width="10" height="10"><clipPath id="c"><rect width="5" height="5"/></clipPath><mask id="m"><rect width="10" height="10" fill="white"/></mask><rect width="10" height="10" clip-path="url(#c)"/><rect width="10" height="10" mask="url(#m)"/><g clip-path="url(#c)" opacity="0.5"><rect width="1" height="1"/></g></svg>"##;
+        let objects = objects(svg);
+        let content = objects.last().unwrap();
+        assert!(content.contains("q\n0 7.5 m\n3.75 7.5 l\n3.75 3.75 l\n0 3.75 l\nh\nW n\n"));
+        assert_eq!(content.matches(" Do Q").count(), 2);
+        assert!(objects
+            .iter()
+            .any(|object| object.contains("/SMask << /Type /Mask /S /Luminosity /G")));
+        assert!(objects
+            .iter()
+            .any(|object| object.contains("/ca 0.5 /CA 0.5")));
+        // the group of the opacity clips its content
+        let group = objects
+            .iter()
+            .find(|object| object.contains("/Subtype /Form") && object.contains("W n"))
+            .unwrap();
+        assert!(group.contains("/Group << /S /Transparency /CS /DeviceRGB >>"));
+    }
+
+    #[test]
+    fn test_render_pdf_image() {
+        let svg = r#"<svg width="4" height="2"><image width="4" height="2" href="data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAIAAAABCAYAAAD0In+KAAAADklEQVR42mP4z8AAQg0AD3oDfmqBMeEAAAAASUVORK5CYII=" style="image-rendering: pixelated"/><image width="4" height="2" href="missing.png"/></svg>"#;
+        let objects = objects(svg);
+        let image = objects
+            .iter()
+            .find(|object| object.contains("/ColorSpace /DeviceRGB"))
+            .unwrap();
+        assert!(image.contains("/Subtype /Image /Width 2 /Height 1"));
+        assert!(image.contains("/Interpolate false /SMask"));
+        assert!(objects
+            .iter()
+            .any(|object| object
+                .contains("/Subtype /Image /Width 2 /Height 1 /ColorSpace /DeviceGray")));
+        let content = objects.last().unwrap();
+        // one image, the missing one is left out
+        assert_eq!(content.matches(" Do").count(), 1);
+        assert!(content.contains("3 0 0 1.5 0 0 cm\n/Im1 Do\n"));
+    }
+
+    #[test]
+    fn test_render_pdf_image_files() {
+        let png = base64("iVBORw0KGgoAAAANSUhEUgAAAAIAAAABCAYAAAD0In+KAAAADklEQVR42mP4z8AAQg0AD3oDfmqBMeEAAAAASUVORK5CYII=").unwrap();
+        let dir = std::env::temp_dir().join(format!("svg-pdf-images-{}", std::process::id()));
+        let base = dir.join("base");
+        std::fs::create_dir_all(&base).unwrap();
+        std::fs::write(base.join("a.png"), &png).unwrap();
+        std::fs::write(dir.join("outside.png"), &png).unwrap();
+        let count = |href: &str, base: Option<&std::path::Path>| {
+            let svg = format!(
+                r#"<svg width="4" height="2"><image width="4" height="2" href="{}"/></svg>"#,
+                href
+            );
+            let (_, root) = parse(&svg).unwrap();
+            let pdf = match base {
+                Some(base) => render_pdf_with_files(&root, &Fonts::default(), base),
+                None => render_pdf(&root),
+            };
+            pdf_objects(&pdf).last().unwrap().matches(" Do").count()
+        };
+        // files are only read from the base directory
+        assert_eq!(count("a.png", None), 0);
+        assert_eq!(count("a.png", Some(&base)), 1);
+        assert_eq!(count("../outside.png", Some(&base)), 0);
+        let outside = dir.join("outside.png");
+        assert_eq!(count(outside.to_str().unwrap(), Some(&base)), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_render_pdf_clip_path_union() {
+        let svg = r##"<svg width="10" height="10"><clipPath id="i"><rect width="2" height="10"/></clipPath><clipPath id="c"><rect width="5" height="5" clip-rule="evenodd"/><rect x="5" y="5" width="5" height="5" clip-path="url(#i)"/></clipPath><rect width="10" height="10" clip-path="url(#c)"/></svg>"##;
+        let objects = objects(svg);
+        let content = objects.last().unwrap();
+        // the content is drawn through a alpha mask of the shapes
+        assert!(content.contains("/GS1 gs /X1 Do Q"));
+        assert!(!content.contains("W n"));
+        assert!(objects
+            .iter()
+            .any(|object| object.contains("/ExtGState /SMask << /Type /Mask /S /Alpha")));
+        let mask = objects
+            .iter()
+            .find(|object| object.contains("/Subtype /Form") && object.contains("f*"))
+            .unwrap();
+        // each shape with its own rule, the second one clipped by its own clip path
+        assert!(mask.contains("h\nf*\nQ\nq\n"));
+        assert!(mask.contains("h\nW n\n"));
+        assert!(mask.ends_with("h\nf\nQ\n"));
+    }
+}
@@ -0,0 +1,352 @@
+#[cfg(feature = "pdf")]
+use crate::deflate::inflate;
+#[cfg(feature = "render")]
+use crate::deflate::zlib;
+
+/// the PNG file signature
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// the most bytes of the rows of a decoded image, 256 MiB
+#[cfg(feature = "pdf")]
+const MAX_IMAGE_BYTES: usize = 1 << 28;
+
+#[cfg(feature = "render")]
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 == 1 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+        }
+        *entry = c;
+    }
+    !bytes.iter().fold(!0u32, |crc, byte| {
+        table[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[cfg(feature = "render")]
+/// filter each row with the filter whose output has the smallest sum of
+/// absolute values, a common heuristic for smaller files
+fn filter_rows(width: usize, height: usize, data: &[u8]) -> Vec<u8> {
+    let stride = width * 4;
+    let mut filtered = Vec::with_capacity((stride + 1) * height);
+    let empty = vec![0; stride];
+    for y in 0..height {
+        let row = &data[y * stride..(y + 1) * stride];
+        let above = if y == 0 {
+            &empty[..]
+        } else {
+            &data[(y - 1) * stride..y * stride]
+        };
+        let candidates: Vec<Vec<u8>> = (0..5u8)
+            .map(|filter| {
+                let mut out = vec![filter];
+                for x in 0..stride {
+                    let left = if x >= 4 { row[x - 4] } else { 0 };
+                    let upper_left = if x >= 4 { above[x - 4] } else { 0 };
+                    let predictor = match filter {
+                        0 => 0,
+                        1 => left,
+                        2 => above[x],
+                        3 => ((left as u16 + above[x] as u16) / 2) as u8,
+                        _ => paeth(left, above[x], upper_left),
+                    };
+                    out.push(row[x].wrapping_sub(predictor));
+                }
+                out
+            })
+            .collect();
+        let best = candidates
+            .into_iter()
+            .min_by_key(|out| {
+                out[1..]
+                    .iter()
+                    .map(|byte| (*byte as i8).unsigned_abs() as u32)
+                    .sum::<u32>()
+            })
+            .unwrap();
+        filtered.extend(best);
+    }
+    filtered
+}
+
+#[cfg(feature = "render")]
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend((data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend(kind);
+    png.extend(data);
+    let crc = crc32(&png[start..]);
+    png.extend(crc.to_be_bytes());
+}
+
+#[cfg(feature = "render")]
+/// encode RGBA8 pixels with straight alpha, rows from the top, as a PNG file
+pub(crate) fn encode_png(width: u32, height: u32, data: &[u8]) -> Vec<u8> {
+    let mut png = SIGNATURE.to_vec();
+    let mut header = vec![];
+    header.extend(width.to_be_bytes());
+    header.extend(height.to_be_bytes());
+    // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlace
+    header.extend([8, 6, 0, 0, 0]);
+    write_chunk(&mut png, b"IHDR", &header);
+    let filtered = filter_rows(width as usize, height as usize, data);
+    write_chunk(&mut png, b"IDAT", &zlib(&filtered));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+/// A decoded PNG image, 8 bits RGB and the alpha if the image has one, rows
+/// from the top
+#[cfg(feature = "pdf")]
+pub(crate) struct DecodedPng {
+    pub width: u32,
+    pub height: u32,
+    pub rgb: Vec<u8>,
+    pub alpha: Option<Vec<u8>>,
+}
+
+/// decode a PNG file of any color type and bit depth, `None` for a invalid or
+/// interlaced file. 16 bits samples are rounded to 8 bits.
+#[cfg(feature = "pdf")]
+pub(crate) fn decode_png(png: &[u8]) -> Option<DecodedPng> {
+    if png.get(..8)? != SIGNATURE {
+        return None;
+    }
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = vec![];
+    let mut offset = 8;
+    while offset + 8 <= png.len() {
+        let length = u32::from_be_bytes(png[offset..offset + 4].try_into().ok()?) as usize;
+        let kind = &png[offset + 4..offset + 8];
+        let data = png.get(offset + 8..(offset + 8).checked_add(length)?)?;
+        match kind {
+            b"IHDR" => header = Some(data),
+            b"PLTE" => palette = data,
+            b"tRNS" => transparency = data,
+            b"IDAT" => compressed.extend(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        offset += length + 12;
+    }
+    let header = header.filter(|header| header.len() >= 13)?;
+    let width = u32::from_be_bytes(header[0..4].try_into().ok()?);
+    let height = u32::from_be_bytes(header[4..8].try_into().ok()?);
+    let (depth, color_type, interlace) = (header[8], header[9], header[12]);
+    let channels = match (color_type, depth) {
+        (0, 1 | 2 | 4 | 8 | 16) => 1,
+        (3, 1 | 2 | 4 | 8) => 1,
+        (2 | 6, 8 | 16) => 3 + (color_type == 6) as usize,
+        (4, 8 | 16) => 2,
+        _ => return None,
+    };
+    if interlace != 0 || width == 0 || height == 0 {
+        return None;
+    }
+    let (width_, height_) = (width as usize, height as usize);
+    let bits = channels * depth as usize;
+    let stride = (width_ * bits).div_ceil(8);
+    let step = bits.div_ceil(8);
+    // the rows with their filter bytes
+    let size = (stride + 1)
+        .checked_mul(height_)
+        .filter(|size| *size <= MAX_IMAGE_BYTES)?;
+    let filtered = inflate(&compressed, size)?;
+    if filtered.len() < (stride + 1) * height_ {
+        return None;
+    }
+    // undo the filters, rows of `stride` bytes
+    let mut data = vec![0u8; stride * height_];
+    for y in 0..height_ {
+        let filter = filtered[y * (stride + 1)];
+        let row = &filtered[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        for x in 0..stride {
+            let left = if x >= step {
+                data[y * stride + x - step]
+            } else {
+                0
+            };
+            let above = if y > 0 { data[(y - 1) * stride + x] } else { 0 };
+            let upper_left = if x >= step && y > 0 {
+                data[(y - 1) * stride + x - step]
+            } else {
+                0
+            };
+            let predictor = match filter {
+                0 => 0,
+                1 => left,
+                2 => above,
+                3 => ((left as u16 + above as u16) / 2) as u8,
+                4 => paeth(left, above, upper_left),
+                _ => return None,
+            };
+            data[y * stride + x] = row[x].wrapping_add(predictor);
+        }
+    }
+    let sample = |y: usize, i: usize| -> u16 {
+        let row = &data[y * stride..];
+        match depth {
+            16 => u16::from_be_bytes([row[i * 2], row[i * 2 + 1]]),
+            8 => row[i] as u16,
+            _ => {
+                let bit = i * depth as usize;
+                let shift = 8 - depth as usize - bit % 8;
+                (row[bit / 8] >> shift) as u16 & ((1 << depth) - 1)
+            }
+        }
+    };
+    let max = (1u32 << depth) - 1;
+    let to_8 = |value: u16| ((value as u32 * 255 + max / 2) / max) as u8;
+    let key = |i: usize| -> Option<u16> {
+        Some(u16::from_be_bytes([
+            *transparency.get(i * 2)?,
+            *transparency.get(i * 2 + 1)?,
+        ]))
+    };
+    let mut rgb = Vec::with_capacity(width_ * height_ * 3);
+    let mut alpha = Vec::with_capacity(width_ * height_);
+    for y in 0..height_ {
+        for x in 0..width_ {
+            let samples: Vec<u16> = (0..channels).map(|c| sample(y, x * channels + c)).collect();
+            let (color, a) = match color_type {
+                0 => {
+                    let gray = to_8(samples[0]);
+                    let a = if key(0) == Some(samples[0]) { 0 } else { 255 };
+                    ([gray; 3], a)
+                }
+                2 => {
+                    let keyed = (0..3).all(|c| key(c) == Some(samples[c]));
+                    let color = [to_8(samples[0]), to_8(samples[1]), to_8(samples[2])];
+                    (color, if keyed { 0 } else { 255 })
+                }
+                3 => {
+                    let i = samples[0] as usize;
+                    let color = palette.get(i * 3..i * 3 + 3)?;
+                    let a = transparency.get(i).copied().unwrap_or(255);
+                    ([color[0], color[1], color[2]], a)
+                }
+                4 => ([to_8(samples[0]); 3], to_8(samples[1])),
+                _ => (
+                    [to_8(samples[0]), to_8(samples[1]), to_8(samples[2])],
+                    to_8(samples[3]),
+                ),
+            };
+            rgb.extend(color);
+            alpha.push(a);
+        }
+    }
+    let alpha = alpha.iter().any(|a| *a < 255).then_some(alpha);
+    Some(DecodedPng {
+        width,
+        height,
+        rgb,
+        alpha,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "render")]
+    use crate::png::{crc32, encode_png};
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn test_encode_png() {
+        let png = encode_png(2, 1, &[255, 0, 0, 255, 255, 0, 0, 255]);
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[8..16], b"\0\0\0\x0dIHDR");
+        assert_eq!(&png[16..29], b"\0\0\0\x02\0\0\0\x01\x08\x06\0\0\0");
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xae\x42\x60\x82");
+    }
+
+    #[cfg(all(feature = "render", feature = "pdf"))]
+    #[test]
+    fn test_decode_png() {
+        use crate::png::decode_png;
+
+        let pixels = [255, 0, 0, 255, 0, 0, 255, 128, 1, 2, 3, 255, 4, 5, 6, 255];
+        let png = decode_png(&encode_png(2, 2, &pixels)).unwrap();
+        assert_eq!((png.width, png.height), (2, 2));
+        assert_eq!(png.rgb, vec![255, 0, 0, 0, 0, 255, 1, 2, 3, 4, 5, 6]);
+        assert_eq!(png.alpha, Some(vec![255, 128, 255, 255]));
+        let png = decode_png(&encode_png(1, 1, &[9, 8, 7, 255])).unwrap();
+        assert_eq!((png.rgb, png.alpha), (vec![9, 8, 7], None));
+        assert!(decode_png(b"\x89PNG\r\n\x1a\nnot a png").is_none());
+    }
+
+    #[cfg(feature = "pdf")]
+    #[test]
+    fn test_decode_png_formats() {
+        use crate::deflate::zlib;
+        use crate::png::decode_png;
+
+        // the checksums are not read
+        let png = |header: [u8; 5], chunks: &[(&[u8; 4], &[u8])], rows: &[u8]| {
+            let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+            let mut ihdr = vec![0, 0, 0, 3, 0, 0, 0, 1];
+            ihdr.extend(header);
+            let idat = zlib(rows);
+            let mut all = vec![(b"IHDR", &ihdr[..])];
+            all.extend(chunks.iter().copied());
+            all.push((b"IDAT", &idat));
+            for (kind, data) in all {
+                png.extend((data.len() as u32).to_be_bytes());
+                png.extend(kind);
+                png.extend(data);
+                png.extend([0; 4]);
+            }
+            decode_png(&png).unwrap()
+        };
+        // 2 bits of palette indices, the second color transparent
+        let decoded = png(
+            [2, 3, 0, 0, 0],
+            &[
+                (b"PLTE", &[255, 0, 0, 0, 255, 0, 0, 0, 255]),
+                (b"tRNS", &[255, 0]),
+            ],
+            &[0, 0b00_01_10_00],
+        );
+        assert_eq!(decoded.rgb, vec![255, 0, 0, 0, 255, 0, 0, 0, 255]);
+        assert_eq!(decoded.alpha, Some(vec![255, 0, 255]));
+        // 16 bits gray with the up filter on the only row
+        let decoded = png([16, 0, 0, 0, 0], &[], &[2, 0xff, 0xff, 0x80, 0x00, 0, 0]);
+        assert_eq!(
+            (decoded.rgb, decoded.alpha),
+            (vec![255, 255, 255, 128, 128, 128, 0, 0, 0], None)
+        );
+        // 4 bits gray
+        let decoded = png([4, 0, 0, 0, 0], &[], &[0, 0xf0, 0x50]);
+        assert_eq!(decoded.rgb, vec![255, 255, 255, 0, 0, 0, 85, 85, 85]);
+    }
+}
@@ -0,0 +1,683 @@
+use std::f64::consts::PI;
+
+use crate::bbox::{LineCap, LineJoin, Stroke};
+use crate::clip::FillRule;
+use crate::color::Rgba;
+use crate::path::{PathData, PathSegment};
+
+pub(crate) type Point = (f64, f64);
+
+/// the premultiplied color of a paint at a pixel
+pub(crate) type Shader = dyn Fn(usize, usize) -> [f32; 4];
+
+/// the number of sub-scanlines sampled for each row of pixels
+const SUBSAMPLES: usize = 16;
+
+/// A flattened subpath
+///
+/// `corners` marks the points which are vertices of the path, the other points
+/// lie inside a curve
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Polyline {
+    pub points: Vec<Point>,
+    pub corners: Vec<bool>,
+    pub closed: bool,
+}
+
+impl Polyline {
+    fn new(start: Point) -> Polyline {
+        Polyline {
+            points: vec![start],
+            corners: vec![true],
+            closed: false,
+        }
+    }
+
+    fn push(&mut self, point: Point, corner: bool) {
+        self.points.push(point);
+        self.corners.push(corner);
+    }
+
+    /// the polyline without repeated points
+    fn deduplicated(&self) -> Polyline {
+        let mut line = Polyline::new(self.points[0]);
+        for (point, corner) in self.points.iter().zip(&self.corners).skip(1) {
+            if *point == *line.points.last().unwrap() {
+                *line.corners.last_mut().unwrap() |= corner;
+            } else {
+                line.push(*point, *corner);
+            }
+        }
+        if self.closed && line.points.len() > 1 && line.points.last() == line.points.first() {
+            line.points.pop();
+            line.corners.pop();
+        }
+        line.closed = self.closed;
+        line
+    }
+}
+
+/// flatten a path into polylines whose distance to the curves is below `tolerance`.
+/// a subpath of a single move is dropped.
+pub(crate) fn flatten(path: &PathData, tolerance: f64) -> Vec<Polyline> {
+    let mut lines = vec![];
+    let mut current: Option<Polyline> = None;
+    let mut point = (0.0, 0.0);
+    let mut start = (0.0, 0.0);
+    let finish = |lines: &mut Vec<Polyline>, line: Option<Polyline>| {
+        if let Some(line) = line.filter(|line| line.points.len() > 1) {
+            lines.push(line);
+        }
+    };
+    for segment in path.to_curves(tolerance).segments {
+        match segment {
+            PathSegment::MoveTo { x, y, .. } => {
+                finish(&mut lines, current.take());
+                point = (x, y);
+                start = point;
+                current = Some(Polyline::new(point));
+            }
+            PathSegment::LineTo { x, y, .. } => {
+                current
+                    .get_or_insert_with(|| Polyline::new(point))
+                    .push((x, y), true);
+                point = (x, y);
+            }
+            PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+                ..
+            } => {
+                let line = current.get_or_insert_with(|| Polyline::new(point));
+                let (p0, p1, p2, p3) = (point, (x1, y1), (x2, y2), (x, y));
+                // the second differences bound the distance of the chords
+                let dd = ((p0.0 - 2.0 * p1.0 + p2.0).hypot(p0.1 - 2.0 * p1.1 + p2.1))
+                    .max((p1.0 - 2.0 * p2.0 + p3.0).hypot(p1.1 - 2.0 * p2.1 + p3.1));
+                let count = ((0.75 * dd / tolerance).sqrt().ceil() as usize).clamp(1, 1000);
+                for i in 1..=count {
+                    let t = i as f64 / count as f64;
+                    let mt = 1.0 - t;
+                    let at = |a: f64, b: f64, c: f64, d: f64| {
+                        mt * mt * mt * a
+                            + 3.0 * mt * mt * t * b
+                            + 3.0 * mt * t * t * c
+                            + t * t * t * d
+                    };
+                    line.push(
+                        (at(p0.0, p1.0, p2.0, p3.0), at(p0.1, p1.1, p2.1, p3.1)),
+                        i == count,
+                    );
+                }
+                point = (x, y);
+            }
+            PathSegment::ClosePath { .. } => {
+                if let Some(mut line) = current.take() {
+                    line.push(start, true);
+                    line.closed = true;
+                    finish(&mut lines, Some(line));
+                }
+                point = start;
+            }
+            _ => {}
+        }
+    }
+    finish(&mut lines, current);
+    lines
+}
+
+/// split polylines into dashes, `dashes` alternate between the lengths of
+/// dashes and gaps and the pattern restarts for each subpath
+pub(crate) fn dash(lines: &[Polyline], dashes: &[f64], offset: f64) -> Vec<Polyline> {
+    let total: f64 = dashes.iter().sum();
+    let mut result = vec![];
+    for line in lines {
+        // the position in the pattern at the start of the subpath
+        let mut index = 0;
+        let mut remaining = offset.rem_euclid(total);
+        while remaining >= dashes[index] {
+            remaining -= dashes[index];
+            index = (index + 1) % dashes.len();
+        }
+        remaining = dashes[index] - remaining;
+        let (mut points, mut corners) = (line.points.clone(), line.corners.clone());
+        if line.closed && points.last() != points.first() {
+            points.push(points[0]);
+            corners.push(true);
+        }
+        let mut current = (index % 2 == 0).then(|| Polyline::new(points[0]));
+        for i in 1..points.len() {
+            let (from, to) = (points[i - 1], points[i]);
+            let length = (to.0 - from.0).hypot(to.1 - from.1);
+            let mut done = 0.0;
+            while length - done > remaining {
+                done += remaining;
+                let t = done / length;
+                let point = (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+                match current.take() {
+                    Some(mut dash) => {
+                        dash.push(point, true);
+                        result.push(dash);
+                    }
+                    None => current = Some(Polyline::new(point)),
+                }
+                index = (index + 1) % dashes.len();
+                remaining = dashes[index];
+            }
+            remaining -= length - done;
+            if let Some(dash) = current.as_mut() {
+                dash.push(to, corners[i]);
+            }
+        }
+        result.extend(current);
+    }
+    result
+}
+
+/// A polygon which is filled with the nonzero rule, in a consistent orientation
+fn push_polygon(polygons: &mut Vec<Vec<Point>>, mut polygon: Vec<Point>) {
+    let area: f64 = (0..polygon.len())
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum();
+    if area < 0.0 {
+        polygon.reverse();
+    }
+    polygons.push(polygon);
+}
+
+fn circle((x, y): Point, radius: f64, tolerance: f64) -> Vec<Point> {
+    let count = if tolerance < radius {
+        (PI / (1.0 - tolerance / radius).acos()).ceil() as usize
+    } else {
+        0
+    };
+    let count = count.clamp(8, 1024);
+    (0..count)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f64 / count as f64;
+            (x + radius * angle.cos(), y + radius * angle.sin())
+        })
+        .collect()
+}
+
+/// the outline of the stroke of polylines, as polygons to fill with the nonzero rule
+///
+/// every segment is a rectangle, joins and caps add their own polygons. points
+/// inside a curve are joined with a bevel, which is within `tolerance`.
+pub(crate) fn stroke_polygons(
+    lines: &[Polyline],
+    stroke: &Stroke,
+    tolerance: f64,
+) -> Vec<Vec<Point>> {
+    let half = stroke.width / 2.0;
+    let mut polygons = vec![];
+    for line in lines {
+        let line = line.deduplicated();
+        let points = &line.points;
+        let count = points.len();
+        if count == 1 {
+            let (x, y) = points[0];
+            match stroke.line_cap {
+                LineCap::Butt => {}
+                LineCap::Round => push_polygon(&mut polygons, circle((x, y), half, tolerance)),
+                LineCap::Square => push_polygon(
+                    &mut polygons,
+                    vec![
+                        (x - half, y - half),
+                        (x + half, y - half),
+                        (x + half, y + half),
+                        (x - half, y + half),
+                    ],
+                ),
+            }
+            continue;
+        }
+        let segments = if line.closed { count } else { count - 1 };
+        let direction = |i: usize| {
+            let (a, b) = (points[i % count], points[(i + 1) % count]);
+            let length = (b.0 - a.0).hypot(b.1 - a.1);
+            ((b.0 - a.0) / length, (b.1 - a.1) / length)
+        };
+        for i in 0..segments {
+            let (a, b) = (points[i], points[(i + 1) % count]);
+            let (dx, dy) = direction(i);
+            let (nx, ny) = (-dy * half, dx * half);
+            push_polygon(
+                &mut polygons,
+                vec![
+                    (a.0 + nx, a.1 + ny),
+                    (b.0 + nx, b.1 + ny),
+                    (b.0 - nx, b.1 - ny),
+                    (a.0 - nx, a.1 - ny),
+                ],
+            );
+        }
+        let joins = if line.closed { 0..count } else { 1..count - 1 };
+        for i in joins {
+            let join = if line.corners[i] {
+                stroke.line_join
+            } else {
+                LineJoin::Bevel
+            };
+            let incoming = direction((i + count - 1) % count);
+            let outgoing = direction(i);
+            if let Some(polygon) =
+                join_polygon(points[i], incoming, outgoing, join, stroke, tolerance)
+            {
+                push_polygon(&mut polygons, polygon);
+            }
+        }
+        if !line.closed {
+            let (first, last) = (direction(0), direction(count - 2));
+            for (point, (dx, dy)) in [(points[0], (-first.0, -first.1)), (points[count - 1], last)]
+            {
+                match stroke.line_cap {
+                    LineCap::Butt => {}
+                    LineCap::Round => push_polygon(&mut polygons, circle(point, half, tolerance)),
+                    LineCap::Square => {
+                        let (nx, ny) = (-dy * half, dx * half);
+                        let (ex, ey) = (point.0 + dx * half, point.1 + dy * half);
+                        push_polygon(
+                            &mut polygons,
+                            vec![
+                                (point.0 + nx, point.1 + ny),
+                                (ex + nx, ey + ny),
+                                (ex - nx, ey - ny),
+                                (point.0 - nx, point.1 - ny),
+                            ],
+                        );
+                    }
+                }
+            }
+        }
+    }
+    polygons
+}
+
+/// the polygon filling the outer side of a join between two unit directions
+fn join_polygon(
+    (x, y): Point,
+    incoming: Point,
+    outgoing: Point,
+    join: LineJoin,
+    stroke: &Stroke,
+    tolerance: f64,
+) -> Option<Vec<Point>> {
+    let half = stroke.width / 2.0;
+    let cross = incoming.0 * outgoing.1 - incoming.1 * outgoing.0;
+    let dot = incoming.0 * outgoing.0 + incoming.1 * outgoing.1;
+    if cross.abs() < 1e-12 && dot > 0.0 {
+        return None;
+    }
+    if join == LineJoin::Round {
+        return Some(circle((x, y), half, tolerance));
+    }
+    // the offsets on the outer side of the turn
+    let side = if cross > 0.0 { -half } else { half };
+    let a = (x - incoming.1 * side, y + incoming.0 * side);
+    let b = (x - outgoing.1 * side, y + outgoing.0 * side);
+    if join == LineJoin::Bevel || cross.abs() < 1e-12 {
+        return Some(vec![(x, y), a, b]);
+    }
+    // the ratio of the miter length to the stroke width
+    let ratio = 1.0 / ((1.0 + dot) / 2.0).sqrt();
+    let (bx, by) = (incoming.0 - outgoing.0, incoming.1 - outgoing.1);
+    let length = bx.hypot(by);
+    let (bx, by) = (bx / length, by / length);
+    let tip = (x + bx * half * ratio, y + by * half * ratio);
+    if ratio <= stroke.miter_limit {
+        return Some(vec![(x, y), a, tip, b]);
+    }
+    if join == LineJoin::MiterClip {
+        // clip the miter at half the miter limit times the width from the vertex
+        let along = |(px, py): Point| (px - x) * bx + (py - y) * by;
+        let t = (stroke.miter_limit * half - along(a)) / (along(tip) - along(a));
+        let clip = |(px, py): Point| (px + (tip.0 - px) * t, py + (tip.1 - py) * t);
+        return Some(vec![(x, y), a, clip(a), clip(b), b]);
+    }
+    Some(vec![(x, y), a, b])
+}
+
+/// A edge of a polygon going down, `winding` is `1` if it was going down before
+struct Edge {
+    x0: f64,
+    y0: f64,
+    x1: f64,
+    y1: f64,
+    winding: i32,
+}
+
+/// add the coverage of the span from `x0` to `x1` to the pixels it crosses
+fn add_span(coverage: &mut [f32], x0: f64, x1: f64) {
+    let width = coverage.len() as f64;
+    let (x0, x1) = (x0.clamp(0.0, width), x1.clamp(0.0, width));
+    if x1 <= x0 {
+        return;
+    }
+    let (first, last) = (x0.floor() as usize, x1.floor() as usize);
+    if first == last {
+        coverage[first] += (x1 - x0) as f32;
+        return;
+    }
+    coverage[first] += (first as f64 + 1.0 - x0) as f32;
+    for pixel in &mut coverage[first + 1..last] {
+        *pixel += 1.0;
+    }
+    if last < coverage.len() {
+        coverage[last] += (x1 - last as f64) as f32;
+    }
+}
+
+/// a color as premultiplied channels in `0..=1`
+pub(crate) fn premultiplied(color: Rgba) -> [f32; 4] {
+    let alpha = color.alpha as f32;
+    [
+        color.red as f32 / 255.0 * alpha,
+        color.green as f32 / 255.0 * alpha,
+        color.blue as f32 / 255.0 * alpha,
+        alpha,
+    ]
+}
+
+/// A image of premultiplied RGBA colors in `0..=1`
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[f32; 4]>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: vec![[0.0; 4]; width * height],
+        }
+    }
+
+    /// a canvas of RGBA8 pixels with straight alpha
+    pub fn from_rgba8(width: usize, height: usize, data: &[u8]) -> Canvas {
+        let pixels = data
+            .chunks_exact(4)
+            .map(|pixel| {
+                premultiplied(Rgba::new(
+                    pixel[0],
+                    pixel[1],
+                    pixel[2],
+                    pixel[3] as f64 / 255.0,
+                ))
+            })
+            .collect();
+        Canvas {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// fill polygons in pixel coordinates with anti-aliasing, `shader` gives the
+    /// premultiplied color at the center of a pixel
+    pub fn fill(&mut self, polygons: &[Vec<Point>], rule: FillRule, shader: &Shader) {
+        let mut edges = vec![];
+        for polygon in polygons {
+            for i in 0..polygon.len() {
+                let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+                if a.1 == b.1
+                    || !(a.0.is_finite() && a.1.is_finite() && b.0.is_finite() && b.1.is_finite())
+                {
+                    continue;
+                }
+                let edge = if a.1 < b.1 {
+                    Edge {
+                        x0: a.0,
+                        y0: a.1,
+                        x1: b.0,
+                        y1: b.1,
+                        winding: 1,
+                    }
+                } else {
+                    Edge {
+                        x0: b.0,
+                        y0: b.1,
+                        x1: a.0,
+                        y1: a.1,
+                        winding: -1,
+                    }
+                };
+                edges.push(edge);
+            }
+        }
+        if edges.is_empty() {
+            return;
+        }
+        edges.sort_by(|a, b| a.y0.total_cmp(&b.y0));
+        let top = edges[0].y0.floor().max(0.0) as usize;
+        let bottom = edges
+            .iter()
+            .map(|edge| edge.y1)
+            .fold(f64::MIN, f64::max)
+            .ceil()
+            .min(self.height as f64);
+        if bottom <= 0.0 {
+            return;
+        }
+        let bottom = bottom as usize;
+        let mut coverage = vec![0.0f32; self.width];
+        let mut active: Vec<&Edge> = vec![];
+        let mut next = 0;
+        let mut crossings: Vec<(f64, i32)> = vec![];
+        for y in top..bottom {
+            let (row_top, row_bottom) = (y as f64, y as f64 + 1.0);
+            while next < edges.len() && edges[next].y0 < row_bottom {
+                active.push(&edges[next]);
+                next += 1;
+            }
+            active.retain(|edge| edge.y1 > row_top);
+            if active.is_empty() {
+                continue;
+            }
+            for sample in 0..SUBSAMPLES {
+                let sy = row_top + (sample as f64 + 0.5) / SUBSAMPLES as f64;
+                crossings.clear();
+                for edge in &active {
+                    if edge.y0 <= sy && sy < edge.y1 {
+                        let x =
+                            edge.x0 + (sy - edge.y0) * (edge.x1 - edge.x0) / (edge.y1 - edge.y0);
+                        crossings.push((x, edge.winding));
+                    }
+                }
+                crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+                let mut winding = 0;
+                for pair in crossings.windows(2) {
+                    winding += pair[0].1;
+                    let inside = match rule {
+                        FillRule::NonZero => winding != 0,
+                        FillRule::EvenOdd => winding % 2 != 0,
+                    };
+                    if inside {
+                        add_span(&mut coverage, pair[0].0, pair[1].0);
+                    }
+                }
+            }
+            let row = y * self.width;
+            for (x, amount) in coverage.iter_mut().enumerate() {
+                if *amount <= 0.0 {
+                    continue;
+                }
+                let alpha = (*amount / SUBSAMPLES as f32).min(1.0);
+                *amount = 0.0;
+                let source = shader(x, y);
+                let destination = &mut self.pixels[row + x];
+                let inverse = 1.0 - source[3] * alpha;
+                for channel in 0..4 {
+                    destination[channel] = source[channel] * alpha + destination[channel] * inverse;
+                }
+            }
+        }
+    }
+
+    /// draw a layer over the canvas with a opacity
+    pub fn composite(&mut self, layer: &Canvas, opacity: f32) {
+        for (destination, source) in self.pixels.iter_mut().zip(&layer.pixels) {
+            let inverse = 1.0 - source[3] * opacity;
+            for channel in 0..4 {
+                destination[channel] = source[channel] * opacity + destination[channel] * inverse;
+            }
+        }
+    }
+
+    /// multiply each pixel by the coverage of a clip path or a mask at the pixel
+    pub fn mask(&mut self, coverage: &[f32]) {
+        for (pixel, coverage) in self.pixels.iter_mut().zip(coverage) {
+            for channel in pixel.iter_mut() {
+                *channel *= coverage;
+            }
+        }
+    }
+
+    /// the pixels as RGBA8 with straight alpha
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(self.pixels.len() * 4);
+        for pixel in &self.pixels {
+            let alpha = pixel[3].clamp(0.0, 1.0);
+            for channel in &pixel[..3] {
+                let value = if alpha > 0.0 { channel / alpha } else { 0.0 };
+                data.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+            data.push((alpha * 255.0).round() as u8);
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::clip::FillRule;
+    use crate::raster::{dash, flatten, stroke_polygons, Canvas, Polyline};
+    use crate::{LineCap, LineJoin, PathData, Stroke};
+
+    fn polylines(d: &str) -> Vec<Polyline> {
+        flatten(&PathData::parse(d).unwrap(), 0.1)
+    }
+
+    fn coverage(polygons: &[Vec<(f64, f64)>], rule: FillRule) -> Canvas {
+        let mut canvas = Canvas::new(10, 10);
+        canvas.fill(polygons, rule, &|_, _| [1.0; 4]);
+        canvas
+    }
+
+    #[test]
+    fn test_flatten() {
+        let lines = polylines("M0 0 L10 0 10 10Z M20 20 M5 5 C5 10 10 10 10 5");
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0].points,
+            vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 0.0)]
+        );
+        assert!(lines[0].closed);
+        let curve = &lines[1];
+        assert!(curve.points.len() > 3);
+        assert_eq!(curve.points.last(), Some(&(10.0, 5.0)));
+        assert!(!curve.corners[1] && *curve.corners.last().unwrap());
+    }
+
+    #[test]
+    fn test_dash() {
+        let dashes = dash(&polylines("M0 0 H10"), &[3.0, 1.0], 1.0);
+        let ends: Vec<_> = dashes
+            .iter()
+            .map(|line| (line.points[0].0, line.points.last().unwrap().0))
+            .collect();
+        assert_eq!(ends, vec![(0.0, 2.0), (3.0, 6.0), (7.0, 10.0)]);
+        // a closed subpath continues the pattern through its closing segment
+        let dashes = dash(&polylines("M0 0 H4 V4 H0Z"), &[6.0, 2.0], 0.0);
+        assert_eq!(dashes.len(), 2);
+        assert_eq!(dashes[1].points, vec![(4.0, 4.0), (0.0, 4.0), (0.0, 2.0)]);
+    }
+
+    #[test]
+    fn test_fill() {
+        let square = vec![(2.0, 2.0), (6.0, 2.0), (6.0, 6.0), (2.0, 6.0)];
+        let canvas = coverage(std::slice::from_ref(&square), FillRule::NonZero);
+        assert_eq!(canvas.pixels[3 * 10 + 3], [1.0; 4]);
+        assert_eq!(canvas.pixels[10 + 3], [0.0; 4]);
+        // a half covered pixel
+        let canvas = coverage(
+            &[vec![(0.0, 0.0), (1.5, 0.0), (1.5, 1.0), (0.0, 1.0)]],
+            FillRule::NonZero,
+        );
+        assert_eq!(canvas.pixels[1][3], 0.5);
+        // a inner square of the same direction is a hole with evenodd only
+        let inner = vec![(3.0, 3.0), (5.0, 3.0), (5.0, 5.0), (3.0, 5.0)];
+        let polygons = [square, inner];
+        assert_eq!(
+            coverage(&polygons, FillRule::NonZero).pixels[4 * 10 + 4][3],
+            1.0
+        );
+        assert_eq!(
+            coverage(&polygons, FillRule::EvenOdd).pixels[4 * 10 + 4][3],
+            0.0
+        );
+        let rgba = coverage(&polygons, FillRule::EvenOdd).to_rgba8();
+        assert_eq!(
+            &rgba[(2 * 10 + 2) * 4..(2 * 10 + 3) * 4],
+            &[255, 255, 255, 255]
+        );
+    }
+
+    #[test]
+    fn test_stroke() {
+        let stroke = Stroke {
+            width: 2.0,
+            ..Default::default()
+        };
+        let fill = |d: &str, stroke: &Stroke| {
+            let mut canvas = Canvas::new(10, 10);
+            let polygons = stroke_polygons(&polylines(d), stroke, 0.1);
+            canvas.fill(&polygons, FillRule::NonZero, &|_, _| [1.0; 4]);
+            canvas
+        };
+        let alpha = |canvas: &Canvas, x: usize, y: usize| canvas.pixels[y * 10 + x][3];
+        let canvas = fill("M2 5 H8", &stroke);
+        assert_eq!(alpha(&canvas, 5, 4), 1.0);
+        assert_eq!(alpha(&canvas, 5, 6), 0.0);
+        assert_eq!(alpha(&canvas, 1, 4), 0.0);
+        let square = Stroke {
+            line_cap: LineCap::Square,
+            ..stroke
+        };
+        assert_eq!(alpha(&fill("M2 5 H8", &square), 1, 4), 1.0);
+        // overlapping parts of the stroke are not painted twice or cancelled
+        let canvas = fill("M2 2 H8 V8 H2Z", &stroke);
+        assert_eq!(alpha(&canvas, 1, 1), 1.0);
+        assert_eq!(alpha(&canvas, 8, 8), 1.0);
+        assert_eq!(alpha(&canvas, 5, 5), 0.0);
+        let bevel = Stroke {
+            line_join: LineJoin::Bevel,
+            ..stroke
+        };
+        assert_eq!(alpha(&fill("M2 2 H8 V8 H2Z", &bevel), 1, 1), 0.5);
+        // a dot with a round cap
+        assert!(
+            alpha(
+                &fill(
+                    "M5 5 Z",
+                    &Stroke {
+                        line_cap: LineCap::Round,
+                        ..stroke
+                    }
+                ),
+                5,
+                5
+            ) > 0.6
+        );
+        assert_eq!(alpha(&fill("M5 5 Z", &stroke), 5, 5), 0.0);
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::Element;
+use crate::document::Document;
+
+/// A reference from a attribute to a element of the document
+///
+/// `source` the element carrying the attribute
+///
+/// `attribute` the name of the attribute, e.g. `fill` or `xlink:href`
+///
+/// `id` the id which is referenced
+///
+/// `target` the referenced element, `None` if the reference is dangling
+#[derive(Debug, Clone)]
+pub struct Reference<'a> {
+    pub source: Rc<Element<'a>>,
+    pub attribute: String,
+    pub id: String,
+    pub target: Option<Rc<Element<'a>>>,
+}
+
+impl<'a> Reference<'a> {
+    /// whether the referenced id is missing in the document
+    pub fn is_dangling(&self) -> bool {
+        self.target.is_none()
+    }
+}
+
+/// parse the ids of every `url(#id)` in a attribute value
+///
+/// ## Example
+/// ``` ignore
+/// url(#a) -> ["a"]
+/// url('#a') red -> ["a"]
+/// fill:url(#a);stroke:url("#b") -> ["a", "b"]
+/// ```
+pub(crate) fn url_ids(value: &str) -> Vec<&str> {
+    let mut ids = vec![];
+    let mut rest = value;
+    while let Some(start) = rest.find("url(") {
+        rest = &rest[start + 4..];
+        let end = match rest.find(')') {
+            Some(end) => end,
+            None => break,
+        };
+        let inner = rest[..end].trim().trim_matches(|c| c == '"' || c == '\'');
+        if let Some(id) = inner.trim().strip_prefix('#') {
+            if !id.is_empty() {
+                ids.push(id);
+            }
+        }
+        rest = &rest[end + 1..];
+    }
+    ids
+}
+
+/// parse the id of a `href` value
+///
+/// ## Example
+/// ``` ignore
+/// #a -> Some("a")
+/// image.png -> None
+/// ```
+pub(crate) fn href_id(value: &str) -> Option<&str> {
+    value.trim().strip_prefix('#').filter(|id| !id.is_empty())
+}
+
+/// the id referenced by the `href` of a element, `href` wins over `xlink:href`
+pub(crate) fn element_href(ele: &Element) -> Option<String> {
+    let attributes = ele.attributes.borrow();
+    attributes
+        .get("href")
+        .or_else(|| attributes.get("xlink:href"))
+        .and_then(|value| href_id(value))
+        .map(String::from)
+}
+
+/// elements whose `href` pulls the referenced content into their rendering
+const RENDERING_HREF_ELEMENTS: [&str; 8] = [
+    "use",
+    "linearGradient",
+    "radialGradient",
+    "pattern",
+    "filter",
+    "feImage",
+    "textPath",
+    "tref",
+];
+
+/// properties whose `url(#id)` pulls the referenced content into the rendering
+const RENDERING_URL_ATTRIBUTES: [&str; 9] = [
+    "fill",
+    "stroke",
+    "clip-path",
+    "mask",
+    "filter",
+    "marker-start",
+    "marker-mid",
+    "marker-end",
+    "style",
+];
+
+/// whether a reference is followed when rendering, hyperlinks like `<a href>` are not
+fn is_rendering_reference(reference: &Reference) -> bool {
+    match reference.attribute.as_str() {
+        "href" | "xlink:href" => RENDERING_HREF_ELEMENTS.contains(&reference.source.ele_type),
+        attribute => RENDERING_URL_ATTRIBUTES.contains(&attribute),
+    }
+}
+
+/// the ids referenced by a attribute
+fn attribute_ids<'v>(key: &str, value: &'v str) -> Vec<&'v str> {
+    match key {
+        "href" | "xlink:href" => href_id(value).into_iter().collect(),
+        _ => url_ids(value),
+    }
+}
+
+impl<'a> Document<'a> {
+    /// resolve a reference value to the element it points at
+    ///
+    /// both `url(#id)` values and `#id` values are accepted.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use svg_simple_parser::parse_document;
+    ///
+    /// let (_, document) = parse_document(r#"<svg><linearGradient id="g1"/><rect fill="url(#g1)"/></svg>"#).unwrap();
+    /// assert_eq!(document.resolve_reference("url(#g1)").unwrap().ele_type, "linearGradient");
+    /// assert_eq!(document.resolve_reference("#g1").unwrap().ele_type, "linearGradient");
+    /// assert!(document.resolve_reference("red").is_none());
+    /// ```
+    ///
+    pub fn resolve_reference(&self, value: &str) -> Option<Rc<Element<'a>>> {
+        let id = href_id(value).or_else(|| url_ids(value).into_iter().next())?;
+        self.element_by_id(id)
+    }
+
+    /// every reference in the document, in document order
+    ///
+    /// a reference is a `url(#id)` in any attribute (`fill`, `clip-path`, `mask`,
+    /// `filter`, `marker-start`, `style`...) or a `#id` in `href`/`xlink:href`.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use svg_simple_parser::parse_document;
+    ///
+    /// let (_, document) = parse_document(r##"<svg><symbol id="s"/><use href="#s"/><rect mask="url(#m)"/></svg>"##).unwrap();
+    /// let references = document.references();
+    /// assert_eq!(references.len(), 2);
+    /// assert_eq!(references[0].attribute, "href");
+    /// assert_eq!(references[0].target.as_ref().unwrap().ele_type, "symbol");
+    /// assert!(references[1].is_dangling());
+    /// ```
+    ///
+    pub fn references(&self) -> Vec<Reference<'a>> {
+        let mut elements = vec![self.root.clone()];
+        elements.append(&mut self.root.descendants());
+        elements
+            .iter()
+            .flat_map(|ele| self.element_references(ele))
+            .collect()
+    }
+
+    /// the references of a single element, sorted by attribute name
+    pub fn element_references(&self, ele: &Rc<Element<'a>>) -> Vec<Reference<'a>> {
+        let mut attributes: Vec<(String, String)> = ele
+            .attributes
+            .borrow()
+            .iter()
+            .map(|(k, v)| (k.clone(), v.to_string()))
+            .collect();
+        attributes.sort();
+        attributes
+            .iter()
+            .flat_map(|(key, value)| {
+                attribute_ids(key, value)
+                    .into_iter()
+                    .map(|id| Reference {
+                        source: ele.clone(),
+                        attribute: key.clone(),
+                        id: id.to_owned(),
+                        target: self.element_by_id(id),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// every reference whose target does not exist
+    pub fn dangling_references(&self) -> Vec<Reference<'a>> {
+        self.references()
+            .into_iter()
+            .filter(Reference::is_dangling)
+            .collect()
+    }
+
+    /// every reference cycle in the document
+    ///
+    /// a referenced element brings its subtree with it, so a element which
+    /// references itself, one of its ancestors, or a element whose subtree
+    /// references it back forms a cycle. each cycle is returned as the list of
+    /// references walked through. only references followed when rendering
+    /// count, a hyperlink `<a href="#id">` never forms a cycle.
+    ///
+    /// ## Example
+    ///
+    /// ``` rust
+    /// use svg_simple_parser::parse_document;
+    ///
+    /// let (_, document) = parse_document(r##"<svg>
+    ///     <linearGradient id="a" href="#b"/>
+    ///     <linearGradient id="b" href="#a"/>
+    /// </svg>"##).unwrap();
+    /// let cycles = document.reference_cycles();
+    /// assert_eq!(cycles.len(), 1);
+    /// assert_eq!(cycles[0].len(), 2);
+    /// ```
+    ///
+    pub fn reference_cycles(&self) -> Vec<Vec<Reference<'a>>> {
+        let mut walker = CycleWalker {
+            document: self,
+            state: HashMap::new(),
+            stack: vec![],
+            cycles: vec![],
+        };
+        walker.visit(&self.root, None);
+        walker.cycles
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum VisitState {
+    Visiting,
+    Done,
+}
+
+/// depth first walker over the child and reference edges of the tree
+struct CycleWalker<'d, 'a> {
+    document: &'d Document<'a>,
+    state: HashMap<*const Element<'a>, VisitState>,
+    /// the elements being visited, with the reference used to reach them
+    stack: Vec<(*const Element<'a>, Option<Reference<'a>>)>,
+    cycles: Vec<Vec<Reference<'a>>>,
+}
+
+impl<'d, 'a> CycleWalker<'d, 'a> {
+    fn visit(&mut self, ele: &Rc<Element<'a>>, via: Option<Reference<'a>>) {
+        let key = Rc::as_ptr(ele);
+        match self.state.get(&key) {
+            Some(VisitState::Done) => return,
+            Some(VisitState::Visiting) => {
+                let start = self
+                    .stack
+                    .iter()
+                    .rposition(|(node, _)| *node == key)
+                    .unwrap_or(0);
+                let mut cycle: Vec<Reference<'a>> = self.stack[start + 1..]
+                    .iter()
+                    .filter_map(|(_, reference)| reference.clone())
+                    .collect();
+                cycle.extend(via);
+                self.cycles.push(cycle);
+                return;
+            }
+            None => {}
+        }
+        self.state.insert(key, VisitState::Visiting);
+        self.stack.push((key, via));
+        for child in ele.children.borrow().iter() {
+            self.visit(child, None);
+        }
+        for reference in self.document.element_references(ele) {
+            if !is_rendering_reference(&reference) {
+                continue;
+            }
+            if let Some(target) = reference.target.clone() {
+                self.visit(&target, Some(reference));
+            }
+        }
+        self.stack.pop();
+        self.state.insert(key, VisitState::Done);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_document;
+    use crate::reference::{href_id, url_ids};
+
+    #[test]
+    fn test_url_ids() {
+        assert_eq!(url_ids("url(#a)"), vec!["a"]);
+        assert_eq!(url_ids("url( '#a' ) red"), vec!["a"]);
+        assert_eq!(url_ids("fill:url(#a);stroke:url(\"#b\")"), vec!["a", "b"]);
+        assert!(url_ids("url(image.png)").is_empty());
+        assert!(url_ids("red").is_empty());
+    }
+
+    #[test]
+    fn test_href_id() {
+        assert_eq!(href_id("#a"), Some("a"));
+        assert_eq!(href_id(" #a "), Some("a"));
+        assert_eq!(href_id("image.png"), None);
+        assert_eq!(href_id("#"), None);
+    }
+
+    #[test]
+    fn test_references() {
+        let (_, document) = parse_document(
+            r##"<svg>
+                <defs>
+                    <linearGradient id="g1"/>
+                    <clipPath id="c1"><rect width="10"/></clipPath>
+                    <marker id="arrow"/>
+                </defs>
+                <path fill="url(#g1)" clip-path="url(#c1)" marker-end="url(#arrow)"/>
+                <use xlink:href="#missing"/>
+                <textPath href="#p1"/>
+            </svg>"##,
+        )
+        .unwrap();
+        let references = document.references();
+        let summary: Vec<(&str, &str, &str, bool)> = references
+            .iter()
+            .map(|r| {
+                (
+                    r.source.ele_type,
+                    r.attribute.as_str(),
+                    r.id.as_str(),
+                    r.is_dangling(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("path", "clip-path", "c1", false),
+                ("path", "fill", "g1", false),
+                ("path", "marker-end", "arrow", false),
+                ("use", "xlink:href", "missing", true),
+                ("textPath", "href", "p1", true),
+            ]
+        );
+        assert_eq!(document.dangling_references().len(), 2);
+        assert!(document.reference_cycles().is_empty());
+    }
+
+    #[test]
+    fn test_reference_cycles() {
+        let (_, document) = parse_document(
+            r##"<svg>
+                <linearGradient id="a" href="#b"/>
+                <linearGradient id="b" href="#c"/>
+                <linearGradient id="c" href="#a"/>
+                <g id="layer"><use href="#layer"/></g>
+                <use href="#a"/>
+                <g id="nav"><a href="#nav"><rect/></a></g>
+            </svg>"##,
+        )
+        .unwrap();
+        let cycles = document.reference_cycles();
+        assert_eq!(cycles.len(), 2);
+        let ids: Vec<&str> = cycles[0].iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "c", "a"]);
+        assert_eq!(cycles[1].len(), 1);
+        assert_eq!(cycles[1][0].source.ele_type, "use");
+        assert_eq!(cycles[1][0].id, "layer");
+    }
+}
@@ -0,0 +1,561 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::ast::Element;
+use crate::bbox::{element_bbox, style_dashes, style_stroke, BBox, CONTAINERS};
+use crate::clip::{ClipPath, FillRule, Mask, MaskType};
+use crate::color::{opacity, Rgba};
+use crate::css::ComputedStyles;
+use crate::document::{drawing_copy, Document};
+use crate::effects::apply_filter;
+use crate::length::LengthContext;
+use crate::paint::{Paint, Pattern, Units};
+use crate::png::encode_png;
+use crate::raster::{dash, flatten, premultiplied, stroke_polygons, Canvas, Point, Shader};
+use crate::shape::shape_path_data;
+use crate::text::Fonts;
+use crate::transform::Matrix;
+use crate::view_box::ViewBox;
+
+/// the largest distance in pixels between a curve and its flattened polyline
+const TOLERANCE: f64 = 0.1;
+
+/// the largest width or height in pixels of a rendered pattern tile
+const MAX_TILE: f64 = 4096.0;
+
+/// A RGBA8 image with straight alpha, rows from the top
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pixmap {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Pixmap {
+    /// the color of a pixel, `None` outside of the image
+    pub fn pixel(&self, x: u32, y: u32) -> Option<Rgba> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let i = (y * self.width + x) as usize * 4;
+        let [red, green, blue, alpha] = self.data[i..i + 4] else {
+            unreachable!()
+        };
+        Some(Rgba::new(red, green, blue, alpha as f64 / 255.0))
+    }
+
+    /// encode the image as a PNG file
+    pub fn encode_png(&self) -> Vec<u8> {
+        encode_png(self.width, self.height, &self.data)
+    }
+}
+
+/// render a svg tree to a image of `width` by `height` pixels
+///
+/// the `viewBox` of the root, or its `width` and `height`, is fit into the
+/// image following `preserveAspectRatio`. a copy of the tree is rendered with
+/// its `<use>` expanded and its stylesheets applied, see `expand_use` and
+/// `inline_styles`. shapes are filled and stroked with solid colors or
+/// gradients or patterns, groups with a filter, a clip path, a mask or a
+/// `opacity` are composited, markers are drawn on paths and lines. text is
+/// not rendered without fonts, see `render_with_fonts`, nor are images.
+///
+/// ## Example
+/// ```rust
+/// use svg_simple_parser::{parse, render, Rgba};
+///
+/// let (_, root) = parse(r#"<svg viewBox="0 0 10 10"><rect width="5" height="10" fill="red"/></svg>"#).unwrap();
+/// let pixmap = render(&root, 20, 20);
+/// assert_eq!(pixmap.pixel(4, 10), Some(Rgba::new(255, 0, 0, 1.0)));
+/// assert_eq!(pixmap.pixel(15, 10), Some(Rgba::TRANSPARENT));
+/// assert_eq!(&pixmap.encode_png()[1..4], b"PNG");
+/// ```
+///
+pub fn render(root: &Rc<Element>, width: u32, height: u32) -> Pixmap {
+    render_with_fonts(root, width, height, &Fonts::default())
+}
+
+/// render a svg tree like `render`, with its text laid out with a set of fonts
+///
+/// the text is drawn as the outlines of its glyphs, see `text_to_paths`.
+///
+/// ## Example
+/// ```rust,no_run
+/// use svg_simple_parser::{parse, render_with_fonts, Fonts};
+///
+/// let mut fonts = Fonts::new();
+/// fonts.load("DejaVuSans.ttf").unwrap();
+/// let (_, root) = parse(r#"<svg viewBox="0 0 40 20"><text y="15">Hi</text></svg>"#).unwrap();
+/// let pixmap = render_with_fonts(&root, 80, 40, &fonts);
+/// ```
+///
+pub fn render_with_fonts(root: &Rc<Element>, width: u32, height: u32, fonts: &Fonts) -> Pixmap {
+    let document = drawing_copy(root, fonts);
+    let root = document.root.clone();
+    let size = (width as f64, height as f64);
+    let context = LengthContext {
+        viewport: size,
+        ..Default::default()
+    };
+    let view_box = match root.view_box() {
+        Ok(Some(view_box)) => Some(view_box),
+        _ => match (
+            root.user_length("width", &context),
+            root.user_length("height", &context),
+        ) {
+            (Ok(Some(w)), Ok(Some(h))) if w > 0.0 && h > 0.0 => Some(ViewBox::new(0.0, 0.0, w, h)),
+            _ => None,
+        },
+    };
+    let matrix = view_box.map_or(Matrix::default(), |view_box| {
+        let aspect = root.preserve_aspect_ratio().unwrap_or_default();
+        view_box.transform((0.0, 0.0, size.0, size.1), &aspect)
+    });
+    let renderer = Renderer {
+        document: &document,
+        styles: ComputedStyles::new(document.stylesheet()),
+        context: LengthContext {
+            viewport: view_box.map_or(size, |view_box| (view_box.width, view_box.height)),
+            ..Default::default()
+        },
+        rendering: RefCell::new(vec![]),
+    };
+    let mut canvas = Canvas::new(width as usize, height as usize);
+    renderer.render_element(&root, &matrix, &mut canvas);
+    Pixmap {
+        width,
+        height,
+        data: canvas.to_rgba8(),
+    }
+}
+
+struct Renderer<'d, 'a> {
+    document: &'d Document<'a>,
+    /// the computed styles of the prepared tree
+    styles: ComputedStyles<'a>,
+    context: LengthContext,
+    /// the patterns, masks and markers whose content is being rendered
+    rendering: RefCell<Vec<Rc<Element<'a>>>>,
+}
+
+impl<'d, 'a> Renderer<'d, 'a> {
+    /// draw a element and its subtree, `matrix` maps its user space to pixels
+    fn render_element(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, canvas: &mut Canvas) {
+        if ele.get_attribute("display").as_deref().map(str::trim) == Some("none") {
+            return;
+        }
+        let alpha = ele
+            .get_attribute("opacity")
+            .and_then(|value| opacity(&value))
+            .unwrap_or(1.0);
+        if alpha <= 0.0 {
+            return;
+        }
+        let clip_path = self.document.resolve_clip_path_with(&self.styles, ele);
+        let mask = self.document.resolve_mask(ele, &self.context);
+        let filter = self.document.resolve_filter(ele, &self.context);
+        if alpha < 1.0 || clip_path.is_some() || mask.is_some() || filter.is_some() {
+            let mut layer = Canvas::new(canvas.width, canvas.height);
+            self.render_content(ele, matrix, &mut layer);
+            let bbox = element_bbox(ele, &Matrix::default(), &self.styles, false);
+            if let Some(filter) = filter {
+                let Some(filtered) = apply_filter(&filter, &layer, matrix, bbox) else {
+                    return;
+                };
+                layer = filtered;
+            }
+            if let Some(clip_path) = clip_path {
+                // a clip path which can not be resolved clips everything
+                let Some(coverage) = self.clip_coverage(&clip_path, matrix, bbox, canvas) else {
+                    return;
+                };
+                layer.mask(&coverage);
+            }
+            if let Some(mask) = mask {
+                let Some(coverage) = self.mask_coverage(&mask, matrix, bbox, canvas) else {
+                    return;
+                };
+                layer.mask(&coverage);
+            }
+            canvas.composite(&layer, alpha as f32);
+        } else {
+            self.render_content(ele, matrix, canvas);
+        }
+    }
+
+    fn render_content(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, canvas: &mut Canvas) {
+        if !CONTAINERS.contains(&ele.ele_type) {
+            self.draw_shape(ele, matrix, canvas);
+            return;
+        }
+        self.render_children(ele, matrix, canvas);
+    }
+
+    /// draw the children of a element, a `switch` draws its first child only
+    fn render_children(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, canvas: &mut Canvas) {
+        let children = ele.children.borrow().clone();
+        let count = if ele.ele_type == "switch" {
+            1
+        } else {
+            children.len()
+        };
+        for child in children.iter().take(count) {
+            let mut m = *matrix * child.transform().unwrap_or_default().to_matrix();
+            if child.ele_type == "svg" {
+                m = m * child.viewport_transform(&self.context).unwrap_or_default();
+            }
+            self.render_element(child, &m, canvas);
+        }
+    }
+
+    /// fill and stroke a path or basic shape
+    fn draw_shape(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, canvas: &mut Canvas) {
+        let path = match ele.ele_type {
+            "path" => ele.path_data().ok(),
+            _ => shape_path_data(ele),
+        };
+        let Some(path) = path else {
+            return;
+        };
+        let style = self.styles.style(ele);
+        if matches!(
+            style.get("visibility").map(str::trim),
+            Some("hidden" | "collapse")
+        ) {
+            return;
+        }
+        let scale = matrix.determinant().abs().sqrt();
+        if scale == 0.0 || !scale.is_finite() {
+            return;
+        }
+        let tolerance = TOLERANCE / scale;
+        let bbox = path.bbox();
+        let lines = flatten(&path, tolerance);
+        let device = |polygon: Vec<Point>| -> Vec<Point> {
+            polygon
+                .into_iter()
+                .map(|(x, y)| matrix.apply(x, y))
+                .collect()
+        };
+        let fill = self
+            .document
+            .resolve_paint_with(&self.styles, ele, "fill", &self.context);
+        if let Some(shader) = fill.and_then(|paint| self.shader(&paint, matrix, bbox)) {
+            let rule = style
+                .get("fill-rule")
+                .and_then(FillRule::parse)
+                .unwrap_or_default();
+            let polygons: Vec<Vec<Point>> = lines
+                .iter()
+                .map(|line| device(line.points.clone()))
+                .collect();
+            canvas.fill(&polygons, rule, &shader);
+        }
+        let stroke = self
+            .document
+            .resolve_paint_with(&self.styles, ele, "stroke", &self.context)
+            .and_then(|paint| self.shader(&paint, matrix, bbox))
+            .zip(style_stroke(&style));
+        if let Some((shader, stroke)) = stroke {
+            let lines = match style_dashes(&style, &self.context) {
+                Some((dashes, offset)) => dash(&lines, &dashes, offset),
+                None => lines,
+            };
+            let polygons: Vec<Vec<Point>> = stroke_polygons(&lines, &stroke, tolerance)
+                .into_iter()
+                .map(device)
+                .collect();
+            canvas.fill(&polygons, FillRule::NonZero, &shader);
+        }
+        self.draw_markers(ele, matrix, canvas);
+    }
+
+    /// draw the markers of a shape, clipped to their viewport
+    fn draw_markers(&self, ele: &Rc<Element<'a>>, matrix: &Matrix, canvas: &mut Canvas) {
+        for instance in self.document.markers_with(&self.styles, ele, &self.context) {
+            let marker = &instance.marker;
+            // a marker drawn in its own content is left out
+            if self.is_rendering(&marker.element) {
+                continue;
+            }
+            let m = *matrix * instance.transform;
+            self.rendering.borrow_mut().push(marker.element.clone());
+            match marker.clip_rect() {
+                Some(clip) => {
+                    let mut layer = Canvas::new(canvas.width, canvas.height);
+                    self.render_children(&marker.element, &m, &mut layer);
+                    layer.mask(&rect_coverage(&clip, &m, canvas));
+                    canvas.composite(&layer, 1.0);
+                }
+                None => self.render_children(&marker.element, &m, canvas),
+            }
+            self.rendering.borrow_mut().pop();
+        }
+    }
+
+    /// the coverage of each pixel by a clip path, `matrix` maps the user space of
+    /// the clipped element with the bounding box `bbox` to pixels
+    fn clip_coverage(
+        &self,
+        clip_path: &ClipPath<'a>,
+        matrix: &Matrix,
+        bbox: Option<BBox>,
+        canvas: &Canvas,
+    ) -> Option<Vec<f32>> {
+        let units = match bbox {
+            Some(bbox) => clip_path.to_user_space(&bbox)?,
+            None if clip_path.units == Units::UserSpaceOnUse => clip_path.transform,
+            None => return None,
+        };
+        let mut union = Canvas::new(canvas.width, canvas.height);
+        for shape in &clip_path.shapes {
+            let m = *matrix * units * shape.transform;
+            let path = match shape.element.ele_type {
+                "path" => shape.element.path_data().ok(),
+                _ => shape_path_data(&shape.element),
+            };
+            let Some(path) = path else {
+                continue;
+            };
+            let scale = m.determinant().abs().sqrt();
+            if scale == 0.0 || !scale.is_finite() {
+                continue;
+            }
+            let polygons: Vec<Vec<Point>> = flatten(&path, TOLERANCE / scale)
+                .into_iter()
+                .map(|line| line.points.iter().map(|(x, y)| m.apply(*x, *y)).collect())
+                .collect();
+            let mut layer = Canvas::new(canvas.width, canvas.height);
+            layer.fill(&polygons, shape.clip_rule, &|_, _| [1.0; 4]);
+            if let Some(clip_path) = &shape.clip_path {
+                layer.mask(&self.clip_coverage(clip_path, &m, path.bbox(), canvas)?);
+            }
+            union.composite(&layer, 1.0);
+        }
+        if let Some(clip_path) = &clip_path.clip_path {
+            union.mask(&self.clip_coverage(clip_path, matrix, bbox, canvas)?);
+        }
+        Some(union.pixels.iter().map(|pixel| pixel[3]).collect())
+    }
+
+    /// the coverage of each pixel by a mask, `matrix` maps the user space of the
+    /// masked element with the bounding box `bbox` to pixels
+    fn mask_coverage(
+        &self,
+        mask: &Mask<'a>,
+        matrix: &Matrix,
+        bbox: Option<BBox>,
+        canvas: &Canvas,
+    ) -> Option<Vec<f32>> {
+        // a mask drawn in its own content hides everything
+        if self.is_rendering(&mask.element) {
+            return None;
+        }
+        let bbox = bbox?;
+        let region = mask.region(&bbox)?;
+        let content = mask.content_transform(&bbox)?;
+        let mut layer = Canvas::new(canvas.width, canvas.height);
+        self.rendering.borrow_mut().push(mask.element.clone());
+        self.render_children(&mask.element, &(*matrix * content), &mut layer);
+        self.rendering.borrow_mut().pop();
+        let inside = rect_coverage(&region, matrix, canvas);
+        let mut coverage: Vec<f32> = layer
+            .pixels
+            .iter()
+            .zip(&inside)
+            .map(|(pixel, inside)| {
+                let value = match mask.mask_type {
+                    // the channels are premultiplied by the alpha already
+                    MaskType::Luminance => {
+                        0.2125 * pixel[0] + 0.7154 * pixel[1] + 0.0721 * pixel[2]
+                    }
+                    MaskType::Alpha => pixel[3],
+                };
+                value.clamp(0.0, 1.0) * inside
+            })
+            .collect();
+        if let Some(mask) = &mask.mask {
+            let inner = self.mask_coverage(mask, matrix, Some(bbox), canvas)?;
+            for (value, inner) in coverage.iter_mut().zip(inner) {
+                *value *= inner;
+            }
+        }
+        Some(coverage)
+    }
+
+    /// whether the content of a pattern, a mask or a marker is being rendered
+    fn is_rendering(&self, ele: &Rc<Element<'a>>) -> bool {
+        self.rendering
+            .borrow()
+            .iter()
+            .any(|other| Rc::ptr_eq(other, ele))
+    }
+
+    /// the color of a paint at each pixel, `None` if nothing is painted. a paint
+    /// server in bounding box units needs the box of the shape in user space.
+    fn shader(
+        &self,
+        paint: &Paint<'a>,
+        matrix: &Matrix,
+        bbox: Option<BBox>,
+    ) -> Option<Box<Shader>> {
+        match paint {
+            Paint::Color(color) => {
+                let color = premultiplied(*color);
+                Some(Box::new(move |_, _| color))
+            }
+            Paint::Gradient(gradient) => {
+                let units = gradient.units.to_user_space(&bbox?)?;
+                let inverse = (*matrix * units * gradient.transform).invert()?;
+                let gradient = gradient.clone();
+                Some(Box::new(move |x, y| {
+                    let (gx, gy) = inverse.apply(x as f64 + 0.5, y as f64 + 0.5);
+                    gradient
+                        .color_at(gradient.offset_at(gx, gy))
+                        .map_or([0.0; 4], premultiplied)
+                }))
+            }
+            Paint::Pattern(pattern) => self.pattern_shader(pattern, matrix, &bbox?),
+        }
+    }
+
+    /// render a tile of a pattern at the resolution of the device, and repeat it
+    fn pattern_shader(
+        &self,
+        pattern: &Pattern<'a>,
+        matrix: &Matrix,
+        bbox: &BBox,
+    ) -> Option<Box<Shader>> {
+        // a pattern drawn in its own content paints nothing
+        if self.is_rendering(&pattern.content) {
+            return None;
+        }
+        let tile = pattern.tile(bbox)?;
+        let content = pattern.content_transform(bbox)?;
+        let m = *matrix * pattern.transform;
+        let inverse = m.invert()?;
+        let size = |scale: f64, length: f64| (length * scale).ceil().clamp(1.0, MAX_TILE) as usize;
+        let columns = size(m.a.hypot(m.b), tile.width());
+        let rows = size(m.c.hypot(m.d), tile.height());
+        let (scale_x, scale_y) = (columns as f64 / tile.width(), rows as f64 / tile.height());
+        let mut canvas = Canvas::new(columns, rows);
+        let to_tile =
+            Matrix::scale(scale_x, scale_y) * Matrix::translate(-tile.min_x, -tile.min_y) * content;
+        self.rendering.borrow_mut().push(pattern.content.clone());
+        self.render_children(&pattern.content, &to_tile, &mut canvas);
+        self.rendering.borrow_mut().pop();
+        let opacity = pattern.opacity as f32;
+        Some(Box::new(move |x, y| {
+            let (px, py) = inverse.apply(x as f64 + 0.5, y as f64 + 0.5);
+            let column = ((px - tile.min_x).rem_euclid(tile.width()) * scale_x) as usize;
+            let row = ((py - tile.min_y).rem_euclid(tile.height()) * scale_y) as usize;
+            let pixel = canvas.pixels[row.min(rows - 1) * columns + column.min(columns - 1)];
+            pixel.map(|channel| channel * opacity)
+        }))
+    }
+}
+
+/// the coverage of each pixel by a rectangle, `matrix` maps it to pixels
+fn rect_coverage(rect: &BBox, matrix: &Matrix, canvas: &Canvas) -> Vec<f32> {
+    let corners = [
+        (rect.min_x, rect.min_y),
+        (rect.max_x, rect.min_y),
+        (rect.max_x, rect.max_y),
+        (rect.min_x, rect.max_y),
+    ];
+    let polygon = corners.iter().map(|(x, y)| matrix.apply(*x, *y)).collect();
+    let mut inside = Canvas::new(canvas.width, canvas.height);
+    inside.fill(&[polygon], FillRule::NonZero, &|_, _| [1.0; 4]);
+    inside.pixels.iter().map(|pixel| pixel[3]).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::font::tests::test_font;
+    use crate::{parse, render, render_with_fonts, Font, Fonts, Rgba};
+
+    fn pixel(svg: &str, x: u32, y: u32) -> Rgba {
+        let (_, root) = parse(svg).unwrap();
+        render(&root, 10, 10).pixel(x, y).unwrap()
+    }
+
+    #[test]
+    fn test_render() {
+        let svg = r##"<svg width="5" height="5"><style>.a { fill: blue }</style><defs><rect id="r" width="2" height="2"/></defs><use href="#r" x="1" class="a"/><circle cx="4" cy="4" r="1" style="fill:lime" opacity="0.5"/><path d="M0 2.5 H5" stroke="red" stroke-dasharray="1"/></svg>"##;
+        // scaled by 2 from the width and height
+        assert_eq!(pixel(svg, 3, 1), Rgba::new(0, 0, 255, 1.0));
+        assert_eq!(pixel(svg, 1, 1), Rgba::TRANSPARENT);
+        assert_eq!(pixel(svg, 8, 8).green, 255);
+        assert!((pixel(svg, 8, 8).alpha - 0.5).abs() < 0.01);
+        assert_eq!(pixel(svg, 0, 9), Rgba::TRANSPARENT);
+        // the line is dashed and not filled
+        assert_eq!(pixel(svg, 0, 4), Rgba::new(255, 0, 0, 1.0));
+        assert_eq!(pixel(svg, 2, 4), Rgba::TRANSPARENT);
+        assert_eq!(pixel(svg, 4, 5), Rgba::new(255, 0, 0, 1.0));
+    }
+
+    #[test]
+    fn test_render_gradient() {
+        let svg = r##"<svg viewBox="0 0 10 10"><linearGradient id="g"><stop offset="0" stop-color="black"/><stop offset="1" stop-color="white"/></linearGradient><rect width="10" height="10" fill="url(#g)" fill-rule="evenodd"/><path d="M0 0 H10 V10 H0Z M2 2 H8 V8 H2Z" fill="red" fill-rule="evenodd" visibility="hidden"/></svg>"##;
+        assert_eq!(pixel(svg, 0, 5), Rgba::new(13, 13, 13, 1.0));
+        assert_eq!(pixel(svg, 9, 5), Rgba::new(242, 242, 242, 1.0));
+        let svg = r#"<svg viewBox="0 0 10 10"><path d="M0 0 H10 V10 H0Z M2 2 H8 V8 H2Z" fill="red" fill-rule="evenodd"/></svg>"#;
+        assert_eq!(pixel(svg, 0, 0), Rgba::new(255, 0, 0, 1.0));
+        assert_eq!(pixel(svg, 5, 5), Rgba::TRANSPARENT);
+    }
+
+    #[test]
+    fn test_render_pattern() {
+        let svg = r##"<svg viewBox="0 0 10 10"><pattern id="p" width="2" height="2" patternUnits="userSpaceOnUse"><rect width="1" height="1" fill="red"/><rect x="1" y="1" width="1" height="1" fill="url(#p)"/></pattern><rect width="10" height="10" fill="url(#p)" fill-opacity="0.5"/></svg>"##;
+        let half = Rgba::new(255, 0, 0, 128.0 / 255.0);
+        assert_eq!(pixel(svg, 0, 0), half);
+        assert_eq!(pixel(svg, 6, 4), half);
+        assert_eq!(pixel(svg, 1, 0), Rgba::TRANSPARENT);
+        // the pattern in its own content is not drawn
+        assert_eq!(pixel(svg, 3, 3), Rgba::TRANSPARENT);
+    }
+
+    #[test]
+    fn test_render_clip_path_and_mask() {
+        let svg = r##"<svg viewBox="0 0 10 10"><clipPath id="c" clipPathUnits="objectBoundingBox"><rect width=".5" height="1"/><rect y=".5" width="1" height=".5" clip-path="url(#d)"/></clipPath><clipPath id="d"><rect width=".8" height="1"/></clipPath><rect width="10" height="10" fill="red" clip-path="url(#c)"/></svg>"##;
+        assert_eq!(pixel(svg, 2, 2), Rgba::new(255, 0, 0, 1.0));
+        assert_eq!(pixel(svg, 7, 2), Rgba::TRANSPARENT);
+        assert_eq!(pixel(svg, 7, 7), Rgba::new(255, 0, 0, 1.0));
+        assert_eq!(pixel(svg, 9, 7), Rgba::TRANSPARENT);
+
+        let svg = r##"<svg viewBox="0 0 10 10"><mask id="m" x="0" y="0" width="1" height=".5"><rect width="5" height="10" fill="white"/><rect x="5" width="5" height="10" fill="black"/></mask><mask id="a" style="mask-type:alpha"><rect width="10" height="10" fill="black" opacity="0.5"/></mask><rect width="10" height="10" fill="blue" mask="url(#m)"/><rect x="5" width="5" height="10" fill="lime" mask="url(#a)"/></svg>"##;
+        assert_eq!(pixel(svg, 2, 2), Rgba::new(0, 0, 255, 1.0));
+        assert_eq!(pixel(svg, 2, 7), Rgba::TRANSPARENT);
+        let half = pixel(svg, 7, 7);
+        assert_eq!((half.green, (half.alpha * 255.0).round()), (255, 128.0));
+    }
+
+    #[test]
+    fn test_render_markers() {
+        let svg = r##"<svg viewBox="0 0 10 10"><marker id="m" markerWidth="1" markerHeight="1" orient="auto"><rect width="2" height="2" fill="red"/></marker><marker id="n" markerWidth="1" markerHeight="1"><rect width="2" height="2" fill="lime"/></marker><path d="M1 5 5 5 5 1" fill="none" marker-mid="url(#n)" marker-end="url(#m)"/></svg>"##;
+        // the content is clipped to the viewport
+        assert_eq!(pixel(svg, 5, 5), Rgba::new(0, 255, 0, 1.0));
+        assert_eq!(pixel(svg, 6, 5), Rgba::TRANSPARENT);
+        assert_eq!(pixel(svg, 5, 6), Rgba::TRANSPARENT);
+        // and rotated along the path
+        assert_eq!(pixel(svg, 5, 0), Rgba::new(255, 0, 0, 1.0));
+        assert_eq!(pixel(svg, 5, 1), Rgba::TRANSPARENT);
+        assert_eq!(pixel(svg, 1, 5), Rgba::TRANSPARENT);
+    }
+
+    #[test]
+    fn test_render_text() {
+        let (_, root) = parse(
+            r#"<svg viewBox="0 0 10 10"><text y="10" font-size="10" fill="red">A</text></svg>"#,
+        )
+        .unwrap();
+        let mut fonts = Fonts::new();
+        fonts.add(Font::parse(test_font()).unwrap());
+        // the glyph of A is a square of 5 by 7
+        let pixmap = render_with_fonts(&root, 10, 10, &fonts);
+        assert_eq!(pixmap.pixel(2, 6), Some(Rgba::new(255, 0, 0, 1.0)));
+        assert_eq!(pixmap.pixel(7, 6), Some(Rgba::TRANSPARENT));
+        assert_eq!(pixmap.pixel(2, 1), Some(Rgba::TRANSPARENT));
+        assert_eq!(render(&root, 10, 10).pixel(2, 6), Some(Rgba::TRANSPARENT));
+    }
+}
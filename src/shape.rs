@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast::Element;
+use crate::document::Document;
+use crate::number::{parse_number, parse_number_list};
+use crate::path::{PathData, PathSegment};
+
+/// the basic shapes which can be written as a `<path>`
+pub(crate) const SHAPES: [&str; 6] = ["rect", "circle", "ellipse", "line", "polyline", "polygon"];
+
+/// the geometry attributes of the basic shapes, replaced by `d` in a `<path>`
+const GEOMETRY_ATTRIBUTES: [&str; 14] = [
+    "x", "y", "width", "height", "rx", "ry", "cx", "cy", "r", "x1", "y1", "x2", "y2", "points",
+];
+
+/// a optional number attribute, `Some(None)` if it is missing or `auto`
+fn optional_number(ele: &Element, key: &str) -> Option<Option<f64>> {
+    match ele.get_attribute(key) {
+        None => Some(None),
+        Some(value) if value.trim() == "auto" => Some(None),
+        Some(value) => parse_number(&value).map(Some),
+    }
+}
+
+/// a number attribute which is `0` if missing, `None` if it is not a plain number
+pub(crate) fn number_or_zero(ele: &Element, key: &str) -> Option<f64> {
+    optional_number(ele, key).map(|n| n.unwrap_or(0.0))
+}
+
+/// the corner radii of a `<rect>` or the radii of a `<ellipse>`, a missing or
+/// `auto` radius takes the other one. the radii are not clamped to the size of the rect.
+pub(crate) fn radii(ele: &Element) -> Option<(f64, f64)> {
+    let (rx, ry) = match (optional_number(ele, "rx")?, optional_number(ele, "ry")?) {
+        (None, None) => (0.0, 0.0),
+        (Some(rx), None) => (rx, rx),
+        (None, Some(ry)) => (ry, ry),
+        (Some(rx), Some(ry)) => (rx, ry),
+    };
+    Some((rx.max(0.0), ry.max(0.0)))
+}
+
+fn move_to(x: f64, y: f64) -> PathSegment {
+    PathSegment::MoveTo { abs: true, x, y }
+}
+
+fn line_to(x: f64, y: f64) -> PathSegment {
+    PathSegment::LineTo { abs: true, x, y }
+}
+
+fn horizontal_to(x: f64) -> PathSegment {
+    PathSegment::HorizontalLineTo { abs: true, x }
+}
+
+fn vertical_to(y: f64) -> PathSegment {
+    PathSegment::VerticalLineTo { abs: true, y }
+}
+
+fn arc_to(rx: f64, ry: f64, x: f64, y: f64) -> PathSegment {
+    PathSegment::ArcTo {
+        abs: true,
+        rx,
+        ry,
+        x_axis_rotation: 0.0,
+        large_arc: false,
+        sweep: true,
+        x,
+        y,
+    }
+}
+
+const CLOSE: PathSegment = PathSegment::ClosePath { abs: true };
+
+/// the outline of a basic shape as path data
+///
+/// `None` if the element is not a basic shape or its geometry is not made of
+/// plain numbers. a shape which is not rendered, like a rect without width, is
+/// a empty path.
+pub(crate) fn shape_path_data(ele: &Element) -> Option<PathData> {
+    let number = |key| number_or_zero(ele, key);
+    let segments = match ele.ele_type {
+        "rect" => {
+            let (x, y) = (number("x")?, number("y")?);
+            let (width, height) = (number("width")?, number("height")?);
+            let (rx, ry) = radii(ele)?;
+            let (rx, ry) = (rx.min(width / 2.0), ry.min(height / 2.0));
+            if width <= 0.0 || height <= 0.0 {
+                vec![]
+            } else if rx <= 0.0 || ry <= 0.0 {
+                vec![
+                    move_to(x, y),
+                    horizontal_to(x + width),
+                    vertical_to(y + height),
+                    horizontal_to(x),
+                    CLOSE,
+                ]
+            } else {
+                let (right, bottom) = (x + width, y + height);
+                // the edges between corners which meet have no length
+                let (horizontal, vertical) = (rx < width / 2.0, ry < height / 2.0);
+                let mut segments = vec![move_to(x + rx, y)];
+                segments.extend(horizontal.then(|| horizontal_to(right - rx)));
+                segments.push(arc_to(rx, ry, right, y + ry));
+                segments.extend(vertical.then(|| vertical_to(bottom - ry)));
+                segments.push(arc_to(rx, ry, right - rx, bottom));
+                segments.extend(horizontal.then(|| horizontal_to(x + rx)));
+                segments.push(arc_to(rx, ry, x, bottom - ry));
+                segments.extend(vertical.then(|| vertical_to(y + ry)));
+                segments.push(arc_to(rx, ry, x + rx, y));
+                segments.push(CLOSE);
+                segments
+            }
+        }
+        "circle" | "ellipse" => {
+            let (cx, cy) = (number("cx")?, number("cy")?);
+            let (rx, ry) = if ele.ele_type == "circle" {
+                let r = number("r")?;
+                (r, r)
+            } else {
+                radii(ele)?
+            };
+            if rx <= 0.0 || ry <= 0.0 {
+                vec![]
+            } else {
+                vec![
+                    move_to(cx + rx, cy),
+                    arc_to(rx, ry, cx - rx, cy),
+                    arc_to(rx, ry, cx + rx, cy),
+                    CLOSE,
+                ]
+            }
+        }
+        "line" => vec![
+            move_to(number("x1")?, number("y1")?),
+            line_to(number("x2")?, number("y2")?),
+        ],
+        "polyline" | "polygon" => {
+            let points = parse_number_list(&ele.get_attribute("points").unwrap_or_default())?;
+            // a odd number of coordinates renders up to the last complete point
+            let mut segments: Vec<PathSegment> = points
+                .chunks_exact(2)
+                .enumerate()
+                .map(|(i, p)| match i {
+                    0 => move_to(p[0], p[1]),
+                    _ => line_to(p[0], p[1]),
+                })
+                .collect();
+            if ele.ele_type == "polygon" && !segments.is_empty() {
+                segments.push(CLOSE);
+            }
+            segments
+        }
+        _ => return None,
+    };
+    Some(PathData { segments })
+}
+
+impl<'a> Element<'a> {
+    /// a equivalent `<path>` of a basic shape, with the other attributes and a
+    /// copy of the children of the shape
+    ///
+    /// `None` if the element is not a `rect`, `circle`, `ellipse`, `line`,
+    /// `polyline` or `polygon`, or if its geometry is not plain numbers.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, stringify};
+    ///
+    /// let (_, rect) = parse(r#"<rect x="1" width="10" height="5" rx="2" fill="red"/>"#).unwrap();
+    /// assert_eq!(
+    ///     stringify(rect.to_path().unwrap()),
+    ///     r#"<path d="M3 0H9A2 2 0 0111 2V3A2 2 0 019 5H3A2 2 0 011 3V2A2 2 0 013 0Z" fill="red"/>"#
+    /// );
+    /// ```
+    ///
+    pub fn to_path(&self) -> Option<Rc<Element<'a>>> {
+        let path_data = shape_path_data(self)?;
+        let path = Element::new(("path", HashMap::new()));
+        for (key, value) in self.attributes.borrow().iter() {
+            if !GEOMETRY_ATTRIBUTES.contains(&key.as_str()) {
+                path.set_attribute(key, value.clone());
+            }
+        }
+        path.set_path_data(&path_data);
+        let children = self
+            .children
+            .borrow()
+            .iter()
+            .map(|child| child.deep_clone())
+            .collect();
+        path.add_children(children);
+        Some(path)
+    }
+}
+
+/// replace every basic shape below the root of the document with a equivalent `<path>`
+///
+/// shapes whose geometry is not plain numbers, like percentages, are left
+/// untouched, see `Element::to_path`.
+///
+/// ## Example
+/// ```rust
+/// use svg_simple_parser::{parse_document, shapes_to_paths, stringify};
+///
+/// let (_, document) = parse_document(r#"<svg><g><circle id="c" r="1"/></g><line x2="5" stroke="red"/></svg>"#).unwrap();
+/// shapes_to_paths(&document);
+/// assert_eq!(
+///     stringify(document.root.clone()),
+///     r#"<svg><g><path d="M1 0A1 1 0 01-1 0 1 1 0 011 0Z" id="c"/></g><path d="M0 0 5 0" stroke="red"/></svg>"#
+/// );
+/// assert_eq!(document.element_by_id("c").unwrap().ele_type, "path");
+/// ```
+///
+pub fn shapes_to_paths(document: &Document) {
+    for ele in document.root.descendants() {
+        if !SHAPES.contains(&ele.ele_type) {
+            continue;
+        }
+        let parent = ele.parent.borrow().upgrade();
+        if let (Some(parent), Some(path)) = (parent, ele.to_path()) {
+            parent.replace_child(&ele, path);
+        }
+    }
+    document.reindex();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+    use crate::shape::shape_path_data;
+    use crate::{parse_document, shapes_to_paths, stringify};
+
+    fn d(source: &str) -> Option<String> {
+        let (_, ele) = parse(source).unwrap();
+        shape_path_data(&ele).map(|path| path.to_string())
+    }
+
+    #[test]
+    fn test_shape_path_data() {
+        assert_eq!(
+            d(r#"<rect x="1" y="2" width="10" height="5"/>"#).unwrap(),
+            "M1 2H11V7H1Z"
+        );
+        assert_eq!(
+            d(r#"<rect width="10" height="4" rx="3"/>"#).unwrap(),
+            "M3 0H7A3 2 0 0110 2 3 2 0 017 4H3A3 2 0 010 2 3 2 0 013 0Z"
+        );
+        assert_eq!(
+            d(r#"<rect width="4" height="4" rx="2"/>"#).unwrap(),
+            "M2 0A2 2 0 014 2 2 2 0 012 4 2 2 0 010 2 2 2 0 012 0Z"
+        );
+        assert_eq!(
+            d(r#"<circle cx="5" cy="5" r="5"/>"#).unwrap(),
+            "M10 5A5 5 0 010 5 5 5 0 0110 5Z"
+        );
+        // a missing or `auto` radius of a ellipse takes the other one
+        assert_eq!(
+            d(r#"<ellipse rx="2" ry="auto"/>"#).unwrap(),
+            "M2 0A2 2 0 01-2 0 2 2 0 012 0Z"
+        );
+        assert_eq!(d(r#"<ellipse rx="2"/>"#), d(r#"<ellipse rx="2" ry="2"/>"#));
+        assert_eq!(d(r#"<ellipse rx="auto" ry="auto"/>"#).unwrap(), "");
+        assert_eq!(d(r#"<line x2="10" y2="5px"/>"#).unwrap(), "M0 0 10 5");
+        assert_eq!(
+            d(r#"<polygon points="0,0 10,0 10,10 5"/>"#).unwrap(),
+            "M0 0 10 0 10 10Z"
+        );
+        assert_eq!(d(r#"<rect width="50%" height="10"/>"#), None);
+        assert_eq!(d(r#"<g/>"#), None);
+    }
+
+    #[test]
+    fn test_to_path() {
+        let (_, rect) =
+            parse(r#"<rect id="a" width="2" height="2" fill="red"><title></title></rect>"#)
+                .unwrap();
+        let path = rect.to_path().unwrap();
+        let (_, expected) =
+            parse(r#"<path id="a" d="M0 0H2V2H0Z" fill="red"><title></title></path>"#).unwrap();
+        assert_eq!(path, expected);
+    }
+
+    #[test]
+    fn test_shapes_to_paths() {
+        let (_, document) = parse_document(
+            r#"<svg><rect width="100%" height="1"/><ellipse rx="2" ry="1" transform="scale(2)"/><polyline points="1 2 3 4"/></svg>"#,
+        )
+        .unwrap();
+        shapes_to_paths(&document);
+        assert_eq!(
+            stringify(document.root.clone()),
+            r#"<svg><rect height="1" width="100%"/><path d="M2 0A2 1 0 01-2 0 2 1 0 012 0Z" transform="scale(2)"/><path d="M1 2 3 4"/></svg>"#
+        );
+    }
+}
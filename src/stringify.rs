@@ -1,4 +1,4 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
 
 use crate::ast::Element;
 
@@ -7,11 +7,11 @@ const LINE: &str = "\r\n";
 
 /// hashmap to string
 #[inline(always)]
-fn stringify_attrubutes_hash(attribute_hash: &RefCell<HashMap<String, &str>>) -> String {
-    let mut arr: Vec<(String, &str)> = attribute_hash
-        .borrow_mut()
+fn stringify_attrubutes_hash<V: Display>(attribute_hash: &RefCell<HashMap<String, V>>) -> String {
+    let attribute_hash = attribute_hash.borrow();
+    let mut arr: Vec<(String, &V)> = attribute_hash
         .iter()
-        .map(|(k, v)| ((*k).clone(), *v))
+        .map(|(k, v)| ((*k).clone(), v))
         .collect();
     arr.sort_by(|(ak, _), (bk, _)| ak.cmp(bk));
     arr.iter()
@@ -29,9 +29,12 @@ fn trasverse(
         attributes,
         parent: _,
         children,
+        text,
+        tail: _,
     } = &*ele;
     let attrs_str = stringify_attrubutes_hash(attributes);
-    let content = if children.borrow().is_empty() {
+    let text = text.borrow();
+    let content = if children.borrow().is_empty() && text.is_empty() {
         format!(
             "{}<{}{}/>{}",
             tab_mark.repeat(z),
@@ -39,6 +42,39 @@ fn trasverse(
             attrs_str,
             line_mark
         )
+    } else if children.borrow().is_empty() {
+        format!(
+            "{}<{}{}>{}</{}>{}",
+            tab_mark.repeat(z),
+            ele_type,
+            attrs_str,
+            text,
+            ele_type,
+            line_mark
+        )
+    } else if !text.is_empty()
+        || children
+            .borrow()
+            .iter()
+            .any(|t| !t.tail.borrow().is_empty())
+    {
+        // mixed content is written as it is, the tails follow their element
+        let content_str = children
+            .borrow()
+            .iter()
+            .map(|t| format!("{}{}", trasverse(t.clone(), 0, ("", "")), t.tail.borrow()))
+            .collect::<Vec<String>>()
+            .join("");
+        format!(
+            "{}<{}{}>{}{}</{}>{}",
+            tab_mark.repeat(z),
+            ele_type,
+            attrs_str,
+            text,
+            content_str,
+            ele_type,
+            line_mark
+        )
     } else {
         let children_str = children
             .borrow()
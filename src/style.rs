@@ -0,0 +1,503 @@
+use std::fmt;
+
+use crate::ast::Element;
+
+/// the presentation attributes of svg 1.1, which can also be written as a property in `style`
+pub(crate) const PRESENTATION_ATTRIBUTES: [&str; 59] = [
+    "alignment-baseline",
+    "baseline-shift",
+    "clip",
+    "clip-path",
+    "clip-rule",
+    "color",
+    "color-interpolation",
+    "color-interpolation-filters",
+    "color-profile",
+    "color-rendering",
+    "cursor",
+    "direction",
+    "display",
+    "dominant-baseline",
+    "enable-background",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "filter",
+    "flood-color",
+    "flood-opacity",
+    "font-family",
+    "font-size",
+    "font-size-adjust",
+    "font-stretch",
+    "font-style",
+    "font-variant",
+    "font-weight",
+    "glyph-orientation-horizontal",
+    "glyph-orientation-vertical",
+    "image-rendering",
+    "kerning",
+    "letter-spacing",
+    "lighting-color",
+    "marker-end",
+    "marker-mid",
+    "marker-start",
+    "mask",
+    "opacity",
+    "overflow",
+    "pointer-events",
+    "shape-rendering",
+    "stop-color",
+    "stop-opacity",
+    "stroke",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-opacity",
+    "stroke-width",
+    "text-anchor",
+    "text-decoration",
+    "text-rendering",
+    "unicode-bidi",
+    "visibility",
+    "word-spacing",
+    "writing-mode",
+];
+
+/// whether a property can be written as a presentation attribute
+pub(crate) fn is_presentation_attribute(name: &str) -> bool {
+    PRESENTATION_ATTRIBUTES.contains(&name)
+}
+
+/// A declaration of a property, like `fill: red !important`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    pub name: String,
+    pub value: String,
+    pub important: bool,
+}
+
+impl Declaration {
+    pub fn new(name: &str, value: &str) -> Declaration {
+        Declaration {
+            name: name.to_owned(),
+            value: value.to_owned(),
+            important: false,
+        }
+    }
+}
+
+impl fmt::Display for Declaration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.name, self.value)?;
+        if self.important {
+            write!(f, "!important")?;
+        }
+        Ok(())
+    }
+}
+
+/// The declarations of a `style` attribute, in source order
+///
+/// `Display` writes the declarations separated by `;`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Style {
+    pub declarations: Vec<Declaration>,
+}
+
+/// split `input` on `separator` outside of strings, parentheses and escapes,
+/// and drop the comments outside of strings
+pub(crate) fn split_top_level(input: &str, separator: char) -> Vec<String> {
+    let mut parts = vec![];
+    let mut current = String::new();
+    let mut chars = input.chars().peekable();
+    let mut quote: Option<char> = None;
+    let mut depth = 0usize;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                current.extend(chars.next());
+            }
+            c if quote == Some(c) => {
+                quote = None;
+                current.push(c);
+            }
+            _ if quote.is_some() => current.push(c),
+            '"' | '\'' => {
+                quote = Some(c);
+                current.push(c);
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+            }
+            '(' | '[' => {
+                depth += 1;
+                current.push(c);
+            }
+            ')' | ']' => {
+                depth = depth.saturating_sub(1);
+                current.push(c);
+            }
+            c if c == separator && depth == 0 => parts.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    parts.push(current);
+    parts
+}
+
+/// resolve the escapes of a identifier, `\` followed by up to 6 hex digits and
+/// a optional whitespace, or by any other character
+pub(crate) fn unescape(input: &str) -> String {
+    let mut output = String::new();
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        let mut hex = String::new();
+        while hex.len() < 6 && chars.peek().is_some_and(|c| c.is_ascii_hexdigit()) {
+            hex.extend(chars.next());
+        }
+        if hex.is_empty() {
+            output.extend(chars.next());
+            continue;
+        }
+        if chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        let code = u32::from_str_radix(&hex, 16).unwrap();
+        output.push(
+            char::from_u32(code)
+                .filter(|&c| c != '\0')
+                .unwrap_or('\u{fffd}'),
+        );
+    }
+    output
+}
+
+/// split a trailing `!important` from a value
+fn strip_important(value: &str) -> (&str, bool) {
+    if let Some(index) = value.rfind('!') {
+        let flag = value[index + 1..].trim();
+        let escaped = value[..index].ends_with('\\');
+        if !escaped && flag.eq_ignore_ascii_case("important") {
+            return (value[..index].trim_end(), true);
+        }
+    }
+    (value, false)
+}
+
+fn parse_declaration(input: &str) -> Option<Declaration> {
+    let (name, value) = input.split_once(':')?;
+    let name = unescape(name.trim());
+    // property names are case-insensitive, custom properties are not
+    let name = if name.starts_with("--") {
+        name
+    } else {
+        name.to_ascii_lowercase()
+    };
+    let valid_name = !name.is_empty() && !name.chars().any(|c| c.is_whitespace());
+    let (value, important) = strip_important(value.trim());
+    if !valid_name || value.is_empty() {
+        return None;
+    }
+    Some(Declaration {
+        name,
+        value: value.to_owned(),
+        important,
+    })
+}
+
+impl Style {
+    /// parse a declaration list, a invalid declaration is skipped like a browser does
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::Style;
+    ///
+    /// let style = Style::parse(r#"fill: white; /* a comment */ STROKE:red !important;font-family:"a;b"; oops"#);
+    /// assert_eq!(style.get("fill"), Some("white"));
+    /// assert_eq!(style.get("stroke"), Some("red"));
+    /// assert_eq!(style.get("font-family"), Some(r#""a;b""#));
+    /// assert_eq!(style.to_string(), r#"fill:white;stroke:red!important;font-family:"a;b""#);
+    /// ```
+    ///
+    pub fn parse(input: &str) -> Style {
+        let declarations = split_top_level(input, ';')
+            .iter()
+            .filter_map(|declaration| parse_declaration(declaration))
+            .collect();
+        Style { declarations }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.declarations.is_empty()
+    }
+
+    /// the declaration which applies to a property, a `!important` one wins over
+    /// the others and a later one over a earlier one
+    pub fn declaration(&self, name: &str) -> Option<&Declaration> {
+        self.declarations
+            .iter()
+            .filter(|declaration| declaration.name == name)
+            .max_by_key(|declaration| declaration.important)
+    }
+
+    /// the value of a property
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.declaration(name)
+            .map(|declaration| declaration.value.as_str())
+    }
+
+    /// set a property, replacing every declaration of it at the position of the first one
+    pub fn set(&mut self, declaration: Declaration) {
+        match self
+            .declarations
+            .iter()
+            .position(|d| d.name == declaration.name)
+        {
+            Some(index) => {
+                self.declarations.retain(|d| d.name != declaration.name);
+                self.declarations.insert(index, declaration);
+            }
+            None => self.declarations.push(declaration),
+        }
+    }
+
+    /// remove every declaration of a property, returning the one which applied
+    pub fn remove(&mut self, name: &str) -> Option<Declaration> {
+        let removed = self.declaration(name).cloned();
+        self.declarations.retain(|d| d.name != name);
+        removed
+    }
+}
+
+impl fmt::Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, declaration) in self.declarations.iter().enumerate() {
+            if i > 0 {
+                write!(f, ";")?;
+            }
+            write!(f, "{}", declaration)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Element<'a> {
+    /// the parsed `style` attribute, empty if it is missing
+    pub fn style(&self) -> Style {
+        Style::parse(&self.get_attribute("style").unwrap_or_default())
+    }
+
+    /// write the `style` attribute, a empty style removes it
+    pub fn set_style(&self, style: &Style) {
+        if style.is_empty() {
+            self.remove_attribute("style");
+        } else {
+            self.set_attribute("style", style.to_string());
+        }
+    }
+
+    /// the value of a property in the `style` attribute
+    pub fn style_property(&self, name: &str) -> Option<String> {
+        self.style().get(name).map(str::to_owned)
+    }
+
+    /// set a property in the `style` attribute
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::parse;
+    ///
+    /// let (_, path) = parse(r#"<path style="fill:white;stroke:red;stroke-width:2"/>"#).unwrap();
+    /// path.set_style_property("stroke", "blue");
+    /// path.remove_style_property("fill");
+    /// assert_eq!(path.get_attribute("style").unwrap(), "stroke:blue;stroke-width:2");
+    /// ```
+    ///
+    pub fn set_style_property(&self, name: &str, value: &str) {
+        let mut style = self.style();
+        style.set(Declaration::new(name, value));
+        self.set_style(&style);
+    }
+
+    /// remove a property from the `style` attribute, returning its value
+    pub fn remove_style_property(&self, name: &str) -> Option<String> {
+        let mut style = self.style();
+        let removed = style.remove(name)?;
+        self.set_style(&style);
+        Some(removed.value)
+    }
+
+    /// move the properties of `style` which are presentation attributes to attributes
+    ///
+    /// a `!important` declaration stays in `style`, as a attribute can not be important.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, stringify};
+    ///
+    /// let (_, path) = parse(r#"<path fill="red" style="fill:white;stroke:red!important;--x:1"/>"#).unwrap();
+    /// path.style_to_attributes();
+    /// assert_eq!(stringify(path), r#"<path fill="white" style="stroke:red!important;--x:1"/>"#);
+    /// ```
+    ///
+    pub fn style_to_attributes(&self) {
+        let mut style = self.style();
+        let mut moved = vec![];
+        for declaration in &style.declarations {
+            let name = declaration.name.as_str();
+            if !declaration.important
+                && is_presentation_attribute(name)
+                && !moved.contains(&name)
+                && !style.declaration(name).is_some_and(|d| d.important)
+            {
+                moved.push(name);
+            }
+        }
+        let moved: Vec<String> = moved.into_iter().map(str::to_owned).collect();
+        for name in moved {
+            let declaration = style.remove(&name).unwrap();
+            self.set_attribute(&name, declaration.value);
+        }
+        self.set_style(&style);
+    }
+
+    /// move the presentation attributes into `style`
+    ///
+    /// a property which is already in `style` keeps its value, as `style` wins over
+    /// a presentation attribute.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, stringify};
+    ///
+    /// let (_, path) = parse(r#"<path d="M0 0" fill="red" stroke="blue" style="fill:white"/>"#).unwrap();
+    /// path.attributes_to_style();
+    /// assert_eq!(stringify(path), r#"<path d="M0 0" style="fill:white;stroke:blue"/>"#);
+    /// ```
+    ///
+    pub fn attributes_to_style(&self) {
+        let mut style = self.style();
+        for name in PRESENTATION_ATTRIBUTES {
+            if let Some(value) = self.remove_attribute(name) {
+                if style.declaration(name).is_none() {
+                    style
+                        .declarations
+                        .push(Declaration::new(name, value.trim()));
+                }
+            }
+        }
+        self.set_style(&style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::style::unescape;
+    use crate::{parse, Declaration, Style};
+
+    #[test]
+    fn test_parse() {
+        let style = Style::parse(
+            r#" ; fill : url("a;b.svg#c") ;; stroke:red/*;*/!IMPORTANT; stroke : blue; : x; a b: c; empty: ;--Custom: 1"#,
+        );
+        assert_eq!(
+            style.declarations,
+            vec![
+                Declaration::new("fill", r#"url("a;b.svg#c")"#),
+                Declaration {
+                    important: true,
+                    ..Declaration::new("stroke", "red")
+                },
+                Declaration::new("stroke", "blue"),
+                Declaration::new("--Custom", "1"),
+            ]
+        );
+        assert_eq!(style.get("stroke"), Some("red"));
+        assert_eq!(
+            Style::parse("content: 'it\\'s;'").get("content"),
+            Some("'it\\'s;'")
+        );
+        assert_eq!(
+            Style::parse("fill: url(data:a;b)").get("fill"),
+            Some("url(data:a;b)")
+        );
+        assert!(Style::parse("/* fill: red */").is_empty());
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape(r"f\69 ll"), "fill");
+        assert_eq!(unescape(r"\:a"), ":a");
+        assert_eq!(Style::parse(r"\66ill:red").get("fill"), Some("red"));
+    }
+
+    #[test]
+    fn test_set_remove() {
+        let mut style = Style::parse("fill:red;stroke:blue;fill:green");
+        style.set(Declaration::new("fill", "white"));
+        assert_eq!(style.to_string(), "fill:white;stroke:blue");
+        assert_eq!(
+            style.remove("stroke"),
+            Some(Declaration::new("stroke", "blue"))
+        );
+        assert_eq!(style.remove("stroke"), None);
+        style.set(Declaration::new("opacity", ".5"));
+        assert_eq!(style.to_string(), "fill:white;opacity:.5");
+    }
+
+    #[test]
+    fn test_element_style() {
+        let (_, ele) = parse(r#"<rect style="fill:red"/>"#).unwrap();
+        assert_eq!(ele.style_property("fill"), Some("red".to_owned()));
+        assert_eq!(ele.remove_style_property("fill"), Some("red".to_owned()));
+        assert_eq!(ele.get_attribute("style"), None);
+        assert_eq!(ele.remove_style_property("fill"), None);
+        ele.set_style_property("stroke", "blue");
+        assert_eq!(ele.get_attribute("style").unwrap(), "stroke:blue");
+    }
+
+    #[test]
+    fn test_round_trip_conversion() {
+        let (_, ele) = parse(
+            r#"<rect width="1" fill="red" style="stroke:blue;stroke:green;cursor:pointer"/>"#,
+        )
+        .unwrap();
+        ele.style_to_attributes();
+        assert_eq!(ele.get_attribute("stroke").unwrap(), "green");
+        assert_eq!(ele.get_attribute("style"), None);
+        ele.attributes_to_style();
+        assert_eq!(ele.get_attribute("width").unwrap(), "1");
+        assert_eq!(
+            ele.get_attribute("style").unwrap(),
+            "cursor:pointer;fill:red;stroke:green"
+        );
+    }
+
+    #[test]
+    fn test_shorthands_stay_in_style() {
+        let (_, ele) =
+            parse(r#"<text style="font:12px serif;marker:url(#m);writing-mode:tb"/>"#).unwrap();
+        ele.style_to_attributes();
+        assert_eq!(ele.get_attribute("font"), None);
+        assert_eq!(ele.get_attribute("marker"), None);
+        assert_eq!(ele.get_attribute("writing-mode").unwrap(), "tb");
+        assert_eq!(
+            ele.get_attribute("style").unwrap(),
+            "font:12px serif;marker:url(#m)"
+        );
+    }
+}
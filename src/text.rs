@@ -0,0 +1,1254 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::ast::{decode_text, Element};
+use crate::css::ComputedStyles;
+use crate::document::Document;
+use crate::font::{Font, FontError};
+use crate::length::{Length, LengthContext, LengthDirection, LengthUnit};
+use crate::number::{parse_number, parse_number_list, snap};
+use crate::path::{PathData, PathSegment};
+use crate::reference::element_href;
+use crate::style::Style;
+use crate::transform::Matrix;
+
+/// the elements whose characters are laid out, `a` is a link in the text
+const TEXT_CONTENT: [&str; 4] = ["text", "tspan", "textPath", "a"];
+
+/// the attributes and properties used by the layout, which are dropped when
+/// the text becomes paths
+const TEXT_PROPERTIES: [&str; 31] = [
+    "x",
+    "y",
+    "dx",
+    "dy",
+    "rotate",
+    "textLength",
+    "lengthAdjust",
+    "font",
+    "font-family",
+    "font-size",
+    "font-style",
+    "font-weight",
+    "font-variant",
+    "font-stretch",
+    "font-kerning",
+    "kerning",
+    "letter-spacing",
+    "word-spacing",
+    "text-anchor",
+    "dominant-baseline",
+    "alignment-baseline",
+    "baseline-shift",
+    "xml:space",
+    "white-space",
+    "writing-mode",
+    "direction",
+    "startOffset",
+    "href",
+    "xlink:href",
+    "side",
+    "path",
+];
+
+type Point = (f64, f64);
+
+/// the number of lines a curve is split into when measuring a `textPath`
+const CURVE_STEPS: usize = 16;
+
+/// A set of fonts to lay out text with
+#[derive(Debug, Clone, Default)]
+pub struct Fonts {
+    pub fonts: Vec<Font>,
+}
+
+impl Fonts {
+    pub fn new() -> Fonts {
+        Fonts::default()
+    }
+
+    pub fn add(&mut self, font: Font) {
+        self.fonts.push(font);
+    }
+
+    /// read a font file and add it, see `Font::parse`
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), FontError> {
+        self.add(Font::from_file(path)?);
+        Ok(())
+    }
+
+    /// the font for a `font-family` list, a `font-weight` and a style
+    ///
+    /// the first family of the list which is in the set is used, or every font
+    /// when none is, like for a generic family as `sans-serif`. among them the
+    /// font with the same style and the closest weight wins. `None` for a empty set.
+    pub fn select(&self, family: &str, weight: u16, italic: bool) -> Option<&Font> {
+        let families = family
+            .split(',')
+            .map(|name| name.trim().trim_matches(['"', '\'']).trim())
+            .filter(|name| !name.is_empty());
+        let mut candidates: Vec<&Font> = vec![];
+        for name in families {
+            candidates = self
+                .fonts
+                .iter()
+                .filter(|font| font.family.eq_ignore_ascii_case(name))
+                .collect();
+            if !candidates.is_empty() {
+                break;
+            }
+        }
+        if candidates.is_empty() {
+            candidates = self.fonts.iter().collect();
+        }
+        candidates
+            .into_iter()
+            .min_by_key(|font| (font.italic != italic, font.weight.abs_diff(weight)))
+    }
+}
+
+/// A glyph placed by the layout of a text
+///
+/// `element` is the `text`, `tspan`, `textPath` or `a` the character is in.
+/// `x` and `y` is the origin of the glyph on the baseline and `rotate` its
+/// rotation in degrees, `advance` the distance to the next glyph along the
+/// baseline. `outline` is the glyph in the user space of the `text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextGlyph<'a> {
+    pub element: Rc<Element<'a>>,
+    pub character: char,
+    pub x: f64,
+    pub y: f64,
+    pub rotate: f64,
+    pub font_size: f64,
+    pub advance: f64,
+    pub outline: PathData,
+}
+
+/// How a chunk of text is aligned to its start position, `text-anchor`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// A element of a text with its computed style
+struct Node<'a> {
+    element: Rc<Element<'a>>,
+    parent: Option<usize>,
+    /// the `textPath` the element is in, itself included
+    text_path: Option<usize>,
+    /// the index of the first character of the element and after its last
+    first: usize,
+    end: usize,
+    x: Vec<f64>,
+    y: Vec<f64>,
+    dx: Vec<f64>,
+    dy: Vec<f64>,
+    rotate: Vec<f64>,
+    font_size: f64,
+    family: String,
+    weight: u16,
+    italic: bool,
+    letter_spacing: f64,
+    word_spacing: f64,
+    kerning: bool,
+    anchor: Anchor,
+    baseline: String,
+    /// the `baseline-shift` of the element and its ancestors in the text, up
+    baseline_shift: f64,
+}
+
+/// A character of a text, with the element it is in
+struct Character {
+    character: char,
+    node: usize,
+}
+
+/// A glyph while the text is laid out
+struct Placed<'f> {
+    character: usize,
+    font: &'f Font,
+    glyph: u16,
+    x: f64,
+    y: f64,
+    rotate: f64,
+    scale: f64,
+    advance: f64,
+    letter_spacing: f64,
+    chunk: usize,
+    anchor: Anchor,
+}
+
+/// A `textPath` measured as a polyline, `None` for a path which can not be resolved
+struct TextPath {
+    /// the lines of the path with the distance along the path to their start
+    lines: Vec<(Point, Point, f64)>,
+    length: f64,
+    start_offset: f64,
+}
+
+impl TextPath {
+    /// the point at a distance along the path and the direction there in degrees
+    fn at(&self, distance: f64) -> Option<(f64, f64, f64)> {
+        if distance < 0.0 || distance > self.length {
+            return None;
+        }
+        let i = self
+            .lines
+            .partition_point(|(_, _, start)| *start <= distance)
+            .saturating_sub(1);
+        let ((x0, y0), (x1, y1), start) = *self.lines.get(i)?;
+        let length = ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt();
+        let t = if length > 0.0 {
+            (distance - start) / length
+        } else {
+            0.0
+        };
+        Some((
+            x0 + (x1 - x0) * t,
+            y0 + (y1 - y0) * t,
+            (y1 - y0).atan2(x1 - x0).to_degrees(),
+        ))
+    }
+}
+
+/// a list of lengths in user units, for `x`, `y`, `dx` and `dy`
+fn length_list(ele: &Element, key: &str, context: &LengthContext) -> Vec<f64> {
+    let Some(value) = ele.get_attribute(key) else {
+        return vec![];
+    };
+    let direction = LengthDirection::of_attribute(key);
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map_while(|s| Length::parse(s).ok())
+        .map(|length| length.to_user_units(context, direction))
+        .collect()
+}
+
+/// the `font-size` of a element in user units, relative sizes from its parent
+fn font_size<'a>(
+    styles: &ComputedStyles<'a>,
+    ele: &Rc<Element<'a>>,
+    context: &LengthContext,
+) -> f64 {
+    let parent = ele.parent.borrow().upgrade();
+    let inherited = || {
+        parent.as_ref().map_or(context.font_size, |parent| {
+            font_size(styles, parent, context)
+        })
+    };
+    let Some(value) = styles.specified(ele, "font-size") else {
+        return inherited();
+    };
+    let size = match value.trim() {
+        "xx-small" => 9.0,
+        "x-small" => 10.0,
+        "small" => 13.0,
+        "medium" => 16.0,
+        "large" => 18.0,
+        "x-large" => 24.0,
+        "xx-large" => 32.0,
+        "larger" => inherited() * 1.2,
+        "smaller" => inherited() / 1.2,
+        value => match Length::parse(value) {
+            Ok(length) if matches!(length.unit, LengthUnit::Em | LengthUnit::Percent) => {
+                let factor = if length.unit == LengthUnit::Em {
+                    1.0
+                } else {
+                    0.01
+                };
+                length.number * factor * inherited()
+            }
+            Ok(length) if length.unit == LengthUnit::Ex => length.number * inherited() / 2.0,
+            Ok(length) => length.to_user_units(context, LengthDirection::Other),
+            Err(_) => inherited(),
+        },
+    };
+    if size.is_finite() && size >= 0.0 {
+        size
+    } else {
+        inherited()
+    }
+}
+
+/// the `font-weight` of a element, `bolder` and `lighter` from its parent
+fn font_weight<'a>(styles: &ComputedStyles<'a>, ele: &Rc<Element<'a>>) -> u16 {
+    let parent = ele.parent.borrow().upgrade();
+    let inherited = || {
+        parent
+            .as_ref()
+            .map_or(400, |parent| font_weight(styles, parent))
+    };
+    match styles
+        .specified(ele, "font-weight")
+        .as_deref()
+        .map(str::trim)
+    {
+        Some("normal") => 400,
+        Some("bold") => 700,
+        Some("bolder") => match inherited() {
+            0..=349 => 400,
+            350..=549 => 700,
+            _ => 900,
+        },
+        Some("lighter") => match inherited() {
+            0..=549 => 100,
+            550..=749 => 400,
+            _ => 700,
+        },
+        Some(value) => match parse_number(value) {
+            Some(weight) if (1.0..=1000.0).contains(&weight) => weight as u16,
+            _ => inherited(),
+        },
+        None => inherited(),
+    }
+}
+
+/// a `letter-spacing` or `word-spacing` in user units, `normal` is none
+fn spacing<'a>(
+    styles: &ComputedStyles<'a>,
+    ele: &Rc<Element<'a>>,
+    key: &str,
+    context: &LengthContext,
+) -> f64 {
+    styles
+        .get(ele, key)
+        .and_then(|value| Length::parse(value.trim()).ok())
+        .map_or(0.0, |length| {
+            length.to_user_units(context, LengthDirection::Horizontal)
+        })
+}
+
+/// the `baseline-shift` of a element in user units, up
+fn baseline_shift<'a>(
+    styles: &ComputedStyles<'a>,
+    ele: &Rc<Element<'a>>,
+    font_size: f64,
+    context: &LengthContext,
+) -> f64 {
+    match styles
+        .specified(ele, "baseline-shift")
+        .as_deref()
+        .map(str::trim)
+    {
+        None | Some("baseline") => 0.0,
+        Some("sub") => -0.2 * font_size,
+        Some("super") => 0.4 * font_size,
+        Some(value) => match Length::parse(value) {
+            Ok(length) if length.unit == LengthUnit::Percent => length.number / 100.0 * font_size,
+            Ok(length) => length.to_user_units(context, LengthDirection::Other),
+            Err(_) => 0.0,
+        },
+    }
+}
+
+/// the position of a baseline above the alphabetic baseline, in font units
+fn baseline_offset(font: &Font, baseline: &str) -> f64 {
+    match baseline {
+        "middle" => font.x_height / 2.0,
+        "central" => (font.ascender + font.descender) / 2.0,
+        "hanging" => font.ascender * 0.8,
+        "mathematical" => font.ascender / 2.0,
+        "text-top" | "text-before-edge" | "before-edge" => font.ascender,
+        "text-bottom" | "text-after-edge" | "after-edge" | "ideographic" => font.descender,
+        _ => 0.0,
+    }
+}
+
+impl<'a> Document<'a> {
+    /// the glyphs of a `text` element laid out with a set of fonts
+    ///
+    /// the characters of the `text` and of its `tspan`, `textPath` and `a`
+    /// are placed following the `x`, `y`, `dx`, `dy` and `rotate` lists, the
+    /// `text-anchor` of each chunk, the `dominant-baseline`, `alignment-baseline`
+    /// and `baseline-shift`, the `letter-spacing` and `word-spacing`, and the
+    /// `font-family`, `font-size`, `font-weight` and `font-style`. a glyph is a
+    /// character of the font with the `kern` table kerning, a character which is
+    /// not in the font is taken from another font of the set. glyphs on a
+    /// `textPath` follow the path and the ones past its end are left out.
+    ///
+    /// white space is collapsed unless `xml:space` is `preserve`. the text of a
+    /// element is laid out before its children, as the tree keeps no order
+    /// between them. text is horizontal and left to right, without ligatures or
+    /// `textLength`. empty without fonts or for another element than a `text`.
+    ///
+    /// ## Example
+    /// ```rust,no_run
+    /// use svg_simple_parser::{parse_document, Fonts, LengthContext};
+    ///
+    /// let mut fonts = Fonts::new();
+    /// fonts.load("DejaVuSans.ttf").unwrap();
+    /// let (_, document) = parse_document(r#"<svg><text id="t" x="10" y="20" font-size="10">Hi</text></svg>"#).unwrap();
+    /// let glyphs = document.layout_text(&document.element_by_id("t").unwrap(), &fonts, &LengthContext::default());
+    /// assert_eq!((glyphs[0].character, glyphs[0].x, glyphs[0].y), ('H', 10.0, 20.0));
+    /// ```
+    ///
+    pub fn layout_text(
+        &self,
+        ele: &Rc<Element<'a>>,
+        fonts: &Fonts,
+        context: &LengthContext,
+    ) -> Vec<TextGlyph<'a>> {
+        let styles = ComputedStyles::new(self.stylesheet());
+        self.layout_text_with(&styles, ele, fonts, context)
+    }
+
+    /// lay out a text like `Document::layout_text`, with the computed styles of the tree
+    pub(crate) fn layout_text_with(
+        &self,
+        styles: &ComputedStyles<'a>,
+        ele: &Rc<Element<'a>>,
+        fonts: &Fonts,
+        context: &LengthContext,
+    ) -> Vec<TextGlyph<'a>> {
+        if ele.ele_type != "text" || fonts.fonts.is_empty() {
+            return vec![];
+        }
+        let mut nodes = vec![];
+        let mut characters = vec![];
+        self.collect_text(
+            styles,
+            ele,
+            None,
+            None,
+            context,
+            &mut nodes,
+            &mut characters,
+        );
+        let characters = collapse_white_space(styles, characters, &nodes);
+        for (i, character) in characters.iter().enumerate() {
+            let mut current = Some(character.node);
+            while let Some(n) = current {
+                let node = &mut nodes[n];
+                if node.end == 0 {
+                    node.first = i;
+                }
+                node.end = i + 1;
+                current = node.parent;
+            }
+        }
+        let paths: HashMap<usize, Option<TextPath>> = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.element.ele_type == "textPath")
+            .map(|(n, node)| (n, self.text_path(&node.element, context)))
+            .collect();
+        let placed = place_glyphs(&characters, &nodes, fonts, &paths);
+        placed
+            .into_iter()
+            .filter_map(|placed| {
+                let character = &characters[placed.character];
+                let node = &nodes[character.node];
+                let font_advance = placed.font.advance(placed.glyph) * placed.scale;
+                let glyph =
+                    Matrix::rotate(placed.rotate) * Matrix::scale(placed.scale, -placed.scale);
+                let (x, y, rotate, matrix) = match node.text_path {
+                    Some(n) => {
+                        let path = paths.get(&n)?.as_ref()?;
+                        let (px, py, angle) = path.at(placed.x + font_advance / 2.0)?;
+                        let along = Matrix::translate(px, py) * Matrix::rotate(angle);
+                        let origin = along * Matrix::translate(-font_advance / 2.0, placed.y);
+                        let (x, y) = origin.apply(0.0, 0.0);
+                        (x, y, angle + placed.rotate, origin * glyph)
+                    }
+                    None => (
+                        placed.x,
+                        placed.y,
+                        placed.rotate,
+                        Matrix::translate(placed.x, placed.y) * glyph,
+                    ),
+                };
+                let outline = placed.font.outline(placed.glyph)?.transformed(&matrix);
+                Some(TextGlyph {
+                    element: node.element.clone(),
+                    character: character.character,
+                    x,
+                    y,
+                    rotate,
+                    font_size: node.font_size,
+                    advance: placed.advance,
+                    outline,
+                })
+            })
+            .collect()
+    }
+
+    /// add a element of a text and its descendants to the nodes, and their
+    /// characters before white space is collapsed
+    #[allow(clippy::too_many_arguments)]
+    fn collect_text(
+        &self,
+        styles: &ComputedStyles<'a>,
+        ele: &Rc<Element<'a>>,
+        parent: Option<usize>,
+        text_path: Option<usize>,
+        context: &LengthContext,
+        nodes: &mut Vec<Node<'a>>,
+        characters: &mut Vec<Character>,
+    ) {
+        let style = styles.style(ele);
+        if style.get("display").map(str::trim) == Some("none") {
+            return;
+        }
+        let n = nodes.len();
+        let text_path = match ele.ele_type {
+            "textPath" if text_path.is_none() => Some(n),
+            _ => text_path,
+        };
+        let size = font_size(styles, ele, context);
+        let context = LengthContext {
+            font_size: size,
+            ..*context
+        };
+        let kerning = !matches!(style.get("font-kerning").map(str::trim), Some("none"))
+            && style.get("kerning").and_then(parse_number) != Some(0.0);
+        let baseline = style
+            .get("alignment-baseline")
+            .filter(|value| !matches!(value.trim(), "auto" | "baseline"))
+            .or_else(|| style.get("dominant-baseline"))
+            .map_or(String::new(), |value| value.trim().to_owned());
+        let parent_shift = parent.map_or(0.0, |p: usize| nodes[p].baseline_shift);
+        nodes.push(Node {
+            element: ele.clone(),
+            parent,
+            text_path,
+            first: 0,
+            end: 0,
+            x: length_list(ele, "x", &context),
+            y: length_list(ele, "y", &context),
+            dx: length_list(ele, "dx", &context),
+            dy: length_list(ele, "dy", &context),
+            rotate: ele
+                .get_attribute("rotate")
+                .and_then(|value| parse_number_list(&value))
+                .unwrap_or_default(),
+            font_size: size,
+            family: style.get("font-family").unwrap_or_default().to_owned(),
+            weight: font_weight(styles, ele),
+            italic: matches!(
+                style.get("font-style").map(str::trim),
+                Some("italic" | "oblique")
+            ),
+            letter_spacing: spacing(styles, ele, "letter-spacing", &context),
+            word_spacing: spacing(styles, ele, "word-spacing", &context),
+            kerning,
+            anchor: match style.get("text-anchor").map(str::trim) {
+                Some("middle") => Anchor::Middle,
+                Some("end") => Anchor::End,
+                _ => Anchor::Start,
+            },
+            baseline,
+            baseline_shift: parent_shift + baseline_shift(styles, ele, size, &context),
+        });
+        for character in decode_text(&ele.text.borrow()).chars() {
+            characters.push(Character { character, node: n });
+        }
+        for child in ele.children.borrow().iter() {
+            if TEXT_CONTENT[1..].contains(&child.ele_type) {
+                self.collect_text(
+                    styles,
+                    child,
+                    Some(n),
+                    text_path,
+                    &context,
+                    nodes,
+                    characters,
+                );
+            }
+            // the text after a child belongs to the element
+            for character in child.tail_content().chars() {
+                characters.push(Character { character, node: n });
+            }
+        }
+    }
+
+    /// measure the path of a `textPath`, from its `href` or its `path`
+    fn text_path(&self, ele: &Rc<Element<'a>>, context: &LengthContext) -> Option<TextPath> {
+        let (path, matrix) = match ele.get_attribute("path") {
+            Some(value) => (PathData::parse(&value).ok()?, Matrix::default()),
+            None => {
+                let target = element_href(ele).and_then(|id| self.element_by_id(&id))?;
+                if target.ele_type != "path" {
+                    return None;
+                }
+                let matrix = target.transform().unwrap_or_default().to_matrix();
+                (target.path_data().ok()?, matrix)
+            }
+        };
+        let mut lines = vec![];
+        let mut length = 0.0;
+        let mut add = |from: (f64, f64), to: (f64, f64)| {
+            let (from, to) = (matrix.apply(from.0, from.1), matrix.apply(to.0, to.1));
+            let distance = ((to.0 - from.0).powi(2) + (to.1 - from.1).powi(2)).sqrt();
+            if distance > 0.0 {
+                lines.push((from, to, length));
+                length += distance;
+            }
+        };
+        let (mut current, mut start) = ((0.0, 0.0), (0.0, 0.0));
+        for segment in path.to_curves(0.1).segments {
+            match segment {
+                PathSegment::MoveTo { x, y, .. } => {
+                    current = (x, y);
+                    start = current;
+                }
+                PathSegment::LineTo { x, y, .. } => {
+                    add(current, (x, y));
+                    current = (x, y);
+                }
+                PathSegment::CurveTo {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                    ..
+                } => {
+                    let point = |t: f64| {
+                        let u = 1.0 - t;
+                        let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                        (
+                            a * current.0 + b * x1 + c * x2 + d * x,
+                            a * current.1 + b * y1 + c * y2 + d * y,
+                        )
+                    };
+                    let mut previous = current;
+                    for step in 1..=CURVE_STEPS {
+                        let next = point(step as f64 / CURVE_STEPS as f64);
+                        add(previous, next);
+                        previous = next;
+                    }
+                    current = (x, y);
+                }
+                PathSegment::ClosePath { .. } => {
+                    add(current, start);
+                    current = start;
+                }
+                _ => {}
+            }
+        }
+        let start_offset = match ele.length_attribute("startOffset") {
+            Ok(Some(offset)) if offset.unit == LengthUnit::Percent => {
+                offset.number / 100.0 * length
+            }
+            Ok(Some(offset)) => offset.to_user_units(context, LengthDirection::Other),
+            _ => 0.0,
+        };
+        Some(TextPath {
+            lines,
+            length,
+            start_offset,
+        })
+    }
+}
+
+/// replace the `text` elements of the document with the outlines of their glyphs
+///
+/// a `text` whose characters are all its own becomes a `<path>`, else a `<g>`
+/// with a `<path>` for each run of glyphs of the same `tspan`, `textPath` or
+/// `a`, which gets the attributes of the elements between the `text` and
+/// itself. in a `clipPath` the paths replace the `text` directly. the
+/// attributes of the layout, like `x` or `font-size`, are removed from the
+/// new elements and the coordinates are rounded to three decimals. a `text`
+/// without glyphs, like a empty one or without a font for it, is kept. see
+/// `Document::layout_text`.
+///
+/// ## Example
+/// ```rust,no_run
+/// use svg_simple_parser::{parse_document, stringify, text_to_paths, Fonts};
+///
+/// let mut fonts = Fonts::new();
+/// fonts.load("DejaVuSans.ttf").unwrap();
+/// let (_, document) = parse_document(r#"<svg><text id="t" y="20" fill="red">Hi</text></svg>"#).unwrap();
+/// text_to_paths(&document, &fonts);
+/// let path = document.element_by_id("t").unwrap();
+/// assert_eq!(path.ele_type, "path");
+/// assert_eq!(path.get_attribute("fill").as_deref(), Some("red"));
+/// ```
+///
+pub fn text_to_paths(document: &Document, fonts: &Fonts) {
+    let context = LengthContext::default();
+    let styles = ComputedStyles::new(document.stylesheet());
+    let texts: Vec<_> = document
+        .root
+        .descendants()
+        .into_iter()
+        .filter(|ele| ele.ele_type == "text")
+        .map(|ele| {
+            let glyphs = document.layout_text_with(&styles, &ele, fonts, &context);
+            (ele, glyphs)
+        })
+        .collect();
+    for (text, glyphs) in texts {
+        if glyphs.is_empty() {
+            continue;
+        }
+        let parent = text.parent.borrow().upgrade();
+        let Some(parent) = parent else {
+            continue;
+        };
+        let mut runs: Vec<(Rc<Element>, PathData)> = vec![];
+        for glyph in glyphs {
+            match runs.last_mut() {
+                Some((ele, path)) if Rc::ptr_eq(ele, &glyph.element) => {
+                    path.segments.extend(glyph.outline.segments)
+                }
+                _ => runs.push((glyph.element, glyph.outline)),
+            }
+        }
+        let in_clip_path = parent.ele_type == "clipPath";
+        if !in_clip_path && runs.iter().all(|(ele, _)| Rc::ptr_eq(ele, &text)) {
+            let path = Element::new(("path", HashMap::new()));
+            copy_attributes(&text, &path, true);
+            let segments = runs.into_iter().flat_map(|(_, path)| path.segments);
+            path.set_path_data(&rounded(segments.collect()));
+            parent.replace_child(&text, path);
+            continue;
+        }
+        let mut paths = vec![];
+        let mut named: Vec<Rc<Element>> = vec![];
+        for (ele, data) in runs {
+            let path = Element::new(("path", HashMap::new()));
+            let mut chain = vec![];
+            let mut current = Some(ele.clone());
+            while let Some(ele) = current {
+                if Rc::ptr_eq(&ele, &text) {
+                    if in_clip_path {
+                        chain.push(ele);
+                    }
+                    break;
+                }
+                current = ele.parent.borrow().upgrade();
+                chain.push(ele);
+            }
+            for ancestor in chain.iter().rev() {
+                let with_id = Rc::ptr_eq(ancestor, chain.last().unwrap_or(&ele))
+                    && !named.iter().any(|named| Rc::ptr_eq(named, ancestor));
+                copy_attributes(ancestor, &path, with_id);
+                if with_id {
+                    named.push(ancestor.clone());
+                }
+            }
+            path.set_path_data(&rounded(data.segments));
+            paths.push(path);
+        }
+        if in_clip_path {
+            let index = parent
+                .children
+                .borrow()
+                .iter()
+                .position(|child| Rc::ptr_eq(child, &text))
+                .unwrap_or(0);
+            parent.remove_child(&text);
+            for (i, path) in paths.into_iter().enumerate() {
+                parent.insert_child(index + i, path);
+            }
+        } else {
+            let group = Element::new(("g", HashMap::new()));
+            copy_attributes(&text, &group, true);
+            group.add_children(paths);
+            parent.replace_child(&text, group);
+        }
+    }
+    document.reindex();
+}
+
+/// copy the attributes of a element of a text to a new element, without the
+/// ones of the layout. a attribute replaces the same property in the `style`
+/// copied from a outer element.
+fn copy_attributes(from: &Element, to: &Element, with_id: bool) {
+    let mut attributes: Vec<(String, String)> = from
+        .attributes
+        .borrow()
+        .iter()
+        .map(|(key, value)| (key.clone(), value.to_string()))
+        .collect();
+    attributes.sort_by(|(a, _), (b, _)| (a == "style", a).cmp(&(b == "style", b)));
+    for (key, value) in attributes {
+        if TEXT_PROPERTIES.contains(&key.as_str()) || (key == "id" && !with_id) {
+            continue;
+        }
+        if key == "style" {
+            let mut style = to.style();
+            for declaration in Style::parse(&value).declarations {
+                if !TEXT_PROPERTIES.contains(&declaration.name.as_str()) {
+                    style.set(declaration);
+                }
+            }
+            to.set_style(&style);
+        } else {
+            to.remove_style_property(&key);
+            to.set_attribute(&key, value);
+        }
+    }
+}
+
+/// round the coordinates of a outline to three decimals
+fn rounded(segments: Vec<PathSegment>) -> PathData {
+    let round = |n: &mut f64| *n = snap((*n * 1000.0).round() / 1000.0);
+    let mut segments = segments;
+    for segment in &mut segments {
+        match segment {
+            PathSegment::MoveTo { x, y, .. }
+            | PathSegment::LineTo { x, y, .. }
+            | PathSegment::SmoothQuadratic { x, y, .. } => {
+                round(x);
+                round(y);
+            }
+            PathSegment::Quadratic { x1, y1, x, y, .. } => {
+                for n in [x1, y1, x, y] {
+                    round(n);
+                }
+            }
+            PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+                ..
+            } => {
+                for n in [x1, y1, x2, y2, x, y] {
+                    round(n);
+                }
+            }
+            _ => {}
+        }
+    }
+    PathData { segments }
+}
+
+/// the `xml:space` of a element or its closest ancestor
+fn xml_space(ele: &Rc<Element>) -> Option<String> {
+    let mut current = Some(ele.clone());
+    while let Some(ele) = current {
+        if let Some(value) = ele.get_attribute("xml:space") {
+            return Some(value.trim().to_owned());
+        }
+        current = ele.parent.borrow().upgrade();
+    }
+    None
+}
+
+/// collapse the white space of the characters of a text, unless `xml:space`
+/// or `white-space` preserves it: line breaks are removed, tabs become spaces,
+/// and spaces at the start, at the end or after another space are removed
+fn collapse_white_space<'a>(
+    styles: &ComputedStyles<'a>,
+    characters: Vec<Character>,
+    nodes: &[Node<'a>],
+) -> Vec<Character> {
+    let preserve: Vec<bool> = nodes
+        .iter()
+        .map(|node| {
+            xml_space(&node.element).as_deref() == Some("preserve")
+                || matches!(
+                    styles
+                        .get(&node.element, "white-space")
+                        .as_deref()
+                        .map(str::trim),
+                    Some("pre" | "pre-wrap" | "break-spaces")
+                )
+        })
+        .collect();
+    let mut collapsed: Vec<Character> = vec![];
+    for Character { character, node } in characters {
+        if preserve[node] {
+            let character = if character.is_whitespace() {
+                ' '
+            } else {
+                character
+            };
+            collapsed.push(Character { character, node });
+            continue;
+        }
+        let character = match character {
+            '\n' | '\r' => continue,
+            '\t' => ' ',
+            c => c,
+        };
+        if character == ' '
+            && collapsed
+                .last()
+                .is_none_or(|last| last.character == ' ' && !preserve[last.node])
+        {
+            continue;
+        }
+        collapsed.push(Character { character, node });
+    }
+    if collapsed
+        .last()
+        .is_some_and(|last| last.character == ' ' && !preserve[last.node])
+    {
+        collapsed.pop();
+    }
+    collapsed
+}
+
+/// the value of a position list for the character `i`, from the closest element
+/// which has one for it
+fn list_value<'a>(
+    nodes: &[Node<'a>],
+    node: usize,
+    i: usize,
+    list: for<'n> fn(&'n Node<'a>) -> &'n Vec<f64>,
+) -> Option<f64> {
+    let mut current = Some(node);
+    while let Some(n) = current {
+        if let Some(value) = list(&nodes[n]).get(i - nodes[n].first) {
+            return Some(*value);
+        }
+        current = nodes[n].parent;
+    }
+    None
+}
+
+/// the rotation of the character `i`, the last value of a `rotate` list goes
+/// on for the rest of the element
+fn rotate_value(nodes: &[Node], node: usize, i: usize) -> f64 {
+    let mut current = Some(node);
+    while let Some(n) = current {
+        let rotate = &nodes[n].rotate;
+        if let Some(last) = rotate.last() {
+            return *rotate.get(i - nodes[n].first).unwrap_or(last);
+        }
+        current = nodes[n].parent;
+    }
+    0.0
+}
+
+/// place the glyph of each character along the baseline, then align the chunks.
+/// the position of a glyph on a `textPath` is its distance along the path and
+/// its offset from it.
+fn place_glyphs<'f>(
+    characters: &[Character],
+    nodes: &[Node],
+    fonts: &'f Fonts,
+    paths: &HashMap<usize, Option<TextPath>>,
+) -> Vec<Placed<'f>> {
+    let mut placed: Vec<Placed> = vec![];
+    let mut pen = (0.0, 0.0);
+    let mut chunk = 0;
+    let mut text_path = None;
+    for (i, character) in characters.iter().enumerate() {
+        let n = character.node;
+        let node = &nodes[n];
+        let mut new_chunk = i == 0;
+        if node.text_path != text_path {
+            match node.text_path.and_then(|p| paths.get(&p)?.as_ref()) {
+                Some(path) => pen = (path.start_offset, 0.0),
+                None => {
+                    // after a path the text goes on from the end of its last glyph
+                    let end = text_path
+                        .and_then(|p| paths.get(&p)?.as_ref())
+                        .and_then(|path: &TextPath| path.at(pen.0.min(path.length)));
+                    if let Some((x, y, _)) = end {
+                        pen = (x, y);
+                    }
+                }
+            }
+            text_path = node.text_path;
+            new_chunk = true;
+        }
+        if let Some(x) = list_value(nodes, n, i, |node| &node.x) {
+            pen.0 = x;
+            new_chunk = true;
+        }
+        if let Some(y) = list_value(nodes, n, i, |node| &node.y).filter(|_| text_path.is_none()) {
+            pen.1 = y;
+            new_chunk = true;
+        }
+        pen.0 += list_value(nodes, n, i, |node| &node.dx).unwrap_or(0.0);
+        pen.1 += list_value(nodes, n, i, |node| &node.dy).unwrap_or(0.0);
+        if new_chunk && !placed.is_empty() {
+            chunk += 1;
+        }
+        let Some(primary) = fonts.select(&node.family, node.weight, node.italic) else {
+            continue;
+        };
+        let c = character.character;
+        let (font, glyph) = match primary.glyph_index(c) {
+            Some(glyph) => (primary, glyph),
+            None => fonts
+                .fonts
+                .iter()
+                .find_map(|font| Some((font, font.glyph_index(c)?)))
+                .unwrap_or((primary, 0)),
+        };
+        let scale = node.font_size / font.units_per_em;
+        if let Some(previous) = placed.last() {
+            if previous.chunk == chunk && std::ptr::eq(previous.font, font) && node.kerning {
+                pen.0 += font.kerning(previous.glyph, glyph) * scale;
+            }
+        }
+        let shift = baseline_offset(font, &node.baseline) * scale - node.baseline_shift;
+        let mut advance = font.advance(glyph) * scale + node.letter_spacing;
+        if c == ' ' {
+            advance += node.word_spacing;
+        }
+        placed.push(Placed {
+            character: i,
+            font,
+            glyph,
+            x: pen.0,
+            y: pen.1 + shift,
+            rotate: rotate_value(nodes, n, i),
+            scale,
+            advance,
+            letter_spacing: node.letter_spacing,
+            chunk,
+            anchor: node.anchor,
+        });
+        pen.0 += advance;
+    }
+    let mut start = 0;
+    while start < placed.len() {
+        let chunk = placed[start].chunk;
+        let end = start
+            + placed[start..]
+                .iter()
+                .take_while(|p| p.chunk == chunk)
+                .count();
+        let last = &placed[end - 1];
+        let width = last.x + last.advance - last.letter_spacing - placed[start].x;
+        let shift = match placed[start].anchor {
+            Anchor::Start => 0.0,
+            Anchor::Middle => -width / 2.0,
+            Anchor::End => -width,
+        };
+        for glyph in &mut placed[start..end] {
+            glyph.x += shift;
+        }
+        start = end;
+    }
+    placed
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::font::tests::test_font;
+    use crate::{parse_document, stringify, text_to_paths, Font, Fonts, LengthContext};
+
+    fn fonts() -> Fonts {
+        let mut fonts = Fonts::new();
+        fonts.add(Font::parse(test_font()).unwrap());
+        fonts
+    }
+
+    /// the character, position and rotation of the glyphs of the text `t`
+    fn layout(svg: &str) -> Vec<(char, f64, f64, f64)> {
+        let (_, document) = parse_document(svg).unwrap();
+        let text = document.element_by_id("t").unwrap();
+        let round = |n: f64| (n * 1000.0).round() / 1000.0 + 0.0;
+        document
+            .layout_text(&text, &fonts(), &LengthContext::default())
+            .iter()
+            .map(|glyph| {
+                (
+                    glyph.character,
+                    round(glyph.x),
+                    round(glyph.y),
+                    round(glyph.rotate),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_select() {
+        let mut fonts = fonts();
+        let mut bold = Font::parse(test_font()).unwrap();
+        bold.weight = 700;
+        fonts.add(bold);
+        let weight =
+            |family: &str, weight: u16| fonts.select(family, weight, false).unwrap().weight;
+        assert_eq!(weight("'test', serif", 400), 400);
+        assert_eq!(weight("Other, \"Test\"", 600), 700);
+        assert_eq!(weight("sans-serif", 900), 700);
+        assert!(Fonts::new().select("Test", 400, false).is_none());
+    }
+
+    #[test]
+    fn test_layout() {
+        // a space is 2.5 wide, A 6, B 5, with a kerning of -1 between A and B
+        assert_eq!(
+            layout(r#"<svg><text id="t" x="10" y="20" font-size="10">AB</text></svg>"#),
+            vec![('A', 10.0, 20.0, 0.0), ('B', 15.0, 20.0, 0.0)]
+        );
+        assert_eq!(
+            layout(r#"<svg><text id="t" font-size="10" style="font-kerning:none">AB</text></svg>"#),
+            vec![('A', 0.0, 0.0, 0.0), ('B', 6.0, 0.0, 0.0)]
+        );
+        assert_eq!(
+            layout(
+                r#"<svg><text id="t" font-size="10" xml:space="default">
+              A  B </text></svg>"#
+            ),
+            vec![
+                ('A', 0.0, 0.0, 0.0),
+                (' ', 6.0, 0.0, 0.0),
+                ('B', 8.5, 0.0, 0.0)
+            ]
+        );
+        assert_eq!(
+            layout(r#"<svg><text id="t" font-size="10" xml:space="preserve"> A</text></svg>"#),
+            vec![(' ', 0.0, 0.0, 0.0), ('A', 2.5, 0.0, 0.0)]
+        );
+        assert_eq!(
+            layout(
+                r#"<svg><text id="t" x="0 20" dx="1" dy="0 2" rotate="10 30" font-size="10">AAA</text></svg>"#
+            ),
+            vec![
+                ('A', 1.0, 0.0, 10.0),
+                ('A', 20.0, 2.0, 30.0),
+                ('A', 26.0, 2.0, 30.0)
+            ]
+        );
+        assert_eq!(
+            layout(
+                r#"<svg><text id="t" font-size="20" letter-spacing="1" word-spacing="2"><tspan font-size="50%">A A</tspan></text></svg>"#
+            ),
+            vec![
+                ('A', 0.0, 0.0, 0.0),
+                (' ', 7.0, 0.0, 0.0),
+                ('A', 12.5, 0.0, 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layout_anchor_and_baseline() {
+        assert_eq!(
+            layout(
+                r#"<svg><text id="t" x="10" font-size="10" text-anchor="middle">AB</text></svg>"#
+            ),
+            vec![('A', 5.0, 0.0, 0.0), ('B', 10.0, 0.0, 0.0)]
+        );
+        // each absolute position starts a chunk
+        assert_eq!(
+            layout(
+                r#"<svg><text id="t" x="10 20" font-size="10" text-anchor="end">AA</text></svg>"#
+            ),
+            vec![('A', 4.0, 0.0, 0.0), ('A', 14.0, 0.0, 0.0)]
+        );
+        assert_eq!(
+            layout(
+                r#"<svg><text id="t" y="10" font-size="10" dominant-baseline="middle">A<tspan baseline-shift="super">B</tspan><tspan dominant-baseline="hanging">B</tspan></text></svg>"#
+            ),
+            vec![
+                ('A', 0.0, 12.5, 0.0),
+                ('B', 5.0, 8.5, 0.0),
+                ('B', 10.0, 16.4, 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layout_tspan() {
+        // the `x` of the text goes on after the one of the tspan
+        assert_eq!(
+            layout(
+                r#"<svg><text id="t" x="1 2 3" font-size="10">A<tspan x="20" dy="5">AB</tspan><tspan style="display:none">A</tspan></text></svg>"#
+            ),
+            vec![
+                ('A', 1.0, 0.0, 0.0),
+                ('A', 20.0, 5.0, 0.0),
+                ('B', 3.0, 5.0, 0.0)
+            ]
+        );
+        assert_eq!(
+            layout(r#"<svg><text id="t" font-size="10" font-family="Unknown">AC</text></svg>"#),
+            vec![('A', 0.0, 0.0, 0.0), ('C', 6.0, 0.0, 0.0)]
+        );
+        assert_eq!(layout(r#"<svg><text id="t"></text></svg>"#), vec![]);
+        // the text after a tspan follows it, the space between tspans is kept
+        assert_eq!(
+            layout(
+                r#"<svg><text id="t" font-size="10">A<tspan>B</tspan>A<tspan>B</tspan> <tspan>A</tspan></text></svg>"#
+            ),
+            vec![
+                ('A', 0.0, 0.0, 0.0),
+                ('B', 5.0, 0.0, 0.0),
+                ('A', 10.0, 0.0, 0.0),
+                ('B', 15.0, 0.0, 0.0),
+                (' ', 20.0, 0.0, 0.0),
+                ('A', 22.5, 0.0, 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_layout_text_path() {
+        assert_eq!(
+            layout(
+                r##"<svg><path id="p" d="M0 0V100" transform="translate(5)"/><text id="t" font-size="10"><textPath href="#p" startOffset="10">AB</textPath></text></svg>"##
+            ),
+            vec![('A', 5.0, 10.0, 90.0), ('B', 5.0, 15.0, 90.0)]
+        );
+        // glyphs past the end of the path are left out
+        assert_eq!(
+            layout(
+                r##"<svg><path id="p" d="M0 0H10"/><text id="t" font-size="10"><textPath href="#p">AAA</textPath></text></svg>"##
+            ),
+            vec![('A', 0.0, 0.0, 0.0), ('A', 6.0, 0.0, 0.0)]
+        );
+        assert_eq!(
+            layout(
+                r##"<svg><text id="t" font-size="10"><textPath path="M0 0H100" startOffset="50%" text-anchor="middle">AA</textPath></text></svg>"##
+            ),
+            vec![('A', 44.0, 0.0, 0.0), ('A', 50.0, 0.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn test_outline() {
+        let (_, document) = parse_document(
+            r#"<svg><text id="t" x="10" y="20" font-size="10" rotate="90">B</text></svg>"#,
+        )
+        .unwrap();
+        let text = document.element_by_id("t").unwrap();
+        let glyphs = document.layout_text(&text, &fonts(), &LengthContext::default());
+        assert_eq!(glyphs[0].outline.to_string(), "M10 20 17 22.5 10 25Z");
+        assert_eq!((glyphs[0].font_size, glyphs[0].advance), (10.0, 5.0));
+    }
+
+    #[test]
+    fn test_text_to_paths() {
+        let (_, document) = parse_document(
+            r#"<svg><text id="t" x="1" y="10" font-size="10" fill="red" transform="scale(2)">B</text></svg>"#,
+        )
+        .unwrap();
+        text_to_paths(&document, &fonts());
+        assert_eq!(
+            stringify(document.root.clone()),
+            r#"<svg><path d="M1 10 3.5 3 6 10Z" fill="red" id="t" transform="scale(2)"/></svg>"#
+        );
+        assert_eq!(document.element_by_id("t").unwrap().ele_type, "path");
+
+        let (_, document) = parse_document(
+            r#"<svg><text id="t" y="10" style="font-size:10px;fill:red">B<tspan id="s" fill="blue" dx="1">B</tspan></text><text/></svg>"#,
+        )
+        .unwrap();
+        text_to_paths(&document, &fonts());
+        assert_eq!(
+            stringify(document.root.clone()),
+            r#"<svg><g id="t" style="fill:red"><path d="M0 10 2.5 3 5 10Z"/><path d="M6 10 8.5 3 11 10Z" fill="blue" id="s"/></g><text/></svg>"#
+        );
+
+        let (_, document) = parse_document(
+            r#"<svg><clipPath><text font-size="10" fill="red">B<tspan>B</tspan></text></clipPath></svg>"#,
+        )
+        .unwrap();
+        text_to_paths(&document, &fonts());
+        assert_eq!(
+            stringify(document.root.clone()),
+            r#"<svg><clipPath><path d="M0 0 2.5-7 5 0Z" fill="red"/><path d="M5 0 7.5-7 10 0Z" fill="red"/></clipPath></svg>"#
+        );
+    }
+}
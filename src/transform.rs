@@ -0,0 +1,606 @@
+use std::error::Error;
+use std::fmt;
+use std::ops::Mul;
+use std::str::FromStr;
+
+use crate::ast::Element;
+use crate::number::snap;
+use crate::path::{comma_wsp, is_wsp, number, shortest_number};
+
+/// A 2D affine matrix
+///
+/// maps `(x, y)` to `(a * x + c * y + e, b * x + d * y + f)`, the same as
+/// `matrix(a b c d e f)`. `m1 * m2` applies `m2` first, like `transform="m1 m2"`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Matrix::IDENTITY
+    }
+}
+
+impl Mul for Matrix {
+    type Output = Matrix;
+
+    fn mul(self, m: Matrix) -> Matrix {
+        Matrix {
+            a: self.a * m.a + self.c * m.b,
+            b: self.b * m.a + self.d * m.b,
+            c: self.a * m.c + self.c * m.d,
+            d: self.b * m.c + self.d * m.d,
+            e: self.a * m.e + self.c * m.f + self.e,
+            f: self.b * m.e + self.d * m.f + self.f,
+        }
+    }
+}
+
+impl Matrix {
+    pub const IDENTITY: Matrix = Matrix::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+
+    pub const fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Matrix {
+        Matrix { a, b, c, d, e, f }
+    }
+
+    pub fn translate(tx: f64, ty: f64) -> Matrix {
+        Matrix::new(1.0, 0.0, 0.0, 1.0, tx, ty)
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Matrix {
+        Matrix::new(sx, 0.0, 0.0, sy, 0.0, 0.0)
+    }
+
+    /// a rotation around the origin, `angle` in degrees
+    pub fn rotate(angle: f64) -> Matrix {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        Matrix::new(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    /// `angle` in degrees
+    pub fn skew_x(angle: f64) -> Matrix {
+        Matrix::new(1.0, 0.0, angle.to_radians().tan(), 1.0, 0.0, 0.0)
+    }
+
+    /// `angle` in degrees
+    pub fn skew_y(angle: f64) -> Matrix {
+        Matrix::new(1.0, angle.to_radians().tan(), 0.0, 1.0, 0.0, 0.0)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Matrix::IDENTITY
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.a * self.d - self.b * self.c
+    }
+
+    /// the inverse matrix, `None` if the matrix is singular
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::Matrix;
+    ///
+    /// let m = Matrix::translate(10.0, 5.0) * Matrix::scale(2.0, 4.0);
+    /// assert_eq!(m.invert().unwrap().apply(30.0, 25.0), (10.0, 5.0));
+    /// assert_eq!(Matrix::scale(0.0, 1.0).invert(), None);
+    /// ```
+    ///
+    pub fn invert(&self) -> Option<Matrix> {
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        Some(Matrix::new(
+            self.d / det,
+            -self.b / det,
+            -self.c / det,
+            self.a / det,
+            (self.c * self.f - self.d * self.e) / det,
+            (self.b * self.e - self.a * self.f) / det,
+        ))
+    }
+
+    /// transform a point
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// transform a vector, ignoring the translation
+    pub fn apply_vector(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.a * x + self.c * y, self.b * x + self.d * y)
+    }
+
+    /// split the matrix into `translate rotate skewX scale`, `None` if the
+    /// matrix is singular
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::Matrix;
+    ///
+    /// let m = Matrix::translate(10.0, 5.0) * Matrix::rotate(90.0) * Matrix::scale(2.0, 3.0);
+    /// let decomposition = m.decompose().unwrap();
+    /// assert_eq!(decomposition.translate, (10.0, 5.0));
+    /// assert_eq!(decomposition.rotate, 90.0);
+    /// assert!(decomposition.skew_x.abs() < 1e-9);
+    /// assert!((decomposition.scale.0 - 2.0).abs() < 1e-9);
+    /// assert!((decomposition.scale.1 - 3.0).abs() < 1e-9);
+    /// ```
+    ///
+    pub fn decompose(&self) -> Option<Decomposition> {
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let sx = self.a.hypot(self.b);
+        let rotate = self.b.atan2(self.a);
+        let sy = det / sx;
+        let (sin, cos) = rotate.sin_cos();
+        let skew = ((self.c * cos + self.d * sin) / sy).atan();
+        Some(Decomposition {
+            translate: (self.e, self.f),
+            rotate: rotate.to_degrees(),
+            skew_x: skew.to_degrees(),
+            scale: (sx, sy),
+        })
+    }
+
+    /// the shortest transform list for the matrix
+    ///
+    /// decomposed values are rounded to 9 decimals.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::Matrix;
+    ///
+    /// let m = Matrix::translate(10.0, 0.0) * Matrix::rotate(45.0);
+    /// assert_eq!(m.to_transform().to_string(), "translate(10) rotate(45)");
+    /// let m = Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0);
+    /// assert_eq!(m.to_transform().to_string(), "matrix(1 2 3 4 5 6)");
+    /// ```
+    ///
+    pub fn to_transform(&self) -> Transform {
+        let matrix = Transform {
+            functions: vec![TransformFunction::Matrix(*self)],
+        };
+        let decomposed = match self.decompose() {
+            Some(decomposition) => decomposition.to_transform(),
+            None => return matrix,
+        };
+        if decomposed.to_string().len() <= matrix.to_string().len() {
+            decomposed
+        } else {
+            matrix
+        }
+    }
+}
+
+/// A matrix split into `translate rotate skewX scale`, see `Matrix::decompose`
+///
+/// angles in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decomposition {
+    pub translate: (f64, f64),
+    pub rotate: f64,
+    pub skew_x: f64,
+    pub scale: (f64, f64),
+}
+
+impl Decomposition {
+    /// the transform list of the decomposition, without identity functions
+    pub fn to_transform(&self) -> Transform {
+        let (tx, ty) = (snap(self.translate.0), snap(self.translate.1));
+        let (sx, sy) = (snap(self.scale.0), snap(self.scale.1));
+        let (rotate, skew) = (snap(self.rotate), snap(self.skew_x));
+        let mut functions = vec![];
+        if tx != 0.0 || ty != 0.0 {
+            functions.push(TransformFunction::Translate { tx, ty });
+        }
+        if rotate != 0.0 {
+            functions.push(TransformFunction::Rotate {
+                angle: rotate,
+                cx: 0.0,
+                cy: 0.0,
+            });
+        }
+        if skew != 0.0 {
+            functions.push(TransformFunction::SkewX { angle: skew });
+        }
+        if sx != 1.0 || sy != 1.0 {
+            functions.push(TransformFunction::Scale { sx, sy });
+        }
+        Transform { functions }
+    }
+}
+
+/// A function of a transform list, angles in degrees
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransformFunction {
+    Matrix(Matrix),
+    Translate { tx: f64, ty: f64 },
+    Scale { sx: f64, sy: f64 },
+    Rotate { angle: f64, cx: f64, cy: f64 },
+    SkewX { angle: f64 },
+    SkewY { angle: f64 },
+}
+
+impl TransformFunction {
+    pub fn to_matrix(&self) -> Matrix {
+        match *self {
+            TransformFunction::Matrix(m) => m,
+            TransformFunction::Translate { tx, ty } => Matrix::translate(tx, ty),
+            TransformFunction::Scale { sx, sy } => Matrix::scale(sx, sy),
+            TransformFunction::Rotate { angle, cx, cy } => {
+                Matrix::translate(cx, cy) * Matrix::rotate(angle) * Matrix::translate(-cx, -cy)
+            }
+            TransformFunction::SkewX { angle } => Matrix::skew_x(angle),
+            TransformFunction::SkewY { angle } => Matrix::skew_y(angle),
+        }
+    }
+}
+
+impl fmt::Display for TransformFunction {
+    /// write the function with the fewest arguments
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, args) = match *self {
+            TransformFunction::Matrix(m) => ("matrix", vec![m.a, m.b, m.c, m.d, m.e, m.f]),
+            TransformFunction::Translate { tx, ty } => {
+                ("translate", if ty == 0.0 { vec![tx] } else { vec![tx, ty] })
+            }
+            TransformFunction::Scale { sx, sy } if sx == sy => ("scale", vec![sx]),
+            TransformFunction::Scale { sx, sy } => ("scale", vec![sx, sy]),
+            TransformFunction::Rotate { angle, cx, cy } if cx == 0.0 && cy == 0.0 => {
+                ("rotate", vec![angle])
+            }
+            TransformFunction::Rotate { angle, cx, cy } => ("rotate", vec![angle, cx, cy]),
+            TransformFunction::SkewX { angle } => ("skewX", vec![angle]),
+            TransformFunction::SkewY { angle } => ("skewY", vec![angle]),
+        };
+        let args: Vec<String> = args.into_iter().map(shortest_number).collect();
+        write!(f, "{}({})", name, args.join(" "))
+    }
+}
+
+/// A parsed transform list, of `transform`, `gradientTransform` or `patternTransform`
+///
+/// `Display` writes the functions separated by a space.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Transform {
+    pub functions: Vec<TransformFunction>,
+}
+
+/// What went wrong when parsing a transform list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformErrorKind {
+    ExpectedFunction,
+    ExpectedParenthesis,
+    ExpectedNumber,
+    /// a function with a number of arguments it does not accept
+    WrongArgumentCount,
+}
+
+/// A error in a transform list
+///
+/// `position` the byte offset of the error in the transform list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransformError {
+    pub position: usize,
+    pub kind: TransformErrorKind,
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            TransformErrorKind::ExpectedFunction => "expected a transform function",
+            TransformErrorKind::ExpectedParenthesis => "expected a parenthesis",
+            TransformErrorKind::ExpectedNumber => "expected a number",
+            TransformErrorKind::WrongArgumentCount => "wrong number of arguments",
+        };
+        write!(f, "{} at position {}", message, self.position)
+    }
+}
+
+impl Error for TransformError {}
+
+impl Transform {
+    /// parse a transform list
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{Transform, TransformFunction};
+    ///
+    /// let transform = Transform::parse("translate(10 20) rotate(45,50,50)skewX(10)").unwrap();
+    /// assert_eq!(transform.functions[1], TransformFunction::Rotate { angle: 45.0, cx: 50.0, cy: 50.0 });
+    ///
+    /// let error = Transform::parse("scale(1 2 3)").unwrap_err();
+    /// assert_eq!(error.to_string(), "wrong number of arguments at position 0");
+    /// ```
+    ///
+    pub fn parse(input: &str) -> Result<Transform, TransformError> {
+        let error = |rest: &str, kind| TransformError {
+            position: input.len() - rest.len(),
+            kind,
+        };
+        let mut functions = vec![];
+        let mut rest = input.trim_start_matches(is_wsp);
+        while !rest.is_empty() {
+            let start = rest;
+            let name_end = rest
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len());
+            let name = &rest[..name_end];
+            if !["matrix", "translate", "scale", "rotate", "skewX", "skewY"].contains(&name) {
+                return Err(error(start, TransformErrorKind::ExpectedFunction));
+            }
+            rest = rest[name_end..].trim_start_matches(is_wsp);
+            rest = rest
+                .strip_prefix('(')
+                .ok_or_else(|| error(rest, TransformErrorKind::ExpectedParenthesis))?
+                .trim_start_matches(is_wsp);
+            let mut args = vec![];
+            while !rest.starts_with(')') {
+                let (after, n) =
+                    number(rest).map_err(|_| error(rest, TransformErrorKind::ExpectedNumber))?;
+                args.push(n);
+                let separated = comma_wsp(after);
+                if separated.starts_with(')') && !after.trim_start_matches(is_wsp).starts_with(')')
+                {
+                    // a comma before the closing parenthesis
+                    return Err(error(separated, TransformErrorKind::ExpectedNumber));
+                }
+                if separated.is_empty() {
+                    return Err(error(separated, TransformErrorKind::ExpectedParenthesis));
+                }
+                rest = separated;
+            }
+            let function = match (name, args.as_slice()) {
+                ("matrix", &[a, b, c, d, e, f]) => {
+                    TransformFunction::Matrix(Matrix::new(a, b, c, d, e, f))
+                }
+                ("translate", &[tx]) => TransformFunction::Translate { tx, ty: 0.0 },
+                ("translate", &[tx, ty]) => TransformFunction::Translate { tx, ty },
+                ("scale", &[s]) => TransformFunction::Scale { sx: s, sy: s },
+                ("scale", &[sx, sy]) => TransformFunction::Scale { sx, sy },
+                ("rotate", &[angle]) => TransformFunction::Rotate {
+                    angle,
+                    cx: 0.0,
+                    cy: 0.0,
+                },
+                ("rotate", &[angle, cx, cy]) => TransformFunction::Rotate { angle, cx, cy },
+                ("skewX", &[angle]) => TransformFunction::SkewX { angle },
+                ("skewY", &[angle]) => TransformFunction::SkewY { angle },
+                _ => return Err(error(start, TransformErrorKind::WrongArgumentCount)),
+            };
+            functions.push(function);
+            rest = comma_wsp(&rest[1..]);
+        }
+        Ok(Transform { functions })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// compose the functions of the list into one matrix
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::Transform;
+    ///
+    /// let transform = Transform::parse("translate(10) scale(2)").unwrap();
+    /// assert_eq!(transform.to_matrix().apply(1.0, 1.0), (12.0, 2.0));
+    /// ```
+    ///
+    pub fn to_matrix(&self) -> Matrix {
+        self.functions
+            .iter()
+            .fold(Matrix::IDENTITY, |m, function| m * function.to_matrix())
+    }
+}
+
+impl FromStr for Transform {
+    type Err = TransformError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Transform::parse(s)
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let functions: Vec<String> = self.functions.iter().map(|f| f.to_string()).collect();
+        write!(f, "{}", functions.join(" "))
+    }
+}
+
+/// the attribute holding the transform of a element type
+pub(crate) fn transform_attribute(ele_type: &str) -> &'static str {
+    match ele_type {
+        "linearGradient" | "radialGradient" => "gradientTransform",
+        "pattern" => "patternTransform",
+        _ => "transform",
+    }
+}
+
+impl<'a> Element<'a> {
+    /// parse the transform of the element, `gradientTransform` for gradients
+    /// and `patternTransform` for patterns. a missing attribute is a empty list.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::parse;
+    ///
+    /// let (_, ele) = parse(r#"<linearGradient gradientTransform="rotate(90)"/>"#).unwrap();
+    /// assert_eq!(ele.transform().unwrap().to_string(), "rotate(90)");
+    /// ```
+    ///
+    pub fn transform(&self) -> Result<Transform, TransformError> {
+        match self
+            .attributes
+            .borrow()
+            .get(transform_attribute(self.ele_type))
+        {
+            Some(value) => Transform::parse(value),
+            None => Ok(Transform::default()),
+        }
+    }
+
+    /// write the transform of the element, a empty list removes the attribute
+    pub fn set_transform(&self, transform: &Transform) {
+        let key = transform_attribute(self.ele_type);
+        if transform.is_empty() {
+            self.remove_attribute(key);
+        } else {
+            self.set_attribute(key, transform.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transform::TransformErrorKind;
+    use crate::{parse, stringify, Matrix, Transform, TransformError, TransformFunction};
+
+    fn close(a: Matrix, b: Matrix) -> bool {
+        [
+            a.a - b.a,
+            a.b - b.b,
+            a.c - b.c,
+            a.d - b.d,
+            a.e - b.e,
+            a.f - b.f,
+        ]
+        .iter()
+        .all(|n| n.abs() < 1e-9)
+    }
+
+    #[test]
+    fn test_parse() {
+        let transform = Transform::parse(
+            " matrix(1,2,3,4,5,6)translate(1) , scale(2 3)rotate(-1e1)skewX(10)\tskewY( .5 ) ",
+        )
+        .unwrap();
+        assert_eq!(
+            transform.functions,
+            vec![
+                TransformFunction::Matrix(Matrix::new(1.0, 2.0, 3.0, 4.0, 5.0, 6.0)),
+                TransformFunction::Translate { tx: 1.0, ty: 0.0 },
+                TransformFunction::Scale { sx: 2.0, sy: 3.0 },
+                TransformFunction::Rotate {
+                    angle: -10.0,
+                    cx: 0.0,
+                    cy: 0.0
+                },
+                TransformFunction::SkewX { angle: 10.0 },
+                TransformFunction::SkewY { angle: 0.5 },
+            ]
+        );
+        assert_eq!(Transform::parse("").unwrap(), Transform::default());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |position, kind| Err(TransformError { position, kind });
+        assert_eq!(
+            Transform::parse("translate(1) foo(2)"),
+            error(13, TransformErrorKind::ExpectedFunction)
+        );
+        assert_eq!(
+            Transform::parse("scale 2"),
+            error(6, TransformErrorKind::ExpectedParenthesis)
+        );
+        assert_eq!(
+            Transform::parse("scale(2"),
+            error(7, TransformErrorKind::ExpectedParenthesis)
+        );
+        assert_eq!(
+            Transform::parse("scale(2,)"),
+            error(8, TransformErrorKind::ExpectedNumber)
+        );
+        assert_eq!(
+            Transform::parse("scale(a)"),
+            error(6, TransformErrorKind::ExpectedNumber)
+        );
+        assert_eq!(
+            Transform::parse("scale(1) rotate(1 2)"),
+            error(9, TransformErrorKind::WrongArgumentCount)
+        );
+    }
+
+    #[test]
+    fn test_compose_and_invert() {
+        let transform = Transform::parse("rotate(90 10 10)").unwrap();
+        let m = transform.to_matrix();
+        let (x, y) = m.apply(20.0, 10.0);
+        assert!((x - 10.0).abs() < 1e-9 && (y - 20.0).abs() < 1e-9);
+        assert!(close(m * m.invert().unwrap(), Matrix::IDENTITY));
+        let skew = Matrix::skew_x(45.0) * Matrix::skew_y(30.0);
+        assert!(close(skew.invert().unwrap() * skew, Matrix::IDENTITY));
+        assert_eq!(Matrix::scale(0.0, 0.0).decompose(), None);
+    }
+
+    #[test]
+    fn test_decompose_round_trip() {
+        for source in [
+            "translate(3 4) rotate(30) scale(2 -3)",
+            "skewX(20) translate(1 2)",
+            "matrix(1 2 3 4 5 6)",
+            "scale(-1 1)",
+        ] {
+            let m = Transform::parse(source).unwrap().to_matrix();
+            let d = m.decompose().unwrap();
+            let recomposed = Matrix::translate(d.translate.0, d.translate.1)
+                * Matrix::rotate(d.rotate)
+                * Matrix::skew_x(d.skew_x)
+                * Matrix::scale(d.scale.0, d.scale.1);
+            assert!(close(m, recomposed), "{}", source);
+            let serialized = m.to_transform();
+            assert!(close(m, serialized.to_matrix()), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_serialize() {
+        let serialize = |s: &str| Transform::parse(s).unwrap().to_string();
+        assert_eq!(
+            serialize("translate(10, 0) scale(2, 2) rotate(45, 0, 0) skewY(-0.5)"),
+            "translate(10) scale(2) rotate(45) skewY(-.5)"
+        );
+        let minimal = |s: &str| {
+            Transform::parse(s)
+                .unwrap()
+                .to_matrix()
+                .to_transform()
+                .to_string()
+        };
+        assert_eq!(minimal("translate(1 1) translate(-1 -1)"), "");
+        assert_eq!(minimal("scale(2) scale(1.5)"), "scale(3)");
+        assert_eq!(minimal("rotate(90 10 10)"), "translate(20) rotate(90)");
+    }
+
+    #[test]
+    fn test_element_transform() {
+        let (_, root) = parse(
+            r#"<svg><pattern patternTransform="scale(2)"/><g transform="translate(1 2)"/></svg>"#,
+        )
+        .unwrap();
+        let pattern = root.children.borrow()[0].clone();
+        let g = root.children.borrow()[1].clone();
+        assert_eq!(pattern.transform().unwrap().to_string(), "scale(2)");
+        g.set_transform(&Transform::default());
+        pattern.set_transform(&Transform::parse("rotate(10)").unwrap());
+        assert_eq!(
+            stringify(root),
+            r#"<svg><pattern patternTransform="rotate(10)"/><g/></svg>"#
+        );
+    }
+}
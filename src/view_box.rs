@@ -0,0 +1,505 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ast::Element;
+use crate::length::{Length, LengthContext, LengthDirection};
+use crate::number::parse_number_list;
+use crate::path::shortest_number;
+use crate::transform::Matrix;
+
+/// A parsed `viewBox`, the rectangle of user space which is fit into the viewport
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewBox {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Where the view box is placed in the viewport, on each axis
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    /// scale the view box to the viewport on each axis independently
+    None,
+    XMinYMin,
+    XMidYMin,
+    XMaxYMin,
+    XMinYMid,
+    XMidYMid,
+    XMaxYMid,
+    XMinYMax,
+    XMidYMax,
+    XMaxYMax,
+}
+
+/// Whether the view box is scaled to be fully visible or to cover the viewport
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeetOrSlice {
+    Meet,
+    Slice,
+}
+
+/// A parsed `preserveAspectRatio`, `xMidYMid meet` by default
+///
+/// `Display` omits `meet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreserveAspectRatio {
+    pub align: Align,
+    pub meet_or_slice: MeetOrSlice,
+}
+
+/// What went wrong when parsing a `viewBox` or a `preserveAspectRatio`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewBoxErrorKind {
+    ExpectedNumber,
+    /// a `viewBox` without exactly four numbers
+    WrongNumberCount,
+    /// a `viewBox` with a negative width or height
+    NegativeSize,
+    InvalidAlign,
+    InvalidMeetOrSlice,
+}
+
+/// A error in a `viewBox` or a `preserveAspectRatio`
+///
+/// `position` the byte offset of the error in the value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ViewBoxError {
+    pub position: usize,
+    pub kind: ViewBoxErrorKind,
+}
+
+impl fmt::Display for ViewBoxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self.kind {
+            ViewBoxErrorKind::ExpectedNumber => "expected a number",
+            ViewBoxErrorKind::WrongNumberCount => "expected four numbers",
+            ViewBoxErrorKind::NegativeSize => "negative size",
+            ViewBoxErrorKind::InvalidAlign => "invalid align",
+            ViewBoxErrorKind::InvalidMeetOrSlice => "expected meet or slice",
+        };
+        write!(f, "{} at position {}", message, self.position)
+    }
+}
+
+impl Error for ViewBoxError {}
+
+/// the byte offset of a part of `input`, which must be a sub slice of it
+fn offset(input: &str, part: &str) -> usize {
+    part.as_ptr() as usize - input.as_ptr() as usize
+}
+
+impl ViewBox {
+    pub fn new(min_x: f64, min_y: f64, width: f64, height: f64) -> ViewBox {
+        ViewBox {
+            min_x,
+            min_y,
+            width,
+            height,
+        }
+    }
+
+    /// parse a `viewBox`, four numbers separated by whitespace and/or a comma
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{ViewBox, ViewBoxErrorKind};
+    ///
+    /// assert_eq!(ViewBox::parse("0,0 24 24").unwrap(), ViewBox::new(0.0, 0.0, 24.0, 24.0));
+    /// assert_eq!(ViewBox::parse("0 0 24").unwrap_err().kind, ViewBoxErrorKind::WrongNumberCount);
+    /// assert_eq!(ViewBox::parse("0 0 -1 24").unwrap_err().position, 4);
+    /// ```
+    ///
+    pub fn parse(input: &str) -> Result<ViewBox, ViewBoxError> {
+        let parts: Vec<&str> = input
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let mut numbers = vec![];
+        for part in &parts {
+            match parse_number_list(part).filter(|list| list.len() == 1) {
+                Some(list) => numbers.push(list[0]),
+                None => {
+                    return Err(ViewBoxError {
+                        position: offset(input, part),
+                        kind: ViewBoxErrorKind::ExpectedNumber,
+                    })
+                }
+            }
+        }
+        if numbers.len() != 4 {
+            return Err(ViewBoxError {
+                position: parts.get(4).map_or(input.len(), |part| offset(input, part)),
+                kind: ViewBoxErrorKind::WrongNumberCount,
+            });
+        }
+        for i in [2, 3] {
+            if numbers[i] < 0.0 {
+                return Err(ViewBoxError {
+                    position: offset(input, parts[i]),
+                    kind: ViewBoxErrorKind::NegativeSize,
+                });
+            }
+        }
+        Ok(ViewBox::new(numbers[0], numbers[1], numbers[2], numbers[3]))
+    }
+
+    /// the transform from the user space of the view box to the coordinates of
+    /// the viewport `(x, y, width, height)`
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{Matrix, PreserveAspectRatio, ViewBox};
+    ///
+    /// let view_box = ViewBox::new(0.0, 0.0, 10.0, 20.0);
+    /// let meet = PreserveAspectRatio::default();
+    /// assert_eq!(view_box.transform((0.0, 0.0, 20.0, 20.0), &meet), Matrix::new(1.0, 0.0, 0.0, 1.0, 5.0, 0.0));
+    /// let slice = PreserveAspectRatio::parse("xMinYMax slice").unwrap();
+    /// assert_eq!(view_box.transform((0.0, 0.0, 20.0, 20.0), &slice), Matrix::new(2.0, 0.0, 0.0, 2.0, 0.0, -20.0));
+    /// ```
+    ///
+    pub fn transform(
+        &self,
+        (x, y, width, height): (f64, f64, f64, f64),
+        aspect: &PreserveAspectRatio,
+    ) -> Matrix {
+        let (mut sx, mut sy) = (width / self.width, height / self.height);
+        if !sx.is_finite() || !sy.is_finite() {
+            return Matrix::new(0.0, 0.0, 0.0, 0.0, x, y);
+        }
+        let (mut tx, mut ty) = (x, y);
+        if let Some((align_x, align_y)) = aspect.align.factors() {
+            let scale = match aspect.meet_or_slice {
+                MeetOrSlice::Meet => sx.min(sy),
+                MeetOrSlice::Slice => sx.max(sy),
+            };
+            sx = scale;
+            sy = scale;
+            tx += (width - self.width * scale) * align_x;
+            ty += (height - self.height * scale) * align_y;
+        }
+        Matrix::new(sx, 0.0, 0.0, sy, tx - self.min_x * sx, ty - self.min_y * sy)
+    }
+}
+
+impl FromStr for ViewBox {
+    type Err = ViewBoxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ViewBox::parse(s)
+    }
+}
+
+impl fmt::Display for ViewBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} {}",
+            shortest_number(self.min_x),
+            shortest_number(self.min_y),
+            shortest_number(self.width),
+            shortest_number(self.height)
+        )
+    }
+}
+
+const ALIGNS: [(&str, Align); 10] = [
+    ("none", Align::None),
+    ("xMinYMin", Align::XMinYMin),
+    ("xMidYMin", Align::XMidYMin),
+    ("xMaxYMin", Align::XMaxYMin),
+    ("xMinYMid", Align::XMinYMid),
+    ("xMidYMid", Align::XMidYMid),
+    ("xMaxYMid", Align::XMaxYMid),
+    ("xMinYMax", Align::XMinYMax),
+    ("xMidYMax", Align::XMidYMax),
+    ("xMaxYMax", Align::XMaxYMax),
+];
+
+impl Align {
+    /// the part of the free space which is before the view box on each axis,
+    /// `None` for `none`
+    pub fn factors(&self) -> Option<(f64, f64)> {
+        let (x, y) = match self {
+            Align::None => return None,
+            Align::XMinYMin => (0.0, 0.0),
+            Align::XMidYMin => (0.5, 0.0),
+            Align::XMaxYMin => (1.0, 0.0),
+            Align::XMinYMid => (0.0, 0.5),
+            Align::XMidYMid => (0.5, 0.5),
+            Align::XMaxYMid => (1.0, 0.5),
+            Align::XMinYMax => (0.0, 1.0),
+            Align::XMidYMax => (0.5, 1.0),
+            Align::XMaxYMax => (1.0, 1.0),
+        };
+        Some((x, y))
+    }
+}
+
+impl fmt::Display for Align {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, _) = ALIGNS.iter().find(|(_, align)| align == self).unwrap();
+        write!(f, "{}", name)
+    }
+}
+
+impl Default for PreserveAspectRatio {
+    fn default() -> Self {
+        PreserveAspectRatio {
+            align: Align::XMidYMid,
+            meet_or_slice: MeetOrSlice::Meet,
+        }
+    }
+}
+
+impl PreserveAspectRatio {
+    /// parse a `preserveAspectRatio`, a leading `defer` is ignored
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{Align, MeetOrSlice, PreserveAspectRatio};
+    ///
+    /// let aspect = PreserveAspectRatio::parse(" xMaxYMin  slice").unwrap();
+    /// assert_eq!(aspect.align, Align::XMaxYMin);
+    /// assert_eq!(aspect.meet_or_slice, MeetOrSlice::Slice);
+    /// assert_eq!(aspect.to_string(), "xMaxYMin slice");
+    /// assert_eq!(PreserveAspectRatio::parse("xMidYMid meet").unwrap().to_string(), "xMidYMid");
+    /// assert!(PreserveAspectRatio::parse("xmidymid").is_err());
+    /// ```
+    ///
+    pub fn parse(input: &str) -> Result<PreserveAspectRatio, ViewBoxError> {
+        let mut parts = input.split_whitespace().peekable();
+        if parts.peek() == Some(&"defer") {
+            parts.next();
+        }
+        let error = |part: Option<&str>, kind| ViewBoxError {
+            position: part.map_or(input.len(), |part| offset(input, part)),
+            kind,
+        };
+        let part = parts.next();
+        let align = ALIGNS
+            .iter()
+            .find(|(name, _)| Some(*name) == part)
+            .map(|(_, align)| *align)
+            .ok_or_else(|| error(part, ViewBoxErrorKind::InvalidAlign))?;
+        let meet_or_slice = match parts.next() {
+            None | Some("meet") => MeetOrSlice::Meet,
+            Some("slice") => MeetOrSlice::Slice,
+            part => return Err(error(part, ViewBoxErrorKind::InvalidMeetOrSlice)),
+        };
+        match parts.next() {
+            None => Ok(PreserveAspectRatio {
+                align,
+                meet_or_slice,
+            }),
+            part => Err(error(part, ViewBoxErrorKind::InvalidMeetOrSlice)),
+        }
+    }
+}
+
+impl FromStr for PreserveAspectRatio {
+    type Err = ViewBoxError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PreserveAspectRatio::parse(s)
+    }
+}
+
+impl fmt::Display for PreserveAspectRatio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.align)?;
+        if self.meet_or_slice == MeetOrSlice::Slice {
+            write!(f, " slice")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Element<'a> {
+    /// parse the `viewBox` of the element, `None` if it is missing
+    pub fn view_box(&self) -> Result<Option<ViewBox>, ViewBoxError> {
+        self.get_attribute("viewBox")
+            .map(|value| ViewBox::parse(&value))
+            .transpose()
+    }
+
+    /// parse the `preserveAspectRatio` of the element, the default if it is missing
+    pub fn preserve_aspect_ratio(&self) -> Result<PreserveAspectRatio, ViewBoxError> {
+        self.get_attribute("preserveAspectRatio")
+            .map_or(Ok(PreserveAspectRatio::default()), |value| {
+                PreserveAspectRatio::parse(&value)
+            })
+    }
+
+    /// a length attribute in user units, `default` if it is missing or invalid
+    fn viewport_length(&self, key: &str, default: &str, context: &LengthContext) -> f64 {
+        let direction = LengthDirection::of_attribute(key);
+        self.get_attribute(key)
+            .and_then(|value| Length::parse(&value).ok())
+            .or_else(|| Length::parse(default).ok())
+            .map_or(0.0, |length| length.to_user_units(context, direction))
+    }
+
+    /// the viewport `(x, y, width, height)` which the element establishes, in the
+    /// user space of its parent
+    ///
+    /// `None` if the element is not a `svg`, `symbol`, `marker` or `pattern`.
+    /// a marker is placed at the origin and the root `svg` ignores `x` and `y`.
+    /// percentages are resolved against the viewport of the context and a
+    /// missing or invalid length takes its initial value.
+    pub fn viewport(&self, context: &LengthContext) -> Option<(f64, f64, f64, f64)> {
+        let length = |key, default| self.viewport_length(key, default, context);
+        let is_root = self.parent.borrow().upgrade().is_none();
+        match self.ele_type {
+            "svg" | "symbol" if is_root => {
+                Some((0.0, 0.0, length("width", "100%"), length("height", "100%")))
+            }
+            "svg" | "symbol" => Some((
+                length("x", "0"),
+                length("y", "0"),
+                length("width", "100%"),
+                length("height", "100%"),
+            )),
+            "marker" => Some((
+                0.0,
+                0.0,
+                length("markerWidth", "3"),
+                length("markerHeight", "3"),
+            )),
+            "pattern" => Some((
+                length("x", "0"),
+                length("y", "0"),
+                length("width", "0"),
+                length("height", "0"),
+            )),
+            _ => None,
+        }
+    }
+
+    /// the transform from the user space of the content of the element to the
+    /// user space of its parent
+    ///
+    /// it maps the `viewBox` into the viewport following `preserveAspectRatio`,
+    /// and for a marker moves the reference point `refX` `refY` to the origin.
+    /// a invalid `viewBox` or `preserveAspectRatio` is ignored like a browser does.
+    /// `None` if the element does not establish a viewport, see `Element::viewport`.
+    ///
+    /// ## Example
+    /// ```rust
+    /// use svg_simple_parser::{parse, LengthContext, Matrix};
+    ///
+    /// let (_, root) = parse(r#"<svg width="48" height="48" viewBox="0 0 24 24"><svg x="10" width="50%" height="24" viewBox="0 0 12 24"/></svg>"#).unwrap();
+    /// let context = LengthContext::default();
+    /// assert_eq!(root.viewport_transform(&context), Some(Matrix::scale(2.0, 2.0)));
+    /// let nested = root.children.borrow()[0].clone();
+    /// let context = LengthContext { viewport: (24.0, 24.0), ..context };
+    /// assert_eq!(nested.viewport_transform(&context), Some(Matrix::translate(10.0, 0.0)));
+    /// ```
+    ///
+    pub fn viewport_transform(&self, context: &LengthContext) -> Option<Matrix> {
+        let (x, y, width, height) = self.viewport(context)?;
+        let aspect = self.preserve_aspect_ratio().unwrap_or_default();
+        let matrix = match self.view_box() {
+            Ok(Some(view_box)) => view_box.transform((x, y, width, height), &aspect),
+            _ => Matrix::translate(x, y),
+        };
+        if self.ele_type != "marker" {
+            return Some(matrix);
+        }
+        let reference = |key: &str| {
+            self.get_attribute(key)
+                .and_then(|value| Length::parse(&value).ok())
+                .map_or(0.0, |length| length.number)
+        };
+        let (ref_x, ref_y) = matrix.apply(reference("refX"), reference("refY"));
+        Some(Matrix::translate(-ref_x, -ref_y) * matrix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::view_box::ViewBoxErrorKind;
+    use crate::{parse, Align, LengthContext, Matrix, PreserveAspectRatio, ViewBox};
+
+    #[test]
+    fn test_parse_view_box() {
+        assert_eq!(
+            ViewBox::parse(" -1.5e1,2 .5 100 ").unwrap(),
+            ViewBox::new(-15.0, 2.0, 0.5, 100.0)
+        );
+        assert_eq!(
+            ViewBox::new(-15.0, 2.0, 0.5, 100.0).to_string(),
+            "-15 2 .5 100"
+        );
+        let error = |input| ViewBox::parse(input).unwrap_err();
+        assert_eq!(error("0 0 a 1").kind, ViewBoxErrorKind::ExpectedNumber);
+        assert_eq!(error("0 0 a 1").position, 4);
+        assert_eq!(error("0 0 1 1 1").position, 8);
+        assert_eq!(error("").kind, ViewBoxErrorKind::WrongNumberCount);
+        assert_eq!(error("0 0 1 -1").kind, ViewBoxErrorKind::NegativeSize);
+    }
+
+    #[test]
+    fn test_parse_preserve_aspect_ratio() {
+        for name in [
+            "none", "xMinYMin", "xMidYMin", "xMaxYMin", "xMinYMid", "xMidYMid", "xMaxYMid",
+            "xMinYMax", "xMidYMax", "xMaxYMax",
+        ] {
+            let aspect = PreserveAspectRatio::parse(name).unwrap();
+            assert_eq!(aspect.to_string(), name);
+        }
+        assert_eq!(
+            PreserveAspectRatio::parse("defer none slice")
+                .unwrap()
+                .align,
+            Align::None
+        );
+        let kind = |input| PreserveAspectRatio::parse(input).unwrap_err().kind;
+        assert_eq!(kind(""), ViewBoxErrorKind::InvalidAlign);
+        assert_eq!(kind("none cut"), ViewBoxErrorKind::InvalidMeetOrSlice);
+        assert_eq!(kind("none meet meet"), ViewBoxErrorKind::InvalidMeetOrSlice);
+    }
+
+    #[test]
+    fn test_transform() {
+        let view_box = ViewBox::new(5.0, 5.0, 10.0, 20.0);
+        let transform = |aspect: &str| {
+            view_box.transform(
+                (1.0, 2.0, 20.0, 20.0),
+                &PreserveAspectRatio::parse(aspect).unwrap(),
+            )
+        };
+        assert_eq!(
+            transform("none"),
+            Matrix::new(2.0, 0.0, 0.0, 1.0, -9.0, -3.0)
+        );
+        assert_eq!(
+            transform("xMaxYMid"),
+            Matrix::new(1.0, 0.0, 0.0, 1.0, 6.0, -3.0)
+        );
+        assert_eq!(
+            transform("xMidYMid slice"),
+            Matrix::new(2.0, 0.0, 0.0, 2.0, -9.0, -18.0)
+        );
+    }
+
+    #[test]
+    fn test_viewport_transform() {
+        let (_, root) = parse(
+            r#"<svg><marker markerWidth="10" markerHeight="10" viewBox="0 0 5 5" refX="5" refY="2.5"/><symbol viewBox="0 0 1 1"/><pattern x="1" y="2" width="3" height="4"/><rect/></svg>"#,
+        )
+        .unwrap();
+        let context = LengthContext::default();
+        let children = root.children.borrow();
+        let transform = |i: usize| children[i].viewport_transform(&context);
+        assert_eq!(
+            transform(0),
+            Some(Matrix::new(2.0, 0.0, 0.0, 2.0, -10.0, -5.0))
+        );
+        assert_eq!(transform(1), Some(Matrix::scale(100.0, 100.0)));
+        assert_eq!(transform(2), Some(Matrix::translate(1.0, 2.0)));
+        assert_eq!(transform(3), None);
+        assert_eq!(root.viewport(&context), Some((0.0, 0.0, 100.0, 100.0)));
+    }
+}
@@ -16,8 +16,8 @@ fn test_parse() {
     assert_eq!(
         *root.attributes.borrow(),
         HashMap::from([
-            ("xmlns".to_owned(), "http://www.w3.org/2000/svg"),
-            ("version".to_owned(), "1.1"),
+            ("xmlns".to_owned(), "http://www.w3.org/2000/svg".into()),
+            ("version".to_owned(), "1.1".into()),
         ])
     );
     let child = &root.children.borrow()[0];
@@ -25,12 +25,12 @@ fn test_parse() {
     assert_eq!(
         *child.attributes.borrow(),
         HashMap::from([
-            ("cx".to_owned(), "100"),
-            ("cy".to_owned(), "50"),
-            ("r".to_owned(), "40"),
-            ("stroke".to_owned(), "black"),
-            ("stroke-width".to_owned(), "2"),
-            ("fill".to_owned(), "red"),
+            ("cx".to_owned(), "100".into()),
+            ("cy".to_owned(), "50".into()),
+            ("r".to_owned(), "40".into()),
+            ("stroke".to_owned(), "black".into()),
+            ("stroke-width".to_owned(), "2".into()),
+            ("fill".to_owned(), "red".into()),
         ])
     );
 }